@@ -2,7 +2,6 @@ use std::time::Duration;
 
 use criterion::{black_box, AxisScale, BenchmarkId, Criterion, PlotConfiguration, Throughput};
 use pomsky::{
-    features::PomskyFeatures,
     options::{CompileOptions, ParseOptions, RegexFlavor},
     Expr,
 };
@@ -41,6 +40,29 @@ pub fn parse(c: &mut Criterion) {
     }
 }
 
+pub fn large_input(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large input");
+
+    for branches in [1_000, 10_000, 100_000] {
+        let input = generate_large_input(branches);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(branches), &input, |b, input| {
+            b.iter(|| Expr::parse(black_box(input), Default::default()).unwrap())
+        });
+    }
+}
+
+/// Generates an alternation with the given number of branches, each preceded
+/// by a comment and followed by a comment and an empty line
+fn generate_large_input(branches: usize) -> String {
+    let mut input = String::new();
+    for i in 0..branches {
+        input.push_str(&format!("# line {i}\n  'a{i}' [w]+ | # alternative\n\n"));
+    }
+    input.push_str("'end'");
+    input
+}
+
 pub fn compile(c: &mut Criterion) {
     let mut group = c.benchmark_group("compile");
 
@@ -65,7 +87,7 @@ pub fn range(c: &mut Criterion) {
             let input = format!("range '0'-'{max}'");
             let (expr, _warnings) = Expr::parse(
                 black_box(&input),
-                ParseOptions { max_range_size: 100, allowed_features: PomskyFeatures::default() },
+                ParseOptions { max_range_size: 100, ..Default::default() },
             )
             .unwrap();
 
@@ -96,11 +118,12 @@ pub fn competition(c: &mut Criterion) {
 }
 
 fn ruby() -> CompileOptions {
-    CompileOptions { flavor: RegexFlavor::Ruby }
+    CompileOptions { flavor: RegexFlavor::Ruby, ..Default::default() }
 }
 
 pub fn benches(c: &mut Criterion) {
     parse(c);
+    large_input(c);
     compile(c);
     range(c);
     competition(c);
@@ -0,0 +1,360 @@
+use std::fmt::Write;
+
+use pomsky::captures::CaptureGroup;
+
+use crate::parse_args::EmitCode;
+
+/// Generates code in the specified language that matches the compiled regex
+/// and extracts the named capturing groups from the match.
+pub(crate) fn emit_code(regex: &str, groups: &[CaptureGroup], lang: EmitCode) -> String {
+    match lang {
+        EmitCode::Rust => emit_rust(regex, groups),
+        EmitCode::DotNet => emit_dotnet(regex, groups),
+    }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Generates a Rust module for the `regex` crate, with a struct containing a
+/// field for the entire match and for each named capturing group.
+fn emit_rust(regex: &str, groups: &[CaptureGroup]) -> String {
+    let named: Vec<(&str, bool)> =
+        groups.iter().filter_map(|g| g.name.as_deref().map(|name| (name, g.optional))).collect();
+
+    let mut full_match = String::from("full_match");
+    while named.iter().any(|&(name, _)| name == full_match) {
+        full_match.push('_');
+    }
+
+    let mut buf = String::new();
+    buf.push_str("use regex::Regex;\n\n");
+    buf.push_str("/// Compiles the regex. It should be compiled only once and then reused.\n");
+    writeln!(
+        buf,
+        "pub fn regex() -> Regex {{\n    Regex::new({}).unwrap()\n}}\n",
+        raw_string(regex)
+    )
+    .unwrap();
+
+    buf.push_str("pub struct Captures<'h> {\n");
+    writeln!(buf, "    pub {full_match}: &'h str,").unwrap();
+    for &(name, optional) in &named {
+        let ty = if optional { "Option<&'h str>" } else { "&'h str" };
+        writeln!(buf, "    pub {}: {ty},", rust_ident(name)).unwrap();
+    }
+    buf.push_str("}\n\n");
+
+    buf.push_str(
+        "pub fn captures<'h>(regex: &Regex, haystack: &'h str) -> Option<Captures<'h>> {\n",
+    );
+    buf.push_str("    let caps = regex.captures(haystack)?;\n");
+    buf.push_str("    Some(Captures {\n");
+    writeln!(buf, "        {full_match}: caps.get(0).unwrap().as_str(),").unwrap();
+    for &(name, optional) in &named {
+        let ident = rust_ident(name);
+        if optional {
+            writeln!(buf, "        {ident}: caps.name({name:?}).map(|m| m.as_str()),").unwrap();
+        } else {
+            writeln!(buf, "        {ident}: caps.name({name:?}).unwrap().as_str(),").unwrap();
+        }
+    }
+    buf.push_str("    })\n}");
+    buf
+}
+
+/// Turns a group name into a valid Rust identifier
+fn rust_ident(name: &str) -> String {
+    match name {
+        // these keywords can't be raw identifiers
+        "crate" | "self" | "super" | "Self" => format!("{name}_"),
+        _ if RUST_KEYWORDS.contains(&name) => format!("r#{name}"),
+        _ => name.to_string(),
+    }
+}
+
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Generates a C# class using the .NET regex source generator (.NET 7 or
+/// later), with a record containing a property for the entire match and for
+/// each named capturing group.
+fn emit_dotnet(regex: &str, groups: &[CaptureGroup]) -> String {
+    let named: Vec<(&str, bool)> =
+        groups.iter().filter_map(|g| g.name.as_deref().map(|name| (name, g.optional))).collect();
+
+    let mut full_match = String::from("FullMatch");
+    while named.iter().any(|&(name, _)| name == full_match) {
+        full_match.push('_');
+    }
+
+    let mut buf = String::new();
+    buf.push_str("using System.Text.RegularExpressions;\n\n");
+    buf.push_str("public static partial class PomskyRegex\n{\n");
+    writeln!(buf, "    [GeneratedRegex({})]", verbatim_string(regex)).unwrap();
+    buf.push_str("    public static partial Regex Regex();\n\n");
+
+    write!(buf, "    public sealed record Captures(string {full_match}").unwrap();
+    for &(name, optional) in &named {
+        let ty = if optional { "string?" } else { "string" };
+        write!(buf, ", {ty} {}", csharp_ident(name)).unwrap();
+    }
+    buf.push_str(");\n\n");
+
+    buf.push_str("    public static Captures? Match(string input)\n    {\n");
+    buf.push_str("        var m = Regex().Match(input);\n");
+    buf.push_str("        if (!m.Success)\n        {\n            return null;\n        }\n");
+    buf.push_str("        return new Captures(\n            m.Value");
+    for &(name, optional) in &named {
+        if optional {
+            write!(
+                buf,
+                ",\n            m.Groups[{name:?}].Success ? m.Groups[{name:?}].Value : null"
+            )
+            .unwrap();
+        } else {
+            write!(buf, ",\n            m.Groups[{name:?}].Value").unwrap();
+        }
+    }
+    buf.push_str(");\n    }\n}");
+    buf
+}
+
+/// Turns a group name into a valid C# identifier
+fn csharp_ident(name: &str) -> String {
+    if CSHARP_KEYWORDS.contains(&name) {
+        format!("@{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Creates a C# verbatim string literal, where only `"` must be escaped
+fn verbatim_string(regex: &str) -> String {
+    format!("@\"{}\"", regex.replace('"', "\"\""))
+}
+
+/// Creates a raw string literal with enough `#`s that the regex can't end it
+/// early
+fn raw_string(regex: &str) -> String {
+    let hashes = regex
+        .split('"')
+        .skip(1)
+        .map(|s| s.len() - s.trim_start_matches('#').len() + 1)
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(hashes);
+    format!("r{hashes}\"{regex}\"{hashes}")
+}
+
+#[cfg(test)]
+mod tests {
+    use pomsky::captures::CaptureGroup;
+
+    use super::{csharp_ident, emit_dotnet, emit_rust, raw_string, rust_ident, verbatim_string};
+
+    fn group(index: u32, name: Option<&str>, optional: bool) -> CaptureGroup {
+        CaptureGroup { index, name: name.map(String::from), optional }
+    }
+
+    #[test]
+    fn rust_named_groups() {
+        let groups =
+            [group(1, Some("year"), false), group(2, None, false), group(3, Some("month"), true)];
+        let code = emit_rust(r"(?P<year>\d+)(-)(?P<month>\d+)?", &groups);
+        assert_eq!(
+            code,
+            r#"use regex::Regex;
+
+/// Compiles the regex. It should be compiled only once and then reused.
+pub fn regex() -> Regex {
+    Regex::new(r"(?P<year>\d+)(-)(?P<month>\d+)?").unwrap()
+}
+
+pub struct Captures<'h> {
+    pub full_match: &'h str,
+    pub year: &'h str,
+    pub month: Option<&'h str>,
+}
+
+pub fn captures<'h>(regex: &Regex, haystack: &'h str) -> Option<Captures<'h>> {
+    let caps = regex.captures(haystack)?;
+    Some(Captures {
+        full_match: caps.get(0).unwrap().as_str(),
+        year: caps.name("year").unwrap().as_str(),
+        month: caps.name("month").map(|m| m.as_str()),
+    })
+}"#
+        );
+    }
+
+    #[test]
+    fn rust_keyword_names() {
+        assert_eq!(rust_ident("type"), "r#type");
+        assert_eq!(rust_ident("self"), "self_");
+        assert_eq!(rust_ident("name"), "name");
+
+        let groups = [group(1, Some("type"), false), group(2, Some("self"), true)];
+        let code = emit_rust("(?P<type>a)(?P<self>b)?", &groups);
+        assert!(code.contains("    pub r#type: &'h str,\n"), "{code}");
+        assert!(code.contains("    pub self_: Option<&'h str>,\n"), "{code}");
+        assert!(code.contains(r#"        r#type: caps.name("type").unwrap().as_str(),"#), "{code}");
+        assert!(
+            code.contains(r#"        self_: caps.name("self").map(|m| m.as_str()),"#),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn rust_raw_string_hashes() {
+        assert_eq!(raw_string("a+"), r#"r"a+""#);
+        assert_eq!(raw_string(r#"a"b"#), r##"r#"a"b"#"##);
+        assert_eq!(raw_string(r##"a"#b"##), r###"r##"a"#b"##"###);
+
+        let code = emit_rust(r##""#"##, &[]);
+        assert!(code.contains(r###"Regex::new(r##""#"##)"###), "{code}");
+    }
+
+    #[test]
+    fn rust_full_match_collision() {
+        let groups = [group(1, Some("full_match"), false)];
+        let code = emit_rust("(?P<full_match>a)", &groups);
+        assert!(code.contains("    pub full_match_: &'h str,\n    pub full_match: &'h str,\n"));
+        assert!(code.contains("        full_match_: caps.get(0).unwrap().as_str(),\n"), "{code}");
+        assert!(code.contains(r#"        full_match: caps.name("full_match").unwrap()"#), "{code}");
+    }
+
+    #[test]
+    fn dotnet_named_groups() {
+        let groups =
+            [group(1, Some("year"), false), group(2, None, false), group(3, Some("month"), true)];
+        let code = emit_dotnet(r"(?<year>\d+)(-)(?<month>\d+)?", &groups);
+        assert_eq!(
+            code,
+            r#"using System.Text.RegularExpressions;
+
+public static partial class PomskyRegex
+{
+    [GeneratedRegex(@"(?<year>\d+)(-)(?<month>\d+)?")]
+    public static partial Regex Regex();
+
+    public sealed record Captures(string FullMatch, string year, string? month);
+
+    public static Captures? Match(string input)
+    {
+        var m = Regex().Match(input);
+        if (!m.Success)
+        {
+            return null;
+        }
+        return new Captures(
+            m.Value,
+            m.Groups["year"].Value,
+            m.Groups["month"].Success ? m.Groups["month"].Value : null);
+    }
+}"#
+        );
+    }
+
+    #[test]
+    fn dotnet_keyword_names() {
+        assert_eq!(csharp_ident("class"), "@class");
+        assert_eq!(csharp_ident("name"), "name");
+
+        let groups = [group(1, Some("class"), false)];
+        let code = emit_dotnet("(?<class>a)", &groups);
+        assert!(code.contains("Captures(string FullMatch, string @class);"), "{code}");
+        assert!(code.contains(r#"m.Groups["class"].Value"#), "{code}");
+    }
+
+    #[test]
+    fn dotnet_verbatim_string_quotes() {
+        assert_eq!(verbatim_string(r"a\d"), r#"@"a\d""#);
+        assert_eq!(verbatim_string(r#"say "hi""#), r#"@"say ""hi""""#);
+
+        let code = emit_dotnet(r#"a"b"#, &[]);
+        assert!(code.contains(r#"[GeneratedRegex(@"a""b")]"#), "{code}");
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::Write;
+
+use crate::parse_args::Escape;
+
+/// Escapes the compiled regex, so it can be embedded in a JSON document, a
+/// YAML document or a shell script.
+pub(crate) fn escape(regex: &str, escape: Escape) -> String {
+    match escape {
+        Escape::None => regex.to_string(),
+        Escape::Json => double_quoted(regex),
+        // single-quoted YAML strings can't contain escape sequences, so
+        // double quotes are used if the regex contains control characters
+        Escape::Yaml if regex.chars().any(char::is_control) => double_quoted(regex),
+        Escape::Yaml => format!("'{}'", regex.replace('\'', "''")),
+        Escape::Shell => format!("'{}'", regex.replace('\'', r"'\''")),
+    }
+}
+
+/// Creates a double-quoted string with the escape sequences that are valid in
+/// both JSON and YAML.
+pub(crate) fn double_quoted(regex: &str) -> String {
+    let mut buf = String::with_capacity(regex.len() + 2);
+    buf.push('"');
+    for c in regex.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                write!(buf, "\\u{:04X}", c as u32).unwrap();
+            }
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+    buf
+}
@@ -1,4 +1,7 @@
-use std::io::{self, Read, Write};
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+};
 
 use atty::Stream;
 use clap::Parser as _;
@@ -6,17 +9,26 @@ use owo_colors::OwoColorize;
 use pomsky::{
     error::{Diagnostic, ParseError, Severity},
     options::{CompileOptions, ParseOptions},
+    snapshots,
+    timings::Timings,
     warning::Warning,
     Expr,
 };
 
+mod emit_code;
+mod escape;
 mod parse_args;
+mod sarif;
 
-use parse_args::{Args, Flavor};
+use parse_args::{Args, Command, EmitCode, Escape, Flavor, Format};
 
 pub fn main() {
     let args = Args::parse();
 
+    if let Some(Command::Snapshots { path }) = &args.command {
+        return run_snapshots(path);
+    }
+
     match (&args.input, &args.path) {
         (Some(input), None) => compile(input, &args),
         (None, Some(path)) => match std::fs::read_to_string(&path) {
@@ -65,35 +77,129 @@ pub fn main() {
     }
 }
 
+fn parse_options(args: &Args) -> ParseOptions {
+    ParseOptions {
+        max_range_size: 12,
+        warn_capture_in_repetition: args.warn_capture_in_repetition,
+        suggest_named_classes: args.suggest_named_classes,
+        warn_end_anchor: args.warn_end_anchor,
+        ..ParseOptions::default()
+    }
+}
+
+fn compile_options(args: &Args, flavor: Flavor) -> CompileOptions {
+    CompileOptions {
+        flavor: flavor.into(),
+        canonical: args.canonical,
+        minimal_classes: args.minimal_classes,
+        explicit_word_boundary: args.explicit_word_boundary,
+        ascii_only_output: args.ascii_only,
+        posix_classes: args.posix_classes,
+        ..Default::default()
+    }
+}
+
 fn compile(input: &str, args: &Args) {
-    let parse_options = ParseOptions { max_range_size: 12, ..ParseOptions::default() };
-    let (parsed, warnings) = match Expr::parse(input, parse_options) {
-        Ok(res) => res,
-        Err(err) => {
-            print_parse_error(err, input);
-            std::process::exit(1);
-        }
-    };
+    if args.format == Format::Sarif {
+        return check_sarif(input, args);
+    }
+
+    let mut timings = Timings::default();
+    let (parsed, warnings) =
+        match Expr::parse_with_timings(input, parse_options(args), &mut timings) {
+            Ok(res) => res,
+            Err(err) => {
+                print_parse_error(err, input);
+                std::process::exit(1);
+            }
+        };
 
     if args.debug {
         eprintln!("======================== debug ========================");
         eprintln!("{parsed:#?}\n");
     }
 
-    print_warnings(warnings, input);
+    let flavor = match (args.flavor, args.emit_code) {
+        (None, Some(lang)) => lang.flavor(),
+        (Some(flavor), Some(lang)) if flavor == lang.flavor() => flavor,
+        (Some(_), Some(lang)) => {
+            let (lang, flavor) = match lang {
+                EmitCode::Rust => ("rust", "Rust"),
+                EmitCode::DotNet => ("dotnet", ".NET"),
+            };
+            print_diagnostic(&Diagnostic::ad_hoc(
+                Severity::Error,
+                None,
+                format!("`--emit-code {lang}` can only be used with the {flavor} flavor"),
+                None,
+            ));
+            std::process::exit(1);
+        }
+        (flavor, None) => flavor.unwrap_or(Flavor::Pcre),
+    };
+    print_warnings(
+        warnings.into_iter().filter(|warning| warning.applies_to(flavor.into())).collect(),
+        input,
+    );
+
+    if args.dump_ast {
+        match serde_json::to_string(&parsed.root()) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                print_diagnostic(&Diagnostic::ad_hoc(Severity::Error, None, err.to_string(), None));
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    let compile_options =
-        CompileOptions { flavor: (*args.flavor.as_ref().unwrap_or(&Flavor::Pcre)).into() };
+    let compile_options = compile_options(args, flavor);
     let compiled = match parsed
-        .compile(compile_options)
-        .map_err(|err| Diagnostic::from_compile_error(err, input))
+        .compile_with_timings(compile_options.clone(), &mut timings)
+        .map_err(|err| Diagnostic::from_compile_errors(err, input))
     {
         Ok(res) => res,
-        Err(err) => {
-            print_diagnostic(&err);
+        Err(diagnostics) => {
+            for diagnostic in &diagnostics {
+                print_diagnostic(diagnostic);
+            }
             std::process::exit(1);
         }
     };
+    print_warnings(parsed.engine_limit_warnings(&compiled, &compile_options), input);
+
+    let optimizations = if args.explain_optimizations {
+        match parsed.compile_with_optimizations(compile_options.clone()) {
+            Ok((_, optimizations)) => optimizations,
+            Err(err) => {
+                print_diagnostic(&Diagnostic::from_compile_error(err, input));
+                std::process::exit(1);
+            }
+        }
+    } else {
+        vec![]
+    };
+
+    let compiled = match (args.explain, args.emit_code) {
+        (Some(lang), _) => match parsed.explain(compile_options, lang.into()) {
+            Ok(explanation) => explanation,
+            Err(err) => {
+                print_diagnostic(&Diagnostic::from_compile_error(err, input));
+                std::process::exit(1);
+            }
+        },
+        (None, Some(lang)) => {
+            let groups = match parsed.capture_groups() {
+                Ok(groups) => groups,
+                Err(err) => {
+                    print_diagnostic(&Diagnostic::from_compile_error(err, input));
+                    std::process::exit(1);
+                }
+            };
+            emit_code::emit_code(&compiled, &groups, lang)
+        }
+        (None, None) => escape::escape(&compiled, args.escape.unwrap_or(Escape::None)),
+    };
 
     if args.no_new_line {
         print!("{compiled}");
@@ -101,6 +207,71 @@ fn compile(input: &str, args: &Args) {
     } else {
         println!("{compiled}");
     }
+
+    if args.explain_optimizations {
+        eprintln!("==================== optimizations ====================");
+        if optimizations.is_empty() {
+            eprintln!("no optimizations were applied");
+        }
+        for optimization in &optimizations {
+            eprintln!("{optimization}");
+        }
+    }
+
+    if args.timings {
+        eprintln!("======================= timings =======================");
+        eprintln!("{timings}");
+    }
+}
+
+/// Compiles the expression and prints all errors and warnings as a SARIF log to
+/// stdout. The compiled regex isn't printed. Exits with status 1 if there was
+/// an error.
+fn check_sarif(input: &str, args: &Args) {
+    let flavor = args.flavor.or(args.emit_code.map(EmitCode::flavor)).unwrap_or(Flavor::Pcre);
+
+    let mut diagnostics = vec![];
+    match Expr::parse(input, parse_options(args)) {
+        Ok((parsed, warnings)) => {
+            diagnostics.extend(
+                warnings
+                    .into_iter()
+                    .filter(|warning| warning.applies_to(flavor.into()))
+                    .map(|warning| Diagnostic::from_warning(warning, input)),
+            );
+            let compile_options = compile_options(args, flavor);
+            match parsed.compile(compile_options.clone()) {
+                Ok(compiled) => diagnostics.extend(
+                    parsed
+                        .engine_limit_warnings(&compiled, &compile_options)
+                        .into_iter()
+                        .map(|warning| Diagnostic::from_warning(warning, input)),
+                ),
+                Err(err) => diagnostics.extend(Diagnostic::from_compile_errors(err, input)),
+            }
+        }
+        Err(err) => diagnostics.extend(Diagnostic::from_parse_errors(err, input)),
+    }
+
+    println!("{}", sarif::to_sarif(&diagnostics, input, args.path.as_deref()));
+    if diagnostics.iter().any(|d| matches!(d.severity, Severity::Error)) {
+        std::process::exit(1);
+    }
+}
+
+fn run_snapshots(path: &Path) {
+    match snapshots::run_snapshots(path) {
+        Ok(report) => {
+            println!("{report}");
+            if !report.is_success() {
+                std::process::exit(1);
+            }
+        }
+        Err(err) => {
+            print_diagnostic(&Diagnostic::ad_hoc(Severity::Error, None, err.to_string(), None));
+            std::process::exit(1);
+        }
+    }
 }
 
 fn print_parse_error(error: ParseError, input: &str) {
@@ -1,13 +1,17 @@
 use std::path::PathBuf;
 
-use clap::{ArgEnum, Parser};
-use pomsky::options::RegexFlavor;
+use clap::{ArgEnum, Parser, Subcommand};
+use pomsky::{explain::Locale, options::RegexFlavor};
 
 /// Compile a Pomsky expression to a regex
 #[derive(Parser, Debug)]
 #[clap(name = "pomsky")]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true)]
 pub(crate) struct Args {
+    #[clap(subcommand)]
+    pub(crate) command: Option<Command>,
+
     /// Pomsky expression to compile
     pub(crate) input: Option<String>,
     /// File containing the pomsky expression to compile
@@ -18,6 +22,15 @@ pub(crate) struct Args {
     #[clap(short, long)]
     pub(crate) debug: bool,
 
+    /// Show the time spent in each phase of the compiler
+    #[clap(long)]
+    pub(crate) timings: bool,
+
+    /// Show the optimizations that were applied, with the part of the input
+    /// that was affected
+    #[clap(long)]
+    pub(crate) explain_optimizations: bool,
+
     /// Regex flavor
     #[clap(long, short, arg_enum, ignore_case(true))]
     pub(crate) flavor: Option<Flavor>,
@@ -25,10 +38,96 @@ pub(crate) struct Args {
     /// Does not print a new-line at the end of the compiled regular expression
     #[clap(long, short)]
     pub(crate) no_new_line: bool,
+
+    /// Show a warning for capturing groups within a repetition, which only
+    /// capture the last repetition
+    #[clap(long)]
+    pub(crate) warn_capture_in_repetition: bool,
+
+    /// Show a warning for character classes that can be written as a named
+    /// ASCII class, e.g. `['0'-'9']` as `[ascii_digit]`
+    #[clap(long)]
+    pub(crate) suggest_named_classes: bool,
+
+    /// Show a warning for `End`, which doesn't match before a trailing
+    /// newline, unlike `$`
+    #[clap(long)]
+    pub(crate) warn_end_anchor: bool,
+
+    /// Normalize the output, so it produces minimal diffs when the input
+    /// changes. For example, ranges in character classes are sorted and merged
+    #[clap(long)]
+    pub(crate) canonical: bool,
+
+    /// Emit character classes in the shortest equivalent form, e.g. `[\da-f]`
+    /// instead of `[0-9a-f]` in JavaScript
+    #[clap(long)]
+    pub(crate) minimal_classes: bool,
+
+    /// Compile word boundaries to lookarounds, so they match the same word
+    /// characters as `[w]`. Requires a flavor that supports lookbehind
+    #[clap(long)]
+    pub(crate) explicit_word_boundary: bool,
+
+    /// Escape all non-ASCII characters, so the output only consists of ASCII
+    /// characters
+    #[clap(long)]
+    pub(crate) ascii_only: bool,
+
+    /// Emit POSIX classes such as `[:alpha:]` for ASCII classes. This only
+    /// has an effect in the PCRE, Rust and RE2 flavors
+    #[clap(long)]
+    pub(crate) posix_classes: bool,
+
+    /// Escape the compiled regex, so it can be embedded in the specified
+    /// format
+    #[clap(long, arg_enum, ignore_case(true), value_name = "FORMAT")]
+    pub(crate) escape: Option<Escape>,
+
+    /// Print code in the specified language that matches the regex and
+    /// extracts the named capturing groups. This implies the flavor used by
+    /// the language, e.g. `--flavor rust` for `rust`
+    #[clap(long, arg_enum, ignore_case(true), value_name = "LANG", conflicts_with = "escape")]
+    pub(crate) emit_code: Option<EmitCode>,
+
+    /// Print a description of the compiled regex in the specified language
+    /// instead of the regex
+    #[clap(
+        long,
+        arg_enum,
+        ignore_case(true),
+        value_name = "LANG",
+        conflicts_with_all = &["escape", "emit-code"]
+    )]
+    pub(crate) explain: Option<ExplainLang>,
+
+    /// Print the parsed expression as JSON instead of compiling it. The output
+    /// contains the kind, span and children of each node
+    #[clap(long, conflicts_with_all = &["escape", "emit-code", "explain"])]
+    pub(crate) dump_ast: bool,
+
+    /// Format of errors and warnings. With `sarif`, the diagnostics are
+    /// printed to stdout instead of the compiled regex
+    #[clap(long, arg_enum, ignore_case(true), value_name = "FORMAT", default_value = "text")]
+    pub(crate) format: Format,
+}
+
+/// Subcommands of the CLI
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Check a file of pomsky expressions against their expected output.
+    /// Entries are separated by a `=====` line; each entry contains an
+    /// optional `#! flavor=...` line, the expression, a `-----` line and the
+    /// expected regex
+    Snapshots {
+        /// The snapshot file
+        #[clap(parse(from_os_str), value_name = "FILE")]
+        path: PathBuf,
+    },
 }
 
 /// Pomsky flavor
-#[derive(Clone, Copy, Debug, ArgEnum)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
 #[clap(rename_all = "lower")]
 pub(crate) enum Flavor {
     Pcre,
@@ -40,6 +139,83 @@ pub(crate) enum Flavor {
     DotNet,
     Ruby,
     Rust,
+    /// GNU sed with basic regular expressions
+    Sed,
+    /// GNU sed with extended regular expressions (`sed -E`)
+    #[clap(name = "sed-extended", alias = "sed-e")]
+    SedExtended,
+    /// RE2, as used in Go
+    Re2,
+    /// ANTLR lexer rules
+    Antlr,
+    /// Lua patterns
+    Lua,
+}
+
+/// Format in which the compiled regex is embedded
+#[derive(Clone, Copy, Debug, ArgEnum)]
+#[clap(rename_all = "lower")]
+pub(crate) enum Escape {
+    /// Don't escape the regex
+    None,
+    /// A JSON string
+    Json,
+    /// A YAML string. Single quotes are used unless the regex contains control
+    /// characters
+    Yaml,
+    /// A single-quoted string for POSIX shells
+    #[clap(alias = "sh")]
+    Shell,
+}
+
+/// Format in which errors and warnings are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+#[clap(rename_all = "lower")]
+pub(crate) enum Format {
+    /// Human-readable messages with a code snippet, printed to stderr
+    Text,
+    /// A SARIF log for code scanning tools, printed to stdout
+    Sarif,
+}
+
+/// Language of the code printed with `--emit-code`
+#[derive(Clone, Copy, Debug, ArgEnum)]
+#[clap(rename_all = "lower")]
+pub(crate) enum EmitCode {
+    /// A Rust module using the `regex` crate
+    Rust,
+    /// A C# class using the .NET regex source generator
+    #[clap(alias = "csharp")]
+    DotNet,
+}
+
+impl EmitCode {
+    /// The flavor required by the generated code
+    pub(crate) fn flavor(self) -> Flavor {
+        match self {
+            EmitCode::Rust => Flavor::Rust,
+            EmitCode::DotNet => Flavor::DotNet,
+        }
+    }
+}
+
+/// Language of the description printed with `--explain`
+#[derive(Clone, Copy, Debug, ArgEnum)]
+#[clap(rename_all = "lower")]
+pub(crate) enum ExplainLang {
+    #[clap(alias = "english")]
+    En,
+    #[clap(alias = "italian")]
+    It,
+}
+
+impl From<ExplainLang> for Locale {
+    fn from(lang: ExplainLang) -> Self {
+        match lang {
+            ExplainLang::En => Locale::English,
+            ExplainLang::It => Locale::Italian,
+        }
+    }
 }
 
 impl From<Flavor> for RegexFlavor {
@@ -52,6 +228,30 @@ impl From<Flavor> for RegexFlavor {
             Flavor::DotNet => RegexFlavor::DotNet,
             Flavor::Ruby => RegexFlavor::Ruby,
             Flavor::Rust => RegexFlavor::Rust,
+            Flavor::Sed => RegexFlavor::Sed { extended: false },
+            Flavor::SedExtended => RegexFlavor::Sed { extended: true },
+            Flavor::Re2 => RegexFlavor::Re2,
+            Flavor::Antlr => RegexFlavor::Antlr,
+            Flavor::Lua => RegexFlavor::Lua,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::Args;
+
+    #[test]
+    fn valid_args() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn conflicting_args() {
+        assert!(Args::try_parse_from(["pomsky", "--explain", "en", "--emit-code", "rust"]).is_err());
+        assert!(Args::try_parse_from(["pomsky", "--dump-ast", "--emit-code", "rust"]).is_err());
+        assert!(Args::try_parse_from(["pomsky", "--dump-ast", "'a'"]).is_ok());
+    }
+}
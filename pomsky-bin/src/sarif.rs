@@ -0,0 +1,128 @@
+//! Formats diagnostics as a [SARIF](https://sarifweb.azurewebsites.net/) log,
+//! so they can be displayed by code scanning tools in CI.
+
+use std::{fmt::Write, path::Path};
+
+use pomsky::error::{Diagnostic, Severity};
+
+use crate::escape::double_quoted;
+
+/// Returns a SARIF 2.1.0 log with a single run containing the diagnostics.
+///
+/// Regions are given as 1-based lines and columns, where columns are counted
+/// in code points. If `path` is `None` (the input was passed as an argument
+/// or via stdin), the results don't have an artifact location.
+pub(crate) fn to_sarif(diagnostics: &[Diagnostic], input: &str, path: Option<&Path>) -> String {
+    let mut buf = String::new();
+    buf.push_str(r#"{"version":"2.1.0","#);
+    buf.push_str(r#""$schema":"https://json.schemastore.org/sarif-2.1.0.json","#);
+    buf.push_str(r#""runs":[{"tool":{"driver":{"name":"pomsky","#);
+    buf.push_str(r#""informationUri":"https://pomsky-lang.org","#);
+    write!(buf, r#""version":"{}"}}}},"#, env!("CARGO_PKG_VERSION")).unwrap();
+    buf.push_str(r#""columnKind":"unicodeCodePoints","results":["#);
+
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        if i > 0 {
+            buf.push(',');
+        }
+        write_result(diagnostic, input, path, &mut buf);
+    }
+
+    buf.push_str("]}]}");
+    buf
+}
+
+fn write_result(diagnostic: &Diagnostic, input: &str, path: Option<&Path>, buf: &mut String) {
+    buf.push('{');
+    // diagnostics without a code are still valid results, they just can't be
+    // associated with a rule
+    if let Some(code) = &diagnostic.code {
+        write!(buf, r#""ruleId":{},"#, double_quoted(code)).unwrap();
+    }
+    let level = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    write!(buf, r#""level":"{level}","#).unwrap();
+
+    let mut msg = diagnostic.msg.clone();
+    if let Some(help) = &diagnostic.help {
+        write!(msg, "\nhelp: {help}").unwrap();
+    }
+    write!(buf, r#""message":{{"text":{}}}"#, double_quoted(&msg)).unwrap();
+
+    if let Some(range) = diagnostic.span.range() {
+        let (start_line, start_column) = line_and_column(input, range.start);
+        let (end_line, end_column) = line_and_column(input, range.end);
+
+        buf.push_str(r#","locations":[{"physicalLocation":{"#);
+        if let Some(path) = path {
+            write!(buf, r#""artifactLocation":{{"uri":{}}},"#, double_quoted(&to_uri(path)))
+                .unwrap();
+        }
+        write!(
+            buf,
+            r#""region":{{"startLine":{start_line},"startColumn":{start_column},"endLine":{end_line},"endColumn":{end_column}}}}}}}]"#
+        )
+        .unwrap();
+    }
+    buf.push('}');
+}
+
+/// Returns the 1-based line and column of a byte offset in the input. The
+/// column is counted in code points.
+fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// Converts a file path to a relative URI reference. Backslashes are
+/// replaced with slashes, and characters that aren't allowed in a URI are
+/// percent-encoded.
+fn to_uri(path: &Path) -> String {
+    let mut uri = String::new();
+    for b in path.to_string_lossy().replace('\\', "/").bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~:".contains(&b) {
+            uri.push(b as char);
+        } else {
+            write!(uri, "%{b:02X}").unwrap();
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pomsky::{error::Diagnostic, options::ParseOptions, Expr};
+
+    use super::to_sarif;
+
+    #[test]
+    fn unknown_class() {
+        let input = "'a'\n  [foo]";
+        let err = Expr::parse(input, ParseOptions::default()).unwrap_err();
+        let diagnostics = Diagnostic::from_parse_errors(err, input);
+
+        let sarif = to_sarif(&diagnostics, input, Some(Path::new("dir/my file.pom")));
+        assert!(sarif.contains(r#""ruleId":"P0305""#), "missing rule ID: {sarif}");
+        assert_eq!(
+            sarif,
+            format!(
+                concat!(
+                    r#"{{"version":"2.1.0","$schema":"https://json.schemastore.org/sarif-2.1.0.json","#,
+                    r#""runs":[{{"tool":{{"driver":{{"name":"pomsky","informationUri":"https://pomsky-lang.org","version":"{}"}}}},"#,
+                    r#""columnKind":"unicodeCodePoints","results":[{{"ruleId":"P0305","level":"error","#,
+                    r#""message":{{"text":"Unknown character class `foo`"}},"#,
+                    r#""locations":[{{"physicalLocation":{{"artifactLocation":{{"uri":"dir/my%20file.pom"}},"#,
+                    r#""region":{{"startLine":2,"startColumn":4,"endLine":2,"endColumn":7}}}}}}]}}]}}]}}"#,
+                ),
+                env!("CARGO_PKG_VERSION"),
+            )
+        );
+    }
+}
@@ -0,0 +1,38 @@
+#![no_main]
+use std::ops::Range;
+
+use libfuzzer_sys::fuzz_target;
+
+use pomsky::{ast::Node, options::ParseOptions, Expr};
+
+fuzz_target!(|data: (&str, ParseOptions)| {
+    let (input, parse_options) = data;
+    match Expr::parse(input, parse_options) {
+        Ok((expr, warnings)) => {
+            for warning in &warnings {
+                check_span(input, warning.span.range());
+            }
+            check_node(input, expr.root());
+        }
+        Err(err) => check_span(input, err.span().range()),
+    }
+});
+
+/// Panics if the span isn't within the input, or doesn't start and end at a
+/// character boundary
+fn check_span(input: &str, range: Option<Range<usize>>) {
+    if let Some(Range { start, end }) = range {
+        assert!(start <= end && end <= input.len(), "span {start}..{end} is out of bounds");
+        assert!(
+            input.is_char_boundary(start) && input.is_char_boundary(end),
+            "span {start}..{end} isn't at a character boundary"
+        );
+    }
+}
+
+fn check_node(input: &str, node: Node<'_, '_>) {
+    check_span(input, node.span().range());
+    for child in node.children() {
+        check_node(input, child);
+    }
+}
@@ -4,13 +4,16 @@
 use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
-    compile::{CompileResult, CompileState},
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts, Output},
     error::{CompileError, ParseError},
     literal::Literal,
-    options::{CompileOptions, ParseOptions, RegexFlavor},
-    regex::Regex,
+    optimization::OptimizationKind,
+    options::{CompileOptions, OptimizationLevel, ParseOptions, RegexFlavor},
+    regex::{factor_common_prefixes, Regex},
     rule::Rule,
     span::Span,
+    warning::Warning,
 };
 
 /// An [alternation](https://www.regular-expressions.info/alternation.html).
@@ -22,7 +25,7 @@ use crate::{
 /// `'a' | ('b' 'c')` compiles to `a|bc`.
 #[derive(Clone)]
 pub(crate) struct Alternation<'i> {
-    rules: Vec<Rule<'i>>,
+    pub(crate) rules: Vec<Rule<'i>>,
     pub(crate) span: Span,
 }
 
@@ -49,6 +52,10 @@ impl<'i> Alternation<'i> {
             .unwrap_or_else(|| Rule::Literal(Literal::new(Cow::Borrowed(""), Span::default())))
     }
 
+    pub(crate) fn into_rules(self) -> Vec<Rule<'i>> {
+        self.rules
+    }
+
     pub(crate) fn get_capturing_groups(
         &self,
         count: &mut u32,
@@ -61,18 +68,56 @@ impl<'i> Alternation<'i> {
         Ok(())
     }
 
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        counts.alternation_branches += self.rules.len() as u64;
+        for rule in &self.rules {
+            rule.count_nodes(counts);
+        }
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        let optional = optional || self.rules.len() > 1;
+        for rule in &self.rules {
+            rule.capture_info(info, optional);
+        }
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        for rule in &self.rules {
+            rule.lint(options, warnings, in_repetition);
+        }
+    }
+
     pub(crate) fn compile<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
-        Ok(Regex::Alternation(RegexAlternation {
-            parts: self
-                .rules
-                .iter()
-                .map(|rule| rule.comp(options, state))
-                .collect::<Result<_, _>>()?,
-        }))
+        let mut parts = self
+            .rules
+            .iter()
+            .map(|rule| rule.comp(options, state))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if options.optimization_level == OptimizationLevel::Full
+            && !matches!(options.flavor, RegexFlavor::Sed { .. } | RegexFlavor::Lua)
+        {
+            let len = parts.len();
+            parts = factor_common_prefixes(parts, options.flavor);
+            if parts.len() < len {
+                state.optimizations.record(OptimizationKind::FactoredPrefix, self.span);
+            }
+            if parts.len() == 1 {
+                return Ok(parts.pop().unwrap());
+            }
+        }
+
+        Ok(Regex::Alternation(RegexAlternation { parts }))
     }
 
     pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
@@ -97,7 +142,7 @@ impl core::fmt::Debug for Alternation<'_> {
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct RegexAlternation<'i> {
-    parts: Vec<Regex<'i>>,
+    pub(crate) parts: Vec<Regex<'i>>,
 }
 
 impl<'i> RegexAlternation<'i> {
@@ -105,11 +150,14 @@ impl<'i> RegexAlternation<'i> {
         Self { parts }
     }
 
-    pub(crate) fn codegen(&self, buf: &mut String, flavor: RegexFlavor) {
-        for rule in &self.parts {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        let separator = if let RegexFlavor::Sed { extended: false } = flavor { "\\|" } else { "|" };
+
+        for (i, rule) in self.parts.iter().enumerate() {
+            if i > 0 {
+                buf.push_str(separator);
+            }
             rule.codegen(buf, flavor);
-            buf.push('|');
         }
-        let _ = buf.pop();
     }
 }
@@ -0,0 +1,401 @@
+//! A read-only view of a parsed expression, returned by
+//! [`Expr::root`](crate::Expr::root). It can be used by tools such as editors
+//! to inspect an expression without compiling it.
+//!
+//! The types in this module don't expose the internal representation of the
+//! syntax tree, which may change between versions. New kinds of nodes can be
+//! added in minor versions, so [`NodeKind`] is `#[non_exhaustive]`.
+//!
+//! ```
+//! use pomsky::{ast::NodeKind, Expr};
+//!
+//! let (expr, _) = Expr::parse("'a' :x([w]+) | 'b'", Default::default()).unwrap();
+//! let root = expr.root();
+//! assert_eq!(root.kind(), NodeKind::Alternation);
+//!
+//! let first = root.children()[0];
+//! assert_eq!(first.kind(), NodeKind::Sequence);
+//! assert_eq!(first.children()[0].kind(), NodeKind::Literal("a"));
+//! assert_eq!(first.children()[1].kind(), NodeKind::CapturingGroup { name: Some("x"), index: 1 });
+//! assert_eq!(first.children()[1].span().range(), Some(4..12));
+//! ```
+
+use crate::{
+    lookaround::LookaroundKind, repetition::RepetitionKind, rule::Rule, sexpr, span::Span,
+    stmt::Stmt,
+};
+
+/// A node in a parsed expression. It borrows the [`Expr`](crate::Expr) it
+/// belongs to.
+#[derive(Clone, Copy)]
+pub struct Node<'a, 'i> {
+    rule: &'a Rule<'i>,
+    /// The index of the first capturing group in this node
+    first_index: u32,
+}
+
+/// The kind of a [`Node`]. The children of the node are returned by
+/// [`Node::children`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NodeKind<'a> {
+    /// A string literal, e.g. `'abc'`
+    Literal(&'a str),
+    /// A character class, e.g. `['a'-'z']` or `![w]`. Its contents can be
+    /// inspected with [`Node::to_sexpr`].
+    CharClass {
+        /// Whether the class is negated
+        negative: bool,
+    },
+    /// The built-in variable `Grapheme`, which matches a single grapheme
+    Grapheme,
+    /// The built-in variable `LineBreak`, which matches any line break
+    LineBreak,
+    /// A sequence of expressions, or an expression in parentheses without a
+    /// name. Its children are the parts of the sequence.
+    Sequence,
+    /// A capturing group, e.g. `:name(...)` or `:(...)`. Its child is the
+    /// content of the group.
+    CapturingGroup {
+        /// The name of the group, if it has one
+        name: Option<&'a str>,
+        /// The index of the group, as returned by
+        /// [`Expr::capture_groups`](crate::Expr::capture_groups). The first
+        /// group has index 1.
+        index: u32,
+    },
+    /// An alternation, e.g. `'a' | 'b'`. Its children are the alternatives.
+    Alternation,
+    /// A repetition, e.g. `'a'{2,5}`. Its child is the repeated expression.
+    Repetition {
+        /// The minimum number of repetitions
+        min: u32,
+        /// The maximum number of repetitions, or `None` if it is unbounded
+        max: Option<u32>,
+    },
+    /// A boundary, e.g. `Start` or `%`
+    Boundary,
+    /// A lookahead or lookbehind, e.g. `(>> 'a')`. Its child is the content.
+    Lookaround {
+        /// Whether this is a lookbehind
+        behind: bool,
+        /// Whether this is a negative lookaround
+        negative: bool,
+    },
+    /// A variable, e.g. `x`. Built-in variables such as `Start` are also
+    /// variables.
+    Variable(&'a str),
+    /// A reference to a capturing group, e.g. `::name`
+    Reference(ReferenceTarget<'a>),
+    /// A range of numbers, e.g. `range '0'-'255'`
+    Range,
+    /// A `let` statement. Its children are the value of the variable and the
+    /// expression after the statement.
+    Let {
+        /// The name of the variable
+        name: &'a str,
+    },
+    /// A `define` block. Its children are the values of the variables,
+    /// followed by the expression after the block. Each variable is compiled
+    /// to a named capturing group, starting at `first_index`.
+    Define {
+        /// The index of the group of the first variable
+        first_index: u32,
+    },
+    /// An `enable` or `disable` statement. Its child is the expression after
+    /// the statement.
+    Setting,
+    /// An expression matched as a whole word, `word(...)`
+    Word,
+    /// A branch reset group, `reset(...)`. Its children are the branches,
+    /// which all start with the same group index.
+    BranchReset,
+    /// Text enclosed in balanced delimiters, `balanced(...)`
+    Balanced,
+    /// Any of a list of strings, `oneOf(...)`
+    OneOf,
+    /// Capturing groups intended to be repeated, `captureEach(...)`
+    CaptureEach,
+    /// An atomic group, `atomic(...)`
+    Atomic,
+    /// A conditional, e.g. `if ::1 { 'a' } else { 'b' }`. Its children are
+    /// the two branches; the `else` branch is optional.
+    Conditional(ReferenceTarget<'a>),
+    /// A block that enables or disables flags, e.g.
+    /// `enable case_insensitive { 'a' }`. Its child is the content of the
+    /// block. The flags can be inspected with [`Node::to_sexpr`].
+    Flags {
+        /// Whether the flags are enabled rather than disabled
+        enable: bool,
+    },
+}
+
+/// The capturing group a [`NodeKind::Reference`] or [`NodeKind::Conditional`]
+/// refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceTarget<'a> {
+    /// A named group, e.g. `::name`
+    Named(&'a str),
+    /// A group with this index, e.g. `::3`
+    Number(u32),
+    /// A group relative to the reference, e.g. `::-1` for the previous group
+    Relative(i32),
+}
+
+impl<'a, 'i> Node<'a, 'i> {
+    pub(crate) fn root(rule: &'a Rule<'i>) -> Self {
+        Node { rule, first_index: 1 }
+    }
+
+    /// Returns the kind of this node
+    pub fn kind(&self) -> NodeKind<'a> {
+        match self.rule {
+            Rule::Literal(l) => NodeKind::Literal(&l.content),
+            Rule::CharClass(c) => NodeKind::CharClass { negative: c.negative },
+            Rule::Grapheme(_) => NodeKind::Grapheme,
+            Rule::LineBreak(_) => NodeKind::LineBreak,
+            Rule::Group(g) => match g.capture {
+                Some(capture) => {
+                    NodeKind::CapturingGroup { name: capture.name, index: self.first_index }
+                }
+                None => NodeKind::Sequence,
+            },
+            Rule::Alternation(_) => NodeKind::Alternation,
+            Rule::Repetition(r) => {
+                let RepetitionKind { lower_bound, upper_bound } = r.kind;
+                NodeKind::Repetition { min: lower_bound, max: upper_bound }
+            }
+            Rule::Boundary(_) => NodeKind::Boundary,
+            Rule::Lookaround(l) => NodeKind::Lookaround {
+                behind: matches!(l.kind, LookaroundKind::Behind | LookaroundKind::BehindNegative),
+                negative: matches!(
+                    l.kind,
+                    LookaroundKind::AheadNegative | LookaroundKind::BehindNegative
+                ),
+            },
+            Rule::Variable(v) => NodeKind::Variable(v.name),
+            Rule::Reference(r) => NodeKind::Reference(r.target.into()),
+            Rule::Range(_) => NodeKind::Range,
+            Rule::StmtExpr(s) => match &s.stmt {
+                Stmt::Let(l) => NodeKind::Let { name: l.name() },
+                Stmt::Define(_) => NodeKind::Define { first_index: self.first_index },
+                Stmt::Enable(_) | Stmt::Disable(_) => NodeKind::Setting,
+            },
+            Rule::Word(_) => NodeKind::Word,
+            Rule::BranchReset(_) => NodeKind::BranchReset,
+            Rule::Balanced(_) => NodeKind::Balanced,
+            Rule::OneOf(_) => NodeKind::OneOf,
+            Rule::CaptureEach(_) => NodeKind::CaptureEach,
+            Rule::Atomic(_) => NodeKind::Atomic,
+            Rule::Conditional(c) => NodeKind::Conditional(c.condition.target.into()),
+            Rule::Flags(f) => NodeKind::Flags { enable: f.enable },
+        }
+    }
+
+    /// Returns the span of this node in the source code. Built-in variables
+    /// and nodes created with [`Expr::with_binding`](crate::Expr::with_binding)
+    /// may have an empty span.
+    pub fn span(&self) -> Span {
+        self.rule.span()
+    }
+
+    /// Returns the children of this node, in the order in which they appear
+    /// in the source code
+    pub fn children(&self) -> Vec<Node<'a, 'i>> {
+        let mut next = self.first_index + own_groups(self.rule);
+        let mut sequential = |rule: &'a Rule<'i>| {
+            let node = Node { rule, first_index: next };
+            next += group_count(rule);
+            node
+        };
+
+        match self.rule {
+            Rule::Group(g) => g.parts.iter().map(sequential).collect(),
+            Rule::Alternation(a) => a.rules.iter().map(sequential).collect(),
+            Rule::BranchReset(b) => {
+                b.branches.iter().map(|rule| Node { rule, first_index: self.first_index }).collect()
+            }
+            Rule::Repetition(r) => vec![sequential(&r.rule)],
+            Rule::Lookaround(l) => vec![sequential(&l.rule)],
+            Rule::Word(w) => vec![sequential(&w.rule)],
+            Rule::CaptureEach(c) => vec![sequential(&c.rule)],
+            Rule::Atomic(a) => vec![sequential(&a.rule)],
+            Rule::Flags(f) => vec![sequential(&f.rule)],
+            Rule::Conditional(c) => {
+                std::iter::once(&c.then).chain(&c.otherwise).map(sequential).collect()
+            }
+            Rule::StmtExpr(s) => match &s.stmt {
+                Stmt::Let(l) => vec![sequential(&l.rule), sequential(&s.rule)],
+                Stmt::Define(lets) => {
+                    lets.iter().map(|l| &l.rule).chain([&s.rule]).map(sequential).collect()
+                }
+                Stmt::Enable(_) | Stmt::Disable(_) => vec![sequential(&s.rule)],
+            },
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Boundary(_)
+            | Rule::Variable(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_) => vec![],
+        }
+    }
+
+    /// Formats this node as an S-expression, like
+    /// [`Expr::to_sexpr`](crate::Expr::to_sexpr)
+    pub fn to_sexpr(&self) -> String {
+        let mut buf = String::new();
+        sexpr::write_rule(self.rule, &mut buf);
+        buf
+    }
+}
+
+impl<'a> From<crate::reference::ReferenceTarget<'a>> for ReferenceTarget<'a> {
+    fn from(target: crate::reference::ReferenceTarget<'a>) -> Self {
+        use crate::reference::ReferenceTarget as Target;
+
+        match target {
+            Target::Named(name) => ReferenceTarget::Named(name),
+            Target::Number(n) => ReferenceTarget::Number(n),
+            Target::Relative(n) => ReferenceTarget::Relative(n),
+        }
+    }
+}
+
+/// Returns the number of groups that belong to the rule itself rather than
+/// its children. The variables in a `define` block are compiled to groups
+/// that come before the groups in their contents.
+fn own_groups(rule: &Rule<'_>) -> u32 {
+    match rule {
+        Rule::Group(g) if g.capture.is_some() => 1,
+        Rule::StmtExpr(s) => match &s.stmt {
+            Stmt::Define(lets) => lets.len() as u32,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Returns the number of group indices used by the rule, which is the number
+/// of indices by which the next group is shifted
+fn group_count(rule: &Rule<'_>) -> u32 {
+    let children = Node { rule, first_index: 0 }.children();
+    let counts = children.iter().map(|child| group_count(child.rule));
+    match rule {
+        // all branches start with the same index
+        Rule::BranchReset(_) => counts.max().unwrap_or(0),
+        _ => own_groups(rule) + counts.sum::<u32>(),
+    }
+}
+
+/// Serializes the node as an object with the fields `kind`, `span` and
+/// `children`, plus fields specific to the kind: `value` for literals, `name`,
+/// `number` or `relative` for references and conditionals, and the fields of
+/// the [`NodeKind`] variant otherwise, e.g. `min` and `max` for repetitions.
+/// The `kind` is the variant name in snake case, e.g. `"capturing_group"`.
+///
+/// The `value` of a literal is its text as written in the expression, without
+/// quotes; it isn't escaped for any regex flavor. Like in diagnostics, the
+/// span is an object with the byte offsets `start` and `end`, and it is
+/// omitted if it is empty.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Node<'_, '_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        fn serialize_target<S: SerializeStruct>(
+            s: &mut S,
+            target: ReferenceTarget<'_>,
+        ) -> Result<(), S::Error> {
+            match target {
+                ReferenceTarget::Named(name) => s.serialize_field("name", name),
+                ReferenceTarget::Number(n) => s.serialize_field("number", &n),
+                ReferenceTarget::Relative(n) => s.serialize_field("relative", &n),
+            }
+        }
+
+        let kind = self.kind();
+        let mut s = serializer.serialize_struct("Node", 5)?;
+        s.serialize_field("kind", kind_name(kind))?;
+        match kind {
+            NodeKind::Literal(value) => s.serialize_field("value", value)?,
+            NodeKind::CharClass { negative } => s.serialize_field("negative", &negative)?,
+            NodeKind::CapturingGroup { name, index } => {
+                match name {
+                    Some(name) => s.serialize_field("name", name)?,
+                    None => s.skip_field("name")?,
+                }
+                s.serialize_field("index", &index)?;
+            }
+            NodeKind::Repetition { min, max } => {
+                s.serialize_field("min", &min)?;
+                s.serialize_field("max", &max)?;
+            }
+            NodeKind::Lookaround { behind, negative } => {
+                s.serialize_field("behind", &behind)?;
+                s.serialize_field("negative", &negative)?;
+            }
+            NodeKind::Variable(name) | NodeKind::Let { name } => {
+                s.serialize_field("name", name)?;
+            }
+            NodeKind::Reference(target) | NodeKind::Conditional(target) => {
+                serialize_target(&mut s, target)?;
+            }
+            NodeKind::Define { first_index } => s.serialize_field("first_index", &first_index)?,
+            NodeKind::Flags { enable } => s.serialize_field("enable", &enable)?,
+            NodeKind::Grapheme
+            | NodeKind::LineBreak
+            | NodeKind::Sequence
+            | NodeKind::Alternation
+            | NodeKind::Boundary
+            | NodeKind::Range
+            | NodeKind::Setting
+            | NodeKind::Word
+            | NodeKind::BranchReset
+            | NodeKind::Balanced
+            | NodeKind::OneOf
+            | NodeKind::CaptureEach
+            | NodeKind::Atomic => {}
+        }
+        match self.span().range() {
+            Some(_) => s.serialize_field("span", &self.span())?,
+            None => s.skip_field("span")?,
+        }
+        s.serialize_field("children", &self.children())?;
+        s.end()
+    }
+}
+
+/// Returns the name of the kind used when serializing a [`Node`]
+#[cfg(feature = "serde")]
+fn kind_name(kind: NodeKind<'_>) -> &'static str {
+    match kind {
+        NodeKind::Literal(_) => "literal",
+        NodeKind::CharClass { .. } => "char_class",
+        NodeKind::Grapheme => "grapheme",
+        NodeKind::LineBreak => "line_break",
+        NodeKind::Sequence => "sequence",
+        NodeKind::CapturingGroup { .. } => "capturing_group",
+        NodeKind::Alternation => "alternation",
+        NodeKind::Repetition { .. } => "repetition",
+        NodeKind::Boundary => "boundary",
+        NodeKind::Lookaround { .. } => "lookaround",
+        NodeKind::Variable(_) => "variable",
+        NodeKind::Reference(_) => "reference",
+        NodeKind::Range => "range",
+        NodeKind::Let { .. } => "let",
+        NodeKind::Define { .. } => "define",
+        NodeKind::Setting => "setting",
+        NodeKind::Word => "word",
+        NodeKind::BranchReset => "branch_reset",
+        NodeKind::Balanced => "balanced",
+        NodeKind::OneOf => "one_of",
+        NodeKind::CaptureEach => "capture_each",
+        NodeKind::Atomic => "atomic",
+        NodeKind::Conditional(_) => "conditional",
+        NodeKind::Flags { .. } => "flags",
+    }
+}
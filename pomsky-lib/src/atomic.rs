@@ -0,0 +1,91 @@
+//! Implements atomic groups, `atomic(...)`, which don't backtrack into their
+//! contents once they have matched.
+
+use std::collections::HashMap;
+
+use crate::{
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
+    group::{RegexCapture, RegexGroup},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
+    regex::Regex,
+    rule::Rule,
+    span::Span,
+    warning::Warning,
+};
+
+/// An atomic group, `atomic(...)`. It is compiled to `(?>...)`.
+///
+/// Once the contents have matched, the regex engine doesn't try other ways
+/// to match them, even if the rest of the expression fails. For example,
+/// `atomic('a'+) 'a'` never matches, because `'a'+` consumes every `a`.
+/// This is only supported by PCRE, Java, Ruby and .NET.
+#[derive(Clone)]
+pub(crate) struct Atomic<'i> {
+    pub(crate) rule: Rule<'i>,
+    pub(crate) span: Span,
+}
+
+impl<'i> Atomic<'i> {
+    pub(crate) fn new(rule: Rule<'i>, span: Span) -> Self {
+        Atomic { rule, span }
+    }
+
+    pub(crate) fn get_capturing_groups(
+        &self,
+        count: &mut u32,
+        map: &mut HashMap<String, u32>,
+        within_variable: bool,
+    ) -> Result<(), CompileError> {
+        self.rule.get_capturing_groups(count, map, within_variable)
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        self.rule.capture_info(info, optional);
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        self.rule.lint(options, warnings, in_repetition);
+    }
+
+    pub(crate) fn compile<'c>(
+        &'c self,
+        options: &CompileOptions,
+        state: &mut CompileState<'c, 'i>,
+    ) -> CompileResult<'i> {
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
+        let content = self.rule.comp(options, state)?;
+        Ok(Regex::Group(RegexGroup::new(vec![content], RegexCapture::Atomic)))
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Ruby | RegexFlavor::DotNet => None,
+            _ => Some(Feature::AtomicGroups),
+        }
+    }
+
+    pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
+        self.rule.validate(options)
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for Atomic<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Atomic").field(&self.rule).finish()
+    }
+}
@@ -0,0 +1,113 @@
+//! Implements `balanced(...)`, which matches text enclosed in balanced
+//! delimiters, e.g. `balanced('(', ')')` matches `(a(b)c)`, but not `(a(b)c`.
+
+use crate::{
+    alternation::RegexAlternation,
+    char_class::{RegexCharClass, RegexClassItem},
+    compile::{CompileResult, CompileState},
+    error::{CompileErrorKind, Feature},
+    group::{RegexCapture, RegexGroup},
+    options::{CompileOptions, RegexFlavor},
+    regex::Regex,
+    repetition::{RegexQuantifier, RegexRepetition, RepetitionKind},
+    span::Span,
+};
+
+/// A `balanced(open, close)` expression. The delimiters are single code
+/// points, which must be different.
+///
+/// Matching balanced delimiters requires recursion, so the pattern is compiled
+/// to a capturing group that calls itself, e.g. `(\((?:[^()]|(?2))*\))` for
+/// `balanced('(', ')')`. This group is added after all other groups, so it
+/// doesn't change the numbers of the groups written by the user; the
+/// expression itself compiles to a call of the group, e.g. `(?2)`. Recursion
+/// is only supported by PCRE and Ruby.
+#[derive(Clone)]
+pub(crate) struct Balanced {
+    pub(crate) open: char,
+    pub(crate) close: char,
+    pub(crate) span: Span,
+}
+
+impl Balanced {
+    pub(crate) fn new(open: char, close: char, span: Span) -> Self {
+        Balanced { open, close, span }
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Pcre | RegexFlavor::Ruby => None,
+            _ => Some(Feature::Recursion),
+        }
+    }
+
+    pub(crate) fn compile(
+        &self,
+        options: &CompileOptions,
+        state: &mut CompileState<'_, '_>,
+    ) -> CompileResult<'static> {
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
+        let delimiters = (self.open, self.close);
+        let index = match state.balanced.iter().position(|&d| d == delimiters) {
+            Some(index) => index,
+            None => {
+                state.balanced.push(delimiters);
+                state.balanced.len() - 1
+            }
+        };
+        Ok(Regex::Recursion(state.groups_count + index as u32 + 1))
+    }
+}
+
+/// Returns the groups called by the `balanced(...)` expressions, which must
+/// be added after all other groups. In PCRE, they're wrapped in a `DEFINE`
+/// group; Ruby doesn't support this, so each group is repeated zero times
+/// instead, e.g. `(?:(\((?:[^()]|\g<1>)*\))){0}`.
+pub(crate) fn definitions(
+    flavor: RegexFlavor,
+    state: &CompileState<'_, '_>,
+) -> Vec<Regex<'static>> {
+    let groups =
+        state.balanced.iter().zip(state.groups_count + 1..).map(|(&(open, close), number)| {
+            let mut others =
+                RegexCharClass::new(vec![RegexClassItem::Char(open), RegexClassItem::Char(close)]);
+            others.negative = true;
+
+            let content = Regex::Alternation(RegexAlternation::new(vec![
+                Regex::CharClass(others),
+                Regex::Recursion(number),
+            ]));
+            let repetition =
+                RegexRepetition::new(content, RepetitionKind::zero_inf(), RegexQuantifier::Greedy);
+            Regex::Group(RegexGroup::new(
+                vec![
+                    Regex::Char(open),
+                    Regex::Repetition(Box::new(repetition)),
+                    Regex::Char(close),
+                ],
+                RegexCapture::Capture,
+            ))
+        });
+
+    if flavor == RegexFlavor::Pcre {
+        vec![Regex::Group(RegexGroup::new(groups.collect(), RegexCapture::Define))]
+    } else {
+        groups
+            .map(|group| {
+                let repetition =
+                    RegexRepetition::new(group, RepetitionKind::fixed(0), RegexQuantifier::Greedy);
+                Regex::Repetition(Box::new(repetition))
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for Balanced {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Balanced({:?}, {:?})", self.open, self.close)
+    }
+}
@@ -3,10 +3,15 @@
 //! [anchors](https://www.regular-expressions.info/anchors.html).
 
 use crate::{
-    compile::CompileResult,
-    error::{ParseError, ParseErrorKind},
+    alternation::RegexAlternation,
+    char_class::{word_char, CharClass, CharGroup},
+    compile::{CompileResult, Output},
+    error::{CompileErrorKind, Feature, ParseError, ParseErrorKind},
     features::PomskyFeatures,
-    options::ParseOptions,
+    group::{RegexCapture, RegexGroup},
+    lookaround::{LookaroundKind, RegexLookaround},
+    optimization::OptimizationLog,
+    options::{CompileOptions, ParseOptions, RegexFlavor},
     regex::Regex,
     span::Span,
 };
@@ -34,7 +39,12 @@ impl Boundary {
 
     pub(crate) fn negate(&mut self) -> Result<(), ParseErrorKind> {
         match self.kind {
-            BoundaryKind::Start | BoundaryKind::End => Err(ParseErrorKind::UnallowedNot),
+            BoundaryKind::Start
+            | BoundaryKind::End
+            | BoundaryKind::LineStart
+            | BoundaryKind::LineEnd
+            | BoundaryKind::EndOfText
+            | BoundaryKind::EndOfTextOrBeforeNewline => Err(ParseErrorKind::UnallowedNot),
             BoundaryKind::NotWord => Err(ParseErrorKind::UnallowedDoubleNot),
             BoundaryKind::Word => {
                 self.kind = BoundaryKind::NotWord;
@@ -45,8 +55,46 @@ impl Boundary {
 }
 
 impl Boundary {
-    pub(crate) fn compile(&self) -> CompileResult<'static> {
-        Ok(Regex::Boundary(self.kind))
+    pub(crate) fn compile(&self, options: &CompileOptions) -> CompileResult<'static> {
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+        match self.kind {
+            BoundaryKind::Word | BoundaryKind::NotWord => {
+                word_boundary(self.kind, options, self.span)
+            }
+            BoundaryKind::LineStart | BoundaryKind::LineEnd
+                if options.flavor == RegexFlavor::JavaScript =>
+            {
+                line_anchor_lookaround(self.kind, options)
+            }
+            kind => Ok(Regex::Boundary(kind)),
+        }
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match (self.kind, flavor) {
+            (_, RegexFlavor::Antlr) => Some(Feature::Boundary),
+            (BoundaryKind::Word | BoundaryKind::NotWord, RegexFlavor::Lua) => {
+                Some(Feature::WordBoundary)
+            }
+            (BoundaryKind::LineStart | BoundaryKind::LineEnd, RegexFlavor::Lua) => {
+                Some(Feature::LineAnchors)
+            }
+            (BoundaryKind::EndOfText, RegexFlavor::JavaScript | RegexFlavor::Sed { .. }) => {
+                Some(Feature::EndOfText)
+            }
+            (
+                BoundaryKind::EndOfTextOrBeforeNewline,
+                RegexFlavor::JavaScript
+                | RegexFlavor::Python
+                | RegexFlavor::Rust
+                | RegexFlavor::Re2
+                | RegexFlavor::Sed { .. }
+                | RegexFlavor::Lua,
+            ) => Some(Feature::EndOfTextOrBeforeNewline),
+            _ => None,
+        }
     }
 
     pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
@@ -54,6 +102,74 @@ impl Boundary {
     }
 }
 
+/// Returns the regex for a word boundary (`%`) or the negation of a word
+/// boundary (`!%`). If [`CompileOptions::explicit_word_boundary`] is enabled,
+/// this is an alternation of lookarounds, e.g. `%` becomes
+///
+/// ```text
+/// (?:(?<!\w)(?=\w)|(?<=\w)(?!\w))
+/// ```
+///
+/// This is also correct at the start and end of the string, since a negative
+/// lookaround succeeds there.
+pub(crate) fn word_boundary(
+    kind: BoundaryKind,
+    options: &CompileOptions,
+    span: Span,
+) -> CompileResult<'static> {
+    if options.flavor == RegexFlavor::Antlr {
+        return Err(CompileErrorKind::Unsupported(Feature::Boundary, options.flavor).at(span));
+    }
+    if options.flavor == RegexFlavor::Lua {
+        return Err(CompileErrorKind::Unsupported(Feature::WordBoundary, options.flavor).at(span));
+    }
+    if !options.explicit_word_boundary {
+        return Ok(Regex::Boundary(kind));
+    }
+    if let RegexFlavor::Rust | RegexFlavor::Re2 | RegexFlavor::Sed { .. } = options.flavor {
+        return Err(CompileErrorKind::Unsupported(Feature::Lookaround, options.flavor).at(span));
+    }
+
+    let flavor = options.flavor;
+    let lookaround =
+        |kind| Regex::Lookaround(Box::new(RegexLookaround { content: word_char(flavor), kind }));
+    // the word boundary is between a word character and a non-word character,
+    // the negated word boundary is between two characters of the same kind
+    let (before_word, before_non_word) = match kind {
+        BoundaryKind::Word => (LookaroundKind::BehindNegative, LookaroundKind::Behind),
+        _ => (LookaroundKind::Behind, LookaroundKind::BehindNegative),
+    };
+    let alternatives = vec![
+        Regex::Group(RegexGroup::new(
+            vec![lookaround(before_word), lookaround(LookaroundKind::Ahead)],
+            RegexCapture::None,
+        )),
+        Regex::Group(RegexGroup::new(
+            vec![lookaround(before_non_word), lookaround(LookaroundKind::AheadNegative)],
+            RegexCapture::None,
+        )),
+    ];
+    Ok(Regex::Group(RegexGroup::new(
+        vec![Regex::Alternation(RegexAlternation::new(alternatives))],
+        RegexCapture::NoneWithParens,
+    )))
+}
+
+/// Returns a lookaround that is equivalent to `^` or `$` in multiline mode.
+/// This is used for JavaScript, which doesn't support inline flags. `LineStart`
+/// becomes `(?<![^\n\r\u2028\u2029])`, i.e. it isn't preceded by a character
+/// that isn't a line terminator.
+fn line_anchor_lookaround(kind: BoundaryKind, options: &CompileOptions) -> CompileResult<'static> {
+    let mut class = CharClass::new(CharGroup::from_chars("\n\r\u{2028}\u{2029}"), Span::empty());
+    class.negative = true;
+    let content = class.compile(options, &mut OptimizationLog::default())?;
+    let kind = match kind {
+        BoundaryKind::LineStart => LookaroundKind::BehindNegative,
+        _ => LookaroundKind::AheadNegative,
+    };
+    Ok(Regex::Lookaround(Box::new(RegexLookaround { content, kind })))
+}
+
 #[cfg(feature = "dbg")]
 impl core::fmt::Debug for Boundary {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -62,6 +178,10 @@ impl core::fmt::Debug for Boundary {
             BoundaryKind::Word => write!(f, "%"),
             BoundaryKind::NotWord => write!(f, "!%"),
             BoundaryKind::End => write!(f, "End"),
+            BoundaryKind::LineStart => write!(f, "LineStart"),
+            BoundaryKind::LineEnd => write!(f, "LineEnd"),
+            BoundaryKind::EndOfText => write!(f, "EndOfText"),
+            BoundaryKind::EndOfTextOrBeforeNewline => write!(f, "EndOfTextOrBeforeNewline"),
         }
     }
 }
@@ -69,23 +189,65 @@ impl core::fmt::Debug for Boundary {
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) enum BoundaryKind {
-    /// `Start`, the start of the string (or start of line in single-line mode)
+    /// `Start`, the start of the string. It is compiled to `\A` where
+    /// supported, so it doesn't match at the start of a line in multiline mode.
     Start,
     /// `%`, a word boundary
     Word,
     /// `!%`, not a word boundary
     NotWord,
-    /// `End`, the end of the string (or end of line in single-line mode)
+    /// `End`, the end of the string. It is compiled to `\z` where supported,
+    /// so it doesn't match at the end of a line in multiline mode.
     End,
+    /// `LineStart`, the start of the string or of a line
+    LineStart,
+    /// `LineEnd`, the end of the string or of a line
+    LineEnd,
+    /// `EndOfText`, the absolute end of the string, even in multiline mode.
+    /// Unlike `End`, it never matches before a final newline.
+    EndOfText,
+    /// `EndOfTextOrBeforeNewline`, the end of the string or the position
+    /// before a newline at the end of the string
+    EndOfTextOrBeforeNewline,
 }
 
 impl BoundaryKind {
-    pub(crate) fn codegen(&self, buf: &mut String) {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
         match self {
+            BoundaryKind::Start if has_string_anchors(flavor) => buf.push_str("\\A"),
             BoundaryKind::Start => buf.push('^'),
             BoundaryKind::Word => buf.push_str("\\b"),
             BoundaryKind::NotWord => buf.push_str("\\B"),
-            BoundaryKind::End => buf.push('$'),
+            BoundaryKind::End if !has_string_anchors(flavor) => buf.push('$'),
+            // in Ruby, `^` and `$` always match at line breaks
+            BoundaryKind::LineStart if flavor == RegexFlavor::Ruby => buf.push('^'),
+            BoundaryKind::LineEnd if flavor == RegexFlavor::Ruby => buf.push('$'),
+            BoundaryKind::LineStart if has_string_anchors(flavor) => buf.push_str("(?m:^)"),
+            BoundaryKind::LineEnd if has_string_anchors(flavor) => buf.push_str("(?m:$)"),
+            // `sed` matches each line separately
+            BoundaryKind::LineStart => buf.push('^'),
+            BoundaryKind::LineEnd => buf.push('$'),
+            // in Python, `\Z` is the absolute end of the string
+            BoundaryKind::End | BoundaryKind::EndOfText if flavor == RegexFlavor::Python => {
+                buf.push_str("\\Z")
+            }
+            BoundaryKind::End | BoundaryKind::EndOfText => buf.push_str("\\z"),
+            BoundaryKind::EndOfTextOrBeforeNewline => buf.push_str("\\Z"),
         }
     }
 }
+
+/// Returns whether the flavor supports `\A` and `\z` (`\Z` in Python). Otherwise,
+/// `Start` and `End` are compiled to `^` and `$`.
+fn has_string_anchors(flavor: RegexFlavor) -> bool {
+    matches!(
+        flavor,
+        RegexFlavor::Pcre
+            | RegexFlavor::Python
+            | RegexFlavor::Java
+            | RegexFlavor::DotNet
+            | RegexFlavor::Ruby
+            | RegexFlavor::Rust
+            | RegexFlavor::Re2
+    )
+}
@@ -0,0 +1,124 @@
+//! Contains a cache for compiled expressions. This module is only available
+//! with the `cache` feature.
+
+use std::collections::HashMap;
+
+use crate::{
+    error::CompileError,
+    options::{CompileOptions, ParseOptions},
+    warning::Warning,
+    Expr,
+};
+
+/// The output of compiling an expression: The regex and the warnings, or an
+/// error
+pub type CompileOutput = Result<(String, Vec<Warning>), CompileError>;
+
+/// Compiles expressions and caches the results, so compiling the same source
+/// code with the same options again doesn't parse or compile it again. This is
+/// useful for servers that compile the same patterns many times.
+///
+/// When the cache is full, the least recently used entry is removed. Errors
+/// are cached as well.
+///
+/// ```
+/// use pomsky::{cache::CachedCompiler, options::CompileOptions};
+///
+/// let mut compiler = CachedCompiler::new(16, Default::default());
+/// let first = compiler.compile("'a'+", &CompileOptions::default()).unwrap();
+/// let second = compiler.compile("'a'+", &CompileOptions::default()).unwrap();
+/// assert_eq!(first.0, "a+");
+/// assert_eq!(second.0, "a+");
+/// assert_eq!((compiler.hits(), compiler.misses()), (1, 1));
+/// ```
+#[derive(Clone)]
+pub struct CachedCompiler {
+    parse_options: ParseOptions,
+    capacity: usize,
+    entries: HashMap<(String, CompileOptions), Entry>,
+    time: u64,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Clone)]
+struct Entry {
+    output: CompileOutput,
+    last_used: u64,
+}
+
+impl CachedCompiler {
+    /// Creates an empty cache that holds up to `capacity` entries. If the
+    /// capacity is 0, nothing is cached. The parse options are used for all
+    /// expressions.
+    pub fn new(capacity: usize, parse_options: ParseOptions) -> Self {
+        CachedCompiler {
+            parse_options,
+            capacity,
+            entries: HashMap::new(),
+            time: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Compiles the source code with the given options, or returns the
+    /// cached result if it was compiled before.
+    pub fn compile(&mut self, source: &str, options: &CompileOptions) -> CompileOutput {
+        self.time += 1;
+        let key = (source.to_string(), options.clone());
+        if let Some(entry) = self.entries.get_mut(&key) {
+            self.hits += 1;
+            entry.last_used = self.time;
+            return entry.output.clone();
+        }
+
+        self.misses += 1;
+        let output = Expr::parse_and_compile(source, self.parse_options, options.clone());
+        if self.capacity > 0 {
+            if self.entries.len() >= self.capacity {
+                self.remove_least_recently_used();
+            }
+            self.entries.insert(key, Entry { output: output.clone(), last_used: self.time });
+        }
+        output
+    }
+
+    fn remove_least_recently_used(&mut self) {
+        let oldest = self.entries.iter().min_by_key(|(_, entry)| entry.last_used);
+        if let Some((key, _)) = oldest {
+            let key = key.clone();
+            self.entries.remove(&key);
+        }
+    }
+
+    /// Returns the maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns how often a result was returned from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns how often an expression had to be compiled
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Removes all entries from the cache
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,86 @@
+//! Implements `captureEach(...)`, which marks capturing groups that are
+//! intended to capture every repetition, e.g.
+//! `captureEach(:item([word]+) ','?)+`.
+
+use std::collections::HashMap;
+
+use crate::{
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts},
+    error::{CompileError, ParseError, ParseErrorKind},
+    options::{CompileOptions, ParseOptions},
+    rule::Rule,
+    span::Span,
+    warning::{RepetitionWarning, Warning, WarningKind},
+};
+
+/// An expression wrapped in `captureEach(...)`. It is compiled to the
+/// expression itself, but makes explicit that the capturing groups within are
+/// expected to be repeated.
+///
+/// Only .NET retains all repetitions of a capturing group (in
+/// `Group.Captures`); the other flavors only keep the last one. For these
+/// flavors, a [`RepetitionWarning::CaptureEachLastOnly`] warning is emitted.
+/// The warning is emitted by the linter, which doesn't know the flavor, so it
+/// is filtered out with [`Warning::applies_to`] after the flavor is known.
+#[derive(Clone)]
+pub(crate) struct CaptureEach<'i> {
+    pub(crate) rule: Rule<'i>,
+    pub(crate) span: Span,
+}
+
+impl<'i> CaptureEach<'i> {
+    pub(crate) fn new(rule: Rule<'i>, span: Span) -> Self {
+        CaptureEach { rule, span }
+    }
+
+    pub(crate) fn get_capturing_groups(
+        &self,
+        count: &mut u32,
+        map: &mut HashMap<String, u32>,
+        within_variable: bool,
+    ) -> Result<(), CompileError> {
+        self.rule.get_capturing_groups(count, map, within_variable)
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        self.rule.capture_info(info, optional);
+    }
+
+    /// Emits the flavor-dependent warning. The contents are linted as if they
+    /// weren't repeated, since `captureEach` states that repeating the
+    /// capturing groups is intended.
+    pub(crate) fn lint(&self, options: &ParseOptions, warnings: &mut Vec<Warning>) {
+        warnings
+            .push(WarningKind::Repetition(RepetitionWarning::CaptureEachLastOnly).at(self.span));
+        self.rule.lint(options, warnings, false);
+    }
+
+    pub(crate) fn compile<'c>(
+        &'c self,
+        options: &CompileOptions,
+        state: &mut CompileState<'c, 'i>,
+    ) -> CompileResult<'i> {
+        self.rule.comp(options, state)
+    }
+
+    pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
+        let mut info = CaptureInfo::default();
+        self.rule.capture_info(&mut info, false);
+        if info.groups.is_empty() {
+            return Err(ParseErrorKind::CaptureEachWithoutGroup.at(self.span));
+        }
+        self.rule.validate(options)
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for CaptureEach<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CaptureEach").field(&self.rule).finish()
+    }
+}
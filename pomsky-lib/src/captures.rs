@@ -0,0 +1,52 @@
+//! Contains information about the capturing groups of an expression, which can
+//! be used to generate code that extracts the groups from a match, or to
+//! rename a group in an editor.
+
+use std::ops::Range;
+
+/// A capturing group in a pomsky expression, as returned by
+/// [`Expr::capture_groups`](crate::Expr::capture_groups).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureGroup {
+    /// The index of the group. The first group has index 1.
+    pub index: u32,
+
+    /// The name of the group, if it is a named group.
+    pub name: Option<String>,
+
+    /// Whether the group might not participate in a match, because it is in
+    /// an alternative, an optional repetition or a negative lookaround.
+    pub optional: bool,
+}
+
+/// A place where a named capturing group is defined or referenced, as returned
+/// by [`Expr::capture_occurrences`](crate::Expr::capture_occurrences).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureOccurrence {
+    /// The name of the group
+    pub name: String,
+
+    /// Whether this is the group's definition or a reference to it
+    pub kind: OccurrenceKind,
+
+    /// The span of the name in the source code. For a reference such as
+    /// `::name`, the span doesn't include the `::`.
+    pub span: Range<usize>,
+}
+
+/// The kind of a [`CaptureOccurrence`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OccurrenceKind {
+    /// The definition of a named group, e.g. `:name(...)`
+    Definition,
+    /// A reference to a named group, e.g. `::name`
+    Reference,
+}
+
+/// Collects information about the capturing groups while walking the syntax
+/// tree
+#[derive(Default)]
+pub(crate) struct CaptureInfo {
+    pub(crate) groups: Vec<CaptureGroup>,
+    pub(crate) occurrences: Vec<CaptureOccurrence>,
+}
@@ -1,6 +1,6 @@
 use crate::error::CharClassError;
 
-use super::GroupItem;
+use super::{GroupItem, RegexClassItem};
 
 pub(super) fn parse_ascii_group(
     name: &str,
@@ -62,6 +62,101 @@ pub(super) fn parse_ascii_group(
     })
 }
 
+/// Returns the name of the ASCII class that contains exactly the same
+/// characters as the items, if there is one
+pub(super) fn find_equivalent_group(items: &[GroupItem]) -> Option<&'static str> {
+    let ranges = merged_ranges(items)?;
+    OPTION_LIST.iter().copied().find(|name| {
+        let group = parse_ascii_group(name, false).ok();
+        group.and_then(|group| merged_ranges(&group)).as_ref() == Some(&ranges)
+    })
+}
+
+/// Sorts the chars and ranges and merges them where they overlap or are
+/// adjacent. Returns `None` if there are named items.
+fn merged_ranges(items: &[GroupItem]) -> Option<Vec<(char, char)>> {
+    let mut ranges = items
+        .iter()
+        .map(|item| match *item {
+            GroupItem::Char(c) => Some((c, c)),
+            GroupItem::Range { first, last } => Some((first, last)),
+            GroupItem::Named { .. } => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(char, char)> = Vec::with_capacity(ranges.len());
+    for (first, last) in ranges {
+        match merged.last_mut() {
+            Some((_, prev_last)) if *prev_last as u32 + 1 >= first as u32 => {
+                *prev_last = last.max(*prev_last);
+            }
+            _ => merged.push((first, last)),
+        }
+    }
+    Some(merged)
+}
+
+/// The POSIX classes supported by PCRE, Rust and RE2, with the ASCII class
+/// containing the same characters. Classes come before their subsets, so the
+/// largest matching class is used.
+const POSIX_CLASSES: &[(&str, &str)] = &[
+    ("ascii", "ascii"),
+    ("print", "ascii_print"),
+    ("graph", "ascii_graph"),
+    ("word", "ascii_word"),
+    ("alnum", "ascii_alnum"),
+    ("alpha", "ascii_alpha"),
+    ("xdigit", "ascii_xdigit"),
+    ("punct", "ascii_punct"),
+    ("cntrl", "ascii_cntrl"),
+    ("space", "ascii_space"),
+    ("blank", "ascii_blank"),
+    ("upper", "ascii_upper"),
+    ("lower", "ascii_lower"),
+    ("digit", "ascii_digit"),
+];
+
+/// Finds the POSIX classes whose chars and ranges all appear in `items`.
+/// Returns their names and the remaining items.
+pub(super) fn extract_posix_classes(
+    items: &[RegexClassItem],
+) -> (Vec<&'static str>, Vec<RegexClassItem>) {
+    let mut rest = items.to_vec();
+    let mut names = vec![];
+
+    for &(posix_name, ascii_name) in POSIX_CLASSES {
+        let group = match parse_ascii_group(ascii_name, false) {
+            Ok(group) => group,
+            Err(_) => continue,
+        };
+        let positions: Option<Vec<usize>> = group
+            .iter()
+            .map(|group_item| rest.iter().position(|item| same_item(group_item, item)))
+            .collect();
+        if let Some(mut positions) = positions {
+            positions.sort_unstable();
+            positions.dedup();
+            for pos in positions.into_iter().rev() {
+                rest.remove(pos);
+            }
+            names.push(posix_name);
+        }
+    }
+    (names, rest)
+}
+
+fn same_item(group_item: &GroupItem, item: &RegexClassItem) -> bool {
+    match (*group_item, *item) {
+        (GroupItem::Char(a), RegexClassItem::Char(b)) => a == b,
+        (
+            GroupItem::Range { first: a1, last: a2 },
+            RegexClassItem::Range { first: b1, last: b2 },
+        ) => a1 == b1 && a2 == b2,
+        _ => false,
+    }
+}
+
 const OPTION_LIST: &[&str] = &[
     "ascii_alpha",
     "ascii_alnum",
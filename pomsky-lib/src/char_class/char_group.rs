@@ -6,9 +6,9 @@
 //!
 //! Refer to the [`char_class` module](crate::char_class) for more information.
 
-use std::fmt::Write;
+use std::{cmp::Ordering, fmt::Write};
 
-use crate::error::CharClassError;
+use crate::{error::CharClassError, options::CustomClass};
 
 use super::unicode::{Category, CodeBlock, OtherProperties, Script};
 
@@ -84,6 +84,33 @@ impl CharGroup {
         })
     }
 
+    /// Try to create a `CharGroup` from a Unicode property with a value, e.g.
+    /// `gc=Lu`, `Script=Greek` or `blk=Basic_Latin`.
+    pub(crate) fn try_from_property(
+        property: &str,
+        value: &str,
+        negative: bool,
+    ) -> Result<Self, CharClassError> {
+        let name = super::unicode::parse_property_value(property, value)?;
+        Ok(CharGroup::Items(vec![GroupItem::Named { name, negative }]))
+    }
+
+    /// Try to create a `CharGroup` from a [`CustomClass`] returned by a
+    /// [`ClassResolver`](crate::options::ClassResolver). Fails if it contains
+    /// a descending range.
+    pub(crate) fn try_from_custom_class(class: CustomClass) -> Result<Self, CharClassError> {
+        let items = class
+            .items
+            .into_iter()
+            .map(|(first, last)| match first.cmp(&last) {
+                Ordering::Less => Ok(GroupItem::Range { first, last }),
+                Ordering::Equal => Ok(GroupItem::Char(first)),
+                Ordering::Greater => Err(CharClassError::DescendingRange(first, last)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(CharGroup::Items(items))
+    }
+
     /// Tries to add another `CharGroup` to this one. Fails if one of them is a
     /// `[.]` or `[cp]`. If it succeeds, it just appends the new items to
     /// the existing ones.
@@ -164,6 +191,10 @@ impl core::fmt::Debug for GroupItem {
                         f.write_str("script=")?;
                         s.as_str()
                     }
+                    GroupName::ScriptExtension(s) => {
+                        f.write_str("script_extensions=")?;
+                        s.as_str()
+                    }
                     GroupName::CodeBlock(b) => {
                         f.write_str("block=")?;
                         b.as_str()
@@ -189,6 +220,7 @@ pub(crate) enum GroupName {
     VertSpace,
     Category(Category),
     Script(Script),
+    ScriptExtension(Script),
     CodeBlock(CodeBlock),
     OtherProperties(OtherProperties),
 }
@@ -101,16 +101,37 @@
 //!   When a negated character class only contains 1 item, which is also
 //! negated, the class is   removed and the negations cancel each other out:
 //! `![!w]` = `\w`, `![!L]` = `\p{L}`.
+//!
+//! ## Intersection
+//!
+//! The items of a character class can be separated with `&` to match only
+//! characters that are matched by every part, e.g. `[Letter & ascii]`.
+//!
+//! - If all parts only contain code points and ranges, the intersection is
+//!   computed by Pomsky, e.g. `['a'-'z' & 'x'-U+FF]` = `[x-z]`.
+//!
+//! - Java, Ruby, Rust and JavaScript with the `v` flag support intersections
+//!   natively: `[Letter & ascii]` = `[[\p{Letter}]&&[\x00-\x7F]]`.
+//!
+//! - Other flavors with lookahead emulate it: `[Letter & ascii]` =
+//!   `(?=[\x00-\x7F])\p{Letter}`. A negated intersection is emulated with a
+//!   negative lookahead followed by `[cp]`.
+//!
+//! - In the remaining flavors, an intersection is an error.
 
 use std::borrow::Cow;
 
 use crate::{
-    compile::CompileResult,
-    error::{CompileError, CompileErrorKind, Feature, ParseErrorKind},
+    compile::{CompileResult, Output},
+    error::{CharClassError, CompileError, CompileErrorKind, Feature, ParseErrorKind},
+    group::{RegexCapture, RegexGroup},
     literal,
-    options::{CompileOptions, RegexFlavor},
+    lookaround::{LookaroundKind, RegexLookaround},
+    optimization::{OptimizationKind, OptimizationLog},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
     regex::{Regex, RegexProperty, RegexShorthand},
     span::Span,
+    warning::{CharClassWarning, Warning, WarningKind},
 };
 
 pub(crate) use char_group::{CharGroup, GroupItem};
@@ -128,14 +149,35 @@ pub(crate) mod unicode;
 /// details.
 #[derive(Clone, PartialEq, Eq)]
 pub(crate) struct CharClass {
-    negative: bool,
-    inner: CharGroup,
+    pub(crate) negative: bool,
+    pub(crate) inner: CharGroup,
     pub(crate) span: Span,
+    /// Whether the class only contains strings, code points and ranges. Only
+    /// these classes are checked by [`CharClass::lint`].
+    pub(crate) only_chars: bool,
+    /// The groups after an `&`, e.g. `[ascii]` in `[Letter & ascii]`. The
+    /// class matches the characters matched by `inner` and all of these
+    /// groups.
+    pub(crate) intersections: Vec<CharGroup>,
 }
 
 impl CharClass {
     pub(crate) fn new(inner: CharGroup, span: Span) -> Self {
-        CharClass { inner, span, negative: false }
+        CharClass { inner, span, negative: false, only_chars: false, intersections: vec![] }
+    }
+
+    pub(crate) fn lint(&self, options: &ParseOptions, warnings: &mut Vec<Warning>) {
+        if !options.suggest_named_classes || !self.only_chars {
+            return;
+        }
+        if let CharGroup::Items(items) = &self.inner {
+            if let Some(name) = ascii::find_equivalent_group(items) {
+                warnings.push(
+                    WarningKind::CharClass(CharClassWarning::EquivalentNamedClass(name))
+                        .at(self.span),
+                );
+            }
+        }
     }
 
     /// Makes a positive character class negative and vice versa.
@@ -148,9 +190,59 @@ impl CharClass {
         }
     }
 
-    pub(crate) fn compile(&self, options: CompileOptions) -> CompileResult<'static> {
+    pub(crate) fn compile(
+        &self,
+        options: &CompileOptions,
+        optimizations: &mut OptimizationLog,
+    ) -> CompileResult<'static> {
+        let mut regex = self.compile_class(options)?;
+        if options.byte_mode {
+            regex.check_byte_mode().map_err(|kind| kind.at(self.span))?;
+        }
+        if let Regex::CharClass(class) = &mut regex {
+            class.unicode_sets = options.unicode_sets && options.flavor == RegexFlavor::JavaScript;
+            class.posix_classes = options.posix_classes
+                && matches!(
+                    options.flavor,
+                    RegexFlavor::Pcre | RegexFlavor::Rust | RegexFlavor::Re2
+                );
+            if (options.canonical || options.minimal_classes) && class.normalize() {
+                optimizations.record(OptimizationKind::MergedClassItems, self.span);
+            }
+            if options.minimal_classes {
+                if class.insert_shorthands(options.flavor) {
+                    optimizations.record(OptimizationKind::ClassShorthand, self.span);
+                }
+                if let Some(single) = class.unwrap_single_item() {
+                    return Ok(single);
+                }
+            }
+        }
+        Ok(regex)
+    }
+
+    fn compile_class(&self, options: &CompileOptions) -> CompileResult<'static> {
+        if !self.intersections.is_empty() {
+            return self.compile_intersection(options);
+        }
+
         let span = self.span;
         match &self.inner {
+            CharGroup::Dot
+                if matches!(options.flavor, RegexFlavor::Sed { .. } | RegexFlavor::Lua) =>
+            {
+                // the dot matches line breaks in sed and Lua
+                Ok(if self.negative {
+                    Regex::Char('\n')
+                } else {
+                    Regex::CharClass(RegexCharClass {
+                        negative: true,
+                        unicode_sets: false,
+                        posix_classes: false,
+                        items: vec![RegexClassItem::Char('\n')],
+                    })
+                })
+            }
             CharGroup::Dot => {
                 Ok(if self.negative { Regex::Literal(Cow::Borrowed("\\n")) } else { Regex::Dot })
             }
@@ -158,8 +250,15 @@ impl CharClass {
                 if self.negative {
                     return Err(CompileErrorKind::EmptyClassNegated.at(span));
                 }
+                // in Lua, this matches a single byte, so it must be repeated,
+                // see `Regex::check_lua_code_points`
+                if let RegexFlavor::Sed { .. } | RegexFlavor::Lua = options.flavor {
+                    return Ok(Regex::Dot);
+                }
                 Ok(Regex::CharClass(RegexCharClass {
                     negative: false,
+                    unicode_sets: false,
+                    posix_classes: false,
                     items: vec![
                         RegexClassItem::Shorthand(RegexShorthand::Space),
                         RegexClassItem::Shorthand(RegexShorthand::NotSpace),
@@ -172,6 +271,8 @@ impl CharClass {
                     GroupItem::Char(c) => Ok(Regex::Char(c)),
                     GroupItem::Range { first, last } => Ok(Regex::CharClass(RegexCharClass {
                         negative: false,
+                        unicode_sets: false,
+                        posix_classes: false,
                         items: vec![RegexClassItem::Range { first, last }],
                     })),
                     GroupItem::Named { name, negative } => {
@@ -181,10 +282,14 @@ impl CharClass {
                 (1, true) => match items[0] {
                     GroupItem::Char(c) => Ok(Regex::CharClass(RegexCharClass {
                         negative: true,
+                        unicode_sets: false,
+                        posix_classes: false,
                         items: vec![RegexClassItem::Char(c)],
                     })),
                     GroupItem::Range { first, last } => Ok(Regex::CharClass(RegexCharClass {
                         negative: true,
+                        unicode_sets: false,
+                        posix_classes: false,
                         items: vec![RegexClassItem::Range { first, last }],
                     })),
                     GroupItem::Named { name, negative } => {
@@ -192,30 +297,160 @@ impl CharClass {
                     }
                 },
                 (_, negative) => {
-                    let mut buf = Vec::new();
-                    for item in items {
-                        match *item {
-                            GroupItem::Char(c) => buf.push(RegexClassItem::Char(c)),
-                            GroupItem::Range { first, last } => {
-                                buf.push(RegexClassItem::Range { first, last })
-                            }
-                            GroupItem::Named { name, negative } => {
-                                named_class_to_regex_class_items(
-                                    name,
-                                    negative,
-                                    options.flavor,
-                                    span,
-                                    &mut buf,
-                                )?;
-                            }
-                        }
-                    }
-
-                    Ok(Regex::CharClass(RegexCharClass { negative, items: buf }))
+                    Ok(Regex::CharClass(compile_items(items, negative, options, span)?))
                 }
             },
         }
     }
+
+    /// Compiles a class with intersections. Refer to the
+    /// [module-level documentation](self) for details.
+    fn compile_intersection(&self, options: &CompileOptions) -> CompileResult<'static> {
+        let span = self.span;
+        let groups = || std::iter::once(&self.inner).chain(&self.intersections);
+
+        if let Some(ranges) = intersect_char_ranges(groups()) {
+            if ranges.is_empty() {
+                if self.negative {
+                    return CharClass::new(CharGroup::CodePoint, span).compile_class(options);
+                }
+                return Err(CompileErrorKind::EmptyClass.at(span));
+            }
+            let mut items = vec![];
+            for (first, last) in ranges {
+                push_range(&mut items, first, last);
+            }
+            let mut class = RegexCharClass::new(items);
+            class.negative = self.negative;
+            return Ok(Regex::CharClass(class));
+        }
+
+        let native = match options.flavor {
+            RegexFlavor::Java | RegexFlavor::Ruby | RegexFlavor::Rust => true,
+            RegexFlavor::JavaScript => options.unicode_sets,
+            RegexFlavor::Pcre | RegexFlavor::Python | RegexFlavor::DotNet => false,
+            RegexFlavor::Re2 | RegexFlavor::Sed { .. } | RegexFlavor::Antlr | RegexFlavor::Lua => {
+                return Err(CompileErrorKind::ParseError(ParseErrorKind::CharClass(
+                    CharClassError::UnsupportedIntersection(options.flavor),
+                ))
+                .at(span));
+            }
+        };
+
+        if native {
+            let operands = groups()
+                .map(|group| match group {
+                    CharGroup::Items(items) => {
+                        let mut class = compile_items(items, false, options, span)?;
+                        class.unicode_sets = options.flavor == RegexFlavor::JavaScript;
+                        Ok(class)
+                    }
+                    CharGroup::Dot | CharGroup::CodePoint => {
+                        unreachable!("`[.]` and `[cp]` can't be intersected")
+                    }
+                })
+                .collect::<Result<_, CompileError>>()?;
+            return Ok(Regex::ClassIntersection(RegexClassIntersection {
+                negative: self.negative,
+                operands,
+            }));
+        }
+
+        // emulated with lookahead: `(?=[B])(?=[C])[A]`
+        let mut parts = self
+            .intersections
+            .iter()
+            .map(|group| {
+                let content = CharClass::new(group.clone(), span).compile_class(options)?;
+                Ok(Regex::Lookaround(Box::new(RegexLookaround {
+                    content,
+                    kind: LookaroundKind::Ahead,
+                })))
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+        parts.push(CharClass::new(self.inner.clone(), span).compile_class(options)?);
+        let positive = Regex::Group(RegexGroup::new(parts, RegexCapture::None));
+
+        if !self.negative {
+            return Ok(positive);
+        }
+        Ok(Regex::Group(RegexGroup::new(
+            vec![
+                Regex::Lookaround(Box::new(RegexLookaround {
+                    content: positive,
+                    kind: LookaroundKind::AheadNegative,
+                })),
+                CharClass::new(CharGroup::CodePoint, span).compile_class(options)?,
+            ],
+            RegexCapture::None,
+        )))
+    }
+}
+
+/// Compiles the items of a character class to a regex character class.
+fn compile_items(
+    items: &[GroupItem],
+    negative: bool,
+    options: &CompileOptions,
+    span: Span,
+) -> Result<RegexCharClass, CompileError> {
+    let mut buf = Vec::new();
+    for item in items {
+        match *item {
+            GroupItem::Char(c) => buf.push(RegexClassItem::Char(c)),
+            GroupItem::Range { first, last } => buf.push(RegexClassItem::Range { first, last }),
+            GroupItem::Named { name, negative } => {
+                named_class_to_regex_class_items(name, negative, options, span, &mut buf)?;
+            }
+        }
+    }
+
+    Ok(RegexCharClass { negative, items: buf, unicode_sets: false, posix_classes: false })
+}
+
+/// Returns the ranges of code points matched by all groups, if the groups only
+/// contain code points and ranges. Otherwise, `None` is returned.
+fn intersect_char_ranges<'a>(
+    groups: impl Iterator<Item = &'a CharGroup>,
+) -> Option<Vec<(u32, u32)>> {
+    let mut result: Option<Vec<(u32, u32)>> = None;
+    for group in groups {
+        let items = match group {
+            CharGroup::Items(items) => items
+                .iter()
+                .map(|&item| match item {
+                    GroupItem::Char(c) => Some(RegexClassItem::Char(c)),
+                    GroupItem::Range { first, last } => Some(RegexClassItem::Range { first, last }),
+                    GroupItem::Named { .. } => None,
+                })
+                .collect::<Option<Vec<_>>>()?,
+            CharGroup::Dot | CharGroup::CodePoint => return None,
+        };
+        let (ranges, _) = RegexCharClass::new(items).merged_ranges();
+
+        result = Some(match result {
+            None => ranges,
+            Some(prev) => {
+                // both lists are sorted and don't overlap
+                let mut intersection = vec![];
+                let (mut i, mut j) = (0, 0);
+                while i < prev.len() && j < ranges.len() {
+                    let first = prev[i].0.max(ranges[j].0);
+                    let last = prev[i].1.min(ranges[j].1);
+                    if first <= last {
+                        intersection.push((first, last));
+                    }
+                    if prev[i].1 < ranges[j].1 {
+                        i += 1;
+                    } else {
+                        j += 1;
+                    }
+                }
+                intersection
+            }
+        });
+    }
+    result
 }
 
 /// Compiles a shorthand character class or Unicode category/script/block.
@@ -228,18 +463,23 @@ fn named_class_to_regex(
     flavor: RegexFlavor,
     span: Span,
 ) -> CompileResult<'static> {
+    check_sed_support(group, flavor, span)?;
+
     Ok(match group {
         GroupName::Word => {
-            if flavor == RegexFlavor::JavaScript {
+            if matches!(flavor, RegexFlavor::JavaScript | RegexFlavor::Antlr) {
                 Regex::CharClass(RegexCharClass {
                     negative,
-                    items: vec![
-                        RegexProperty::Other(OtherProperties::Alphabetic).negative_item(false),
-                        RegexProperty::Category(Category::Mark).negative_item(false),
-                        RegexProperty::Category(Category::Decimal_Number).negative_item(false),
-                        RegexProperty::Category(Category::Connector_Punctuation)
-                            .negative_item(false),
-                    ],
+                    unicode_sets: false,
+                    posix_classes: false,
+                    items: JS_WORD.to_vec(),
+                })
+            } else if flavor == RegexFlavor::Re2 {
+                Regex::CharClass(RegexCharClass {
+                    negative,
+                    unicode_sets: false,
+                    posix_classes: false,
+                    items: RE2_WORD.to_vec(),
                 })
             } else {
                 Regex::Shorthand(if negative {
@@ -250,7 +490,7 @@ fn named_class_to_regex(
             }
         }
         GroupName::Digit => {
-            if flavor == RegexFlavor::JavaScript {
+            if matches!(flavor, RegexFlavor::JavaScript | RegexFlavor::Antlr | RegexFlavor::Re2) {
                 RegexProperty::Category(Category::Decimal_Number).negative(negative)
             } else {
                 Regex::Shorthand(if negative {
@@ -260,11 +500,21 @@ fn named_class_to_regex(
                 })
             }
         }
+        GroupName::Space if flavor == RegexFlavor::Re2 => Regex::CharClass(RegexCharClass {
+            negative,
+            unicode_sets: false,
+            posix_classes: false,
+            items: RE2_SPACE.to_vec(),
+        }),
         GroupName::Space if negative => Regex::Shorthand(RegexShorthand::NotSpace),
         GroupName::Space => Regex::Shorthand(RegexShorthand::Space),
 
+        // Ruby supports `\h`, but it matches a hex digit
         GroupName::HorizSpace | GroupName::VertSpace
-            if matches!(flavor, RegexFlavor::Pcre | RegexFlavor::Java) =>
+            if matches!(
+                flavor,
+                RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Sed { .. }
+            ) =>
         {
             let shorthand = if group == GroupName::HorizSpace {
                 RegexShorthand::HorizSpace
@@ -272,17 +522,26 @@ fn named_class_to_regex(
                 RegexShorthand::VertSpace
             };
 
-            if negative {
+            if !negative {
+                Regex::Shorthand(shorthand)
+            } else if let RegexFlavor::Sed { .. } = flavor {
+                // sed doesn't support `\H` and `\V`
                 Regex::CharClass(RegexCharClass {
                     negative: true,
+                    unicode_sets: false,
+                    posix_classes: false,
                     items: vec![RegexClassItem::Shorthand(shorthand)],
                 })
+            } else if group == GroupName::HorizSpace {
+                Regex::Shorthand(RegexShorthand::NotHorizSpace)
             } else {
-                Regex::Shorthand(shorthand)
+                Regex::Shorthand(RegexShorthand::NotVertSpace)
             }
         }
         GroupName::HorizSpace => Regex::CharClass(RegexCharClass {
             negative,
+            unicode_sets: false,
+            posix_classes: false,
             items: vec![
                 RegexClassItem::Char('\t'),
                 RegexProperty::Category(Category::Space_Separator).negative_item(false),
@@ -290,6 +549,8 @@ fn named_class_to_regex(
         }),
         GroupName::VertSpace => Regex::CharClass(RegexCharClass {
             negative,
+            unicode_sets: false,
+            posix_classes: false,
             items: vec![
                 RegexClassItem::Range { first: '\x0A', last: '\x0D' },
                 RegexClassItem::Char('\u{85}'),
@@ -300,6 +561,10 @@ fn named_class_to_regex(
 
         GroupName::Category(c) => RegexProperty::Category(c).negative(negative),
         GroupName::Script(s) => RegexProperty::Script(s).negative(negative),
+        GroupName::ScriptExtension(s) => {
+            check_script_extension_support(flavor, span)?;
+            RegexProperty::ScriptExtension(s).negative(negative)
+        }
         GroupName::CodeBlock(b) => match flavor {
             RegexFlavor::DotNet | RegexFlavor::Java | RegexFlavor::Ruby => {
                 RegexProperty::Block(b).negative(negative)
@@ -307,8 +572,8 @@ fn named_class_to_regex(
             _ => return Err(CompileErrorKind::Unsupported(Feature::UnicodeBlock, flavor).at(span)),
         },
         GroupName::OtherProperties(o) => {
-            // TODO: Find out which regex engines (other than PCRE) don't support these
-            if flavor == RegexFlavor::Pcre {
+            // TODO: Find out which regex engines (other than PCRE and RE2) don't support these
+            if let RegexFlavor::Pcre | RegexFlavor::Re2 = flavor {
                 return Err(CompileErrorKind::Unsupported(Feature::UnicodeProp, flavor).at(span));
             }
             RegexProperty::Other(o).negative(negative)
@@ -316,27 +581,89 @@ fn named_class_to_regex(
     })
 }
 
+/// Returns the regex for a single word character, i.e. `[w]`
+pub(crate) fn word_char(flavor: RegexFlavor) -> Regex<'static> {
+    if flavor == RegexFlavor::JavaScript {
+        Regex::CharClass(RegexCharClass {
+            negative: false,
+            unicode_sets: false,
+            posix_classes: false,
+            items: JS_WORD.to_vec(),
+        })
+    } else {
+        Regex::Shorthand(RegexShorthand::Word)
+    }
+}
+
+/// Returns an error if a Unicode property is used in the `sed` flavor.
+fn check_sed_support(
+    group: GroupName,
+    flavor: RegexFlavor,
+    span: Span,
+) -> Result<(), CompileError> {
+    match (group, flavor) {
+        (
+            GroupName::Category(_)
+            | GroupName::Script(_)
+            | GroupName::ScriptExtension(_)
+            | GroupName::OtherProperties(_),
+            RegexFlavor::Sed { .. },
+        ) => Err(CompileErrorKind::Unsupported(Feature::UnicodeProp, flavor).at(span)),
+        _ => Ok(()),
+    }
+}
+
+/// Returns an error if the flavor doesn't support the `Script_Extensions`
+/// property. Lua is rejected later, like all Unicode properties.
+fn check_script_extension_support(flavor: RegexFlavor, span: Span) -> Result<(), CompileError> {
+    match flavor {
+        RegexFlavor::Java
+        | RegexFlavor::DotNet
+        | RegexFlavor::Ruby
+        | RegexFlavor::Re2
+        | RegexFlavor::Antlr => {
+            Err(CompileErrorKind::Unsupported(Feature::ScriptExtensions, flavor).at(span))
+        }
+        _ => Ok(()),
+    }
+}
+
 fn named_class_to_regex_class_items(
     group: GroupName,
     negative: bool,
-    flavor: RegexFlavor,
+    options: &CompileOptions,
     span: Span,
     buf: &mut Vec<RegexClassItem>,
 ) -> Result<(), CompileError> {
+    let flavor = options.flavor;
+    check_sed_support(group, flavor, span)?;
+
+    if let (true, RegexFlavor::Sed { .. }, GroupName::Word | GroupName::Digit | GroupName::Space)
+    | (true, RegexFlavor::Re2, GroupName::Word | GroupName::Space) = (negative, flavor, group)
+    {
+        return Err(
+            CompileErrorKind::Unsupported(Feature::NegativeShorthandInClass, flavor).at(span)
+        );
+    }
+
     match group {
         GroupName::Word => {
-            if let RegexFlavor::JavaScript = flavor {
+            if let RegexFlavor::JavaScript | RegexFlavor::Antlr = flavor {
                 if negative {
-                    return Err(
-                        CompileErrorKind::Unsupported(Feature::NegativeShorthandW, flavor).at(span)
-                    );
+                    // with the `v` flag, the negated class can be nested
+                    if !options.unicode_sets || flavor == RegexFlavor::Antlr {
+                        return Err(CompileErrorKind::Unsupported(
+                            Feature::NegativeShorthandW,
+                            flavor,
+                        )
+                        .at(span));
+                    }
+                    buf.push(RegexClassItem::Nested { negative: true, items: JS_WORD });
+                } else {
+                    buf.extend_from_slice(JS_WORD);
                 }
-                buf.push(RegexProperty::Other(OtherProperties::Alphabetic).negative_item(false));
-                buf.push(RegexProperty::Category(Category::Mark).negative_item(false));
-                buf.push(RegexProperty::Category(Category::Decimal_Number).negative_item(false));
-                buf.push(
-                    RegexProperty::Category(Category::Connector_Punctuation).negative_item(false),
-                );
+            } else if flavor == RegexFlavor::Re2 {
+                buf.extend_from_slice(RE2_WORD);
             } else {
                 buf.push(RegexClassItem::Shorthand(if negative {
                     RegexShorthand::NotWord
@@ -346,7 +673,7 @@ fn named_class_to_regex_class_items(
             }
         }
         GroupName::Digit => {
-            if flavor == RegexFlavor::JavaScript {
+            if matches!(flavor, RegexFlavor::JavaScript | RegexFlavor::Antlr | RegexFlavor::Re2) {
                 buf.push(RegexProperty::Category(Category::Decimal_Number).negative_item(negative));
             } else if negative {
                 buf.push(RegexClassItem::Shorthand(RegexShorthand::NotDigit));
@@ -354,6 +681,7 @@ fn named_class_to_regex_class_items(
                 buf.push(RegexClassItem::Shorthand(RegexShorthand::Digit));
             }
         }
+        GroupName::Space if flavor == RegexFlavor::Re2 => buf.extend_from_slice(RE2_SPACE),
         GroupName::Space => buf.push(RegexClassItem::Shorthand(if negative {
             RegexShorthand::NotSpace
         } else {
@@ -368,7 +696,10 @@ fn named_class_to_regex_class_items(
         }
 
         GroupName::HorizSpace | GroupName::VertSpace
-            if matches!(flavor, RegexFlavor::Pcre | RegexFlavor::Java) =>
+            if matches!(
+                flavor,
+                RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Sed { .. }
+            ) =>
         {
             buf.push(RegexClassItem::Shorthand(if group == GroupName::HorizSpace {
                 RegexShorthand::HorizSpace
@@ -389,6 +720,10 @@ fn named_class_to_regex_class_items(
 
         GroupName::Category(c) => buf.push(RegexProperty::Category(c).negative_item(negative)),
         GroupName::Script(s) => buf.push(RegexProperty::Script(s).negative_item(negative)),
+        GroupName::ScriptExtension(s) => {
+            check_script_extension_support(flavor, span)?;
+            buf.push(RegexProperty::ScriptExtension(s).negative_item(negative));
+        }
         GroupName::CodeBlock(b) => match flavor {
             RegexFlavor::DotNet | RegexFlavor::Java | RegexFlavor::Ruby => {
                 buf.push(RegexProperty::Block(b).negative_item(negative));
@@ -396,8 +731,8 @@ fn named_class_to_regex_class_items(
             _ => return Err(CompileErrorKind::Unsupported(Feature::UnicodeBlock, flavor).at(span)),
         },
         GroupName::OtherProperties(o) => {
-            // TODO: Find out which regex engines (other than PCRE) don't support these
-            if flavor == RegexFlavor::Pcre {
+            // TODO: Find out which regex engines (other than PCRE and RE2) don't support these
+            if let RegexFlavor::Pcre | RegexFlavor::Re2 = flavor {
                 return Err(CompileErrorKind::Unsupported(Feature::UnicodeProp, flavor).at(span));
             }
             buf.push(RegexProperty::Other(o).negative_item(negative));
@@ -417,15 +752,20 @@ impl core::fmt::Debug for CharClass {
             f.write_str("not ")?;
         }
 
-        match &self.inner {
-            CharGroup::Dot => f.write_str(".")?,
-            CharGroup::CodePoint => f.write_str("codepoint")?,
-            CharGroup::Items(items) => {
-                for (i, item) in items.iter().enumerate() {
-                    if i > 0 {
-                        f.write_char(' ')?;
+        for (i, group) in std::iter::once(&self.inner).chain(&self.intersections).enumerate() {
+            if i > 0 {
+                f.write_str(" & ")?;
+            }
+            match group {
+                CharGroup::Dot => f.write_str(".")?,
+                CharGroup::CodePoint => f.write_str("codepoint")?,
+                CharGroup::Items(items) => {
+                    for (i, item) in items.iter().enumerate() {
+                        if i > 0 {
+                            f.write_char(' ')?;
+                        }
+                        item.fmt(f)?;
                     }
-                    item.fmt(f)?;
                 }
             }
         }
@@ -435,39 +775,255 @@ impl core::fmt::Debug for CharClass {
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct RegexCharClass {
-    negative: bool,
-    items: Vec<RegexClassItem>,
+    pub(crate) negative: bool,
+    pub(crate) items: Vec<RegexClassItem>,
+    /// Whether the class is emitted for JavaScript's `v` flag, which requires
+    /// more characters to be escaped
+    pub(crate) unicode_sets: bool,
+    /// Whether ASCII classes such as `[a-zA-Z]` are emitted as POSIX classes
+    /// (`[[:alpha:]]`)
+    pub(crate) posix_classes: bool,
 }
 
 impl RegexCharClass {
     pub(crate) fn new(items: Vec<RegexClassItem>) -> Self {
-        Self { negative: false, items }
+        Self { negative: false, items, unicode_sets: false, posix_classes: false }
+    }
+
+    /// Sorts the characters and ranges in the class and merges the ones that
+    /// overlap or are adjacent. They are moved before the other items, which
+    /// keep their order. Returns `true` if any items were merged.
+    fn normalize(&mut self) -> bool {
+        let len = self.items.len();
+        let (merged, others) = self.merged_ranges();
+
+        self.items.clear();
+        for (first, last) in merged {
+            push_range(&mut self.items, first, last);
+        }
+        self.items.extend(others);
+        self.items.len() < len
+    }
+
+    /// Returns the characters and ranges in the class as sorted ranges of
+    /// code points, where overlapping and adjacent ranges are merged, and the
+    /// other items.
+    fn merged_ranges(&self) -> (Vec<(u32, u32)>, Vec<RegexClassItem>) {
+        let mut ranges = vec![];
+        let mut others = vec![];
+        for &item in &self.items {
+            match item {
+                RegexClassItem::Char(c) => ranges.push((c as u32, c as u32)),
+                RegexClassItem::Range { first, last } => ranges.push((first as u32, last as u32)),
+                item => others.push(item),
+            }
+        }
+        ranges.sort_unstable();
+
+        let mut merged: Vec<(u32, u32)> = vec![];
+        for (first, last) in ranges {
+            match merged.last_mut() {
+                Some((_, prev_last)) if first <= *prev_last + 1 => {
+                    *prev_last = last.max(*prev_last);
+                }
+                _ => merged.push((first, last)),
+            }
+        }
+        (merged, others)
+    }
+
+    /// Replaces characters and ranges with a shorthand such as `\d` if the
+    /// shorthand matches exactly the same characters in the flavor, and the
+    /// output gets shorter. This is only the case in JavaScript and RE2, where
+    /// shorthands only match ASCII characters. Returns `true` if a shorthand was inserted.
+    fn insert_shorthands(&mut self, flavor: RegexFlavor) -> bool {
+        let candidates: &[(RegexShorthand, &[(char, char)])] = match flavor {
+            RegexFlavor::JavaScript => {
+                &[(RegexShorthand::Word, ASCII_WORD), (RegexShorthand::Digit, ASCII_DIGIT)]
+            }
+            RegexFlavor::Re2 => &[
+                (RegexShorthand::Word, ASCII_WORD),
+                (RegexShorthand::Digit, ASCII_DIGIT),
+                (RegexShorthand::Space, RE2_SPACE_CHARS),
+            ],
+            _ => return false,
+        };
+
+        let mut changed = false;
+        for &(shorthand, chars) in candidates {
+            let (ranges, others) = self.merged_ranges();
+
+            // the ranges are merged, so each range of the shorthand must be
+            // contained in a single range of the class
+            let contained = chars.iter().all(|&(first, last)| {
+                ranges.iter().any(|&(f, l)| f <= first as u32 && last as u32 <= l)
+            });
+            if !contained {
+                continue;
+            }
+
+            let mut items = vec![RegexClassItem::Shorthand(shorthand)];
+            for (mut first, last) in ranges {
+                for &(sh_first, sh_last) in chars {
+                    let (sh_first, sh_last) = (sh_first as u32, sh_last as u32);
+                    if sh_first > last || sh_last < first {
+                        continue;
+                    }
+                    if sh_first > first {
+                        push_range(&mut items, first, sh_first - 1);
+                    }
+                    first = sh_last + 1;
+                }
+                if first <= last {
+                    push_range(&mut items, first, last);
+                }
+            }
+            items.extend(others);
+
+            let candidate = RegexCharClass { items, ..*self };
+            if candidate.output_len(flavor) < self.output_len(flavor) {
+                *self = candidate;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn output_len(&self, flavor: RegexFlavor) -> usize {
+        let mut buf = String::new();
+        self.codegen(&mut buf, flavor);
+        buf.len()
+    }
+
+    /// Returns an equivalent expression without brackets if the class
+    /// contains only a single character or shorthand, e.g. `[a]` or `[^\d]`
+    fn unwrap_single_item(&self) -> Option<Regex<'static>> {
+        match (self.negative, self.items.as_slice()) {
+            (false, &[RegexClassItem::Char(c)]) => Some(Regex::Char(c)),
+            (false, &[RegexClassItem::Shorthand(s)]) => Some(Regex::Shorthand(s)),
+            (true, &[RegexClassItem::Shorthand(s)]) => Some(Regex::Shorthand(match s {
+                RegexShorthand::Word => RegexShorthand::NotWord,
+                RegexShorthand::Digit => RegexShorthand::NotDigit,
+                RegexShorthand::Space => RegexShorthand::NotSpace,
+                RegexShorthand::NotWord => RegexShorthand::Word,
+                RegexShorthand::NotDigit => RegexShorthand::Digit,
+                RegexShorthand::NotSpace => RegexShorthand::Space,
+                _ => return None,
+            })),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        if let RegexFlavor::Sed { .. } = flavor {
+            return self.codegen_posix(buf, flavor);
+        }
+
+        if self.negative {
+            buf.push_str("[^");
+        } else {
+            buf.push('[');
+        }
+
+        if self.posix_classes {
+            let (names, rest) = ascii::extract_posix_classes(&self.items);
+            for name in names {
+                buf.push_str("[:");
+                buf.push_str(name);
+                buf.push_str(":]");
+            }
+            for item in &rest {
+                item.codegen(buf, flavor, self.unicode_sets);
+            }
+        } else {
+            for item in &self.items {
+                item.codegen(buf, flavor, self.unicode_sets);
+            }
+        }
+
+        buf.push(']');
     }
 
-    pub(crate) fn codegen(&self, buf: &mut String, flavor: RegexFlavor) {
+    /// Emits a POSIX bracket expression. Backslashes have no special meaning
+    /// in bracket expressions, so `]` must appear first, `^` must not appear
+    /// first and `-` must appear last. Characters with a special meaning are
+    /// written as collating symbols (e.g. `[.-.]`) when used in a range.
+    fn codegen_posix(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        let has_char = |c: char| {
+            self.items.iter().any(|item| matches!(item, &RegexClassItem::Char(i) if i == c))
+        };
+        let (bracket, caret, dash) = (has_char(']'), has_char('^'), has_char('-'));
+        let others =
+            self.items.iter().any(|item| !matches!(item, RegexClassItem::Char(']' | '^' | '-')));
+
         if self.negative {
             buf.push_str("[^");
         } else {
             buf.push('[');
         }
+        if bracket {
+            buf.push(']');
+        }
 
+        // `[` is written after other chars, so it can't form a `[:` or `[.` sequence, and
+        // `\` is written after that, so sed doesn't treat it as an escape sequence like `\n`
+        let (mut open_bracket, mut backslash) = (false, false);
         for item in &self.items {
             match *item {
-                RegexClassItem::Char(c) => {
-                    literal::compile_char_esc_in_class(c, buf, flavor);
-                }
+                RegexClassItem::Char(']' | '^' | '-') => {}
+                RegexClassItem::Char('[') => open_bracket = true,
+                RegexClassItem::Char('\\') => backslash = true,
+                RegexClassItem::Char(c) => literal::compile_char_esc_in_class(c, buf, flavor),
                 RegexClassItem::Range { first, last } => {
-                    literal::compile_char_esc_in_class(first, buf, flavor);
+                    codegen_posix_range_bound(first, buf, flavor);
                     buf.push('-');
-                    literal::compile_char_esc_in_class(last, buf, flavor);
+                    codegen_posix_range_bound(last, buf, flavor);
                 }
-                RegexClassItem::Shorthand(s) => s.codegen(buf),
-                RegexClassItem::Property { negative, value } => {
-                    value.codegen(buf, negative, flavor)
+                RegexClassItem::Shorthand(s) => s.codegen_posix_in_class(buf),
+                RegexClassItem::Property { .. } | RegexClassItem::Nested { .. } => {
+                    unreachable!("Unicode properties are rejected when compiling for sed")
                 }
             }
         }
+        if open_bracket {
+            buf.push('[');
+        }
+        if backslash {
+            buf.push('\\');
+        }
 
+        if caret {
+            if bracket || others || self.negative {
+                buf.push('^');
+            } else {
+                buf.push_str("[.^.]");
+            }
+        }
+        if dash {
+            buf.push('-');
+        }
+        buf.push(']');
+    }
+}
+
+/// An intersection of character classes, e.g. `[[\p{L}]&&[a-z]]`. This is
+/// only emitted for flavors that support it natively; in other flavors, it is
+/// emulated with lookahead.
+#[cfg_attr(feature = "dbg", derive(Debug))]
+pub(crate) struct RegexClassIntersection {
+    pub(crate) negative: bool,
+    pub(crate) operands: Vec<RegexCharClass>,
+}
+
+impl RegexClassIntersection {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        buf.push_str(if self.negative { "[^" } else { "[" });
+        for (i, operand) in self.operands.iter().enumerate() {
+            if i > 0 {
+                buf.push_str("&&");
+            }
+            operand.codegen(buf, flavor);
+        }
         buf.push(']');
     }
 }
@@ -476,12 +1032,130 @@ impl RegexCharClass {
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) enum RegexClassItem {
     Char(char),
-    Range { first: char, last: char },
+    Range {
+        first: char,
+        last: char,
+    },
     Shorthand(RegexShorthand),
-    Property { negative: bool, value: RegexProperty },
+    Property {
+        negative: bool,
+        value: RegexProperty,
+    },
+    /// A nested character class, which is only supported in JavaScript with the
+    /// `v` flag
+    Nested {
+        negative: bool,
+        items: &'static [RegexClassItem],
+    },
+}
+
+/// The polyfill for `\w` in JavaScript, since `\w` only matches ASCII characters.
+/// It is also used in ANTLR, which has no shorthands.
+const JS_WORD: &[RegexClassItem] = &[
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Other(OtherProperties::Alphabetic),
+    },
+    RegexClassItem::Property { negative: false, value: RegexProperty::Category(Category::Mark) },
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Category(Category::Decimal_Number),
+    },
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Category(Category::Connector_Punctuation),
+    },
+];
+
+/// The polyfill for `\w` in RE2, since `\w` only matches ASCII characters. RE2
+/// doesn't support the `Alphabetic` property, so letters and letter numbers are
+/// used instead, which is almost equivalent.
+const RE2_WORD: &[RegexClassItem] = &[
+    RegexClassItem::Property { negative: false, value: RegexProperty::Category(Category::Letter) },
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Category(Category::Letter_Number),
+    },
+    RegexClassItem::Property { negative: false, value: RegexProperty::Category(Category::Mark) },
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Category(Category::Decimal_Number),
+    },
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Category(Category::Connector_Punctuation),
+    },
+];
+
+/// The polyfill for `\s` in RE2, since `\s` only matches `[\t\n\f\r ]`
+const RE2_SPACE: &[RegexClassItem] = &[
+    RegexClassItem::Shorthand(RegexShorthand::Space),
+    RegexClassItem::Char('\x0B'),
+    RegexClassItem::Char('\u{85}'),
+    RegexClassItem::Property {
+        negative: false,
+        value: RegexProperty::Category(Category::Separator),
+    },
+];
+
+/// The characters matched by `\w` in flavors where it only matches ASCII
+const ASCII_WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+
+/// The characters matched by `\d` in flavors where it only matches ASCII
+const ASCII_DIGIT: &[(char, char)] = &[('0', '9')];
+
+/// The characters matched by `\s` in RE2
+const RE2_SPACE_CHARS: &[(char, char)] = &[('\t', '\n'), ('\x0C', '\r'), (' ', ' ')];
+
+/// Appends a range of code points as a character, two characters or a range
+fn push_range(items: &mut Vec<RegexClassItem>, first: u32, last: u32) {
+    // the ranges are created from chars, so they don't contain surrogates
+    let (first, last) = (char::from_u32(first).unwrap(), char::from_u32(last).unwrap());
+    match last as u32 - first as u32 {
+        0 => items.push(RegexClassItem::Char(first)),
+        1 => items.extend([RegexClassItem::Char(first), RegexClassItem::Char(last)]),
+        _ => items.push(RegexClassItem::Range { first, last }),
+    }
+}
+
+fn codegen_posix_range_bound(c: char, buf: &mut impl Output, flavor: RegexFlavor) {
+    match c {
+        '[' | ']' | '^' | '-' | '\\' => {
+            buf.push_str("[.");
+            buf.push(c);
+            buf.push_str(".]");
+        }
+        c => literal::compile_char_esc_in_class(c, buf, flavor),
+    }
 }
 
 impl RegexClassItem {
+    fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor, unicode_sets: bool) {
+        let compile_char = if unicode_sets {
+            literal::compile_char_esc_in_class_set
+        } else {
+            literal::compile_char_esc_in_class
+        };
+
+        match *self {
+            RegexClassItem::Char(c) => compile_char(c, buf, flavor),
+            RegexClassItem::Range { first, last } => {
+                compile_char(first, buf, flavor);
+                buf.push('-');
+                compile_char(last, buf, flavor);
+            }
+            RegexClassItem::Shorthand(s) => s.codegen(buf, flavor),
+            RegexClassItem::Property { negative, value } => value.codegen(buf, negative, flavor),
+            RegexClassItem::Nested { negative, items } => {
+                buf.push_str(if negative { "[^" } else { "[" });
+                for item in items {
+                    item.codegen(buf, flavor, unicode_sets);
+                }
+                buf.push(']');
+            }
+        }
+    }
+
     pub(crate) fn range_unchecked(first: char, last: char) -> Self {
         Self::Range { first, last }
     }
@@ -2,17 +2,97 @@ use crate::error::CharClassError;
 
 use super::char_group::GroupName;
 
+/// Parses the name of a character class. If there is no exact match, Unicode
+/// categories, scripts, blocks and properties are matched loosely, see
+/// [`loose_eq`].
 pub(super) fn parse_group_name(name: &str) -> Result<GroupName, CharClassError> {
     match PARSE_LUT.binary_search_by_key(&name, |(k, _)| k) {
         Ok(n) => Ok(PARSE_LUT[n].1),
-        Err(_) => Err(CharClassError::UnknownNamedClass {
-            found: name.into(),
-            #[cfg(feature = "suggestions")]
-            similar: crate::util::find_suggestion(name, PARSE_LUT.iter().map(|&(name, _)| name)),
-        }),
+        Err(_) => PARSE_LUT
+            .iter()
+            .find(|&&(k, v)| !is_shorthand(v) && loose_eq(k, name))
+            .map(|&(_, v)| v)
+            .ok_or_else(|| CharClassError::UnknownNamedClass {
+                found: name.into(),
+                #[cfg(feature = "suggestions")]
+                similar: crate::util::find_suggestion(
+                    name,
+                    PARSE_LUT.iter().map(|&(name, _)| name),
+                ),
+            }),
     }
 }
 
+/// Compares two Unicode names with the loose matching rule from
+/// [UAX #44](https://www.unicode.org/reports/tr44/#UAX44-LM3): Case,
+/// whitespace, underscores and hyphens are ignored, so e.g. `Basic_Latin`,
+/// `basic-latin` and `BasicLatin` are equal.
+fn loose_eq(a: &str, b: &str) -> bool {
+    let significant = |c: &char| !matches!(c, '_' | '-') && !c.is_whitespace();
+    let a = a.chars().filter(significant).map(|c| c.to_ascii_lowercase());
+    let b = b.chars().filter(significant).map(|c| c.to_ascii_lowercase());
+    a.eq(b)
+}
+
+/// Shorthands such as `w` aren't Unicode names, so they must be spelled exactly
+fn is_shorthand(name: GroupName) -> bool {
+    matches!(
+        name,
+        GroupName::Word
+            | GroupName::Digit
+            | GroupName::Space
+            | GroupName::HorizSpace
+            | GroupName::VertSpace
+    )
+}
+
+#[cfg(feature = "suggestions")]
+const PROPERTIES: &[&str] =
+    &["Block", "General_Category", "Script", "Script_Extensions", "blk", "gc", "sc", "scx"];
+
+/// Parses a Unicode property with a value, e.g. `gc=Lu`. The value must be of
+/// the kind specified by the property. Property names are matched loosely,
+/// like the values.
+pub(super) fn parse_property_value(
+    property: &str,
+    value: &str,
+) -> Result<GroupName, CharClassError> {
+    let property =
+        ["General_Category", "gc", "Script", "sc", "Script_Extensions", "scx", "Block", "blk"]
+            .into_iter()
+            .find(|&p| loose_eq(p, property))
+            .unwrap_or(property);
+
+    let name = match property {
+        "General_Category" | "gc" | "Script" | "sc" => parse_group_name(value)?,
+        "Script_Extensions" | "scx" => match parse_group_name(value)? {
+            GroupName::Script(s) => GroupName::ScriptExtension(s),
+            _ => return Err(invalid_property_value(property, value)),
+        },
+        "Block" | "blk" => parse_group_name(&format!("In{value}"))
+            .map_err(|_| invalid_property_value(property, value))?,
+        _ => {
+            return Err(CharClassError::UnknownProperty {
+                found: property.into(),
+                #[cfg(feature = "suggestions")]
+                similar: crate::util::find_suggestion(property, PROPERTIES.iter().copied()),
+            })
+        }
+    };
+
+    match (property, name) {
+        ("General_Category" | "gc", GroupName::Category(_))
+        | ("Script" | "sc", GroupName::Script(_))
+        | ("Script_Extensions" | "scx", GroupName::ScriptExtension(_))
+        | ("Block" | "blk", GroupName::CodeBlock(_)) => Ok(name),
+        _ => Err(invalid_property_value(property, value)),
+    }
+}
+
+fn invalid_property_value(property: &str, value: &str) -> CharClassError {
+    CharClassError::InvalidPropertyValue { property: property.into(), value: value.into() }
+}
+
 // The following macro is used to generate the code below; however,
 // the lookup table must be sorted manually to allow binary search.
 
@@ -1,9 +1,84 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
-use crate::{error::CompileError, regex::Regex, repetition::RegexQuantifier, rule::Rule};
+use crate::{
+    error::CompileError, optimization::OptimizationLog, regex::Regex, repetition::RegexQuantifier,
+    rule::Rule,
+};
 
 pub(crate) type CompileResult<'i> = Result<Regex<'i>, CompileError>;
 
+/// The output the regex is written to during code generation. This is
+/// implemented for `String`, and for [`Writer`], so [`Expr::compile_to`]
+/// can write the regex directly to any [`fmt::Write`] implementation.
+///
+/// [`Expr::compile_to`]: crate::Expr::compile_to
+pub(crate) trait Output: fmt::Write {
+    fn push(&mut self, c: char);
+
+    fn push_str(&mut self, s: &str);
+}
+
+impl Output for String {
+    fn push(&mut self, c: char) {
+        String::push(self, c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        String::push_str(self, s);
+    }
+}
+
+/// Writes to a [`fmt::Write`] implementation. Code generation can't fail,
+/// so the first error is stored, and everything after it is discarded.
+pub(crate) struct Writer<'w, W> {
+    out: &'w mut W,
+    pub(crate) result: fmt::Result,
+}
+
+impl<'w, W: fmt::Write> Writer<'w, W> {
+    pub(crate) fn new(out: &'w mut W) -> Self {
+        Writer { out, result: Ok(()) }
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for Writer<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.result.is_ok() {
+            self.result = self.out.write_str(s);
+        }
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        if self.result.is_ok() {
+            self.result = self.out.write_char(c);
+        }
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> Output for Writer<'_, W> {
+    fn push(&mut self, c: char) {
+        let _ = fmt::Write::write_char(self, c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        let _ = fmt::Write::write_str(self, s);
+    }
+}
+
+/// The number of nodes in the syntax tree, which is compared to the limits in
+/// the [`CompileOptions`](crate::options::CompileOptions) before compiling.
+/// Variables are counted once where they are declared.
+#[derive(Default)]
+pub(crate) struct NodeCounts {
+    pub(crate) nodes: u64,
+    pub(crate) alternation_branches: u64,
+}
+
 #[derive(Clone)]
 pub(crate) struct CompileState<'c, 'i> {
     pub(crate) next_idx: u32,
@@ -12,5 +87,20 @@ pub(crate) struct CompileState<'c, 'i> {
 
     pub(crate) default_quantifier: RegexQuantifier,
     pub(crate) variables: Vec<(&'i str, &'c Rule<'i>)>,
-    pub(crate) current_vars: HashSet<usize>,
+    /// The indices of the variables that are currently being compiled, in
+    /// the order in which they were entered. This is used to detect cycles.
+    pub(crate) current_vars: Vec<usize>,
+    /// The indices of the variables declared in a `define` block. Using one
+    /// of these compiles to a subroutine call instead of the variable's
+    /// content.
+    pub(crate) subroutines: HashSet<usize>,
+    /// The delimiters of the `balanced(...)` expressions. Each pair is
+    /// compiled to a capturing group after all other groups, which is called
+    /// where the expression is used.
+    pub(crate) balanced: Vec<(char, char)>,
+    /// Group names that were sanitized because of
+    /// [`NameHandling::Sanitize`](crate::options::NameHandling), mapped to
+    /// their new names
+    pub(crate) renamed_groups: HashMap<String, String>,
+    pub(crate) optimizations: OptimizationLog,
 }
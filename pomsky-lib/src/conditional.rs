@@ -0,0 +1,167 @@
+//! Implements conditionals, `if ::1 { 'a' } else { 'b' }`, which match one of
+//! two expressions depending on whether a capturing group has participated in
+//! the match.
+
+use std::collections::HashMap;
+
+use crate::{
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts, Output},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
+    group::{codegen_close_paren, codegen_open_paren},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
+    reference::{Reference, RegexReference},
+    regex::Regex,
+    rule::Rule,
+    span::Span,
+    warning::Warning,
+};
+
+/// A conditional, `if ::group { ... } else { ... }`. It is compiled to
+/// `(?(group)then|else)`, which is only supported by PCRE, .NET and Python.
+///
+/// The `else` branch is optional; if it is missing, the conditional matches
+/// the empty string when the group hasn't participated in the match. The
+/// group can be referenced by name, number or relative number, and may
+/// appear after the conditional.
+#[derive(Clone)]
+pub(crate) struct Conditional<'i> {
+    pub(crate) condition: Reference<'i>,
+    pub(crate) then: Rule<'i>,
+    pub(crate) otherwise: Option<Rule<'i>>,
+    pub(crate) span: Span,
+}
+
+impl<'i> Conditional<'i> {
+    pub(crate) fn new(
+        condition: Reference<'i>,
+        then: Rule<'i>,
+        otherwise: Option<Rule<'i>>,
+        span: Span,
+    ) -> Self {
+        Conditional { condition, then, otherwise, span }
+    }
+
+    pub(crate) fn get_capturing_groups(
+        &self,
+        count: &mut u32,
+        map: &mut HashMap<String, u32>,
+        within_variable: bool,
+    ) -> Result<(), CompileError> {
+        self.then.get_capturing_groups(count, map, within_variable)?;
+        if let Some(otherwise) = &self.otherwise {
+            otherwise.get_capturing_groups(count, map, within_variable)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.then.count_nodes(counts);
+        if let Some(otherwise) = &self.otherwise {
+            otherwise.count_nodes(counts);
+        }
+    }
+
+    /// Only one of the branches is matched, so the groups in both branches
+    /// are optional.
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, _optional: bool) {
+        self.condition.capture_info(info);
+        self.then.capture_info(info, true);
+        if let Some(otherwise) = &self.otherwise {
+            otherwise.capture_info(info, true);
+        }
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        self.then.lint(options, warnings, in_repetition);
+        if let Some(otherwise) = &self.otherwise {
+            otherwise.lint(options, warnings, in_repetition);
+        }
+    }
+
+    pub(crate) fn compile<'c>(
+        &'c self,
+        options: &CompileOptions,
+        state: &mut CompileState<'c, 'i>,
+    ) -> CompileResult<'i> {
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
+        let condition = self.condition.compile_condition(options, state)?;
+        let then = self.then.comp(options, state)?;
+        let otherwise = match &self.otherwise {
+            Some(otherwise) => Some(otherwise.comp(options, state)?),
+            None => None,
+        };
+        Ok(Regex::Conditional(Box::new(RegexConditional { condition, then, otherwise })))
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Pcre | RegexFlavor::DotNet | RegexFlavor::Python => None,
+            _ => Some(Feature::Conditional),
+        }
+    }
+
+    pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
+        self.condition.validate(options)?;
+        self.then.validate(options)?;
+        if let Some(otherwise) = &self.otherwise {
+            otherwise.validate(options)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for Conditional<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("Conditional");
+        d.field(&self.condition).field(&self.then);
+        if let Some(otherwise) = &self.otherwise {
+            d.field(otherwise);
+        }
+        d.finish()
+    }
+}
+
+#[cfg_attr(feature = "dbg", derive(Debug))]
+pub(crate) struct RegexConditional<'i> {
+    pub(crate) condition: RegexReference<'i>,
+    pub(crate) then: Regex<'i>,
+    pub(crate) otherwise: Option<Regex<'i>>,
+}
+
+impl RegexConditional<'_> {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        match &self.condition.name {
+            Some(name) => write!(buf, "(?({name})").unwrap(),
+            None => write!(buf, "(?({})", self.condition.number).unwrap(),
+        }
+        codegen_branch(&self.then, buf, flavor);
+        if let Some(otherwise) = &self.otherwise {
+            buf.push('|');
+            codegen_branch(otherwise, buf, flavor);
+        }
+        buf.push(')');
+    }
+}
+
+/// Emits a branch of a conditional. An alternation must be wrapped in a
+/// group, since a conditional can't have more than two branches.
+fn codegen_branch(branch: &Regex<'_>, buf: &mut impl Output, flavor: RegexFlavor) {
+    let needs_parens = branch.needs_parens_in_group();
+    if needs_parens {
+        codegen_open_paren(buf, false, flavor);
+    }
+    branch.codegen(buf, flavor);
+    if needs_parens {
+        codegen_close_paren(buf, flavor);
+    }
+}
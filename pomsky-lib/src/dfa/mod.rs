@@ -0,0 +1,914 @@
+//! Compiles the regular subset of pomsky to a
+//! [deterministic finite automaton](https://en.wikipedia.org/wiki/Deterministic_finite_automaton)
+//! (DFA), so it can be matched without a regex engine.
+//!
+//! The expression is first compiled to an NFA (using
+//! [Thompson's construction](https://en.wikipedia.org/wiki/Thompson%27s_construction)),
+//! which is then converted to a DFA with the powerset construction. Finally,
+//! states that can't lead to a match are removed, and the DFA is minimized.
+//!
+//! Lookaround, references, word boundaries, `Grapheme`, shorthand character
+//! classes (e.g. `[w]`) and Unicode properties can't be compiled to a DFA.
+//!
+//! A DFA always matches the _entire_ input, as if the expression was wrapped
+//! in `Start` and `End`:
+//!
+//! ```
+//! use pomsky::Expr;
+//!
+//! let (expr, _warnings) = Expr::parse("'ab'+ ['0'-'9']?", Default::default()).unwrap();
+//! let dfa = expr.compile_to_dfa().unwrap();
+//!
+//! assert!(dfa.is_match("abab5"));
+//! assert!(dfa.is_match("ab"));
+//! assert!(!dfa.is_match("aba"));
+//! assert!(!dfa.is_match("xab"));
+//! assert_eq!(dfa.states().len(), 4);
+//! ```
+
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, VecDeque};
+
+use crate::{
+    boundary::BoundaryKind,
+    char_class::{RegexCharClass, RegexClassItem},
+    error::{CompileError, CompileErrorKind},
+    group::RegexCapture,
+    line_break::LineBreak,
+    options::CompileOptions,
+    regex::{Regex, RegexShorthand},
+    repetition::{RegexQuantifier, RepetitionKind},
+    rule::Rule,
+    span::Span,
+    Expr,
+};
+
+mod state_elimination;
+
+/// The maximum number of states in the NFA or the unminimized DFA. Without a
+/// limit, a small expression such as `C* 'a' C{20}` could take a very long time
+/// to compile, since the number of DFA states can grow exponentially.
+const MAX_STATES: usize = 10_000;
+
+const MAX_CHAR: u32 = char::MAX as u32;
+
+const MAGIC: &[u8; 4] = b"PDFA";
+const VERSION: u8 = 1;
+
+/// A minimal deterministic finite automaton, created with
+/// [`Expr::compile_to_dfa`](crate::Expr::compile_to_dfa).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dfa {
+    states: Vec<DfaState>,
+    start: u32,
+}
+
+/// A state of a [`Dfa`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DfaState {
+    accepting: bool,
+    transitions: Vec<Transition>,
+}
+
+/// A transition from one [`DfaState`] to another, which is taken when the next
+/// char is in the range `first..=last`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    /// The first char of the range (inclusive)
+    pub first: char,
+    /// The last char of the range (inclusive)
+    pub last: char,
+    /// The index of the target state
+    pub target: u32,
+}
+
+impl Dfa {
+    /// Returns the index of the start state.
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    /// Returns the states of the DFA. A state's index in this slice is the
+    /// number used to refer to it in [`Transition::target`].
+    pub fn states(&self) -> &[DfaState] {
+        &self.states
+    }
+
+    /// Returns `true` if the DFA matches the entire input.
+    pub fn is_match(&self, input: &str) -> bool {
+        let mut state = &self.states[self.start as usize];
+        for c in input.chars() {
+            match state.next(c) {
+                Some(target) => state = &self.states[target as usize],
+                None => return false,
+            }
+        }
+        state.accepting
+    }
+
+    /// Returns the first `n` strings matched by the DFA in shortlex order, i.e.
+    /// shorter strings come first, and strings of the same length are sorted
+    /// by their code points. If the DFA matches fewer than `n` strings, all of
+    /// them are returned.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("'b' | ['a'-'c']{2}", Default::default()).unwrap();
+    /// let dfa = expr.compile_to_dfa().unwrap();
+    /// assert_eq!(dfa.enumerate(4), ["b", "aa", "ab", "ac"]);
+    /// ```
+    pub fn enumerate(&self, n: usize) -> Vec<String> {
+        let mut result = vec![];
+        // the states from which an accepting state can be reached with exactly
+        // `len` more chars, for each `len`
+        let mut reaches_accept = vec![self.states.iter().map(|s| s.accepting).collect::<Vec<_>>()];
+        // the states that can be reached from the start state with `len` chars
+        let mut reachable = vec![false; self.states.len()];
+        reachable[self.start as usize] = true;
+
+        let mut len = 0;
+        while result.len() < n && reachable.contains(&true) {
+            while reaches_accept.len() <= len {
+                let prev = reaches_accept.last().unwrap();
+                let next = self
+                    .states
+                    .iter()
+                    .map(|s| s.transitions.iter().any(|t| prev[t.target as usize]))
+                    .collect();
+                reaches_accept.push(next);
+            }
+
+            let mut prefix = String::new();
+            self.enumerate_len(self.start, len, &reaches_accept, &mut prefix, n, &mut result);
+
+            let mut next = vec![false; self.states.len()];
+            for (state, _) in self.states.iter().enumerate().filter(|&(i, _)| reachable[i]) {
+                for t in &self.states[state].transitions {
+                    next[t.target as usize] = true;
+                }
+            }
+            reachable = next;
+            len += 1;
+        }
+        result
+    }
+
+    /// Appends the strings with `len` more chars that are matched starting at
+    /// `state`, in lexicographic order, until there are `n` strings. Only
+    /// states from which an accepting state can be reached are visited, so
+    /// every path leads to a string.
+    fn enumerate_len(
+        &self,
+        state: u32,
+        len: usize,
+        reaches_accept: &[Vec<bool>],
+        prefix: &mut String,
+        n: usize,
+        result: &mut Vec<String>,
+    ) {
+        if len == 0 {
+            if self.states[state as usize].accepting && result.len() < n {
+                result.push(prefix.clone());
+            }
+            return;
+        }
+        for t in &self.states[state as usize].transitions {
+            if !reaches_accept[len - 1][t.target as usize] {
+                continue;
+            }
+            for c in t.first..=t.last {
+                if result.len() >= n {
+                    return;
+                }
+                prefix.push(c);
+                self.enumerate_len(t.target, len - 1, reaches_accept, prefix, n, result);
+                prefix.pop();
+            }
+        }
+    }
+
+    /// Serializes the DFA to a compact binary format. All numbers are
+    /// little-endian `u32`s, except where noted:
+    ///
+    /// - the magic bytes `PDFA`, followed by the format version (a `u8`,
+    ///   currently 1)
+    /// - the number of states and the index of the start state
+    /// - for each state: a `u8` that is 1 if the state is accepting and 0
+    ///   otherwise, the number of transitions, and for each transition the
+    ///   first char, the last char and the index of the target state
+    ///
+    /// The transitions of each state are sorted and don't overlap.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = MAGIC.to_vec();
+        buf.push(VERSION);
+        push_u32(&mut buf, self.states.len() as u32);
+        push_u32(&mut buf, self.start);
+        for state in &self.states {
+            buf.push(state.accepting as u8);
+            push_u32(&mut buf, state.transitions.len() as u32);
+            for t in &state.transitions {
+                push_u32(&mut buf, t.first as u32);
+                push_u32(&mut buf, t.last as u32);
+                push_u32(&mut buf, t.target);
+            }
+        }
+        buf
+    }
+
+    /// Deserializes a DFA that was serialized with [`Dfa::to_bytes`]. Returns
+    /// `None` if the input isn't a valid DFA.
+    ///
+    /// ```
+    /// use pomsky::{dfa::Dfa, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a' | 'bc'", Default::default()).unwrap();
+    /// let dfa = expr.compile_to_dfa().unwrap();
+    ///
+    /// let bytes = dfa.to_bytes();
+    /// assert_eq!(Dfa::from_bytes(&bytes), Some(dfa));
+    /// assert_eq!(Dfa::from_bytes(&bytes[..bytes.len() - 1]), None);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC || reader.take(1)? != [VERSION] {
+            return None;
+        }
+
+        let len = reader.u32()?;
+        let start = reader.u32()?;
+        if start >= len {
+            return None;
+        }
+
+        let mut states = Vec::new();
+        for _ in 0..len {
+            let accepting = match reader.take(1)? {
+                [0] => false,
+                [1] => true,
+                _ => return None,
+            };
+            let transitions_len = reader.u32()?;
+            let mut transitions: Vec<Transition> = Vec::new();
+            for _ in 0..transitions_len {
+                let first = char::from_u32(reader.u32()?)?;
+                let last = char::from_u32(reader.u32()?)?;
+                let target = reader.u32()?;
+                let overlaps = matches!(transitions.last(), Some(t) if t.last >= first);
+                if first > last || target >= len || overlaps {
+                    return None;
+                }
+                transitions.push(Transition { first, last, target });
+            }
+            states.push(DfaState { accepting, transitions });
+        }
+
+        if !reader.0.is_empty() {
+            return None;
+        }
+        Some(Dfa { states, start })
+    }
+
+    pub(crate) fn from_regex(regex: &Regex<'_>) -> Result<Self, CompileError> {
+        let mut nfa = Nfa { states: vec![] };
+        let start = nfa.add_state()?;
+        let end = nfa.build(regex, start)?;
+
+        let mut dfa = nfa.to_dfa(start, end)?;
+        dfa.remove_dead_states();
+        Ok(dfa.minimize())
+    }
+
+    /// Matches the input without constructing the DFA, see
+    /// [`Expr::is_match`](crate::Expr::is_match).
+    pub(crate) fn regex_is_match(regex: &Regex<'_>, input: &str) -> Result<bool, CompileError> {
+        let mut nfa = Nfa { states: vec![] };
+        let start = nfa.add_state()?;
+        let end = nfa.build(regex, start)?;
+        Ok(nfa.is_match(start, end, input))
+    }
+
+    /// Returns a DFA that matches exactly the strings this DFA doesn't match.
+    ///
+    /// Missing transitions are redirected to a new sink state, so that every
+    /// state has a transition for every char; then accepting and non-accepting
+    /// states are swapped.
+    pub(crate) fn complement(&self) -> Self {
+        let sink = self.states.len() as u32;
+        let mut states: Vec<DfaState> = self
+            .states
+            .iter()
+            .map(|state| {
+                let ranges: Vec<(u32, u32)> =
+                    state.transitions.iter().map(|t| (t.first as u32, t.last as u32)).collect();
+                let mut transitions = state.transitions.clone();
+                transitions.extend(
+                    complement(&merge_ranges(ranges))
+                        .into_iter()
+                        .filter_map(|(first, last)| to_transition(first, last, sink)),
+                );
+                transitions.sort_unstable_by_key(|t| t.first);
+                DfaState { accepting: !state.accepting, transitions }
+            })
+            .collect();
+        states.push(DfaState {
+            accepting: true,
+            transitions: vec![Transition { first: '\0', last: char::MAX, target: sink }],
+        });
+
+        let mut dfa = Dfa { states, start: self.start };
+        dfa.remove_dead_states();
+        dfa.minimize()
+    }
+
+    /// Converts the DFA to an equivalent expression, which is wrapped in
+    /// `Start` and `End`. Returns `None` if the DFA doesn't match anything.
+    pub(crate) fn to_rule(&self) -> Option<Rule<'static>> {
+        state_elimination::dfa_to_rule(self)
+    }
+
+    /// Removes the transitions to states from which no accepting state can be
+    /// reached. States that are no longer reachable are dropped when the DFA
+    /// is minimized.
+    fn remove_dead_states(&mut self) {
+        let mut live = vec![false; self.states.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, state) in self.states.iter().enumerate() {
+                if !live[i]
+                    && (state.accepting
+                        || state.transitions.iter().any(|t| live[t.target as usize]))
+                {
+                    live[i] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        for state in &mut self.states {
+            state.transitions.retain(|t| live[t.target as usize]);
+        }
+    }
+
+    /// Minimizes the DFA with Moore's algorithm, by splitting the states into
+    /// equivalence classes until no class can be split further. Afterwards,
+    /// the classes are numbered in breadth-first order, starting with the start
+    /// state, so the result is deterministic.
+    fn minimize(&self) -> Self {
+        let mut classes: Vec<u32> = self.states.iter().map(|s| s.accepting as u32).collect();
+        let mut class_count = count_distinct(&classes);
+
+        loop {
+            let mut signatures = HashMap::new();
+            let new_classes: Vec<u32> = self
+                .states
+                .iter()
+                .enumerate()
+                .map(|(i, state)| {
+                    let signature = (classes[i], state.transitions_to_classes(&classes));
+                    let next_id = signatures.len() as u32;
+                    *signatures.entry(signature).or_insert(next_id)
+                })
+                .collect();
+
+            let new_count = signatures.len();
+            classes = new_classes;
+            if new_count == class_count {
+                break;
+            }
+            class_count = new_count;
+        }
+
+        let mut representatives = vec![None; class_count];
+        for (i, &class) in classes.iter().enumerate() {
+            representatives[class as usize].get_or_insert(i);
+        }
+
+        let mut order = vec![None; class_count];
+        let mut queue = vec![classes[self.start as usize]];
+        order[queue[0] as usize] = Some(0);
+        let mut i = 0;
+        while let Some(&class) = queue.get(i) {
+            let state = &self.states[representatives[class as usize].unwrap()];
+            for t in &state.transitions {
+                let target = classes[t.target as usize];
+                if order[target as usize].is_none() {
+                    order[target as usize] = Some(queue.len() as u32);
+                    queue.push(target);
+                }
+            }
+            i += 1;
+        }
+
+        let states = queue
+            .iter()
+            .map(|&class| {
+                let state = &self.states[representatives[class as usize].unwrap()];
+                let renumbered: Vec<u32> =
+                    classes.iter().map(|&c| order[c as usize].unwrap_or(u32::MAX)).collect();
+                let transitions = state
+                    .transitions_to_classes(&renumbered)
+                    .into_iter()
+                    .filter_map(|(first, last, target)| to_transition(first, last, target))
+                    .collect();
+                DfaState { accepting: state.accepting, transitions }
+            })
+            .collect();
+
+        Dfa { states, start: 0 }
+    }
+}
+
+/// The result of [`overlaps`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Overlap {
+    /// Both expressions match the contained string, which is one of the
+    /// shortest strings they have in common
+    Overlapping {
+        /// A string matched by both expressions
+        witness: String,
+    },
+    /// There is no string that is matched by both expressions
+    Disjoint,
+    /// At least one of the expressions can't be compiled to a DFA, so it can't
+    /// be determined whether they overlap
+    Undecidable,
+}
+
+/// Checks whether two expressions can match the same string. Like
+/// [`Dfa::is_match`], this considers only matches of the entire string.
+///
+/// This works by searching the product of the expressions' DFAs for a state
+/// that is accepting in both. If one of the expressions can't be compiled to a
+/// DFA, [`Overlap::Undecidable`] is returned.
+///
+/// ```
+/// use pomsky::{dfa::{overlaps, Overlap}, Expr};
+///
+/// let (a, _) = Expr::parse("'foo' | ['0'-'9']+", Default::default()).unwrap();
+/// let (b, _) = Expr::parse("['5'-'7'] 'x'?", Default::default()).unwrap();
+/// let (c, _) = Expr::parse("'bar'", Default::default()).unwrap();
+/// let (d, _) = Expr::parse("(>> 'foo') C*", Default::default()).unwrap();
+///
+/// assert_eq!(overlaps(&a, &b), Overlap::Overlapping { witness: "5".into() });
+/// assert_eq!(overlaps(&a, &c), Overlap::Disjoint);
+/// assert_eq!(overlaps(&a, &d), Overlap::Undecidable);
+/// ```
+pub fn overlaps(a: &Expr<'_>, b: &Expr<'_>) -> Overlap {
+    let (a, b) = match (a.compile_to_dfa(), b.compile_to_dfa()) {
+        (Ok(a), Ok(b)) => (a, b),
+        _ => return Overlap::Undecidable,
+    };
+
+    // breadth-first search, so the witness is as short as possible
+    let start = (a.start, b.start);
+    let mut predecessors = HashMap::new();
+    predecessors.insert(start, None);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(pair @ (sa, sb)) = queue.pop_front() {
+        let (state_a, state_b) = (&a.states[sa as usize], &b.states[sb as usize]);
+        if state_a.accepting && state_b.accepting {
+            let mut witness = vec![];
+            let mut current = pair;
+            while let Some((prev, c)) = predecessors[&current] {
+                witness.push(c);
+                current = prev;
+            }
+            return Overlap::Overlapping { witness: witness.into_iter().rev().collect() };
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while let (Some(ta), Some(tb)) = (state_a.transitions.get(i), state_b.transitions.get(j)) {
+            let first = ta.first.max(tb.first);
+            if first <= ta.last.min(tb.last) {
+                let next = (ta.target, tb.target);
+                if let Entry::Vacant(entry) = predecessors.entry(next) {
+                    entry.insert(Some((pair, first)));
+                    queue.push_back(next);
+                }
+            }
+            if ta.last < tb.last {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    Overlap::Disjoint
+}
+
+impl DfaState {
+    /// Returns `true` if the input is matched when it ends in this state.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting
+    }
+
+    /// Returns the outgoing transitions, sorted by their char ranges. Chars
+    /// for which there is no transition can't be matched in this state.
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// Returns the index of the state to go to when the next char is `c`, or
+    /// `None` if `c` can't be matched in this state.
+    pub fn next(&self, c: char) -> Option<u32> {
+        let idx = self.transitions.partition_point(|t| t.last < c);
+        self.transitions.get(idx).filter(|t| t.first <= c).map(|t| t.target)
+    }
+
+    /// Returns the transitions with the targets replaced by their classes.
+    /// Adjacent ranges with the same target class are merged.
+    fn transitions_to_classes(&self, classes: &[u32]) -> Vec<(u32, u32, u32)> {
+        let mut result: Vec<(u32, u32, u32)> = vec![];
+        for t in &self.transitions {
+            push_merged(&mut result, (t.first as u32, t.last as u32, classes[t.target as usize]));
+        }
+        result
+    }
+}
+
+/// A state of a non-deterministic finite automaton. Besides epsilon
+/// transitions, there are transitions that can only be taken at the start or
+/// at the end of the input.
+#[derive(Default)]
+struct NfaState {
+    ranges: Vec<(u32, u32, usize)>,
+    epsilon: Vec<usize>,
+    at_start: Vec<usize>,
+    at_end: Vec<usize>,
+}
+
+struct Nfa {
+    states: Vec<NfaState>,
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> Result<usize, CompileError> {
+        if self.states.len() >= MAX_STATES {
+            return Err(too_large());
+        }
+        self.states.push(NfaState::default());
+        Ok(self.states.len() - 1)
+    }
+
+    fn add_ranges(&mut self, from: usize, ranges: &[(u32, u32)]) -> Result<usize, CompileError> {
+        let to = self.add_state()?;
+        self.states[from].ranges.extend(ranges.iter().map(|&(first, last)| (first, last, to)));
+        Ok(to)
+    }
+
+    /// Adds the states for `regex`, starting at `from`, and returns the state
+    /// where they end.
+    fn build(&mut self, regex: &Regex<'_>, from: usize) -> Result<usize, CompileError> {
+        match regex {
+            Regex::Literal(l) => {
+                l.chars().try_fold(from, |from, c| self.add_ranges(from, &[(c as u32, c as u32)]))
+            }
+            &Regex::Char(c) => self.add_ranges(from, &[(c as u32, c as u32)]),
+            Regex::CharClass(class) => {
+                let ranges = class_ranges(class)?;
+                self.add_ranges(from, &ranges)
+            }
+            Regex::Dot => self.add_ranges(from, &complement(&[('\n' as u32, '\n' as u32)])),
+            // an atomic group can prevent a match, like a possessive repetition
+            Regex::Group(g) if matches!(g.capture, RegexCapture::Atomic) => {
+                Err(unsupported("Atomic groups"))
+            }
+            Regex::Group(g) if matches!(g.capture, RegexCapture::Flags { .. }) => {
+                Err(unsupported("Inline flags"))
+            }
+            Regex::Group(g) => g.parts.iter().try_fold(from, |from, part| self.build(part, from)),
+            Regex::Alternation(a) => {
+                let end = self.add_state()?;
+                for part in &a.parts {
+                    let start = self.add_state()?;
+                    self.states[from].epsilon.push(start);
+                    let part_end = self.build(part, start)?;
+                    self.states[part_end].epsilon.push(end);
+                }
+                Ok(end)
+            }
+            // a possessive repetition can prevent a match, which a DFA can't express
+            Regex::Repetition(r) if matches!(r.quantifier, RegexQuantifier::Possessive) => {
+                Err(unsupported("Possessive quantifiers"))
+            }
+            Regex::Repetition(r) => {
+                let RepetitionKind { lower_bound, upper_bound } = r.kind;
+
+                let mut current = from;
+                for _ in 0..lower_bound {
+                    let len = self.states.len();
+                    current = self.build(&r.content, current)?;
+                    if self.states.len() == len {
+                        // the content is empty, so there is no need to repeat it
+                        break;
+                    }
+                }
+
+                match upper_bound {
+                    None => {
+                        let repeat = self.add_state()?;
+                        self.states[current].epsilon.push(repeat);
+                        let content_end = self.build(&r.content, repeat)?;
+                        self.states[content_end].epsilon.push(repeat);
+                        Ok(repeat)
+                    }
+                    Some(upper_bound) => {
+                        let end = self.add_state()?;
+                        for _ in lower_bound..upper_bound {
+                            let len = self.states.len();
+                            self.states[current].epsilon.push(end);
+                            current = self.build(&r.content, current)?;
+                            if self.states.len() == len {
+                                break;
+                            }
+                        }
+                        self.states[current].epsilon.push(end);
+                        Ok(end)
+                    }
+                }
+            }
+            Regex::Boundary(BoundaryKind::Start) => {
+                let to = self.add_state()?;
+                self.states[from].at_start.push(to);
+                Ok(to)
+            }
+            // the DFA always matches the entire input, so there's no final newline
+            Regex::Boundary(BoundaryKind::End | BoundaryKind::EndOfText) => {
+                let to = self.add_state()?;
+                self.states[from].at_end.push(to);
+                Ok(to)
+            }
+            Regex::Boundary(BoundaryKind::Word | BoundaryKind::NotWord) => {
+                Err(unsupported("Word boundaries"))
+            }
+            Regex::Boundary(BoundaryKind::EndOfTextOrBeforeNewline) => {
+                Err(unsupported("`EndOfTextOrBeforeNewline`"))
+            }
+            Regex::Boundary(BoundaryKind::LineStart | BoundaryKind::LineEnd) => {
+                Err(unsupported("`LineStart` and `LineEnd`"))
+            }
+            Regex::Shorthand(_) | Regex::Property { .. } => {
+                Err(unsupported("Shorthand character classes and Unicode properties"))
+            }
+            Regex::ClassIntersection(_) => Err(unsupported("Character class intersections")),
+            Regex::Grapheme => Err(unsupported("`Grapheme`")),
+            Regex::LineBreak => self.build(&LineBreak::expand(&CompileOptions::default())?, from),
+            Regex::Lookaround(_) => Err(unsupported("Lookarounds")),
+            Regex::Reference(_) => Err(unsupported("References")),
+            Regex::Conditional(_) => Err(unsupported("Conditionals")),
+            Regex::SubroutineCall(_) => Err(unsupported("Subroutine calls")),
+            Regex::Recursion(_) => Err(unsupported("Recursion")),
+        }
+    }
+
+    /// Returns `true` if the NFA matches the entire input. The NFA is simulated
+    /// by tracking the set of states it can be in, so unlike converting it to
+    /// a DFA, this takes linear time in the length of the input.
+    fn is_match(&self, start: usize, end: usize, input: &str) -> bool {
+        let mut current = self.closure([start], true, input.is_empty());
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            let c = c as u32;
+            let targets = current
+                .iter()
+                .flat_map(|&s| &self.states[s].ranges)
+                .filter(|&&(first, last, _)| first <= c && c <= last)
+                .map(|&(_, _, target)| target);
+            current = self.closure(targets, false, chars.peek().is_none());
+            if current.is_empty() {
+                return false;
+            }
+        }
+        current.contains(&end)
+    }
+
+    /// Returns the states that can be reached from `states` without consuming
+    /// a char. `at_start` and `at_end` specify whether the current position is
+    /// at the start or at the end of the input.
+    fn closure(
+        &self,
+        states: impl IntoIterator<Item = usize>,
+        at_start: bool,
+        at_end: bool,
+    ) -> BTreeSet<usize> {
+        let mut result = BTreeSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+        while let Some(s) = stack.pop() {
+            if result.insert(s) {
+                let state = &self.states[s];
+                stack.extend(&state.epsilon);
+                if at_start {
+                    stack.extend(&state.at_start);
+                }
+                if at_end {
+                    stack.extend(&state.at_end);
+                }
+            }
+        }
+        result
+    }
+
+    /// Converts the NFA to a DFA using the powerset construction. The start
+    /// state is distinguished from other states with the same NFA states,
+    /// since only the start state is at the start of the input.
+    fn to_dfa(&self, start: usize, end: usize) -> Result<Dfa, CompileError> {
+        let mut sets = vec![(self.closure([start], true, false), true)];
+        let mut ids = HashMap::new();
+        ids.insert(sets[0].clone(), 0);
+
+        let mut states = vec![];
+        while states.len() < sets.len() {
+            let (set, is_start) = &sets[states.len()];
+            let accepting = self.closure(set.iter().copied(), *is_start, true).contains(&end);
+
+            let ranges: Vec<(u32, u32, usize)> =
+                set.iter().flat_map(|&s| self.states[s].ranges.iter().copied()).collect();
+            let mut bounds: Vec<u32> =
+                ranges.iter().flat_map(|&(first, last, _)| [first, last + 1]).collect();
+            bounds.sort_unstable();
+            bounds.dedup();
+
+            let mut transitions = vec![];
+            for window in bounds.windows(2) {
+                let (first, last) = (window[0], window[1] - 1);
+                let targets = ranges
+                    .iter()
+                    .filter(|&&(f, l, _)| f <= first && first <= l)
+                    .map(|&(_, _, target)| target);
+                let target_set = (self.closure(targets, false, false), false);
+                if target_set.0.is_empty() {
+                    continue;
+                }
+
+                let target = match ids.get(&target_set) {
+                    Some(&id) => id,
+                    None => {
+                        if sets.len() >= MAX_STATES {
+                            return Err(too_large());
+                        }
+                        let id = sets.len() as u32;
+                        ids.insert(target_set.clone(), id);
+                        sets.push(target_set);
+                        id
+                    }
+                };
+                push_merged(&mut transitions, (first, last, target));
+            }
+
+            let transitions = transitions
+                .into_iter()
+                .filter_map(|(first, last, target)| to_transition(first, last, target))
+                .collect();
+            states.push(DfaState { accepting, transitions });
+        }
+
+        Ok(Dfa { states, start: 0 })
+    }
+}
+
+/// Returns the sorted, non-overlapping ranges matched by a character class.
+fn class_ranges(class: &RegexCharClass) -> Result<Vec<(u32, u32)>, CompileError> {
+    let mut ranges = vec![];
+    let mut shorthands = vec![];
+    for item in &class.items {
+        match *item {
+            RegexClassItem::Char(c) => ranges.push((c as u32, c as u32)),
+            RegexClassItem::Range { first, last } => ranges.push((first as u32, last as u32)),
+            RegexClassItem::Shorthand(s) => shorthands.push(s),
+            RegexClassItem::Property { .. } | RegexClassItem::Nested { .. } => {
+                return Err(unsupported("Shorthand character classes and Unicode properties"))
+            }
+        }
+    }
+
+    if !shorthands.is_empty() {
+        // `Codepoint` is compiled to `[\s\S]`, which can be supported without
+        // knowing which chars are matched by `\s`
+        let matches_all =
+            shorthands.iter().any(|&a| shorthands.iter().any(|&b| are_complementary(a, b)));
+        if !matches_all {
+            return Err(unsupported("Shorthand character classes and Unicode properties"));
+        }
+        ranges = vec![(0, MAX_CHAR)];
+    }
+
+    let merged = merge_ranges(ranges);
+    Ok(if class.negative { complement(&merged) } else { merged })
+}
+
+/// Sorts the ranges and merges the ones that overlap or are adjacent. Ranges
+/// separated only by surrogates are adjacent, since surrogates aren't valid
+/// chars.
+fn merge_ranges(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = vec![];
+    for (first, last) in ranges {
+        match merged.last_mut() {
+            Some(prev)
+                if first <= prev.1.saturating_add(1) || (prev.1 == 0xD7FF && first == 0xE000) =>
+            {
+                prev.1 = prev.1.max(last)
+            }
+            _ => merged.push((first, last)),
+        }
+    }
+    merged
+}
+
+fn are_complementary(a: RegexShorthand, b: RegexShorthand) -> bool {
+    use RegexShorthand::*;
+
+    matches!(
+        (a, b),
+        (Word, NotWord)
+            | (NotWord, Word)
+            | (Digit, NotDigit)
+            | (NotDigit, Digit)
+            | (Space, NotSpace)
+            | (NotSpace, Space)
+            | (HorizSpace, NotHorizSpace)
+            | (NotHorizSpace, HorizSpace)
+            | (VertSpace, NotVertSpace)
+            | (NotVertSpace, VertSpace)
+    )
+}
+
+/// Returns the ranges that aren't matched by `ranges`, which must be sorted
+/// and non-overlapping.
+fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut result = vec![];
+    let mut next = 0;
+    for &(first, last) in ranges {
+        if first > next {
+            result.push((next, first - 1));
+        }
+        next = last + 1;
+    }
+    if next <= MAX_CHAR {
+        result.push((next, MAX_CHAR));
+    }
+    result
+}
+
+/// Pushes a transition, merging it with the previous one if they're adjacent
+/// and have the same target. Ranges separated only by surrogates are adjacent,
+/// since surrogates aren't valid chars.
+fn push_merged(transitions: &mut Vec<(u32, u32, u32)>, (first, last, target): (u32, u32, u32)) {
+    match transitions.last_mut() {
+        Some(prev)
+            if prev.2 == target
+                && (prev.1 + 1 == first || (prev.1 == 0xD7FF && first == 0xE000)) =>
+        {
+            prev.1 = last;
+        }
+        _ => transitions.push((first, last, target)),
+    }
+}
+
+/// Converts a range to a [`Transition`]. The range is shrunk if it starts or
+/// ends with a surrogate, and `None` is returned if it only contains
+/// surrogates.
+fn to_transition(first: u32, last: u32, target: u32) -> Option<Transition> {
+    let first = if (0xD800..=0xDFFF).contains(&first) { 0xE000 } else { first };
+    let last = if (0xD800..=0xDFFF).contains(&last) { 0xD7FF } else { last };
+    if first > last {
+        return None;
+    }
+    Some(Transition { first: char::from_u32(first)?, last: char::from_u32(last)?, target })
+}
+
+fn count_distinct(classes: &[u32]) -> usize {
+    classes.iter().collect::<BTreeSet<_>>().len()
+}
+
+fn push_u32(buf: &mut Vec<u8>, n: u32) {
+    buf.extend_from_slice(&n.to_le_bytes());
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+fn unsupported(feature: &'static str) -> CompileError {
+    CompileErrorKind::UnsupportedInDfa(feature).at(Span::empty())
+}
+
+fn too_large() -> CompileError {
+    CompileErrorKind::Other("The expression is too large to be compiled to a DFA").at(Span::empty())
+}
@@ -0,0 +1,299 @@
+//! Converts a [`Dfa`] back to an expression using
+//! [state elimination](https://en.wikipedia.org/wiki/Kleene%27s_algorithm).
+//!
+//! The DFA is turned into a generalized NFA, whose transitions are labelled
+//! with regular expressions. Then the states are removed one by one; when a
+//! state is removed, the transitions going through it are replaced with
+//! direct transitions. When only the start and the final state remain, the
+//! label of the transition between them is the result.
+
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::{
+    alternation::Alternation,
+    boundary::{Boundary, BoundaryKind},
+    char_class::{CharClass, CharGroup, GroupItem},
+    group::Group,
+    literal::Literal,
+    repetition::{Quantifier, Repetition, RepetitionKind},
+    rule::Rule,
+    span::Span,
+};
+
+use super::{complement, merge_ranges, to_transition, Dfa};
+
+/// A simple regular expression, used as the label of a transition
+#[derive(Clone, PartialEq, Eq)]
+enum Re {
+    /// The empty string
+    Epsilon,
+    /// A set of code points, as sorted, non-overlapping ranges
+    Set(Vec<(u32, u32)>),
+    Concat(Vec<Re>),
+    Alt(Vec<Re>),
+    Star(Box<Re>),
+    Plus(Box<Re>),
+}
+
+impl Re {
+    fn concat(parts: impl IntoIterator<Item = Re>) -> Re {
+        let mut result = vec![];
+        for part in parts {
+            match part {
+                Re::Epsilon => {}
+                Re::Concat(inner) => result.extend(inner),
+                part => result.push(part),
+            }
+        }
+
+        // `a a*` is simplified to `a+`
+        let mut i = 0;
+        while i + 1 < result.len() {
+            if matches!(&result[i + 1], Re::Star(inner) if **inner == result[i]) {
+                let part = result.remove(i);
+                result[i] = Re::Plus(Box::new(part));
+            }
+            i += 1;
+        }
+
+        match result.len() {
+            0 => Re::Epsilon,
+            1 => result.pop().unwrap(),
+            _ => Re::Concat(result),
+        }
+    }
+
+    fn alt(a: Re, b: Re) -> Re {
+        let mut parts: Vec<Re> = vec![];
+        let mut ranges = vec![];
+        for part in [a, b] {
+            let inner = match part {
+                Re::Alt(inner) => inner,
+                part => vec![part],
+            };
+            for part in inner {
+                match part {
+                    Re::Set(r) => ranges.extend(r),
+                    part if !parts.contains(&part) => parts.push(part),
+                    _ => {}
+                }
+            }
+        }
+        if !ranges.is_empty() {
+            parts.insert(0, Re::Set(merge_ranges(ranges)));
+        }
+
+        if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Re::Alt(parts)
+        }
+    }
+
+    fn star(self) -> Re {
+        match self {
+            Re::Epsilon => Re::Epsilon,
+            Re::Star(_) => self,
+            Re::Plus(inner) => Re::Star(inner),
+            // `(a | ε)*` is equivalent to `a*`
+            Re::Alt(parts) if parts.contains(&Re::Epsilon) => {
+                let parts = parts.into_iter().filter(|p| *p != Re::Epsilon);
+                parts.reduce(Re::alt).map_or(Re::Epsilon, Re::star)
+            }
+            re => Re::Star(Box::new(re)),
+        }
+    }
+
+    fn matches_empty(&self) -> bool {
+        match self {
+            Re::Epsilon | Re::Star(_) => true,
+            Re::Set(_) => false,
+            Re::Concat(parts) => parts.iter().all(Re::matches_empty),
+            Re::Alt(parts) => parts.iter().any(Re::matches_empty),
+            Re::Plus(inner) => inner.matches_empty(),
+        }
+    }
+
+    fn into_rule(self) -> Rule<'static> {
+        let span = Span::empty();
+        match self {
+            Re::Epsilon => Rule::Literal(Literal::new(Cow::Borrowed(""), span)),
+            Re::Set(ranges) => set_to_rule(ranges),
+            Re::Concat(parts) => {
+                let mut rules = vec![];
+                let mut string = String::new();
+                for part in parts {
+                    match part {
+                        Re::Set(ranges) if ranges.len() == 1 && ranges[0].0 == ranges[0].1 => {
+                            string.push(char::from_u32(ranges[0].0).unwrap());
+                        }
+                        part => {
+                            if !string.is_empty() {
+                                let literal = std::mem::take(&mut string);
+                                rules.push(Rule::Literal(Literal::new(Cow::Owned(literal), span)));
+                            }
+                            rules.push(part.into_rule());
+                        }
+                    }
+                }
+                if !string.is_empty() {
+                    rules.push(Rule::Literal(Literal::new(Cow::Owned(string), span)));
+                }
+
+                if rules.len() == 1 {
+                    rules.pop().unwrap()
+                } else {
+                    Rule::Group(Group::new(rules, None, span))
+                }
+            }
+            Re::Alt(parts) => {
+                let optional = parts.contains(&Re::Epsilon)
+                    && !parts.iter().any(|p| *p != Re::Epsilon && p.matches_empty());
+                let rules = parts
+                    .into_iter()
+                    .filter(|p| *p != Re::Epsilon)
+                    .map(Re::into_rule)
+                    .collect::<Vec<_>>();
+                let rule = Alternation::new_expr(rules);
+                if optional {
+                    repetition(rule, RepetitionKind::zero_one())
+                } else {
+                    rule
+                }
+            }
+            Re::Star(inner) => repetition(inner.into_rule(), RepetitionKind::zero_inf()),
+            Re::Plus(inner) => repetition(inner.into_rule(), RepetitionKind::one_inf()),
+        }
+    }
+}
+
+fn repetition(rule: Rule<'static>, kind: RepetitionKind) -> Rule<'static> {
+    let span = Span::empty();
+    Rule::Repetition(Box::new(Repetition::new(rule, kind, Quantifier::Greedy, span)))
+}
+
+/// Converts a set of code points to a character class. If the set is
+/// simpler to express as a negated character class, a negated class is
+/// returned.
+fn set_to_rule(ranges: Vec<(u32, u32)>) -> Rule<'static> {
+    let span = Span::empty();
+    if let [(first, last)] = ranges[..] {
+        if first == last {
+            let c = char::from_u32(first).unwrap();
+            return Rule::Literal(Literal::new(Cow::Owned(c.to_string()), span));
+        }
+    }
+
+    // surrogates aren't valid chars, so they're removed from the complement
+    let negated: Vec<(u32, u32)> = complement(&ranges)
+        .into_iter()
+        .filter_map(|(first, last)| to_transition(first, last, 0))
+        .map(|t| (t.first as u32, t.last as u32))
+        .collect();
+    if negated.is_empty() {
+        return Rule::CharClass(CharClass::new(CharGroup::CodePoint, span));
+    }
+
+    let (ranges, negative) =
+        if negated.len() < ranges.len() { (negated, true) } else { (ranges, false) };
+    let items = ranges
+        .into_iter()
+        .map(|(first, last)| {
+            let (first, last) = (char::from_u32(first).unwrap(), char::from_u32(last).unwrap());
+            if first == last {
+                GroupItem::Char(first)
+            } else {
+                GroupItem::Range { first, last }
+            }
+        })
+        .collect();
+
+    let mut class = CharClass::new(CharGroup::Items(items), span);
+    if negative {
+        class.negate().expect("class is negated only once");
+    }
+    Rule::CharClass(class)
+}
+
+/// Returns an expression matching exactly the strings matched by the DFA,
+/// wrapped in `Start` and `End`. Returns `None` if the DFA doesn't match any
+/// string.
+pub(super) fn dfa_to_rule(dfa: &Dfa) -> Option<Rule<'static>> {
+    let len = dfa.states.len();
+    let (start, end) = (len, len + 1);
+
+    let mut edges: BTreeMap<(usize, usize), Re> = BTreeMap::new();
+    let add_edge = |edges: &mut BTreeMap<_, _>, from, to, re| {
+        let re = match edges.remove(&(from, to)) {
+            Some(prev) => Re::alt(prev, re),
+            None => re,
+        };
+        edges.insert((from, to), re);
+    };
+
+    add_edge(&mut edges, start, dfa.start as usize, Re::Epsilon);
+    for (i, state) in dfa.states.iter().enumerate() {
+        for t in &state.transitions {
+            let set = Re::Set(vec![(t.first as u32, t.last as u32)]);
+            add_edge(&mut edges, i, t.target as usize, set);
+        }
+        if state.accepting {
+            add_edge(&mut edges, i, end, Re::Epsilon);
+        }
+    }
+
+    let mut remaining: Vec<usize> = (0..len).collect();
+    while !remaining.is_empty() {
+        // removing the state with the fewest incoming and outgoing transitions
+        // first keeps the result small
+        let (idx, &state) = remaining
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &q)| {
+                let incoming = edges.keys().filter(|&&(f, t)| t == q && f != q).count();
+                let outgoing = edges.keys().filter(|&&(f, t)| f == q && t != q).count();
+                incoming * outgoing
+            })
+            .unwrap();
+        remaining.remove(idx);
+
+        let self_loop = edges.remove(&(state, state)).map(Re::star);
+        let incoming: Vec<(usize, Re)> = take_edges(&mut edges, |_, t| t == state);
+        let outgoing: Vec<(usize, Re)> = take_edges(&mut edges, |f, _| f == state);
+
+        for (from, re_in) in &incoming {
+            for (to, re_out) in &outgoing {
+                let parts = [Some(re_in.clone()), self_loop.clone(), Some(re_out.clone())];
+                let re = Re::concat(parts.into_iter().flatten());
+                add_edge(&mut edges, *from, *to, re);
+            }
+        }
+    }
+
+    let re = edges.remove(&(start, end))?;
+    let span = Span::empty();
+    Some(Rule::Group(Group::new(
+        vec![
+            Rule::Boundary(Boundary::new(BoundaryKind::Start, span)),
+            re.into_rule(),
+            Rule::Boundary(Boundary::new(BoundaryKind::End, span)),
+        ],
+        None,
+        span,
+    )))
+}
+
+/// Removes the edges matching the predicate, and returns them together with
+/// their other endpoint.
+fn take_edges(
+    edges: &mut BTreeMap<(usize, usize), Re>,
+    predicate: impl Fn(usize, usize) -> bool,
+) -> Vec<(usize, Re)> {
+    let keys: Vec<_> = edges.keys().copied().filter(|&(f, t)| predicate(f, t)).collect();
+    keys.into_iter()
+        .map(|(f, t)| {
+            let re = edges.remove(&(f, t)).unwrap();
+            (if predicate(f, usize::MAX) { t } else { f }, re)
+        })
+        .collect()
+}
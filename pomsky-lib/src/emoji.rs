@@ -0,0 +1,110 @@
+//! Contains the built-in `Emoji` variable, which approximately matches a
+//! single emoji.
+
+use std::borrow::Cow;
+
+use crate::{
+    alternation::Alternation,
+    char_class::{CharClass, CharGroup, GroupItem},
+    group::Group,
+    literal::Literal,
+    repetition::{Quantifier, Repetition, RepetitionKind},
+    rule::Rule,
+    span::Span,
+};
+
+/// Code points that are usually displayed as emoji, approximating the
+/// `Extended_Pictographic` property. Regional indicators and skin tone
+/// modifiers are excluded, since they are only emoji as part of a sequence.
+const PICTOGRAPHIC: &[(char, char)] = &[
+    ('\u{A9}', '\u{A9}'),
+    ('\u{AE}', '\u{AE}'),
+    ('\u{203C}', '\u{203C}'),
+    ('\u{2049}', '\u{2049}'),
+    ('\u{2122}', '\u{2122}'),
+    ('\u{2139}', '\u{2139}'),
+    ('\u{2194}', '\u{2199}'),
+    ('\u{21A9}', '\u{21AA}'),
+    ('\u{231A}', '\u{231B}'),
+    ('\u{2328}', '\u{2328}'),
+    ('\u{23CF}', '\u{23CF}'),
+    ('\u{23E9}', '\u{23F3}'),
+    ('\u{23F8}', '\u{23FA}'),
+    ('\u{24C2}', '\u{24C2}'),
+    ('\u{25AA}', '\u{25AB}'),
+    ('\u{25B6}', '\u{25B6}'),
+    ('\u{25C0}', '\u{25C0}'),
+    ('\u{25FB}', '\u{25FE}'),
+    ('\u{2600}', '\u{27BF}'),
+    ('\u{2934}', '\u{2935}'),
+    ('\u{2B05}', '\u{2B07}'),
+    ('\u{2B1B}', '\u{2B1C}'),
+    ('\u{2B50}', '\u{2B50}'),
+    ('\u{2B55}', '\u{2B55}'),
+    ('\u{3030}', '\u{3030}'),
+    ('\u{303D}', '\u{303D}'),
+    ('\u{3297}', '\u{3297}'),
+    ('\u{3299}', '\u{3299}'),
+    ('\u{1F000}', '\u{1F1E5}'),
+    ('\u{1F200}', '\u{1F3FA}'),
+    ('\u{1F400}', '\u{1FAFF}'),
+];
+
+/// Returns the expression for the built-in `Emoji` variable. It matches
+///
+/// - a flag, i.e. two regional indicators
+/// - a keycap sequence such as `1️⃣`
+/// - a tag sequence such as the flag of Scotland
+/// - a pictographic character, optionally followed by a variation selector or
+///   a skin tone modifier. Several of these can be joined with a zero width
+///   joiner (U+200D), e.g. `👩‍💻`
+///
+/// This is an approximation that doesn't depend on Unicode properties, so it
+/// works in every flavor. It accepts some sequences that aren't valid emoji,
+/// and doesn't know about emoji added in future Unicode versions.
+pub(crate) fn emoji() -> Rule<'static> {
+    let span = Span::empty();
+    let char = |c: char| Rule::Literal(Literal::new(Cow::Owned(c.to_string()), span));
+    let class = |ranges: &[(char, char)]| {
+        let items = ranges
+            .iter()
+            .map(|&(first, last)| {
+                if first == last {
+                    GroupItem::Char(first)
+                } else {
+                    GroupItem::range_unchecked(first, last)
+                }
+            })
+            .collect();
+        Rule::CharClass(CharClass::new(CharGroup::Items(items), span))
+    };
+    let repeat = |rule: Rule<'static>, kind: RepetitionKind| {
+        Rule::Repetition(Box::new(Repetition::new(rule, kind, Quantifier::Greedy, span)))
+    };
+    let seq = |parts: Vec<Rule<'static>>| Rule::Group(Group::new(parts, None, span));
+
+    let flag = repeat(class(&[('\u{1F1E6}', '\u{1F1FF}')]), RepetitionKind::fixed(2));
+    let keycap = seq(vec![
+        class(&[('0', '9'), ('#', '#'), ('*', '*')]),
+        repeat(char('\u{FE0F}'), RepetitionKind::zero_one()),
+        char('\u{20E3}'),
+    ]);
+    let tag = seq(vec![
+        char('\u{1F3F4}'),
+        repeat(class(&[('\u{E0020}', '\u{E007E}')]), RepetitionKind::one_inf()),
+        char('\u{E007F}'),
+    ]);
+    let element = seq(vec![
+        class(PICTOGRAPHIC),
+        repeat(
+            class(&[('\u{FE0F}', '\u{FE0F}'), ('\u{1F3FB}', '\u{1F3FF}')]),
+            RepetitionKind::zero_one(),
+        ),
+    ]);
+    let zwj_sequence = seq(vec![
+        element.clone(),
+        repeat(seq(vec![char('\u{200D}'), element]), RepetitionKind::zero_inf()),
+    ]);
+
+    Alternation::new_expr(vec![flag, keycap, tag, zwj_sequence])
+}
@@ -0,0 +1,98 @@
+//! Checks the compiled regex against limits of the regex engines, such as the
+//! maximum number of capturing groups or the largest repetition count.
+//! Exceeding them isn't an error, since some engines can be configured
+//! differently, but a warning is emitted.
+
+use crate::{
+    ast::{Node, NodeKind},
+    options::RegexFlavor,
+    span::Span,
+    warning::{EngineLimitWarning, Warning, WarningKind},
+};
+
+/// Limits of a regex engine. `None` means that there is no (known) limit.
+struct Limits {
+    max_length: Option<usize>,
+    max_capture_groups: Option<u32>,
+    max_repetition: Option<u32>,
+}
+
+fn limits(flavor: RegexFlavor) -> Limits {
+    match flavor {
+        // with the default link size of 2, a compiled pattern can't exceed
+        // 64 KiB, which is exceeded by almost all patterns longer than that
+        RegexFlavor::Pcre => Limits {
+            max_length: Some(65_535),
+            max_capture_groups: Some(65_535),
+            max_repetition: Some(65_535),
+        },
+        // `ONIG_MAX_CAPTURE_NUM` and `ONIG_MAX_REPEAT_NUM` in Onigmo
+        RegexFlavor::Ruby => Limits {
+            max_length: None,
+            max_capture_groups: Some(32_767),
+            max_repetition: Some(100_000),
+        },
+        // `LUA_MAXCAPTURES` in Lua's `lstrlib.c`
+        RegexFlavor::Lua => {
+            Limits { max_length: None, max_capture_groups: Some(32), max_repetition: None }
+        }
+        // repetition counts are parsed as `int`
+        RegexFlavor::Java | RegexFlavor::DotNet => Limits {
+            max_length: None,
+            max_capture_groups: None,
+            max_repetition: Some(i32::MAX as u32),
+        },
+        // counts must be smaller than `MAXREPEAT`, which is `u32::MAX`
+        RegexFlavor::Python => Limits {
+            max_length: None,
+            max_capture_groups: None,
+            max_repetition: Some(u32::MAX - 1),
+        },
+        _ => Limits { max_length: None, max_capture_groups: None, max_repetition: None },
+    }
+}
+
+pub(crate) fn check(
+    root: Node<'_, '_>,
+    regex: &str,
+    capture_groups: u32,
+    flavor: RegexFlavor,
+    warnings: &mut Vec<Warning>,
+) {
+    let limits = limits(flavor);
+
+    if let Some(limit) = limits.max_length {
+        if regex.len() > limit {
+            let warning = EngineLimitWarning::PatternLength { length: regex.len(), limit, flavor };
+            warnings.push(WarningKind::EngineLimit(warning).at(Span::empty()));
+        }
+    }
+    if let Some(limit) = limits.max_capture_groups {
+        if capture_groups > limit {
+            let warning =
+                EngineLimitWarning::CaptureGroups { count: capture_groups, limit, flavor };
+            warnings.push(WarningKind::EngineLimit(warning).at(Span::empty()));
+        }
+    }
+    if let Some(limit) = limits.max_repetition {
+        check_repetitions(root, limit, flavor, warnings);
+    }
+}
+
+fn check_repetitions(
+    node: Node<'_, '_>,
+    limit: u32,
+    flavor: RegexFlavor,
+    warnings: &mut Vec<Warning>,
+) {
+    if let NodeKind::Repetition { min, max } = node.kind() {
+        let count = max.unwrap_or(min);
+        if count > limit {
+            let warning = EngineLimitWarning::Repetition { count, limit, flavor };
+            warnings.push(WarningKind::EngineLimit(warning).at(node.span()));
+        }
+    }
+    for child in node.children() {
+        check_repetitions(child, limit, flavor, warnings);
+    }
+}
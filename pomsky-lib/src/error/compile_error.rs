@@ -3,13 +3,23 @@ use crate::{options::RegexFlavor, span::Span};
 use super::{Diagnostic, ParseError, ParseErrorKind};
 
 /// An error that can occur during parsing or compiling
-#[derive(Debug, Clone, thiserror::Error)]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub struct CompileError {
     pub(super) kind: CompileErrorKind,
     pub(super) span: Span,
 }
 
 impl CompileError {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &CompileErrorKind {
+        &self.kind
+    }
+
+    /// Returns the position in the source code where this error occurred.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     pub(crate) fn set_missing_span(&mut self, span: Span) {
         if self.span.is_empty() {
             self.span = span;
@@ -43,54 +53,166 @@ impl From<ParseError> for CompileError {
     }
 }
 
-/// An error kind (without span) that can occur during parsing or compiling
+/// An error kind (without span) that can occur during parsing or compiling.
+///
+/// ```
+/// use pomsky::{error::CompileErrorKind, Expr};
+///
+/// let err = Expr::parse_and_compile("foo", Default::default(), Default::default()).unwrap_err();
+/// assert!(matches!(err.kind(), CompileErrorKind::UnknownVariable { .. }));
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum CompileErrorKind {
+pub enum CompileErrorKind {
+    /// Several errors, which are reported together. This is returned when an
+    /// expression uses several features that the regex flavor doesn't
+    /// support.
+    #[error("Multiple compile errors encountered")]
+    Multiple(Box<[CompileError]>),
+
+    /// An error that occurred during parsing
     #[error("Parse error: {}", .0)]
     ParseError(ParseErrorKind),
 
-    #[error("Compile error: Unsupported feature `{}` in the `{:?}` regex flavor", .0.name(), .1)]
+    /// A feature that isn't supported by the targeted regex flavor
+    #[error("Compile error: Unsupported feature `{}` in the `{}` regex flavor", .0.name(), .1.name())]
     Unsupported(Feature, RegexFlavor),
 
+    /// A reference to a group with a number that is too large
     #[error("Group references this large aren't supported")]
     HugeReference,
 
+    /// A reference to a group number that doesn't exist
     #[error("Reference to unknown group. There is no group number {}", .0)]
     UnknownReferenceNumber(i32),
 
+    /// A relative reference to a group that doesn't exist, e.g. `::-2` when
+    /// there is only one group before it
+    #[error("Reference to unknown group. There is no group at relative position {:+}", .0)]
+    UnknownRelativeReference(i32),
+
+    /// A reference to a group name that doesn't exist
     #[error("Reference to unknown group. There is no group named `{}`", .found)]
     UnknownReferenceName {
+        /// The name of the referenced group
         found: Box<str>,
+        /// A similar group name, if one exists
         #[cfg(feature = "suggestions")]
         similar: Option<Box<str>>,
     },
 
+    /// Several groups with the same name
     #[error("Compile error: Group name `{}` used multiple times", .0)]
     NameUsedMultipleTimes(String),
 
+    /// A group name that is valid in pomsky, but not in the targeted regex
+    /// flavor. See [`NameHandling`](crate::options::NameHandling).
+    #[error(
+        "Compile error: Group name `{}` isn't supported in the `{}` regex flavor: {}",
+        .name,
+        .flavor.name(),
+        .reason
+    )]
+    UnsupportedGroupName {
+        /// The group name
+        name: String,
+        /// The targeted regex flavor
+        flavor: RegexFlavor,
+        /// Why the name isn't supported
+        reason: &'static str,
+    },
+
+    /// A character class without any items
     #[error("Compile error: This character class is empty")]
     EmptyClass,
 
+    /// A negated character class that matches nothing, e.g. `![C]`
     #[error("Compile error: This negated character class matches nothing")]
     EmptyClassNegated,
 
+    /// A capturing group within a `let` statement
     #[error("Capturing groups within `let` statements are currently not supported")]
     CaptureInLet,
 
+    /// A reference within a `let` statement
     #[error("References within `let` statements are currently not supported")]
     ReferenceInLet,
 
+    /// A variable that isn't declared
     #[error("Variable `{}` doesn't exist", .found)]
     UnknownVariable {
+        /// The name of the variable
         found: Box<str>,
+        /// A similar variable name, if one exists
         #[cfg(feature = "suggestions")]
         similar: Option<Box<str>>,
     },
 
-    #[error("Variables can't be used recursively")]
-    RecursiveVariable,
+    /// A variable that refers to itself, directly or through other variables
+    #[error("Variable `{}` can't be used recursively: {}", .name, .cycle.join(" -> "))]
+    RecursiveVariable {
+        /// The name of the variable
+        name: Box<str>,
+        /// The variables involved in the cycle, starting and ending with
+        /// `name`, e.g. `["a", "b", "a"]`
+        cycle: Vec<Box<str>>,
+    },
+
+    /// A feature that can't be compiled to a [DFA](crate::dfa::Dfa)
+    #[error("Compile error: {} can't be compiled to a DFA", .0)]
+    UnsupportedInDfa(&'static str),
 
+    /// The expression has more alternation branches than allowed by
+    /// [`CompileOptions::max_alternation_branches`](crate::options::CompileOptions::max_alternation_branches)
+    #[error(
+        "Compile error: The expression has {} alternation branches, but the limit is {}",
+        .count,
+        .limit
+    )]
+    TooManyAlternationBranches {
+        /// The number of alternation branches in the expression
+        count: u64,
+        /// The maximum number of alternation branches
+        limit: u32,
+    },
+
+    /// The expression has more nodes than allowed by
+    /// [`CompileOptions::max_nodes`](crate::options::CompileOptions::max_nodes)
+    #[error("Compile error: The expression has {} nodes, but the limit is {}", .count, .limit)]
+    TooManyNodes {
+        /// The number of nodes in the expression
+        count: u64,
+        /// The maximum number of nodes
+        limit: u32,
+    },
+
+    /// A Unicode feature, e.g. a Unicode property, in
+    /// [byte mode](crate::options::CompileOptions::byte_mode)
+    #[error("Compile error: {} can't be used in byte mode", .0)]
+    UnicodeInByteMode(&'static str),
+
+    /// A code point above U+FF in
+    /// [byte mode](crate::options::CompileOptions::byte_mode), which can't be
+    /// matched as a single byte
+    #[error(
+        "Compile error: U+{:04X} can't be matched in byte mode, since it is greater than U+FF",
+        *.0 as u32
+    )]
+    CodePointInByteMode(char),
+
+    /// A boundary or lookaround that is repeated more than once, e.g. `%+`.
+    /// Assertions don't consume characters, so this is pointless. An optional
+    /// assertion, e.g. `%?`, is allowed.
+    #[error("Compile error: An assertion can't be repeated")]
+    RepeatedAssertion,
+
+    /// A numeric reference, e.g. `::1`, in an expression with a `define`
+    /// block. The variables in the block are numbered capturing groups, so
+    /// the number might refer to one of them instead of the intended group.
+    #[error("Compile error: Numeric references can't be used in an expression with `define`")]
+    NumericReferenceWithDefine,
+
+    /// Another error, described by the message
     #[error("Compile error: {}", .0)]
     Other(&'static str),
 }
@@ -99,6 +221,86 @@ impl CompileErrorKind {
     pub(crate) fn at(self, span: Span) -> CompileError {
         CompileError { kind: self, span }
     }
+
+    /// Returns a stable code that identifies this kind of error, e.g. `C0011`
+    /// for an unknown variable. It is used as the [`Diagnostic::code`] of the
+    /// error.
+    ///
+    /// Codes of compile errors start with `C`. Unsupported features have a
+    /// code in the `C01xx` range, which depends on the feature but not on the
+    /// regex flavor. For a [`CompileErrorKind::ParseError`], the code of the
+    /// parse error is returned.
+    ///
+    /// ```
+    /// use pomsky::{options::{CompileOptions, RegexFlavor}, Expr};
+    ///
+    /// let options = CompileOptions { flavor: RegexFlavor::Rust, ..Default::default() };
+    /// let err = Expr::parse_and_compile("(>> 'a')", Default::default(), options).unwrap_err();
+    /// assert_eq!(err.kind().error_code(), "C0102");
+    /// ```
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            CompileErrorKind::Multiple(_) => "C0001",
+            CompileErrorKind::ParseError(kind) => kind.error_code(),
+            CompileErrorKind::HugeReference => "C0002",
+            CompileErrorKind::UnknownReferenceNumber(_) => "C0003",
+            CompileErrorKind::UnknownReferenceName { .. } => "C0004",
+            CompileErrorKind::NameUsedMultipleTimes(_) => "C0005",
+            CompileErrorKind::UnsupportedGroupName { .. } => "C0006",
+            CompileErrorKind::EmptyClass => "C0007",
+            CompileErrorKind::EmptyClassNegated => "C0008",
+            CompileErrorKind::CaptureInLet => "C0009",
+            CompileErrorKind::ReferenceInLet => "C0010",
+            CompileErrorKind::UnknownVariable { .. } => "C0011",
+            CompileErrorKind::RecursiveVariable { .. } => "C0012",
+            CompileErrorKind::UnsupportedInDfa(_) => "C0013",
+            CompileErrorKind::TooManyAlternationBranches { .. } => "C0014",
+            CompileErrorKind::TooManyNodes { .. } => "C0015",
+            CompileErrorKind::Other(_) => "C0016",
+            CompileErrorKind::UnicodeInByteMode(_) => "C0017",
+            CompileErrorKind::CodePointInByteMode(_) => "C0018",
+            CompileErrorKind::RepeatedAssertion => "C0019",
+            CompileErrorKind::UnknownRelativeReference(_) => "C0020",
+            CompileErrorKind::NumericReferenceWithDefine => "C0021",
+
+            CompileErrorKind::Unsupported(feature, _) => match feature {
+                Feature::NamedCaptureGroups => "C0101",
+                Feature::Lookaround => "C0102",
+                Feature::Grapheme => "C0103",
+                Feature::UnicodeBlock => "C0104",
+                Feature::UnicodeProp => "C0105",
+                Feature::Backreference => "C0106",
+                Feature::ForwardReference => "C0107",
+                Feature::RelativeReference => "C0108",
+                Feature::NonNegativeRelativeReference => "C0109",
+                Feature::NegativeShorthandW => "C0110",
+                Feature::NegativeShorthandInClass => "C0111",
+                Feature::LazyQuantifier => "C0112",
+                Feature::PossessiveQuantifier => "C0113",
+                Feature::EndOfText => "C0114",
+                Feature::EndOfTextOrBeforeNewline => "C0115",
+                Feature::BranchReset => "C0116",
+                Feature::AtomicGroups => "C0117",
+                Feature::Conditional => "C0118",
+                Feature::DefineGroup => "C0119",
+                Feature::SubroutineCall => "C0120",
+                Feature::Recursion => "C0121",
+                Feature::Boundary => "C0122",
+                Feature::LargeRepetition => "C0123",
+                Feature::Alternation => "C0124",
+                Feature::ComplexRepetition => "C0125",
+                Feature::WordBoundary => "C0126",
+                Feature::NonAsciiCharClass => "C0127",
+                Feature::AnchorPosition => "C0128",
+                Feature::ByteMode => "C0129",
+                Feature::InlineFlags => "C0130",
+                Feature::MultilineFlag => "C0131",
+                Feature::LineAnchors => "C0132",
+                Feature::ScriptExtensions => "C0133",
+                Feature::SingleByteCodePoint => "C0134",
+            },
+        }
+    }
 }
 
 /// A regex feature, which might not be supported in every regex flavor.
@@ -137,6 +339,65 @@ pub enum Feature {
     /// Negative `\w` shorthand, i.e. `[\W]`. This is not supported in
     /// JavaScript when polyfilling Unicode support for `\w` and `\d`.
     NegativeShorthandW,
+    /// A negated shorthand within a character class, e.g. `[\D\S]`. This is
+    /// not supported in POSIX bracket expressions.
+    NegativeShorthandInClass,
+    /// Lazy quantifiers, e.g. `x+?`
+    LazyQuantifier,
+    /// Possessive quantifiers, e.g. `x++`
+    PossessiveQuantifier,
+    /// The end of the text, `\z`. Unlike `$`, it doesn't match before a final
+    /// newline.
+    EndOfText,
+    /// The end of the text or the position before a final newline, `\Z`
+    EndOfTextOrBeforeNewline,
+    /// Branch reset groups, `(?|...)`
+    BranchReset,
+    /// Atomic groups, `(?>...)`
+    AtomicGroups,
+    /// Conditionals, e.g. `(?(1)a|b)`
+    Conditional,
+    /// A group that defines subpatterns without matching them,
+    /// `(?(DEFINE)...)`
+    DefineGroup,
+    /// A call to a subpattern, e.g. `(?&name)`
+    SubroutineCall,
+    /// A group that calls itself recursively, e.g. `(a(?1)?b)`
+    Recursion,
+    /// Anchors and word boundaries, e.g. `^` or `\b`
+    Boundary,
+    /// A repetition with a bound greater than 1000, e.g. `x{2000}`
+    LargeRepetition,
+    /// An alternation, e.g. `a|b`. Lua patterns have no alternation.
+    Alternation,
+    /// A repetition of something other than a single character or character
+    /// class, e.g. `(?:ab)*`. Lua patterns can only repeat single characters.
+    ComplexRepetition,
+    /// Word boundaries, e.g. `\b`
+    WordBoundary,
+    /// A non-ASCII character in a character class, e.g. `[ä]`. Lua patterns
+    /// match bytes, so a character class can't contain multi-byte characters.
+    NonAsciiCharClass,
+    /// An anchor that isn't at the start or end of the pattern, e.g. `a^`.
+    /// In Lua patterns, `^` and `$` are matched literally elsewhere.
+    AnchorPosition,
+    /// Matching bytes instead of code points. See
+    /// [`CompileOptions::byte_mode`](crate::options::CompileOptions::byte_mode).
+    ByteMode,
+    /// Inline flags that apply to a group, e.g. `(?i:...)`
+    InlineFlags,
+    /// The `multiline` flag, `(?m:...)`. Ruby has no such flag, because `^`
+    /// and `$` always match at the start and end of each line.
+    MultilineFlag,
+    /// Anchors that match at the start or end of each line, `LineStart` and
+    /// `LineEnd`
+    LineAnchors,
+    /// The `Script_Extensions` Unicode property, e.g. `[scx=Latin]`
+    ScriptExtensions,
+    /// Matching a single code point that might not be ASCII, e.g. `C` or
+    /// `![n]`. Lua patterns match bytes, so this is only supported in a `*`,
+    /// `+` or lazy `*` repetition.
+    SingleByteCodePoint,
 }
 
 impl Feature {
@@ -152,6 +413,32 @@ impl Feature {
             Feature::RelativeReference => "Relative backreference",
             Feature::NonNegativeRelativeReference => "Non-negative relative backreference",
             Feature::NegativeShorthandW => "Negative `\\w` shorthand in character class",
+            Feature::NegativeShorthandInClass => "Negative shorthand in character class",
+            Feature::LazyQuantifier => "Lazy quantifiers",
+            Feature::PossessiveQuantifier => "Possessive quantifiers",
+            Feature::EndOfText => "End of text (\\z)",
+            Feature::EndOfTextOrBeforeNewline => "End of text or before final newline (\\Z)",
+            Feature::BranchReset => "branch reset group",
+            Feature::AtomicGroups => "atomic groups",
+            Feature::Conditional => "conditionals",
+            Feature::DefineGroup => "DEFINE group",
+            Feature::SubroutineCall => "subroutine call",
+            Feature::Recursion => "recursion",
+            Feature::Boundary => "anchors and word boundaries",
+            Feature::LargeRepetition => "repetitions with a bound greater than 1000",
+            Feature::Alternation => "alternation",
+            Feature::ComplexRepetition => {
+                "repetition of anything other than a single character or character class"
+            }
+            Feature::WordBoundary => "word boundaries",
+            Feature::NonAsciiCharClass => "non-ASCII characters in character classes",
+            Feature::AnchorPosition => "anchors that aren't at the start or end of the pattern",
+            Feature::ByteMode => "byte mode",
+            Feature::InlineFlags => "inline flags",
+            Feature::MultilineFlag => "multiline flag",
+            Feature::LineAnchors => "line anchors",
+            Feature::ScriptExtensions => "Unicode script extensions (\\p{scx=Script})",
+            Feature::SingleByteCodePoint => "matching a single non-ASCII code point",
         }
     }
 }
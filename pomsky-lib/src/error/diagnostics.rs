@@ -1,8 +1,13 @@
-use crate::{parse::ParseErrorMsg, repetition::RepetitionError, span::Span, warning::Warning};
+use crate::{
+    parse::ParseErrorMsg,
+    repetition::RepetitionError,
+    span::Span,
+    warning::{BoundaryWarning, RepetitionWarning, Warning, WarningKind},
+};
 
 use super::{
-    compile_error::CompileErrorKind, CharClassError, CharStringError, CompileError, ParseError,
-    ParseErrorKind,
+    compile_error::CompileErrorKind, CharClassError, CharStringError, CompileError, Feature,
+    ParseError, ParseErrorKind,
 };
 
 #[cfg_attr(feature = "miette", derive(Debug, thiserror::Error))]
@@ -15,7 +20,10 @@ pub struct Diagnostic {
     pub severity: Severity,
     /// The error message
     pub msg: String,
-    /// The error code (optional, currently unused)
+    /// A stable code identifying the kind of error or warning, e.g. `P0103`.
+    /// See [`ParseErrorKind::error_code`], [`CompileErrorKind::error_code`] and
+    /// [`WarningKind::warning_code`](crate::warning::WarningKind::warning_code).
+    /// Ad-hoc diagnostics may not have a code.
     pub code: Option<String>,
     /// The source code where the error occurred
     pub source_code: Option<String>,
@@ -35,6 +43,44 @@ pub enum Severity {
     Warning,
 }
 
+/// Serializes the diagnostic as an object with the fields `severity`, `code`,
+/// `msg`, `help` and `span`. Fields that are `None` are omitted, as is the span
+/// if it is empty. The source code isn't included.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Diagnostic {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("Diagnostic", 5)?;
+        s.serialize_field("severity", &self.severity)?;
+        match &self.code {
+            Some(code) => s.serialize_field("code", code)?,
+            None => s.skip_field("code")?,
+        }
+        s.serialize_field("msg", &self.msg)?;
+        match &self.help {
+            Some(help) => s.serialize_field("help", help)?,
+            None => s.skip_field("help")?,
+        }
+        match self.span.range() {
+            Some(_) => s.serialize_field("span", &self.span)?,
+            None => s.skip_field("span")?,
+        }
+        s.end()
+    }
+}
+
+/// Serializes the severity as `"error"` or `"warning"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Severity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
 #[cfg(feature = "miette")]
 impl miette::Diagnostic for Diagnostic {
     fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
@@ -80,34 +126,49 @@ impl miette::Diagnostic for Diagnostic {
 
 impl Diagnostic {
     /// Create a [Diagnostic] from a [ParseError]
+    ///
+    /// The help text is derived from the part of the source code the error
+    /// points to. If the source code isn't the one the error was created from,
+    /// the help is omitted if it can't be derived, and the span is clamped to
+    /// the source code:
+    ///
+    /// ```
+    /// use pomsky::{error::Diagnostic, Expr};
+    ///
+    /// let err = Expr::parse("range '20'-'10'", Default::default()).map(|_| ()).unwrap_err();
+    /// let diagnostic = Diagnostic::from_parse_error(err.clone(), "'a' ['b'] 'c' 'd'");
+    /// assert_eq!(diagnostic.help, None);
+    ///
+    /// let diagnostic = Diagnostic::from_parse_error(err, "'äöü'");
+    /// assert_eq!(diagnostic.span.range(), Some(5..8));
+    /// ```
     pub fn from_parse_error(error: ParseError, source_code: &str) -> Self {
-        let range = error.span.range().unwrap_or(0..source_code.len());
+        let range = error.span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
         let slice = &source_code[range.clone()];
         let mut span = Span::from(range);
 
         let help = match error.kind {
             ParseErrorKind::LexErrorWithMessage(msg) => get_parse_error_msg_help(slice, msg),
-            ParseErrorKind::RangeIsNotIncreasing => {
-                let dash_pos = slice.find('-').unwrap();
-                let (part1, part2) = slice.split_at(dash_pos);
+            ParseErrorKind::RangeIsNotIncreasing => slice.split_once('-').map(|(part1, part2)| {
                 let part2 = part2.trim_start_matches('-');
-                Some(format!("Switch the numbers: {}-{}", part2.trim(), part1.trim()))
-            }
+                format!("Switch the numbers: {}-{}", part2.trim(), part1.trim())
+            }),
             ParseErrorKind::Dot => Some(
                 "The dot is deprecated. Use `Codepoint` to match any code point, \
                 or `![n]` to exclude line breaks"
                     .into(),
             ),
             #[cfg(feature = "suggestions")]
-            ParseErrorKind::CharClass(CharClassError::UnknownNamedClass {
-                similar: Some(ref similar),
-                ..
-            }) => Some(format!("Perhaps you meant `{similar}`")),
+            ParseErrorKind::CharClass(
+                CharClassError::UnknownNamedClass { similar: Some(ref similar), .. }
+                | CharClassError::UnknownProperty { similar: Some(ref similar), .. },
+            ) => Some(format!("Perhaps you meant `{similar}`")),
+            // the range might come from a custom class, in which case there's no dash
             ParseErrorKind::CharClass(CharClassError::DescendingRange(..)) => {
-                let dash_pos = slice.find('-').unwrap();
-                let (part1, part2) = slice.split_at(dash_pos);
-                let part2 = part2.trim_start_matches('-');
-                Some(format!("Switch the characters: {}-{}", part2.trim(), part1.trim()))
+                slice.split_once('-').map(|(part1, part2)| {
+                    let part2 = part2.trim_start_matches('-');
+                    format!("Switch the characters: {}-{}", part2.trim(), part1.trim())
+                })
             }
             ParseErrorKind::CharClass(CharClassError::Empty) => {
                 Some("You can use `![s !s]` to match nothing, and `C` to match anything".into())
@@ -124,14 +185,35 @@ impl Diagnostic {
             ParseErrorKind::KeywordAfterLet(_) => Some("Use a different variable name".into()),
             ParseErrorKind::UnallowedDoubleNot => Some("Remove 2 exclamation marks".into()),
             ParseErrorKind::LetBindingExists => Some("Use a different name".into()),
+            ParseErrorKind::UnknownFlag(_) => Some(
+                "The available flags are `case_insensitive`, `multiline` and `dot_all`".into(),
+            ),
+            ParseErrorKind::Repetition(RepetitionError::NotAscending { lower, upper }) => {
+                if slice.contains(',') {
+                    Some(format!("Switch the numbers: {{{upper},{lower}}}"))
+                } else {
+                    Some(format!("Switch the numbers: {upper} to {lower}"))
+                }
+            }
             ParseErrorKind::Repetition(RepetitionError::QuestionMarkAfterRepetition) => Some(
                 "If you meant to make the repetition lazy, append the `lazy` keyword instead.\n\
                 If this is intentional, consider adding parentheses around the inner repetition."
                     .into(),
             ),
+            ParseErrorKind::Repetition(RepetitionError::DoubleQuantifier) => Some(
+                "If this is intentional, add parentheses around the inner repetition, e.g. `('a'+)*`.\n\
+                Otherwise, remove one of the quantifiers, or use braces, e.g. `{2,}`."
+                    .into(),
+            ),
             ParseErrorKind::InvalidEscapeInStringAt(offset) => {
-                let span_start = span.range_unchecked().start;
-                span = Span::new(span_start + offset - 1, span_start + offset + 1);
+                // the span covers the backslash and the next character, which may be
+                // longer than one byte
+                let escaped = span.range_unchecked().start + offset;
+                let char_len = source_code
+                    .get(escaped..)
+                    .and_then(|rest| rest.chars().next())
+                    .map_or(0, char::len_utf8);
+                span = Span::new(escaped - 1, escaped + char_len).clamp_to(source_code);
                 None
             }
             ParseErrorKind::RecursionLimit => Some(
@@ -144,7 +226,7 @@ impl Diagnostic {
 
         Diagnostic {
             severity: Severity::Error,
-            code: None,
+            code: Some(error.kind.error_code().into()),
             msg: error.kind.to_string(),
             source_code: Some(source_code.into()),
             help,
@@ -176,25 +258,112 @@ impl Diagnostic {
             #[cfg(feature = "suggestions")]
             CompileErrorKind::UnknownVariable { similar: Some(ref similar), .. }
             | CompileErrorKind::UnknownReferenceName { similar: Some(ref similar), .. } => {
-                let range = span.range().unwrap_or(0..source_code.len());
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
                 let span = Span::from(range);
 
                 Diagnostic {
                     severity: Severity::Error,
-                    code: None,
+                    code: Some(kind.error_code().into()),
                     msg: kind.to_string(),
                     source_code: Some(source_code.into()),
                     help: Some(format!("Perhaps you meant `{similar}`")),
                     span,
                 }
             }
+            CompileErrorKind::RecursiveVariable { ref name, .. } => {
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
+                let span = Span::from(range);
+
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: Some(kind.error_code().into()),
+                    msg: kind.to_string(),
+                    source_code: Some(source_code.into()),
+                    help: Some(format!(
+                        "Variables are replaced with their content, so `{name}` can't contain \
+                        itself. Break the cycle by removing one of the references"
+                    )),
+                    span,
+                }
+            }
+            CompileErrorKind::RepeatedAssertion => {
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
+                let span = Span::from(range);
+
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: Some(kind.error_code().into()),
+                    msg: kind.to_string(),
+                    source_code: Some(source_code.into()),
+                    help: Some(
+                        "Boundaries and lookarounds don't consume any characters, so repeating \
+                        them has no effect. Remove the repetition, or use `?` to make the \
+                        assertion optional"
+                            .into(),
+                    ),
+                    span,
+                }
+            }
+            CompileErrorKind::NumericReferenceWithDefine => {
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
+                let span = Span::from(range);
+
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: Some(kind.error_code().into()),
+                    msg: kind.to_string(),
+                    source_code: Some(source_code.into()),
+                    help: Some(
+                        "The variables in a `define` block are compiled to capturing groups, \
+                        which are counted when numbering groups. Use a named reference, e.g. \
+                        `::name`, or a relative reference, e.g. `::-1`, instead"
+                            .into(),
+                    ),
+                    span,
+                }
+            }
+            CompileErrorKind::Unsupported(Feature::SingleByteCodePoint, _) => {
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
+                let span = Span::from(range);
+
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: Some(kind.error_code().into()),
+                    msg: kind.to_string(),
+                    source_code: Some(source_code.into()),
+                    help: Some(
+                        "Lua patterns match bytes, so this would only match the first byte of a \
+                        multi-byte character. Repeat it with `*` or `+`, or match specific \
+                        characters instead"
+                            .into(),
+                    ),
+                    span,
+                }
+            }
+            CompileErrorKind::Unsupported(Feature::ScriptExtensions, _) => {
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
+                let span = Span::from(range);
+
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: Some(kind.error_code().into()),
+                    msg: kind.to_string(),
+                    source_code: Some(source_code.into()),
+                    help: Some(
+                        "Use the `Script` property instead, e.g. `[sc=Latin]`. Note that it \
+                        doesn't match characters that are used by multiple scripts"
+                            .into(),
+                    ),
+                    span,
+                }
+            }
             _ => {
-                let range = span.range().unwrap_or(0..source_code.len());
+                let range = span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
                 let span = Span::from(range);
 
                 Diagnostic {
                     severity: Severity::Error,
-                    code: None,
+                    code: Some(kind.error_code().into()),
                     msg: kind.to_string(),
                     source_code: Some(source_code.into()),
                     help: None,
@@ -213,33 +382,48 @@ impl Diagnostic {
             CompileErrorKind::ParseError(kind) => {
                 Diagnostic::from_parse_errors(ParseError { kind, span }, source_code)
             }
-            _ => {
-                let range = span.range().unwrap_or(0..source_code.len());
-                let span = Span::from(range);
-
-                vec![Diagnostic {
-                    severity: Severity::Error,
-                    code: None,
-                    msg: kind.to_string(),
-                    source_code: Some(source_code.into()),
-                    help: None,
-                    span,
-                }]
-            }
+            CompileErrorKind::Multiple(multiple) => Vec::from(multiple)
+                .into_iter()
+                .flat_map(|err| Diagnostic::from_compile_errors(err, source_code))
+                .collect(),
+            kind => vec![Diagnostic::from_compile_error(CompileError { kind, span }, source_code)],
         }
     }
 
-    /// Create a [Diagnostic] from a [CompileError]
+    /// Create a [Diagnostic] from a [Warning]
+    ///
+    /// ```
+    /// use pomsky::{error::Diagnostic, options::ParseOptions, Expr};
+    ///
+    /// let options = ParseOptions { warn_end_anchor: true, ..Default::default() };
+    /// let (_, warnings) = Expr::parse("'a' End", options).unwrap();
+    /// let diagnostic = Diagnostic::from_warning(warnings[1], "'a' End");
+    /// assert_eq!(diagnostic.code.as_deref(), Some("W0601"));
+    /// assert!(diagnostic.help.unwrap().contains("EndOfTextOrBeforeNewline"));
+    /// ```
     pub fn from_warning(warning: Warning, source_code: &str) -> Self {
-        let range = warning.span.range().unwrap_or(0..source_code.len());
+        let range = warning.span.clamp_to(source_code).range().unwrap_or(0..source_code.len());
         let span = Span::from(range);
 
+        let help = match warning.kind {
+            WarningKind::Boundary(BoundaryWarning::EndBeforeNewline) => Some(
+                "`End` is compiled to `\\z`, which only matches at the end of the string. \
+                `$` also matches before a final newline, like `EndOfTextOrBeforeNewline` (`\\Z`).\n\
+                To match at the end of every line, use `LineEnd` instead."
+                    .into(),
+            ),
+            WarningKind::Repetition(RepetitionWarning::NestedRepetition) => {
+                Some("Remove one of the repetitions, e.g. write `'a'+` instead of `('a'+)+`".into())
+            }
+            _ => None,
+        };
+
         Diagnostic {
             severity: Severity::Warning,
-            code: None,
+            code: Some(warning.kind.warning_code().into()),
             msg: warning.kind.to_string(),
             source_code: Some(source_code.into()),
-            help: None,
+            help,
             span,
         }
     }
@@ -296,6 +480,15 @@ fn get_parse_error_msg_help(slice: &str, msg: ParseErrorMsg) -> Option<String> {
         ParseErrorMsg::GroupComment => "Comments start with `#` and go until the \
             end of the line."
             .into(),
+        ParseErrorMsg::GroupAtomic => "Atomic groups use the `atomic(...)` syntax. \
+            For example, `atomic('a'+)` matches as many a's as possible, without backtracking."
+            .into(),
+        ParseErrorMsg::GroupConditional => {
+            "Conditionals use the `if ::group { ... } else { ... }` \
+            syntax. For example, `if ::1 { 'a' } else { 'b' }` matches a if group 1 has matched, \
+            and b otherwise."
+                .into()
+        }
         ParseErrorMsg::GroupNamedCapture => return get_named_capture_help(slice),
         ParseErrorMsg::GroupPcreBackreference => return get_pcre_backreference_help(slice),
         ParseErrorMsg::Backslash => return get_backslash_help(slice),
@@ -305,9 +498,7 @@ fn get_parse_error_msg_help(slice: &str, msg: ParseErrorMsg) -> Option<String> {
         ParseErrorMsg::BackslashGK => return get_backslash_gk_help(slice),
         ParseErrorMsg::BackslashProperty => return get_backslash_property_help(slice),
 
-        ParseErrorMsg::GroupAtomic
-        | ParseErrorMsg::GroupConditional
-        | ParseErrorMsg::GroupBranchReset
+        ParseErrorMsg::GroupBranchReset
         | ParseErrorMsg::GroupSubroutineCall
         | ParseErrorMsg::GroupOther
         | ParseErrorMsg::UnclosedString => return None,
@@ -344,12 +535,12 @@ fn get_backslash_help(str: &str) -> Option<String> {
         Some('B') => "Replace `\\B` with `!%` to match a place without a word boundary".into(),
         Some('A') => "Replace `\\A` with `Start` to match the start of the string".into(),
         Some('z') => "Replace `\\z` with `End` to match the end of the string".into(),
-        Some('Z') => "\\Z is not supported. Use `End` to match the end of the string.\n\
-            Note, however, that `End` doesn't match the position before the final newline."
+        Some('Z') => "Replace `\\Z` with `EndOfTextOrBeforeNewline` to match the end of the \
+            string or the position before a final newline"
             .into(),
         Some('N') => "Replace `\\N` with `![n]`".into(),
         Some('X') => "Replace `\\X` with `Grapheme`".into(),
-        Some('R') => "Replace `\\R` with `([r] [n] | [v])`".into(),
+        Some('R') => "Replace `\\R` with `LineBreak`".into(),
         Some('D') => "Replace `\\D` with `[!d]`".into(),
         Some('W') => "Replace `\\W` with `[!w]`".into(),
         Some('S') => "Replace `\\S` with `[!s]`".into(),
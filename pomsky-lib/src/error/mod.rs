@@ -1,13 +1,15 @@
 //! Contains different kinds of errors emitted by Pomsky.
 
-pub(crate) use compile_error::CompileErrorKind;
-pub(crate) use parse_error::{
-    CharClassError, CharStringError, CodePointError, NumberError, ParseErrorKind, UnsupportedError,
+pub use crate::{
+    parse::{ParseErrorMsg, Token},
+    repetition::RepetitionError,
 };
-
-pub use compile_error::{CompileError, Feature};
+pub use compile_error::{CompileError, CompileErrorKind, Feature};
 pub use diagnostics::{Diagnostic, Severity};
-pub use parse_error::ParseError;
+pub use parse_error::{
+    CharClassError, CharStringError, CodePointError, NumberError, ParseError, ParseErrorKind,
+    UnsupportedError,
+};
 
 mod compile_error;
 mod diagnostics;
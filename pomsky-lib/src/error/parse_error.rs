@@ -1,6 +1,7 @@
 use std::num::{IntErrorKind, ParseIntError};
 
 use crate::{
+    options::RegexFlavor,
     parse::{Input, ParseErrorMsg, Token},
     repetition::RepetitionError,
     span::Span,
@@ -16,6 +17,16 @@ pub struct ParseError {
 }
 
 impl ParseError {
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Returns the position in the source code where this error occurred.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     /// Create a [Diagnostic] from this error.
     pub fn diagnostic(self, source_code: &str) -> Diagnostic {
         Diagnostic::from_parse_error(self, source_code)
@@ -54,59 +65,103 @@ impl<'i, 'b> nom::error::ParseError<Input<'i, 'b>> for ParseError {
 /// An error kind (without a span) than can occur only during parsing
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum ParseErrorKind {
+pub enum ParseErrorKind {
+    /// Several errors, which are reported together
     #[error("Multiple parsing errors encountered")]
     Multiple(Box<[ParseError]>),
 
+    /// A token that isn't valid in Pomsky
     #[error("Unknown token")]
     UnknownToken,
+    /// A token that isn't valid in Pomsky, but is valid in other regex
+    /// syntaxes, e.g. `^`
     #[error(transparent)]
     LexErrorWithMessage(ParseErrorMsg),
+    /// A dot outside of a character class
     #[error("Unexpected dot")]
     Dot,
+    /// A keyword after `let`, e.g. `let if = ...`
     #[error("Unexpected keyword `{}`", .0)]
     KeywordAfterLet(String),
+    /// A keyword where an expression was expected
     #[error("Unexpected keyword `{}`", .0)]
     UnexpectedKeyword(String),
 
+    /// Something else was expected at this position
     #[error("Expected {}", .0)]
     Expected(&'static str),
+    /// The expression is followed by tokens that couldn't be parsed
     #[error("There are leftover tokens that couldn't be parsed")]
     LeftoverTokens,
+    /// A specific token was expected at this position
     #[error("Expected {}", .0)]
     ExpectedToken(Token),
+    /// A code point or character was expected, e.g. in a range
     #[error("Expected code point or character")]
     ExpectedCodePointOrChar,
+    /// A `range` whose first number is greater than the second
     #[error("The first number in a range must be smaller than the second")]
     RangeIsNotIncreasing,
+    /// A negated expression that can't be negated
     #[error("This expression can't be negated")]
     UnallowedNot,
+    /// An expression that is negated twice, e.g. `!!%`
     #[error("An expression can't be negated twice")]
     UnallowedDoubleNot,
+    /// A `range` with more digits than allowed by
+    /// [`ParseOptions::max_range_size`](crate::options::ParseOptions)
     #[error("Range is too big, it isn't allowed to contain more than {} digits", .0)]
     RangeIsTooBig(u8),
+    /// A delimiter in `balanced(...)` that isn't a single code point
+    #[error("The delimiters of `balanced(...)` must be single code points")]
+    BalancedDelimiterNotChar,
+    /// A `balanced(...)` expression whose delimiters are the same
+    #[error("The delimiters of `balanced(...)` must be different")]
+    BalancedDelimitersEqual,
+    /// A `oneOf(...)` expression without strings
+    #[error("`oneOf(...)` must contain at least one string")]
+    OneOfEmpty,
+    /// A `captureEach(...)` expression without a capturing group
+    #[error("`captureEach(...)` must contain a capturing group")]
+    CaptureEachWithoutGroup,
+    /// A flag in an `enable` or `disable` block that doesn't exist
+    #[error("Unknown flag `{}`", .0)]
+    UnknownFlag(String),
+    /// A variable that is declared more than once in the same scope
     #[error("A variable with the same name already exists in this scope")]
     LetBindingExists,
+    /// An invalid escape sequence in a double-quoted string. The value is the
+    /// offset of the backslash within the string
     #[error("Unsupported escape sequence in string")]
     InvalidEscapeInStringAt(usize),
+    /// An error related to a string in a character class
     #[error(transparent)]
     CharString(CharStringError),
+    /// An error related to a character class
     #[error(transparent)]
     CharClass(CharClassError),
+    /// An error related to a code point
     #[error(transparent)]
     CodePoint(CodePointError),
+    /// An error related to a number
     #[error(transparent)]
     Number(#[from] NumberError),
+    /// An error related to a repetition
     #[error(transparent)]
     Repetition(RepetitionError),
+    /// A feature that isn't allowed by
+    /// [`ParseOptions::allowed_features`](crate::options::ParseOptions)
     #[error(transparent)]
     Unsupported(UnsupportedError),
 
+    /// The expression is nested too deeply
     #[error("Recursion limit reached")]
     RecursionLimit,
 
+    /// An unexpected error in the parser
     #[error("Unknown error: {:?}", .0)]
     Nom(nom::error::ErrorKind),
+    /// The input ended unexpectedly
     #[error("Incomplete parse")]
     Incomplete,
 }
@@ -115,6 +170,115 @@ impl ParseErrorKind {
     pub(crate) fn at(self, span: Span) -> ParseError {
         ParseError { kind: self, span }
     }
+
+    /// Returns a stable code that identifies this kind of error, e.g. `P0103`
+    /// for a `$` token. It is used as the [`Diagnostic::code`] of the error.
+    ///
+    /// Codes of parse errors start with `P`. The first two digits identify
+    /// the category, e.g. `P03xx` for errors in character classes.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let err = Expr::parse("'a' $", Default::default()).map(|_| ()).unwrap_err();
+    /// assert_eq!(err.kind().error_code(), "P0103");
+    /// ```
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ParseErrorKind::Multiple(_) => "P0001",
+            ParseErrorKind::UnknownToken => "P0002",
+            ParseErrorKind::Dot => "P0003",
+            ParseErrorKind::KeywordAfterLet(_) => "P0004",
+            ParseErrorKind::UnexpectedKeyword(_) => "P0005",
+            ParseErrorKind::Expected(_) => "P0006",
+            ParseErrorKind::LeftoverTokens => "P0007",
+            ParseErrorKind::ExpectedToken(_) => "P0008",
+            ParseErrorKind::ExpectedCodePointOrChar => "P0009",
+            ParseErrorKind::RangeIsNotIncreasing => "P0010",
+            ParseErrorKind::UnallowedNot => "P0011",
+            ParseErrorKind::UnallowedDoubleNot => "P0012",
+            ParseErrorKind::RangeIsTooBig(_) => "P0013",
+            ParseErrorKind::BalancedDelimiterNotChar => "P0014",
+            ParseErrorKind::BalancedDelimitersEqual => "P0015",
+            ParseErrorKind::OneOfEmpty => "P0016",
+            ParseErrorKind::CaptureEachWithoutGroup => "P0017",
+            ParseErrorKind::LetBindingExists => "P0018",
+            ParseErrorKind::InvalidEscapeInStringAt(_) => "P0019",
+            ParseErrorKind::RecursionLimit => "P0020",
+            ParseErrorKind::Nom(_) => "P0021",
+            ParseErrorKind::Incomplete => "P0022",
+            ParseErrorKind::UnknownFlag(_) => "P0023",
+
+            ParseErrorKind::LexErrorWithMessage(msg) => match msg {
+                ParseErrorMsg::Caret => "P0101",
+                ParseErrorMsg::CaretInGroup => "P0102",
+                ParseErrorMsg::Dollar => "P0103",
+                ParseErrorMsg::GroupNonCapturing => "P0104",
+                ParseErrorMsg::GroupLookahead => "P0105",
+                ParseErrorMsg::GroupLookaheadNeg => "P0106",
+                ParseErrorMsg::GroupLookbehind => "P0107",
+                ParseErrorMsg::GroupLookbehindNeg => "P0108",
+                ParseErrorMsg::GroupNamedCapture => "P0109",
+                ParseErrorMsg::GroupPcreBackreference => "P0110",
+                ParseErrorMsg::GroupComment => "P0111",
+                ParseErrorMsg::GroupAtomic => "P0112",
+                ParseErrorMsg::GroupConditional => "P0113",
+                ParseErrorMsg::GroupBranchReset => "P0114",
+                ParseErrorMsg::GroupSubroutineCall => "P0115",
+                ParseErrorMsg::GroupOther => "P0116",
+                ParseErrorMsg::Backslash => "P0117",
+                ParseErrorMsg::BackslashU4 => "P0118",
+                ParseErrorMsg::BackslashX2 => "P0119",
+                ParseErrorMsg::BackslashUnicode => "P0120",
+                ParseErrorMsg::BackslashProperty => "P0121",
+                ParseErrorMsg::BackslashGK => "P0122",
+                ParseErrorMsg::UnclosedString => "P0123",
+            },
+            ParseErrorKind::CharString(e) => match e {
+                CharStringError::Empty => "P0201",
+                CharStringError::TooManyCodePoints => "P0202",
+            },
+            ParseErrorKind::CharClass(e) => match e {
+                CharClassError::Empty => "P0301",
+                CharClassError::DescendingRange(..) => "P0302",
+                CharClassError::Invalid => "P0303",
+                CharClassError::Unallowed => "P0304",
+                CharClassError::UnknownNamedClass { .. } => "P0305",
+                CharClassError::UnknownProperty { .. } => "P0306",
+                CharClassError::InvalidPropertyValue { .. } => "P0307",
+                CharClassError::Negative => "P0308",
+                CharClassError::Keyword(_) => "P0309",
+                CharClassError::UnsupportedIntersection(_) => "P0310",
+            },
+            ParseErrorKind::CodePoint(e) => match e {
+                CodePointError::Invalid => "P0401",
+            },
+            ParseErrorKind::Number(e) => match e {
+                NumberError::Empty => "P0501",
+                NumberError::InvalidDigit => "P0502",
+                NumberError::TooLarge => "P0503",
+                NumberError::TooSmall => "P0504",
+                NumberError::Zero => "P0505",
+            },
+            ParseErrorKind::Repetition(e) => match e {
+                RepetitionError::NotAscending { .. } => "P0601",
+                RepetitionError::QuestionMarkAfterRepetition => "P0602",
+                RepetitionError::DoubleQuantifier => "P0603",
+            },
+            ParseErrorKind::Unsupported(e) => match e {
+                UnsupportedError::Grapheme => "P0701",
+                UnsupportedError::NumberedGroups => "P0702",
+                UnsupportedError::NamedGroups => "P0703",
+                UnsupportedError::References => "P0704",
+                UnsupportedError::LazyMode => "P0705",
+                UnsupportedError::Ranges => "P0706",
+                UnsupportedError::Variables => "P0707",
+                UnsupportedError::Lookahead => "P0708",
+                UnsupportedError::Lookbehind => "P0709",
+                UnsupportedError::Boundaries => "P0710",
+            },
+        }
+    }
 }
 
 impl From<RepetitionError> for ParseErrorKind {
@@ -126,7 +290,7 @@ impl From<RepetitionError> for ParseErrorKind {
 /// An error that relates to a character string
 #[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum CharStringError {
+pub enum CharStringError {
     /// Empty string in a code point range within a character class, e.g.
     /// `[''-'z']`
     #[error("Strings used in ranges can't be empty")]
@@ -141,7 +305,7 @@ pub(crate) enum CharStringError {
 /// An error that relates to a character class
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum CharClassError {
+pub enum CharClassError {
     /// Empty character class, i.e. `[]`
     #[error("This character class is empty")]
     Empty,
@@ -164,11 +328,33 @@ pub(crate) enum CharClassError {
     /// Unknown shorthand character class or Unicode property
     #[error("Unknown character class `{}`", .found)]
     UnknownNamedClass {
+        /// The name of the character class
         found: Box<str>,
+        /// A similar, valid name, if one exists
         #[cfg(feature = "suggestions")]
         similar: Option<Box<str>>,
     },
 
+    /// Unknown Unicode property in a property with a value, e.g. `[foo=Lu]`
+    #[error("Unknown Unicode property `{}`", .found)]
+    UnknownProperty {
+        /// The name of the property
+        found: Box<str>,
+        /// A similar, valid property, if one exists
+        #[cfg(feature = "suggestions")]
+        similar: Option<Box<str>>,
+    },
+
+    /// A value that doesn't belong to the Unicode property, e.g.
+    /// `[Script=Lu]`
+    #[error("`{}` is not a valid value of the `{}` property", .value, .property)]
+    InvalidPropertyValue {
+        /// The name of the property
+        property: Box<str>,
+        /// The invalid value
+        value: Box<str>,
+    },
+
     /// A character class that can't be negated, e.g. `[!ascii]`
     #[error("This character class can't be negated")]
     Negative,
@@ -176,12 +362,18 @@ pub(crate) enum CharClassError {
     /// Unexpected keyword within a character class, e.g. `[let]`
     #[error("Unexpected keyword `{}`", .0)]
     Keyword(String),
+
+    /// An intersection of character classes, e.g. `[Letter & ascii]`, in a
+    /// flavor that supports neither intersections nor lookahead, so it can't
+    /// be emulated
+    #[error("Intersections of character classes are not supported in the `{}` regex flavor", .0.name())]
+    UnsupportedIntersection(RegexFlavor),
 }
 
 /// An error that relates to a Unicode code point
 #[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum CodePointError {
+pub enum CodePointError {
     /// Code point that is outside the allowed range, e.g. `U+200000`
     #[error("This code point is outside the allowed range")]
     Invalid,
@@ -190,7 +382,7 @@ pub(crate) enum CodePointError {
 /// An error that relates to parsing a number
 #[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum NumberError {
+pub enum NumberError {
     /// The parsed string is empty
     #[error("cannot parse integer from empty string")]
     Empty,
@@ -230,34 +422,44 @@ impl From<ParseIntError> for NumberError {
 /// See [`crate::features::PomskyFeatures`] for details.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
-pub(crate) enum UnsupportedError {
+pub enum UnsupportedError {
+    /// `Grapheme`
     #[error("Grapheme is not supported")]
     Grapheme,
 
+    /// Numbered capturing groups
     #[error("Numbered capturing groups is not supported")]
     NumberedGroups,
 
+    /// Named capturing groups
     #[error("Named capturing groups is not supported")]
     NamedGroups,
 
+    /// References
     #[error("References aren't supported")]
     References,
 
+    /// `enable lazy` and `disable lazy`
     #[error("Lazy mode isn't supported")]
     LazyMode,
 
+    /// `range` expressions
     #[error("Ranges aren't supported")]
     Ranges,
 
+    /// Variables
     #[error("Variables aren't supported")]
     Variables,
 
+    /// Lookahead
     #[error("Lookahead isn't supported")]
     Lookahead,
 
+    /// Lookbehind
     #[error("Lookbehind isn't supported")]
     Lookbehind,
 
+    /// Word boundaries
     #[error("Word boundaries aren't supported")]
     Boundaries,
 }
@@ -0,0 +1,483 @@
+//! Contains the [`Locale`] for
+//! [`Expr::explain`](crate::Expr::explain), which describes a compiled regex
+//! in a human language.
+//!
+//! Each kind of regex node has an entry in a message catalog with a
+//! translation for every supported locale. To add a language, add a variant to
+//! [`Locale`] and a translation to every message.
+
+use crate::{
+    boundary::BoundaryKind,
+    char_class::{RegexCharClass, RegexClassItem},
+    group::RegexCapture,
+    lookaround::LookaroundKind,
+    regex::{Regex, RegexProperty, RegexShorthand},
+    repetition::RegexQuantifier,
+};
+
+/// The language of an explanation produced by
+/// [`Expr::explain`](crate::Expr::explain)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Locale {
+    /// English
+    #[default]
+    English,
+    /// Italian
+    Italian,
+}
+
+/// The message catalog. Messages can contain placeholders `{0}` and `{1}`,
+/// which are replaced by [`Msg::format`].
+#[derive(Clone, Copy)]
+enum Msg {
+    Text,
+    Char,
+    EmptyString,
+    OneCharOf,
+    OneCharExcept,
+    OneCharInAll,
+    OneCharNotInAll,
+    Range,
+    Word,
+    Digit,
+    Space,
+    NotWord,
+    NotDigit,
+    NotSpace,
+    VertSpace,
+    HorizSpace,
+    NotVertSpace,
+    NotHorizSpace,
+    LineBreak,
+    Property,
+    NotProperty,
+    Grapheme,
+    Dot,
+    Sequence,
+    CapturingGroup,
+    NamedGroup,
+    OneOf,
+    NoneOf,
+    BranchReset,
+    Atomic,
+    EnableFlags,
+    DisableFlags,
+    Optional,
+    ZeroOrMore,
+    OneOrMore,
+    Exactly,
+    AtLeast,
+    Between,
+    Lazy,
+    Possessive,
+    Start,
+    End,
+    LineStart,
+    LineEnd,
+    EndOfText,
+    EndOfTextOrBeforeNewline,
+    WordBoundary,
+    NotWordBoundary,
+    FollowedBy,
+    NotFollowedBy,
+    PrecededBy,
+    NotPrecededBy,
+    Reference,
+    Conditional,
+    Otherwise,
+    SubroutineCall,
+    Recursion,
+    Definitions,
+}
+
+impl Msg {
+    fn text(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::English => match self {
+                Msg::Text => "the text \"{0}\"",
+                Msg::Char => "the character '{0}'",
+                Msg::EmptyString => "the empty string",
+                Msg::OneCharOf => "one character of",
+                Msg::OneCharExcept => "one character except",
+                Msg::OneCharInAll => "one character matched by all of",
+                Msg::OneCharNotInAll => "one character not matched by all of",
+                Msg::Range => "'{0}' to '{1}'",
+                Msg::Word => "a word character",
+                Msg::Digit => "a digit",
+                Msg::Space => "a whitespace character",
+                Msg::NotWord => "a character that isn't a word character",
+                Msg::NotDigit => "a character that isn't a digit",
+                Msg::NotSpace => "a character that isn't whitespace",
+                Msg::VertSpace => "a vertical whitespace character",
+                Msg::HorizSpace => "a horizontal whitespace character",
+                Msg::NotVertSpace => "a character that isn't vertical whitespace",
+                Msg::NotHorizSpace => "a character that isn't horizontal whitespace",
+                Msg::LineBreak => "a line break",
+                Msg::Property => "a character with the Unicode property {0}",
+                Msg::NotProperty => "a character without the Unicode property {0}",
+                Msg::Grapheme => "a grapheme cluster",
+                Msg::Dot => "any character except a line break",
+                Msg::Sequence => "in this order",
+                Msg::CapturingGroup => "capturing group {0}",
+                Msg::NamedGroup => "capturing group {0} named \"{1}\"",
+                Msg::OneOf => "one of",
+                Msg::NoneOf => "none of",
+                Msg::BranchReset => {
+                    "one of, where the groups in each alternative are numbered the same"
+                }
+                Msg::Atomic => "atomic group (without backtracking)",
+                Msg::EnableFlags => "with the flags {0} enabled",
+                Msg::DisableFlags => "with the flags {0} disabled",
+                Msg::Optional => "optionally",
+                Msg::ZeroOrMore => "zero or more times",
+                Msg::OneOrMore => "one or more times",
+                Msg::Exactly => "exactly {0} times",
+                Msg::AtLeast => "at least {0} times",
+                Msg::Between => "between {0} and {1} times",
+                Msg::Lazy => "{0} (as few as possible)",
+                Msg::Possessive => "{0} (as many as possible, without backtracking)",
+                Msg::Start => "the start of the string",
+                Msg::End => "the end of the string",
+                Msg::LineStart => "the start of a line",
+                Msg::LineEnd => "the end of a line",
+                Msg::EndOfText => "the very end of the string",
+                Msg::EndOfTextOrBeforeNewline => {
+                    "the end of the string or the position before a final line break"
+                }
+                Msg::WordBoundary => "a word boundary",
+                Msg::NotWordBoundary => "a position that isn't a word boundary",
+                Msg::FollowedBy => "followed by",
+                Msg::NotFollowedBy => "not followed by",
+                Msg::PrecededBy => "preceded by",
+                Msg::NotPrecededBy => "not preceded by",
+                Msg::Reference => "the same text as capturing group {0}",
+                Msg::Conditional => "if capturing group {0} has matched",
+                Msg::Otherwise => "otherwise",
+                Msg::SubroutineCall => "the pattern of the group named \"{0}\"",
+                Msg::Recursion => "the pattern of capturing group {0}",
+                Msg::Definitions => "definitions of patterns, which don't match here",
+            },
+            Locale::Italian => match self {
+                Msg::Text => "il testo \"{0}\"",
+                Msg::Char => "il carattere '{0}'",
+                Msg::EmptyString => "la stringa vuota",
+                Msg::OneCharOf => "un carattere tra",
+                Msg::OneCharExcept => "un carattere tranne",
+                Msg::OneCharInAll => "un carattere corrispondente a tutti",
+                Msg::OneCharNotInAll => "un carattere non corrispondente a tutti",
+                Msg::Range => "da '{0}' a '{1}'",
+                Msg::Word => "un carattere di parola",
+                Msg::Digit => "una cifra",
+                Msg::Space => "uno spazio bianco",
+                Msg::NotWord => "un carattere che non è di parola",
+                Msg::NotDigit => "un carattere che non è una cifra",
+                Msg::NotSpace => "un carattere che non è uno spazio bianco",
+                Msg::VertSpace => "uno spazio bianco verticale",
+                Msg::HorizSpace => "uno spazio bianco orizzontale",
+                Msg::NotVertSpace => "un carattere che non è uno spazio bianco verticale",
+                Msg::NotHorizSpace => "un carattere che non è uno spazio bianco orizzontale",
+                Msg::LineBreak => "un a capo",
+                Msg::Property => "un carattere con la proprietà Unicode {0}",
+                Msg::NotProperty => "un carattere senza la proprietà Unicode {0}",
+                Msg::Grapheme => "un grafema",
+                Msg::Dot => "qualsiasi carattere tranne un a capo",
+                Msg::Sequence => "in quest'ordine",
+                Msg::CapturingGroup => "gruppo di cattura {0}",
+                Msg::NamedGroup => "gruppo di cattura {0} chiamato \"{1}\"",
+                Msg::OneOf => "uno tra",
+                Msg::NoneOf => "nessuno tra",
+                Msg::BranchReset => {
+                    "uno tra, dove i gruppi di ogni alternativa sono numerati allo stesso modo"
+                }
+                Msg::Atomic => "gruppo atomico (senza backtracking)",
+                Msg::EnableFlags => "con i flag {0} attivati",
+                Msg::DisableFlags => "con i flag {0} disattivati",
+                Msg::Optional => "facoltativamente",
+                Msg::ZeroOrMore => "zero o più volte",
+                Msg::OneOrMore => "una o più volte",
+                Msg::Exactly => "esattamente {0} volte",
+                Msg::AtLeast => "almeno {0} volte",
+                Msg::Between => "tra {0} e {1} volte",
+                Msg::Lazy => "{0} (il meno possibile)",
+                Msg::Possessive => "{0} (il più possibile, senza backtracking)",
+                Msg::Start => "l'inizio della stringa",
+                Msg::End => "la fine della stringa",
+                Msg::LineStart => "l'inizio di una riga",
+                Msg::LineEnd => "la fine di una riga",
+                Msg::EndOfText => "la fine assoluta della stringa",
+                Msg::EndOfTextOrBeforeNewline => {
+                    "la fine della stringa o la posizione prima di un a capo finale"
+                }
+                Msg::WordBoundary => "un confine di parola",
+                Msg::NotWordBoundary => "una posizione che non è un confine di parola",
+                Msg::FollowedBy => "seguito da",
+                Msg::NotFollowedBy => "non seguito da",
+                Msg::PrecededBy => "preceduto da",
+                Msg::NotPrecededBy => "non preceduto da",
+                Msg::Reference => "lo stesso testo del gruppo di cattura {0}",
+                Msg::Conditional => "se il gruppo di cattura {0} ha trovato una corrispondenza",
+                Msg::Otherwise => "altrimenti",
+                Msg::SubroutineCall => "il pattern del gruppo chiamato \"{0}\"",
+                Msg::Recursion => "il pattern del gruppo di cattura {0}",
+                Msg::Definitions => "definizioni di pattern, che qui non corrispondono a nulla",
+            },
+        }
+    }
+
+    /// Returns the message in the given locale, with the placeholders replaced
+    /// by the arguments
+    fn format(self, locale: Locale, args: &[&str]) -> String {
+        let mut text = self.text(locale).to_string();
+        for (i, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{i}}}"), arg);
+        }
+        text
+    }
+}
+
+/// Walks the regex and writes one line per node. Nodes with children end with
+/// a colon, and their children are indented.
+pub(crate) struct Explainer {
+    locale: Locale,
+    buf: String,
+    next_group: u32,
+}
+
+impl Explainer {
+    pub(crate) fn new(locale: Locale, next_group: u32) -> Self {
+        Explainer { locale, buf: String::new(), next_group }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.buf
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        if !self.buf.is_empty() {
+            self.buf.push('\n');
+        }
+        for _ in 0..depth {
+            self.buf.push_str("  ");
+        }
+        self.buf.push_str(text);
+    }
+
+    fn header(&mut self, depth: usize, text: &str) {
+        self.line(depth, text);
+        self.buf.push(':');
+    }
+
+    fn msg(&mut self, depth: usize, msg: Msg, args: &[&str]) {
+        let text = msg.format(self.locale, args);
+        self.line(depth, &text);
+    }
+
+    pub(crate) fn explain(&mut self, regex: &Regex<'_>, depth: usize) {
+        let locale = self.locale;
+        match regex {
+            Regex::Literal(s) if s.is_empty() => self.msg(depth, Msg::EmptyString, &[]),
+            Regex::Literal(s) if s.chars().count() == 1 => {
+                self.msg(depth, Msg::Char, &[&s.escape_debug().to_string()]);
+            }
+            Regex::Literal(s) => self.msg(depth, Msg::Text, &[&s.escape_debug().to_string()]),
+            Regex::Char(c) => self.msg(depth, Msg::Char, &[&c.escape_debug().to_string()]),
+            Regex::CharClass(class) => self.explain_class(class, depth),
+            Regex::ClassIntersection(intersection) => {
+                let msg =
+                    if intersection.negative { Msg::OneCharNotInAll } else { Msg::OneCharInAll };
+                self.header(depth, msg.text(locale));
+                for operand in &intersection.operands {
+                    self.explain_class(operand, depth + 1);
+                }
+            }
+            &Regex::Shorthand(s) => self.msg(depth, shorthand_msg(s), &[]),
+            &Regex::Property { value, negative } => {
+                let msg = if negative { Msg::NotProperty } else { Msg::Property };
+                self.msg(depth, msg, &[&property_name(value)]);
+            }
+            Regex::Grapheme => self.msg(depth, Msg::Grapheme, &[]),
+            Regex::LineBreak => self.msg(depth, Msg::LineBreak, &[]),
+            Regex::Dot => self.msg(depth, Msg::Dot, &[]),
+            Regex::Group(group) if matches!(group.capture, RegexCapture::BranchReset) => {
+                self.header(depth, Msg::BranchReset.text(locale));
+                let start = self.next_group;
+                let mut end = start;
+                for part in &group.parts {
+                    let branches = match part {
+                        Regex::Alternation(alt) => alt.parts.as_slice(),
+                        part => std::slice::from_ref(part),
+                    };
+                    for branch in branches {
+                        self.next_group = start;
+                        self.explain(branch, depth + 1);
+                        end = end.max(self.next_group);
+                    }
+                }
+                self.next_group = end;
+            }
+            Regex::Group(group) => {
+                let number = self.next_group.to_string();
+                let child_depth = match &group.capture {
+                    RegexCapture::Capture => {
+                        self.next_group += 1;
+                        self.header(depth, &Msg::CapturingGroup.format(locale, &[&number]));
+                        depth + 1
+                    }
+                    RegexCapture::NamedCapture(name) => {
+                        self.next_group += 1;
+                        self.header(depth, &Msg::NamedGroup.format(locale, &[&number, name]));
+                        depth + 1
+                    }
+                    RegexCapture::Define => {
+                        self.header(depth, Msg::Definitions.text(locale));
+                        depth + 1
+                    }
+                    RegexCapture::Atomic => {
+                        self.header(depth, Msg::Atomic.text(locale));
+                        depth + 1
+                    }
+                    RegexCapture::Flags { enable, flags } => {
+                        let names = flags.iter().map(|f| f.name()).collect::<Vec<_>>().join(", ");
+                        let msg = if *enable { Msg::EnableFlags } else { Msg::DisableFlags };
+                        self.header(depth, &msg.format(locale, &[&names]));
+                        depth + 1
+                    }
+                    RegexCapture::None
+                    | RegexCapture::NoneWithParens
+                    | RegexCapture::BranchReset => depth,
+                };
+                match group.parts.as_slice() {
+                    [] => self.msg(child_depth, Msg::EmptyString, &[]),
+                    [part] => self.explain(part, child_depth),
+                    parts => {
+                        let child_depth = if child_depth == depth {
+                            self.header(depth, Msg::Sequence.text(locale));
+                            depth + 1
+                        } else {
+                            child_depth
+                        };
+                        for part in parts {
+                            self.explain(part, child_depth);
+                        }
+                    }
+                }
+            }
+            Regex::Alternation(alt) => {
+                self.header(depth, Msg::OneOf.text(locale));
+                for part in &alt.parts {
+                    self.explain(part, depth + 1);
+                }
+            }
+            Regex::Repetition(rep) => {
+                let lower = rep.kind.lower_bound.to_string();
+                let text = match (rep.kind.lower_bound, rep.kind.upper_bound) {
+                    (1, Some(1)) => return self.explain(&rep.content, depth),
+                    (0, Some(1)) => Msg::Optional.format(locale, &[]),
+                    (0, None) => Msg::ZeroOrMore.format(locale, &[]),
+                    (1, None) => Msg::OneOrMore.format(locale, &[]),
+                    (_, None) => Msg::AtLeast.format(locale, &[&lower]),
+                    (lo, Some(hi)) if lo == hi => Msg::Exactly.format(locale, &[&lower]),
+                    (_, Some(hi)) => Msg::Between.format(locale, &[&lower, &hi.to_string()]),
+                };
+                let text = match rep.quantifier {
+                    RegexQuantifier::Greedy => text,
+                    RegexQuantifier::Lazy => Msg::Lazy.format(locale, &[&text]),
+                    RegexQuantifier::Possessive => Msg::Possessive.format(locale, &[&text]),
+                };
+                self.header(depth, &text);
+                self.explain(&rep.content, depth + 1);
+            }
+            &Regex::Boundary(kind) => {
+                let msg = match kind {
+                    BoundaryKind::Start => Msg::Start,
+                    BoundaryKind::End => Msg::End,
+                    BoundaryKind::LineStart => Msg::LineStart,
+                    BoundaryKind::LineEnd => Msg::LineEnd,
+                    BoundaryKind::EndOfText => Msg::EndOfText,
+                    BoundaryKind::EndOfTextOrBeforeNewline => Msg::EndOfTextOrBeforeNewline,
+                    BoundaryKind::Word => Msg::WordBoundary,
+                    BoundaryKind::NotWord => Msg::NotWordBoundary,
+                };
+                self.msg(depth, msg, &[]);
+            }
+            Regex::Lookaround(look) => {
+                let msg = match look.kind {
+                    LookaroundKind::Ahead => Msg::FollowedBy,
+                    LookaroundKind::AheadNegative => Msg::NotFollowedBy,
+                    LookaroundKind::Behind => Msg::PrecededBy,
+                    LookaroundKind::BehindNegative => Msg::NotPrecededBy,
+                };
+                self.header(depth, msg.text(locale));
+                self.explain(&look.content, depth + 1);
+            }
+            Regex::Reference(r) => self.msg(depth, Msg::Reference, &[&r.number.to_string()]),
+            Regex::Conditional(c) => {
+                let number = c.condition.number.to_string();
+                self.header(depth, &Msg::Conditional.format(locale, &[&number]));
+                self.explain(&c.then, depth + 1);
+                if let Some(otherwise) = &c.otherwise {
+                    self.header(depth, Msg::Otherwise.text(locale));
+                    self.explain(otherwise, depth + 1);
+                }
+            }
+            Regex::SubroutineCall(name) => self.msg(depth, Msg::SubroutineCall, &[name]),
+            Regex::Recursion(number) => self.msg(depth, Msg::Recursion, &[&number.to_string()]),
+        }
+    }
+
+    fn explain_class(&mut self, class: &RegexCharClass, depth: usize) {
+        let msg = if class.negative { Msg::OneCharExcept } else { Msg::OneCharOf };
+        self.header(depth, msg.text(self.locale));
+        self.explain_class_items(&class.items, depth + 1);
+    }
+
+    fn explain_class_items(&mut self, items: &[RegexClassItem], depth: usize) {
+        for item in items {
+            match *item {
+                RegexClassItem::Char(c) => {
+                    self.line(depth, &format!("'{}'", c.escape_debug()));
+                }
+                RegexClassItem::Range { first, last } => {
+                    let (first, last) = (first.escape_debug(), last.escape_debug());
+                    self.msg(depth, Msg::Range, &[&first.to_string(), &last.to_string()]);
+                }
+                RegexClassItem::Shorthand(s) => self.msg(depth, shorthand_msg(s), &[]),
+                RegexClassItem::Property { negative, value } => {
+                    let msg = if negative { Msg::NotProperty } else { Msg::Property };
+                    self.msg(depth, msg, &[&property_name(value)]);
+                }
+                RegexClassItem::Nested { negative, items } => {
+                    let msg = if negative { Msg::NoneOf } else { Msg::OneOf };
+                    self.header(depth, msg.text(self.locale));
+                    self.explain_class_items(items, depth + 1);
+                }
+            }
+        }
+    }
+}
+
+fn shorthand_msg(shorthand: RegexShorthand) -> Msg {
+    match shorthand {
+        RegexShorthand::Word => Msg::Word,
+        RegexShorthand::Digit => Msg::Digit,
+        RegexShorthand::Space => Msg::Space,
+        RegexShorthand::NotWord => Msg::NotWord,
+        RegexShorthand::NotDigit => Msg::NotDigit,
+        RegexShorthand::NotSpace => Msg::NotSpace,
+        RegexShorthand::VertSpace => Msg::VertSpace,
+        RegexShorthand::HorizSpace => Msg::HorizSpace,
+        RegexShorthand::NotVertSpace => Msg::NotVertSpace,
+        RegexShorthand::NotHorizSpace => Msg::NotHorizSpace,
+    }
+}
+
+fn property_name(property: RegexProperty) -> String {
+    match property {
+        RegexProperty::Category(c) => c.as_str().to_string(),
+        RegexProperty::Script(s) => s.as_str().to_string(),
+        RegexProperty::ScriptExtension(s) => format!("scx={}", s.as_str()),
+        RegexProperty::Block(b) => format!("In{}", b.as_str()),
+        RegexProperty::Other(o) => o.as_str().to_string(),
+    }
+}
@@ -0,0 +1,169 @@
+//! Implements blocks that change inline flags for an expression, e.g.
+//! `enable case_insensitive { ... }`.
+
+use std::collections::HashMap;
+
+use crate::{
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts, Output},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
+    group::{RegexCapture, RegexGroup},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
+    regex::Regex,
+    rule::Rule,
+    span::Span,
+    warning::Warning,
+};
+
+/// A flag that can be enabled or disabled for part of an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Flag {
+    /// Letters are matched case-insensitively (the `i` flag)
+    CaseInsensitive,
+    /// `^` and `$` match at the start and end of each line (the `m` flag).
+    /// This only affects verbatim regexes, since `Start` and `End` are
+    /// compiled to `\A` and `\z`, and `LineStart` and `LineEnd` always match
+    /// at line breaks.
+    Multiline,
+    /// The dot also matches line breaks (the `s` flag, called `m` in Ruby)
+    DotAll,
+}
+
+impl Flag {
+    pub(crate) fn from_name(name: &str) -> Option<Flag> {
+        match name {
+            "case_insensitive" => Some(Flag::CaseInsensitive),
+            "multiline" => Some(Flag::Multiline),
+            "dot_all" => Some(Flag::DotAll),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Flag::CaseInsensitive => "case_insensitive",
+            Flag::Multiline => "multiline",
+            Flag::DotAll => "dot_all",
+        }
+    }
+
+    /// Returns the letter of the flag in the given flavor. Ruby's `^` and `$`
+    /// always match at line breaks, so it has no `multiline` flag.
+    fn letter(self, flavor: RegexFlavor) -> char {
+        match (self, flavor) {
+            (Flag::CaseInsensitive, _) => 'i',
+            (Flag::Multiline, _) => 'm',
+            (Flag::DotAll, RegexFlavor::Ruby) => 'm',
+            (Flag::DotAll, _) => 's',
+        }
+    }
+}
+
+/// A block that enables or disables flags for its contents, e.g.
+/// `enable case_insensitive, dot_all { ... }`. It is compiled to a group with
+/// inline flags, e.g. `(?is:...)` or `(?-i:...)`.
+#[derive(Clone)]
+pub(crate) struct FlagGroup<'i> {
+    /// Whether the flags are enabled (`enable`) or disabled (`disable`)
+    pub(crate) enable: bool,
+    /// The flags, sorted and without duplicates
+    pub(crate) flags: Vec<Flag>,
+    pub(crate) rule: Rule<'i>,
+    pub(crate) span: Span,
+}
+
+impl<'i> FlagGroup<'i> {
+    pub(crate) fn new(enable: bool, mut flags: Vec<Flag>, rule: Rule<'i>, span: Span) -> Self {
+        flags.sort();
+        flags.dedup();
+        FlagGroup { enable, flags, rule, span }
+    }
+
+    pub(crate) fn get_capturing_groups(
+        &self,
+        count: &mut u32,
+        map: &mut HashMap<String, u32>,
+        within_variable: bool,
+    ) -> Result<(), CompileError> {
+        self.rule.get_capturing_groups(count, map, within_variable)
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        self.rule.capture_info(info, optional);
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        self.rule.lint(options, warnings, in_repetition);
+    }
+
+    pub(crate) fn compile<'c>(
+        &'c self,
+        options: &CompileOptions,
+        state: &mut CompileState<'c, 'i>,
+    ) -> CompileResult<'i> {
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
+        let content = self.rule.comp(options, state)?;
+        let capture = RegexCapture::Flags { enable: self.enable, flags: self.flags.clone() };
+        Ok(Regex::Group(RegexGroup::new(vec![content], capture)))
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Ruby if self.flags.contains(&Flag::Multiline) => {
+                Some(Feature::MultilineFlag)
+            }
+            RegexFlavor::Pcre
+            | RegexFlavor::Python
+            | RegexFlavor::Java
+            | RegexFlavor::DotNet
+            | RegexFlavor::Ruby
+            | RegexFlavor::Rust
+            | RegexFlavor::Re2 => None,
+            _ => Some(Feature::InlineFlags),
+        }
+    }
+
+    pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
+        self.rule.validate(options)
+    }
+}
+
+/// Writes the opening of a group with inline flags, e.g. `(?is:` or `(?-i:`
+pub(crate) fn codegen_flags(
+    buf: &mut impl Output,
+    enable: bool,
+    flags: &[Flag],
+    flavor: RegexFlavor,
+) {
+    buf.push_str("(?");
+    if !enable {
+        buf.push('-');
+    }
+    for &flag in flags {
+        buf.push(flag.letter(flavor));
+    }
+    buf.push(':');
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for FlagGroup<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FlagGroup")
+            .field("enable", &self.enable)
+            .field("flags", &self.flags)
+            .field("rule", &self.rule)
+            .finish()
+    }
+}
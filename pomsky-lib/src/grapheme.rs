@@ -17,11 +17,22 @@ use crate::{
 pub(crate) struct Grapheme;
 
 impl Grapheme {
-    pub(crate) fn compile(&self, options: CompileOptions) -> CompileResult<'static> {
-        if matches!(options.flavor, RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Ruby) {
-            Ok(Regex::Grapheme)
-        } else {
-            Err(CompileErrorKind::Unsupported(Feature::Grapheme, options.flavor).at(Span::empty()))
+    pub(crate) fn compile(&self, options: &CompileOptions) -> CompileResult<'static> {
+        match self.unsupported_feature(options.flavor) {
+            Some(feature) => {
+                Err(CompileErrorKind::Unsupported(feature, options.flavor).at(Span::empty()))
+            }
+            None if options.byte_mode => {
+                Err(CompileErrorKind::UnicodeInByteMode("`Grapheme`").at(Span::empty()))
+            }
+            None => Ok(Regex::Grapheme),
+        }
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Ruby => None,
+            _ => Some(Feature::Grapheme),
         }
     }
 
@@ -1,12 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use crate::{
-    compile::{CompileResult, CompileState},
-    error::{CompileError, CompileErrorKind, ParseError},
+    captures::{CaptureGroup, CaptureInfo, CaptureOccurrence, OccurrenceKind},
+    compile::{CompileResult, CompileState, NodeCounts, Output},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
+    flags::{codegen_flags, Flag},
     options::{CompileOptions, ParseOptions, RegexFlavor},
     regex::Regex,
     rule::Rule,
     span::Span,
+    warning::{RepetitionWarning, Warning, WarningKind},
 };
 
 /// A group, i.e. sequence of rules. A group is either capturing or
@@ -16,8 +22,8 @@ use crate::{
 /// If it is non-capturing, the parentheses can be omitted in same cases.
 #[derive(Clone)]
 pub(crate) struct Group<'i> {
-    parts: Vec<Rule<'i>>,
-    capture: Option<Capture<'i>>,
+    pub(crate) parts: Vec<Rule<'i>>,
+    pub(crate) capture: Option<Capture<'i>>,
     pub(crate) span: Span,
 }
 
@@ -40,7 +46,7 @@ impl<'i> Group<'i> {
         within_variable: bool,
     ) -> Result<(), CompileError> {
         match self.capture {
-            Some(Capture { name: Some(name) }) => {
+            Some(Capture { name: Some(name), .. }) => {
                 if within_variable {
                     return Err(CompileErrorKind::CaptureInLet.at(self.span));
                 }
@@ -54,7 +60,7 @@ impl<'i> Group<'i> {
                 *count += 1;
                 map.insert(name.to_string(), *count);
             }
-            Some(Capture { name: None }) => {
+            Some(Capture { name: None, .. }) => {
                 if within_variable {
                     return Err(CompileErrorKind::CaptureInLet.at(self.span));
                 }
@@ -69,15 +75,45 @@ impl<'i> Group<'i> {
         Ok(())
     }
 
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        for part in &self.parts {
+            part.count_nodes(counts);
+        }
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        if let Some(Capture { name, name_span }) = self.capture {
+            info.groups.push(CaptureGroup {
+                index: info.groups.len() as u32 + 1,
+                name: name.map(ToString::to_string),
+                optional,
+            });
+            if let Some(name) = name {
+                info.occurrences.push(CaptureOccurrence {
+                    name: name.to_string(),
+                    kind: OccurrenceKind::Definition,
+                    span: name_span.range_unchecked(),
+                });
+            }
+        }
+        for rule in &self.parts {
+            rule.capture_info(info, optional);
+        }
+    }
+
     pub(crate) fn compile<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
         if self.capture.is_some() {
             state.next_idx += 1;
         }
 
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
         Ok(Regex::Group(RegexGroup {
             parts: self
                 .parts
@@ -85,26 +121,54 @@ impl<'i> Group<'i> {
                 .map(|part| part.comp(options, state))
                 .collect::<Result<_, _>>()?,
             capture: match self.capture {
-                Some(Capture { name: Some(name) }) => RegexCapture::NamedCapture(name),
-                Some(Capture { name: None }) => RegexCapture::Capture,
+                Some(Capture { name: Some(name), name_span }) => {
+                    RegexCapture::NamedCapture(output_group_name(name, name_span, options, state)?)
+                }
+                Some(Capture { name: None, .. }) => RegexCapture::Capture,
                 None => RegexCapture::None,
             },
         }))
     }
 
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match (self.capture, flavor) {
+            (
+                Some(Capture { name: Some(_), .. }),
+                RegexFlavor::Sed { .. } | RegexFlavor::Antlr | RegexFlavor::Lua,
+            ) => Some(Feature::NamedCaptureGroups),
+            _ => None,
+        }
+    }
+
     pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
         for rule in &self.parts {
             rule.validate(options)?;
         }
         Ok(())
     }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        if in_repetition && self.capture.is_some() && options.warn_capture_in_repetition {
+            warnings.push(
+                WarningKind::Repetition(RepetitionWarning::CaptureInRepetition).at(self.span),
+            );
+        }
+        for rule in &self.parts {
+            rule.lint(options, warnings, in_repetition);
+        }
+    }
 }
 
 #[cfg(feature = "dbg")]
 impl core::fmt::Debug for Group<'_> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self.capture {
-            Some(Capture { name: Some(name) }) => write!(f, "Group :{name}")?,
+            Some(Capture { name: Some(name), .. }) => write!(f, "Group :{name}")?,
             Some(_) => write!(f, "Group :")?,
             None => write!(f, "Group")?,
         }
@@ -125,26 +189,40 @@ impl core::fmt::Debug for Group<'_> {
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct Capture<'i> {
     pub(crate) name: Option<&'i str>,
+    /// The span of the name, or an empty span if the group is unnamed
+    pub(crate) name_span: Span,
 }
 
 impl<'i> Capture<'i> {
-    pub(crate) fn new(name: Option<&'i str>) -> Self {
-        Capture { name }
+    pub(crate) fn new(name: Option<&'i str>, name_span: Span) -> Self {
+        Capture { name, name_span }
     }
 }
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct RegexGroup<'i> {
-    parts: Vec<Regex<'i>>,
-    capture: RegexCapture<'i>,
+    pub(crate) parts: Vec<Regex<'i>>,
+    pub(crate) capture: RegexCapture<'i>,
 }
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) enum RegexCapture<'i> {
     Capture,
-    NamedCapture(&'i str),
+    NamedCapture(Cow<'i, str>),
     None,
     NoneWithParens,
+    /// A branch reset group, `(?|...)`, containing an alternation
+    BranchReset,
+    /// A `(?(DEFINE)...)` group, containing named groups that are only
+    /// matched by subroutine calls
+    Define,
+    /// An atomic group, `(?>...)`
+    Atomic,
+    /// A group with inline flags, e.g. `(?i:...)` or `(?-s:...)`
+    Flags {
+        enable: bool,
+        flags: Vec<Flag>,
+    },
 }
 
 impl<'i> RegexGroup<'i> {
@@ -152,12 +230,15 @@ impl<'i> RegexGroup<'i> {
         Self { parts, capture }
     }
 
-    pub(crate) fn codegen(&self, buf: &mut String, flavor: RegexFlavor) {
-        match self.capture {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        match &self.capture {
             RegexCapture::NamedCapture(name) => {
                 // https://www.regular-expressions.info/named.html
                 match flavor {
-                    RegexFlavor::Python | RegexFlavor::Pcre | RegexFlavor::Rust => {
+                    RegexFlavor::Python
+                    | RegexFlavor::Pcre
+                    | RegexFlavor::Rust
+                    | RegexFlavor::Re2 => {
                         buf.push_str("(?P<");
                     }
                     RegexFlavor::DotNet
@@ -166,6 +247,11 @@ impl<'i> RegexGroup<'i> {
                     | RegexFlavor::JavaScript => {
                         buf.push_str("(?<");
                     }
+                    RegexFlavor::Sed { .. } | RegexFlavor::Antlr | RegexFlavor::Lua => {
+                        unreachable!(
+                            "named groups are rejected when compiling for sed, ANTLR or Lua"
+                        )
+                    }
                 }
                 buf.push_str(name);
                 buf.push('>');
@@ -175,30 +261,58 @@ impl<'i> RegexGroup<'i> {
                 buf.push(')');
             }
             RegexCapture::Capture => {
-                buf.push('(');
+                codegen_open_paren(buf, true, flavor);
                 for part in &self.parts {
                     part.codegen(buf, flavor);
                 }
-                buf.push(')');
+                codegen_close_paren(buf, flavor);
             }
             RegexCapture::None => {
                 for part in &self.parts {
                     let needs_parens = part.needs_parens_in_group();
                     if needs_parens {
-                        buf.push_str("(?:");
+                        codegen_open_paren(buf, false, flavor);
                     }
                     part.codegen(buf, flavor);
                     if needs_parens {
-                        buf.push(')');
+                        codegen_close_paren(buf, flavor);
                     }
                 }
             }
             RegexCapture::NoneWithParens => {
                 for part in &self.parts {
-                    buf.push_str("(?:");
+                    codegen_open_paren(buf, false, flavor);
+                    part.codegen(buf, flavor);
+                    codegen_close_paren(buf, flavor);
+                }
+            }
+            RegexCapture::BranchReset => {
+                buf.push_str("(?|");
+                for part in &self.parts {
+                    part.codegen(buf, flavor);
+                }
+                buf.push(')');
+            }
+            RegexCapture::Define => {
+                buf.push_str("(?(DEFINE)");
+                for part in &self.parts {
+                    part.codegen(buf, flavor);
+                }
+                buf.push(')');
+            }
+            RegexCapture::Atomic => {
+                buf.push_str("(?>");
+                for part in &self.parts {
+                    part.codegen(buf, flavor);
+                }
+                buf.push(')');
+            }
+            RegexCapture::Flags { enable, flags } => {
+                codegen_flags(buf, *enable, flags, flavor);
+                for part in &self.parts {
                     part.codegen(buf, flavor);
-                    buf.push(')');
                 }
+                buf.push(')');
             }
         }
     }
@@ -208,8 +322,161 @@ impl<'i> RegexGroup<'i> {
             RegexCapture::None if self.parts.len() == 1 => {
                 self.parts[0].needs_parens_before_repetition()
             }
-            RegexCapture::NoneWithParens => false,
+            RegexCapture::NoneWithParens
+            | RegexCapture::BranchReset
+            | RegexCapture::Define
+            | RegexCapture::Atomic
+            | RegexCapture::Flags { .. } => false,
             _ => true,
         }
     }
 }
+
+/// Writes the opening parenthesis of a group. POSIX regular expressions have no
+/// non-capturing groups, so a capturing group is opened instead in the `sed`
+/// flavor.
+pub(crate) fn codegen_open_paren(buf: &mut impl Output, capturing: bool, flavor: RegexFlavor) {
+    match flavor {
+        RegexFlavor::Sed { extended: false } => buf.push_str("\\("),
+        RegexFlavor::Sed { extended: true } => buf.push('('),
+        _ if capturing => buf.push('('),
+        _ => buf.push_str("(?:"),
+    }
+}
+
+/// Writes the closing parenthesis of a group.
+pub(crate) fn codegen_close_paren(buf: &mut impl Output, flavor: RegexFlavor) {
+    match flavor {
+        RegexFlavor::Sed { extended: false } => buf.push_str("\\)"),
+        _ => buf.push(')'),
+    }
+}
+
+/// Returns the name of a named group in the output. If the name was sanitized
+/// because of [`NameHandling::Sanitize`](crate::options::NameHandling), the
+/// new name is returned. Otherwise, an error is returned if the flavor doesn't
+/// support the name.
+pub(crate) fn output_group_name<'i>(
+    name: &'i str,
+    span: Span,
+    options: &CompileOptions,
+    state: &CompileState<'_, '_>,
+) -> Result<Cow<'i, str>, CompileError> {
+    if let Some(new_name) = state.renamed_groups.get(name) {
+        return Ok(Cow::Owned(new_name.clone()));
+    }
+    if let Some(reason) = unsupported_name_reason(name, options.flavor) {
+        let name = name.to_string();
+        let flavor = options.flavor;
+        return Err(CompileErrorKind::UnsupportedGroupName { name, flavor, reason }.at(span));
+    }
+    Ok(Cow::Borrowed(name))
+}
+
+/// Returns why the flavor doesn't support the group name, or `None` if it is
+/// supported. Java only allows ASCII letters and digits; PCRE and RE2 only
+/// allow ASCII letters, digits and underscores, and PCRE allows at most 32
+/// characters.
+pub(crate) fn unsupported_name_reason(name: &str, flavor: RegexFlavor) -> Option<&'static str> {
+    if matches!(flavor, RegexFlavor::Sed { .. } | RegexFlavor::Antlr | RegexFlavor::Lua) {
+        // named groups are rejected in these flavors anyway
+        return None;
+    }
+
+    let first = match name.chars().next() {
+        Some(c) => c,
+        None => return Some("it is empty"),
+    };
+    if !name.chars().all(|c| is_name_char(c, flavor)) {
+        return Some(match flavor {
+            RegexFlavor::Java => "only ASCII letters and digits are allowed",
+            RegexFlavor::Pcre | RegexFlavor::Re2 => {
+                "only ASCII letters, digits and underscores are allowed"
+            }
+            _ => "only letters, digits and underscores are allowed",
+        });
+    }
+    if !is_name_start(first, flavor) {
+        return Some("it can't start with a digit");
+    }
+    if let Some(max_len) = max_name_len(flavor) {
+        if name.chars().count() > max_len {
+            return Some("it can't be longer than 32 characters");
+        }
+    }
+    None
+}
+
+/// Sanitizes all names that aren't supported by the flavor, and returns a map
+/// from the original names to the new names. New names are different from all
+/// other names, so `used_names` must contain every group name.
+pub(crate) fn sanitize_names(
+    used_names: &HashMap<String, u32>,
+    flavor: RegexFlavor,
+) -> HashMap<String, String> {
+    // sort the names by group index, so the output is deterministic
+    let mut names = used_names.iter().collect::<Vec<_>>();
+    names.sort_by_key(|&(_, &index)| index);
+
+    let mut taken = names
+        .iter()
+        .map(|&(name, _)| name.clone())
+        .filter(|name| unsupported_name_reason(name, flavor).is_none())
+        .collect::<HashSet<_>>();
+
+    let mut renamed = HashMap::new();
+    for (name, _) in names {
+        if unsupported_name_reason(name, flavor).is_some() {
+            let new_name = sanitize_name(name, flavor, &taken);
+            taken.insert(new_name.clone());
+            renamed.insert(name.clone(), new_name);
+        }
+    }
+    renamed
+}
+
+/// Replaces invalid characters with underscores, or removes them if the flavor
+/// doesn't allow underscores. If the result doesn't start with a valid
+/// character, it is prefixed with `_` (or `g`). If it is already taken, a
+/// number is appended.
+fn sanitize_name(name: &str, flavor: RegexFlavor, taken: &HashSet<String>) -> String {
+    let replacement = if is_name_char('_', flavor) { Some('_') } else { None };
+    let mut sanitized = name
+        .chars()
+        .filter_map(|c| if is_name_char(c, flavor) { Some(c) } else { replacement })
+        .collect::<String>();
+    if !sanitized.starts_with(|c| is_name_start(c, flavor)) {
+        sanitized.insert(0, replacement.unwrap_or('g'));
+    }
+
+    let max_len = max_name_len(flavor).unwrap_or(usize::MAX);
+    let truncate = |s: &str, len: usize| s.chars().take(len).collect::<String>();
+
+    let mut new_name = truncate(&sanitized, max_len);
+    let mut n = 2;
+    while taken.contains(&new_name) {
+        let suffix = n.to_string();
+        new_name = truncate(&sanitized, max_len.saturating_sub(suffix.len())) + &suffix;
+        n += 1;
+    }
+    new_name
+}
+
+fn is_name_char(c: char, flavor: RegexFlavor) -> bool {
+    match flavor {
+        RegexFlavor::Java => c.is_ascii_alphanumeric(),
+        RegexFlavor::Pcre | RegexFlavor::Re2 => c.is_ascii_alphanumeric() || c == '_',
+        _ => c.is_alphanumeric() || c == '_',
+    }
+}
+
+fn is_name_start(c: char, flavor: RegexFlavor) -> bool {
+    is_name_char(c, flavor) && !c.is_numeric()
+}
+
+fn max_name_len(flavor: RegexFlavor) -> Option<usize> {
+    match flavor {
+        RegexFlavor::Pcre => Some(32),
+        _ => None,
+    }
+}
@@ -0,0 +1,256 @@
+//! Inlines variables that are only used once, as done by
+//! [`Expr::inline_variables`](crate::Expr::inline_variables).
+//!
+//! Variables are resolved when they are used, not where they are declared, so
+//! the same variable in a `let` statement can refer to different variables
+//! depending on where the `let` statement's variable is used. Therefore the
+//! uses are counted by expanding the variables like the compiler does. A
+//! variable is only inlined if it is expanded exactly once, and the variable
+//! that refers to it is also only expanded once.
+//!
+//! Capturing groups aren't allowed in `let` statements, so inlining doesn't
+//! change the numbering of groups. Variables in a `define` block are never
+//! inlined, since they are compiled to named groups.
+
+use std::collections::HashMap;
+
+use crate::{
+    alternation::Alternation,
+    atomic::Atomic,
+    capture_each::CaptureEach,
+    conditional::Conditional,
+    flags::FlagGroup,
+    group::Group,
+    lookaround::Lookaround,
+    repetition::Repetition,
+    reset::BranchReset,
+    rule::Rule,
+    stmt::{Let, Stmt, StmtExpr},
+    var::Variable,
+    word::Word,
+};
+
+/// Returns a copy of the rule where variables that are only used once are
+/// replaced with their content.
+///
+/// If a variable can't be resolved because it is used recursively, nothing is
+/// inlined, so the compiler reports the same error.
+pub(crate) fn inline_variables<'i>(rule: &Rule<'i>) -> Rule<'i> {
+    let mut counter = UseCounter::default();
+    counter.visit(rule);
+    if counter.failed {
+        return rule.clone();
+    }
+
+    let mut inlined = HashMap::new();
+    for &(decl, count, var) in counter.lets.values() {
+        if count == 1 && counter.vars.get(&(var as *const _)) == Some(&1) {
+            inlined.insert(var as *const _, decl);
+        }
+    }
+    if inlined.is_empty() {
+        return rule.clone();
+    }
+
+    let removed = inlined.values().map(|&decl| decl as *const _).collect();
+    Inliner { inlined, removed }.rebuild(rule)
+}
+
+/// The variables that are in scope while counting, like
+/// [`CompileState::variables`](crate::compile::CompileState).
+struct Binding<'r, 'i> {
+    name: &'i str,
+    rule: &'r Rule<'i>,
+    /// `None` for a variable in a `define` block, which isn't expanded
+    decl: Option<&'r Let<'i>>,
+}
+
+#[derive(Default)]
+struct UseCounter<'r, 'i> {
+    variables: Vec<Binding<'r, 'i>>,
+    current_vars: Vec<usize>,
+    /// For each `let` statement, how often its variable was expanded, and the
+    /// variable that referred to it the last time
+    lets: HashMap<*const Let<'i>, (&'r Let<'i>, u32, &'r Variable<'i>)>,
+    /// For each variable, how often it was expanded
+    vars: HashMap<*const Variable<'i>, u32>,
+    failed: bool,
+}
+
+impl<'r, 'i> UseCounter<'r, 'i> {
+    fn visit(&mut self, rule: &'r Rule<'i>) {
+        if self.failed {
+            return;
+        }
+        match rule {
+            Rule::Group(g) => g.parts.iter().for_each(|part| self.visit(part)),
+            Rule::Alternation(a) => a.rules.iter().for_each(|rule| self.visit(rule)),
+            Rule::BranchReset(b) => b.branches.iter().for_each(|branch| self.visit(branch)),
+            Rule::Repetition(r) => self.visit(&r.rule),
+            Rule::Lookaround(l) => self.visit(&l.rule),
+            Rule::Word(w) => self.visit(&w.rule),
+            Rule::CaptureEach(c) => self.visit(&c.rule),
+            Rule::Atomic(a) => self.visit(&a.rule),
+            Rule::Flags(f) => self.visit(&f.rule),
+            Rule::Conditional(c) => {
+                self.visit(&c.then);
+                if let Some(otherwise) = &c.otherwise {
+                    self.visit(otherwise);
+                }
+            }
+            Rule::Variable(v) => self.visit_variable(v),
+            Rule::StmtExpr(s) => {
+                let len = self.variables.len();
+                match &s.stmt {
+                    Stmt::Let(l) => self.variables.push(Binding {
+                        name: l.name(),
+                        rule: &l.rule,
+                        decl: Some(l),
+                    }),
+                    Stmt::Define(lets) => {
+                        for l in lets {
+                            self.variables.push(Binding {
+                                name: l.name(),
+                                rule: &l.rule,
+                                decl: None,
+                            });
+                        }
+                        // the content of each variable is compiled once, in a `(?(DEFINE)...)` group
+                        for l in lets {
+                            self.visit(&l.rule);
+                        }
+                    }
+                    Stmt::Enable(_) | Stmt::Disable(_) => {}
+                }
+                self.visit(&s.rule);
+                self.variables.truncate(len);
+            }
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Boundary(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_) => {}
+        }
+    }
+
+    /// Resolves a variable like [`Variable::compile`] and expands it
+    fn visit_variable(&mut self, v: &'r Variable<'i>) {
+        *self.vars.entry(v).or_default() += 1;
+
+        let found = self
+            .variables
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|&(i, b)| b.name == v.name && !self.current_vars.contains(&i));
+        match found {
+            Some((i, &Binding { rule, decl: Some(decl), .. })) => {
+                let entry = self.lets.entry(decl).or_insert((decl, 0, v));
+                entry.1 += 1;
+                entry.2 = v;
+
+                self.current_vars.push(i);
+                self.visit(rule);
+                self.current_vars.pop();
+            }
+            // a subroutine call
+            Some((_, Binding { decl: None, .. })) => {}
+            None => {
+                // the variable is used recursively; built-in variables can be shadowed, so
+                // this isn't necessarily an error, but inlining is skipped to be safe
+                if self.current_vars.iter().any(|&i| self.variables[i].name == v.name) {
+                    self.failed = true;
+                }
+                // otherwise it is a built-in variable, or an unknown variable, which is
+                // reported when compiling
+            }
+        }
+    }
+}
+
+struct Inliner<'r, 'i> {
+    /// Maps the variables that are inlined to the `let` statement they refer to
+    inlined: HashMap<*const Variable<'i>, &'r Let<'i>>,
+    /// The `let` statements that are removed
+    removed: Vec<*const Let<'i>>,
+}
+
+impl<'i> Inliner<'_, 'i> {
+    fn rebuild(&self, rule: &Rule<'i>) -> Rule<'i> {
+        match rule {
+            Rule::Variable(v) => match self.inlined.get(&(v as *const _)) {
+                Some(decl) => self.rebuild(&decl.rule),
+                None => rule.clone(),
+            },
+            Rule::StmtExpr(s) => match &s.stmt {
+                Stmt::Let(l) if self.removed.contains(&(l as *const _)) => self.rebuild(&s.rule),
+                stmt => {
+                    let stmt = match stmt {
+                        Stmt::Let(l) => Stmt::Let(self.rebuild_let(l)),
+                        Stmt::Define(lets) => {
+                            Stmt::Define(lets.iter().map(|l| self.rebuild_let(l)).collect())
+                        }
+                        stmt => stmt.clone(),
+                    };
+                    Rule::StmtExpr(Box::new(StmtExpr::new(stmt, self.rebuild(&s.rule), s.span)))
+                }
+            },
+            Rule::Group(g) => {
+                Rule::Group(Group::new(self.rebuild_all(&g.parts), g.capture, g.span))
+            }
+            Rule::Alternation(a) => {
+                Rule::Alternation(Alternation { rules: self.rebuild_all(&a.rules), span: a.span })
+            }
+            Rule::BranchReset(b) => {
+                Rule::BranchReset(BranchReset::new(self.rebuild_all(&b.branches), b.span))
+            }
+            Rule::Repetition(r) => Rule::Repetition(Box::new(Repetition::new(
+                self.rebuild(&r.rule),
+                r.kind,
+                r.quantifier,
+                r.span,
+            ))),
+            Rule::Lookaround(l) => {
+                Rule::Lookaround(Box::new(Lookaround::new(self.rebuild(&l.rule), l.kind, l.span)))
+            }
+            Rule::Word(w) => Rule::Word(Box::new(Word::new(self.rebuild(&w.rule), w.span))),
+            Rule::CaptureEach(c) => {
+                Rule::CaptureEach(Box::new(CaptureEach::new(self.rebuild(&c.rule), c.span)))
+            }
+            Rule::Atomic(a) => Rule::Atomic(Box::new(Atomic::new(self.rebuild(&a.rule), a.span))),
+            Rule::Flags(f) => Rule::Flags(Box::new(FlagGroup::new(
+                f.enable,
+                f.flags.clone(),
+                self.rebuild(&f.rule),
+                f.span,
+            ))),
+            Rule::Conditional(c) => Rule::Conditional(Box::new(Conditional::new(
+                c.condition,
+                self.rebuild(&c.then),
+                c.otherwise.as_ref().map(|otherwise| self.rebuild(otherwise)),
+                c.span,
+            ))),
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Boundary(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_) => rule.clone(),
+        }
+    }
+
+    fn rebuild_all(&self, rules: &[Rule<'i>]) -> Vec<Rule<'i>> {
+        rules.iter().map(|rule| self.rebuild(rule)).collect()
+    }
+
+    fn rebuild_let(&self, l: &Let<'i>) -> Let<'i> {
+        Let::new(l.name(), self.rebuild(&l.rule), l.name_span)
+    }
+}
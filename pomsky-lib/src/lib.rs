@@ -12,7 +12,7 @@
 //! use pomsky::Expr;
 //! use pomsky::options::{CompileOptions, RegexFlavor};
 //!
-//! let options = CompileOptions { flavor: RegexFlavor::Java };
+//! let options = CompileOptions { flavor: RegexFlavor::Java, ..Default::default() };
 //! let (regex, _warnings) = match Expr::parse_and_compile("'test'", Default::default(), options) {
 //!     Ok(regex) => regex,
 //!     Err(_) => {
@@ -31,51 +31,121 @@
 //! use pomsky::error::Diagnostic;
 //!
 //! pub fn compile(input: &str) -> miette::Result<String> {
-//!     let options = CompileOptions { flavor: RegexFlavor::Java };
+//!     let options = CompileOptions { flavor: RegexFlavor::Java, ..Default::default() };
 //!     let (compiled, _warnings) = Expr::parse_and_compile(input, Default::default(), options)
 //!         .map_err(|e| e.diagnostic(input))?;
 //!     Ok(compiled)
 //! }
 //! ```
+//!
+//! ## Inspecting expressions
+//!
+//! A parsed expression can be inspected with [`Expr::root`], e.g. to find all
+//! capturing groups and their indices:
+//!
+//! ```
+//! use pomsky::{ast::{Node, NodeKind}, Expr};
+//!
+//! fn find_groups<'i>(node: Node<'_, 'i>, groups: &mut Vec<(Option<String>, u32)>) {
+//!     if let NodeKind::CapturingGroup { name, index } = node.kind() {
+//!         groups.push((name.map(String::from), index));
+//!     }
+//!     for child in node.children() {
+//!         find_groups(child, groups);
+//!     }
+//! }
+//!
+//! let (expr, _) = Expr::parse(":x('a' :('b')) reset(:y('c') | :z('d'))", Default::default()).unwrap();
+//! let mut groups = vec![];
+//! find_groups(expr.root(), &mut groups);
+//! assert_eq!(groups, [
+//!     (Some("x".into()), 1),
+//!     (None, 2),
+//!     (Some("y".into()), 3),
+//!     (Some("z".into()), 3),
+//! ]);
+//! ```
 
 #![warn(missing_docs)]
 
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fmt};
 
+use alternation::Alternation;
 use boundary::{Boundary, BoundaryKind};
+use captures::{CaptureGroup, CaptureInfo, CaptureOccurrence};
 use char_class::{CharClass, CharGroup};
-use compile::CompileState;
-use error::{CompileError, ParseError};
+use compile::{CompileState, NodeCounts, Output, Writer};
+use error::{CompileError, CompileErrorKind, Feature, ParseError};
 use grapheme::Grapheme;
-use options::{CompileOptions, ParseOptions};
-use repetition::RegexQuantifier;
+use group::{Capture, Group, RegexCapture, RegexGroup};
+use line_break::LineBreak;
+use literal::Literal;
+use optimization::Optimization;
+use options::{
+    CodePointEscape, CompileOptions, NameHandling, ParseOptions, RegexFlags, RegexFlavor,
+};
+use regex::Regex;
+use repetition::{Quantifier, RegexQuantifier, Repetition, RepetitionKind};
 use rule::Rule;
 use span::Span;
+use stmt::{Let, Stmt, StmtExpr};
+use unsupported::UnsupportedCollector;
 use warning::Warning;
 
+pub mod ast;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod captures;
+pub mod dfa;
 pub mod error;
+pub mod explain;
 pub mod features;
+pub mod optimization;
 pub mod options;
+pub mod snapshots;
+#[cfg(feature = "timings")]
+pub mod timings;
 pub mod warning;
 
 mod alternation;
+mod atomic;
+mod balanced;
 mod boundary;
+mod capture_each;
 mod char_class;
 mod compile;
+mod conditional;
+mod emoji;
+mod engine_limits;
+mod flags;
 mod grapheme;
 mod group;
+mod inline;
+mod line_break;
 mod literal;
 mod lookaround;
+mod one_of;
 mod parse;
 mod range;
 mod reference;
 mod regex;
 mod repetition;
+mod reset;
 mod rule;
+mod sexpr;
 mod span;
 mod stmt;
+mod unsupported;
 mod util;
 mod var;
+mod word;
+
+/// For each regex flavor, whether an expression is supported, or the first
+/// feature that isn't supported. Returned by [`Expr::compatibility_report`].
+pub type CompatibilityReport = Vec<(RegexFlavor, Result<(), Feature>)>;
+
+/// A compiled regex with the applied optimizations and the renamed groups
+type LoggedRegex<'o> = (Regex<'o>, Vec<Optimization>, HashMap<String, String>);
 
 /// A parsed pomsky expression, which might contain more sub-expressions.
 #[derive(Clone)]
@@ -90,60 +160,976 @@ impl<'i> Expr<'i> {
         input: &'i str,
         options: ParseOptions,
     ) -> Result<(Self, Vec<Warning>), ParseError> {
-        let (rule, warning) = parse::parse(input, 256)?;
+        let (rule, mut warnings) = parse::parse(input, 256, options.resolve_class)?;
         rule.validate(&options)?;
-        Ok((Expr(rule), warning))
+        rule.lint(&options, &mut warnings, false);
+        Ok((Expr(rule), warnings))
+    }
+
+    /// Binds a variable to an expression, as if the expression was wrapped in
+    /// a `let` statement. This can be used to parse a template with a free
+    /// variable once, and compile it with different values:
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (template, _) = Expr::parse("Start value (',' value)* End", Default::default()).unwrap();
+    /// let (digits, _) = Expr::parse("[d]+", Default::default()).unwrap();
+    /// let (words, _) = Expr::parse("[w]+", Default::default()).unwrap();
+    ///
+    /// let numbers = template.clone().with_binding("value", digits);
+    /// assert_eq!(numbers.compile(Default::default()).unwrap(), "\\A\\d+(?:,\\d+)*\\z");
+    /// let words = template.with_binding("value", words);
+    /// assert_eq!(words.compile(Default::default()).unwrap(), "\\A\\w+(?:,\\w+)*\\z");
+    /// ```
+    ///
+    /// Variables declared in the template shadow the binding. Like in a `let`
+    /// statement, the value can't contain capturing groups. Note that the
+    /// spans of errors in the value refer to the value's source code.
+    pub fn with_binding(self, name: &'i str, value: Expr<'i>) -> Expr<'i> {
+        let binding = Stmt::Let(Let::new(name, value.0, Span::empty()));
+        Expr(Rule::StmtExpr(Box::new(StmtExpr::new(binding, self.0, Span::empty()))))
+    }
+
+    /// Combines two expressions to a sequence, which matches `a` followed by
+    /// `b`. Nested sequences are flattened, so this can be called repeatedly
+    /// to assemble a pattern from pieces:
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (a, _) = Expr::parse("'a' | 'b'", Default::default()).unwrap();
+    /// let (b, _) = Expr::parse(":([d]+)", Default::default()).unwrap();
+    /// let (c, _) = Expr::parse("'c' ::1", Default::default()).unwrap();
+    ///
+    /// let combined = Expr::concat(Expr::concat(a, b), c);
+    /// assert_eq!(combined.compile(Default::default()).unwrap(), "(?:a|b)(\\d+)c\\1");
+    /// ```
+    ///
+    /// The span of the result covers the spans of both expressions. Capturing
+    /// groups are numbered in the combined expression, so numeric references
+    /// in `b` refer to different groups if `a` contains capturing groups.
+    /// Group names must still be unique, which is checked when compiling.
+    pub fn concat(a: Expr<'i>, b: Expr<'i>) -> Expr<'i> {
+        let span = a.0.span().join(b.0.span());
+        let mut parts = vec![];
+        for rule in [a.0, b.0] {
+            match rule {
+                Rule::Group(g) if g.capture.is_none() => parts.extend(g.parts),
+                rule => parts.push(rule),
+            }
+        }
+        Expr(Rule::Group(Group::new(parts, None, span)))
+    }
+
+    /// Combines two expressions to an alternation, which matches either `a`
+    /// or `b`. Nested alternations are flattened.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (a, _) = Expr::parse("'a' | 'b'", Default::default()).unwrap();
+    /// let (b, _) = Expr::parse("'c' 'd'", Default::default()).unwrap();
+    /// let combined = Expr::alternate(a, b);
+    /// assert_eq!(combined.compile(Default::default()).unwrap(), "a|b|cd");
+    ///
+    /// // both expressions contain a group with the same name
+    /// let (a, _) = Expr::parse(":x('a')", Default::default()).unwrap();
+    /// let (b, _) = Expr::parse(":x('b')", Default::default()).unwrap();
+    /// assert!(Expr::alternate(a, b).compile(Default::default()).is_err());
+    /// ```
+    ///
+    /// Like with [`Expr::concat`], the span of the result covers the spans of
+    /// both expressions, and group names must be unique.
+    pub fn alternate(a: Expr<'i>, b: Expr<'i>) -> Expr<'i> {
+        Expr(Alternation::new_expr(vec![a.0, b.0]))
+    }
+
+    /// Replaces variables that are only used once with their content. This
+    /// doesn't change the compiled regex, but the expression is simpler, e.g.
+    /// when formatted with [`Expr::to_sexpr`]:
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (mut expr, _) = Expr::parse("let x = 'a'; let y = 'b'; x y y", Default::default()).unwrap();
+    /// expr.inline_variables();
+    /// assert_eq!(expr.to_sexpr(), r#"(let y (lit "b") (seq (lit "a") (var y) (var y)))"#);
+    /// ```
+    ///
+    /// Variables in a `define` block aren't inlined. This is also done when
+    /// compiling with [`CompileOptions::inline_variables`].
+    pub fn inline_variables(&mut self) {
+        self.0 = inline::inline_variables(&self.0);
+    }
+
+    /// Calls `f` for every sub-expression, and replaces the sub-expression
+    /// with the result. This can be used to implement custom optimizations or
+    /// rewrites without compiling the expression first.
+    ///
+    /// The expression is traversed in post-order: `f` is called for the
+    /// children of a node before the node itself, so the node that `f`
+    /// receives already contains the transformed children. Children are
+    /// visited in the order in which they appear in the source code; the
+    /// content of a variable is visited where the variable is declared, not
+    /// where it is used. `f` is called exactly once for every node, and it
+    /// isn't called again for the nodes it returns.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _) = Expr::parse("let x = 'a'; x ('a' | 'c')+", Default::default()).unwrap();
+    /// let (b, _) = Expr::parse("'b'", Default::default()).unwrap();
+    ///
+    /// let mut visited = vec![];
+    /// let expr = expr.transform(&mut |node| {
+    ///     visited.push(node.to_sexpr());
+    ///     if node.to_sexpr() == r#"(lit "a")"# { b.clone() } else { node }
+    /// });
+    /// assert_eq!(expr.compile(Default::default()).unwrap(), "b(?:b|c)+");
+    /// assert_eq!(visited[..4], [r#"(lit "a")"#, "(var x)", r#"(lit "a")"#, r#"(lit "c")"#]);
+    /// ```
+    pub fn transform(self, f: &mut impl FnMut(Expr<'i>) -> Expr<'i>) -> Expr<'i> {
+        Expr(self.0.transform(&mut |rule| f(Expr(rule)).0))
     }
 
     /// Compile a `Expr` that has been parsed, to a regex
     pub fn compile(&self, options: CompileOptions) -> Result<String, CompileError> {
+        let mut buf = String::new();
+        self.compile_into(options, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Like [`Expr::compile`], but also returns the optimizations that were
+    /// applied, with the span of the affected source code.
+    ///
+    /// ```
+    /// use pomsky::{optimization::OptimizationKind, options::CompileOptions, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse("['a'-'f' 'c'-'z']", Default::default()).unwrap();
+    /// let options = CompileOptions { canonical: true, ..Default::default() };
+    /// let (regex, optimizations) = expr.compile_with_optimizations(options).unwrap();
+    /// assert_eq!(regex, "[a-z]");
+    /// assert_eq!(optimizations[0].kind, OptimizationKind::MergedClassItems);
+    /// assert_eq!(optimizations[0].span.range(), Some(0..17));
+    /// ```
+    pub fn compile_with_optimizations(
+        &self,
+        options: CompileOptions,
+    ) -> Result<(String, Vec<Optimization>), CompileError> {
+        let (compiled, optimizations, _) = self.compile_to_regex_logged(&options)?;
+
+        let mut buf = String::new();
+        codegen(&compiled, &options, &mut buf)?;
+        Ok((buf, optimizations))
+    }
+
+    /// Like [`Expr::compile`], but also returns the group names that were
+    /// changed because of [`NameHandling::Sanitize`], mapped to their new
+    /// names. Names that weren't changed aren't included.
+    ///
+    /// ```
+    /// use pomsky::{options::{CompileOptions, NameHandling, RegexFlavor}, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse(":my_name('a') ::my_name", Default::default()).unwrap();
+    /// let options = CompileOptions {
+    ///     flavor: RegexFlavor::Java,
+    ///     name_handling: NameHandling::Sanitize,
+    ///     ..Default::default()
+    /// };
+    /// let (regex, renamed) = expr.compile_with_renamed_groups(options).unwrap();
+    /// assert_eq!(regex, "(?<myname>a)\\1");
+    /// assert_eq!(renamed["my_name"], "myname");
+    /// ```
+    pub fn compile_with_renamed_groups(
+        &self,
+        options: CompileOptions,
+    ) -> Result<(String, HashMap<String, String>), CompileError> {
+        let (compiled, _, renamed_groups) = self.compile_to_regex_logged(&options)?;
+
+        let mut buf = String::new();
+        codegen(&compiled, &options, &mut buf)?;
+        Ok((buf, renamed_groups))
+    }
+
+    /// Like [`Expr::compile`], but appends the regex to an existing buffer.
+    /// The buffer can be cleared and reused to avoid allocating a new string
+    /// every time an expression is compiled. If an error occurs, the buffer
+    /// is left unchanged.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let mut buf = String::new();
+    /// for input in ["'a'+", "[w]"] {
+    ///     let (expr, _warnings) = Expr::parse(input, Default::default()).unwrap();
+    ///     buf.clear();
+    ///     expr.compile_into(Default::default(), &mut buf).unwrap();
+    ///     println!("{buf}");
+    /// }
+    /// assert_eq!(buf, "\\w");
+    /// ```
+    pub fn compile_into(
+        &self,
+        options: CompileOptions,
+        buf: &mut String,
+    ) -> Result<(), CompileError> {
+        let compiled = self.compile_to_regex(&options)?;
+        codegen(&compiled, &options, buf)?;
+        Ok(())
+    }
+
+    /// Like [`Expr::compile`], but writes the regex directly to a
+    /// [`fmt::Write`] implementation, e.g. a [`fmt::Formatter`], without
+    /// allocating a `String` for it. Only when non-ASCII characters or code
+    /// point escapes must be rewritten, the regex is buffered first.
+    ///
+    /// Code generation only starts once the expression was compiled
+    /// successfully, so nothing is written if a compile error occurs. If the
+    /// writer fails, nothing more is written and an error is returned.
+    ///
+    /// ```
+    /// use std::fmt::Write;
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a'+", Default::default()).unwrap();
+    /// let mut out = String::from("const RE = /");
+    /// expr.compile_to(Default::default(), &mut out).unwrap();
+    /// out.write_str("/u;").unwrap();
+    /// assert_eq!(out, "const RE = /a+/u;");
+    /// ```
+    pub fn compile_to(
+        &self,
+        options: CompileOptions,
+        out: &mut impl fmt::Write,
+    ) -> Result<(), CompileError> {
+        let compiled = self.compile_to_regex(&options)?;
+        let mut writer = Writer::new(out);
+        codegen(&compiled, &options, &mut writer)?;
+        writer.result.map_err(|_| {
+            CompileErrorKind::Other("The compiled regex couldn't be written").at(Span::empty())
+        })
+    }
+
+    /// Compiles the expression for JavaScript, and returns the regex and the
+    /// flags that must be passed to the `RegExp` constructor. JavaScript
+    /// doesn't support global inline flags such as `(?i)`, so this is how the
+    /// requested flags are applied:
+    ///
+    /// ```
+    /// use pomsky::{options::{CompileOptions, RegexFlags, RegexFlavor}, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse("'hello' [w]", Default::default()).unwrap();
+    /// let options = CompileOptions { flavor: RegexFlavor::JavaScript, ..Default::default() };
+    /// let flags = RegexFlags { ignore_case: true, ..Default::default() };
+    /// let (regex, flags) = expr.compile_with_flags(options, flags).unwrap();
+    /// assert_eq!(regex, "hello[\\p{Alphabetic}\\p{M}\\p{Nd}\\p{Pc}]");
+    /// assert_eq!(flags, "iu");
+    /// ```
+    ///
+    /// The flags are in the order of
+    /// [`RegExp.prototype.flags`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/RegExp/flags).
+    /// The `u` flag is always included, since Pomsky emits regexes for
+    /// Unicode mode. If [`CompileOptions::unicode_sets`] is enabled, the `v`
+    /// flag is included instead.
+    ///
+    /// This is an error if the flavor isn't JavaScript.
+    pub fn compile_with_flags(
+        &self,
+        options: CompileOptions,
+        flags: RegexFlags,
+    ) -> Result<(String, String), CompileError> {
+        if options.flavor != RegexFlavor::JavaScript {
+            return Err(CompileErrorKind::Other(
+                "Flags can only be returned separately when compiling for JavaScript",
+            )
+            .at(Span::empty()));
+        }
+        let regex = self.compile(options.clone())?;
+
+        let mut flag_string = String::new();
+        if flags.ignore_case {
+            flag_string.push('i');
+        }
+        if flags.multiline {
+            flag_string.push('m');
+        }
+        if flags.dot_all {
+            flag_string.push('s');
+        }
+        flag_string.push(if options.unicode_sets { 'v' } else { 'u' });
+        Ok((regex, flag_string))
+    }
+
+    /// Compiles several expressions to a single regex that matches any of
+    /// them. Literal text that all expressions start with is only emitted
+    /// once, e.g. for a family of URL patterns sharing the same scheme.
+    ///
+    /// Each expression is wrapped in a named capturing group, so the matched
+    /// expression can be identified. The names of the groups are returned
+    /// in the same order as the expressions. They are `branch0`, `branch1`,
+    /// and so on, so these names can't be used by the expressions. Note that
+    /// the shared prefix is outside of the groups.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let inputs = ["'https://' 'example.com'", "'https://' ['a'-'z']+ '.org'"];
+    /// let exprs: Vec<_> = inputs.iter().map(|i| Expr::parse(i, Default::default()).unwrap().0).collect();
+    ///
+    /// let (regex, branches) = Expr::compile_shared_prefix(&exprs, Default::default()).unwrap();
+    /// assert_eq!(regex, "https://(?:(?P<branch0>example\\.com)|(?P<branch1>[a-z]+\\.org))");
+    /// assert_eq!(branches, ["branch0", "branch1"]);
+    /// ```
+    pub fn compile_shared_prefix(
+        exprs: &[Expr<'i>],
+        options: CompileOptions,
+    ) -> Result<(String, Vec<String>), CompileError> {
+        let names: Vec<String> = (0..exprs.len()).map(|i| format!("branch{i}")).collect();
+        let branches = exprs
+            .iter()
+            .zip(&names)
+            .map(|(expr, name)| {
+                let capture = Capture::new(Some(name.as_str()), Span::empty());
+                Rule::Group(Group::new(vec![expr.0.clone()], Some(capture), Span::empty()))
+            })
+            .collect();
+        let combined = Expr(Alternation::new_expr(branches));
+
+        let compiled = combined.compile_to_regex(&options)?;
+        let compiled = match compiled {
+            Regex::Group(mut g) if options.wrap_in_named_group.is_some() => {
+                let content = g.parts.pop().unwrap();
+                g.parts.push(content.factor_shared_prefix());
+                Regex::Group(g)
+            }
+            compiled => compiled.factor_shared_prefix(),
+        };
+
+        let mut buf = String::new();
+        codegen(&compiled, &options, &mut buf)?;
+        Ok((buf, names))
+    }
+
+    /// Like [`Expr::parse`], but records the time spent in the tokenize,
+    /// parse and validate phases. Requires the `timings` feature.
+    #[cfg(feature = "timings")]
+    pub fn parse_with_timings(
+        input: &'i str,
+        options: ParseOptions,
+        timings: &mut timings::Timings,
+    ) -> Result<(Self, Vec<Warning>), ParseError> {
+        use std::time::Instant;
+
+        let start = Instant::now();
+        let tokens = parse::tokenize(input);
+        timings.tokenize = start.elapsed();
+
+        let start = Instant::now();
+        let (rule, mut warnings) = parse::parse_tokens(input, &tokens, 256, options.resolve_class)?;
+        timings.parse = start.elapsed();
+
+        let start = Instant::now();
+        rule.validate(&options)?;
+        rule.lint(&options, &mut warnings, false);
+        timings.validate = start.elapsed();
+
+        Ok((Expr(rule), warnings))
+    }
+
+    /// Like [`Expr::compile`], but records the time spent in the compile and
+    /// codegen phases. Requires the `timings` feature.
+    #[cfg(feature = "timings")]
+    pub fn compile_with_timings(
+        &self,
+        options: CompileOptions,
+        timings: &mut timings::Timings,
+    ) -> Result<String, CompileError> {
+        use std::time::Instant;
+
+        let start = Instant::now();
+        let compiled = self.compile_to_regex(&options)?;
+        timings.compile = start.elapsed();
+
+        let start = Instant::now();
+        let mut buf = String::new();
+        codegen(&compiled, &options, &mut buf)?;
+        timings.codegen = start.elapsed();
+
+        Ok(buf)
+    }
+
+    /// Compile a `Expr` that has been parsed, to a [`Dfa`](dfa::Dfa). This
+    /// fails if the expression contains features that can't be compiled to a
+    /// DFA, such as lookarounds or references.
+    ///
+    /// Refer to the [`dfa` module](dfa) for more information.
+    pub fn compile_to_dfa(&self) -> Result<dfa::Dfa, CompileError> {
+        let options = CompileOptions::default();
+        let compiled = self.compile_to_regex(&options)?;
+        dfa::Dfa::from_regex(&compiled)
+    }
+
+    /// Returns `true` if the expression matches the _entire_ input. This uses
+    /// a built-in matcher, so it works without a regex engine, but only for
+    /// the features that can be compiled to a [`Dfa`](dfa::Dfa). For other
+    /// expressions, such as lookarounds or references, an error is returned;
+    /// use a regex engine to match them instead.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("['a'-'z']+ '@' ['a'-'z']+", Default::default()).unwrap();
+    /// assert_eq!(expr.is_match("user@example").unwrap(), true);
+    /// assert_eq!(expr.is_match("user@example!").unwrap(), false);
+    ///
+    /// let (expr, _warnings) = Expr::parse("(>> 'a') C", Default::default()).unwrap();
+    /// assert!(expr.is_match("a").is_err());
+    /// ```
+    ///
+    /// The input is matched in a single pass, without backtracking, so the
+    /// time is linear in the length of the input. When matching the same
+    /// expression many times, [`Expr::compile_to_dfa`] is faster.
+    pub fn is_match(&self, input: &str) -> Result<bool, CompileError> {
+        let options = CompileOptions::default();
+        let compiled = self.compile_to_regex(&options)?;
+        dfa::Dfa::regex_is_match(&compiled, input)
+    }
+
+    /// Returns the first `n` strings matched by the expression, in shortlex
+    /// order: Shorter strings come first, and strings of the same length are
+    /// sorted by their code points. This is useful for checking that an
+    /// expression matches exactly the intended strings. Like a
+    /// [`Dfa`](dfa::Dfa), the expression must match the _entire_ string.
+    ///
+    /// This fails if the expression can't be compiled to a DFA, e.g. because
+    /// it contains lookarounds or references.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("'0' | ['1'-'9'] ['0'-'9']?", Default::default()).unwrap();
+    /// assert_eq!(expr.enumerate(12).unwrap(), ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11"]);
+    /// ```
+    pub fn enumerate(&self, n: usize) -> Result<Vec<String>, CompileError> {
+        Ok(self.compile_to_dfa()?.enumerate(n))
+    }
+
+    /// Returns warnings if the compiled regex exceeds a limit of the regex
+    /// engine, such as the maximum length, number of capturing groups or
+    /// repetition count, so the engine might reject it. Pass the regex
+    /// compiled from this expression with the same options. [`Expr::parse_and_compile`] includes
+    /// these warnings automatically.
+    ///
+    /// ```
+    /// use pomsky::{options::{CompileOptions, RegexFlavor}, Expr};
+    ///
+    /// let input = ":('a')".repeat(40_000);
+    /// let (expr, _warnings) = Expr::parse(&input, Default::default()).unwrap();
+    ///
+    /// let options = CompileOptions { flavor: RegexFlavor::Ruby, ..Default::default() };
+    /// let regex = expr.compile(options.clone()).unwrap();
+    /// let warnings = expr.engine_limit_warnings(&regex, &options);
+    /// assert_eq!(
+    ///     warnings[0].to_string(),
+    ///     "The regex has 40000 capturing groups, but the `Ruby` regex flavor only supports up to 32767.",
+    /// );
+    ///
+    /// let regex = expr.compile(Default::default()).unwrap();
+    /// let warnings = expr.engine_limit_warnings(&regex, &Default::default());
+    /// assert_eq!(
+    ///     warnings[0].to_string(),
+    ///     "The regex is 120000 bytes long, but the `Pcre` regex flavor only supports regexes up to 65535 bytes.",
+    /// );
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a'{2,70000}", Default::default()).unwrap();
+    /// let regex = expr.compile(Default::default()).unwrap();
+    /// let warnings = expr.engine_limit_warnings(&regex, &Default::default());
+    /// assert_eq!(
+    ///     warnings[0].to_string(),
+    ///     "The repetition count 70000 is greater than 65535, the maximum supported by the `Pcre` regex flavor.\n  at 0..12",
+    /// );
+    /// ```
+    pub fn engine_limit_warnings(&self, regex: &str, options: &CompileOptions) -> Vec<Warning> {
+        let mut groups_count = u32::from(options.wrap_in_named_group.is_some());
+        // the expression was already compiled, so this can't fail
+        let _ = self.0.get_capturing_groups(&mut groups_count, &mut HashMap::new(), false);
+
+        let mut warnings = vec![];
+        engine_limits::check(self.root(), regex, groups_count, options.flavor, &mut warnings);
+        warnings
+    }
+
+    /// Returns an expression that matches exactly the strings this expression
+    /// doesn't match. Like a [`Dfa`](dfa::Dfa), both expressions are meant to
+    /// match the _entire_ input, so the result is wrapped in `Start` and `End`.
+    ///
+    /// This fails if the expression can't be compiled to a DFA, or if it
+    /// matches every string, so its complement is empty.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a'*", Default::default()).unwrap();
+    /// let complement = expr.complement().unwrap();
+    /// let regex = complement.compile(Default::default()).unwrap();
+    /// assert_eq!(regex, "\\Aa*[^a][\\s\\S]*\\z");
+    /// ```
+    pub fn complement(&self) -> Result<Expr<'static>, CompileError> {
+        let dfa = self.compile_to_dfa()?.complement();
+        match dfa.to_rule() {
+            Some(rule) => Ok(Expr(rule)),
+            None => Err(CompileErrorKind::Other("The complement of this expression is empty")
+                .at(Span::empty())),
+        }
+    }
+
+    /// Returns the capturing groups of the expression, ordered by their index.
+    /// This can be used to generate code that extracts the groups from a
+    /// match.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let input = ":year([d]{4}) ('-' :month([d]{2}))?";
+    /// let (expr, _warnings) = Expr::parse(input, Default::default()).unwrap();
+    /// let groups = expr.capture_groups().unwrap();
+    /// assert_eq!(groups[0].name.as_deref(), Some("year"));
+    /// assert!(!groups[0].optional);
+    /// assert_eq!(groups[1].name.as_deref(), Some("month"));
+    /// assert!(groups[1].optional);
+    /// ```
+    pub fn capture_groups(&self) -> Result<Vec<CaptureGroup>, CompileError> {
+        let mut used_names = HashMap::new();
+        self.0.get_capturing_groups(&mut 0, &mut used_names, false)?;
+
+        let mut info = CaptureInfo::default();
+        self.0.capture_info(&mut info, false);
+        Ok(info.groups)
+    }
+
+    /// Returns every definition of a named capturing group and every reference
+    /// to one, with the span of the name. This can be used to rename a group
+    /// in an editor. The occurrences are ordered by their position in the
+    /// source code.
+    ///
+    /// ```
+    /// use pomsky::{captures::OccurrenceKind, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse(":foo('a') ::foo", Default::default()).unwrap();
+    /// let occurrences = expr.capture_occurrences();
+    /// assert_eq!(occurrences[0].kind, OccurrenceKind::Definition);
+    /// assert_eq!(occurrences[0].span, 1..4);
+    /// assert_eq!(occurrences[1].kind, OccurrenceKind::Reference);
+    /// assert_eq!(occurrences[1].span, 12..15);
+    /// ```
+    pub fn capture_occurrences(&self) -> Vec<CaptureOccurrence> {
+        let mut info = CaptureInfo::default();
+        self.0.capture_info(&mut info, false);
+        info.occurrences
+    }
+
+    /// Returns `true` if the expression never consumes any characters, e.g.
+    /// `%` or `(>> 'a') | ''`. The result is conservative: Expressions that
+    /// can't be analyzed without compiling, such as variables (including
+    /// `Start` and `End`) and references, are assumed to consume characters.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("(>> 'a') | % | ''", Default::default()).unwrap();
+    /// assert!(expr.is_zero_width());
+    ///
+    /// let (expr, _warnings) = Expr::parse("% 'a'?", Default::default()).unwrap();
+    /// assert!(!expr.is_zero_width());
+    /// ```
+    pub fn is_zero_width(&self) -> bool {
+        self.0.is_zero_width()
+    }
+
+    /// Compiles the expression and describes the resulting regex in a human
+    /// language. Every line of the explanation describes one part of the
+    /// regex; lines ending with a colon are followed by the indented parts
+    /// they contain.
+    ///
+    /// ```
+    /// use pomsky::{explain::Locale, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a'+ | [d]", Default::default()).unwrap();
+    /// let explanation = expr.explain(Default::default(), Locale::Italian).unwrap();
+    /// assert_eq!(explanation, "uno tra:\n  una o più volte:\n    il carattere 'a'\n  una cifra");
+    /// ```
+    pub fn explain(
+        &self,
+        options: CompileOptions,
+        locale: explain::Locale,
+    ) -> Result<String, CompileError> {
+        let compiled = self.compile_to_regex(&options)?;
+
+        let mut explainer = explain::Explainer::new(locale, 1);
+        explainer.explain(&compiled, 0);
+        Ok(explainer.finish())
+    }
+
+    /// Returns the root node of the expression, which can be used to inspect
+    /// it. See the [`ast`] module for details.
+    ///
+    /// ```
+    /// use pomsky::{ast::NodeKind, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a'{2,}", Default::default()).unwrap();
+    /// let root = expr.root();
+    /// assert_eq!(root.kind(), NodeKind::Repetition { min: 2, max: None });
+    /// assert_eq!(root.children()[0].kind(), NodeKind::Literal("a"));
+    /// ```
+    pub fn root(&self) -> ast::Node<'_, 'i> {
+        ast::Node::root(&self.0)
+    }
+
+    /// Formats the expression as a compact S-expression, which is useful for
+    /// debugging and in snapshot tests. Unlike the `Debug` output, the format
+    /// is stable. Variables aren't resolved.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let (expr, _warnings) = Expr::parse("'a' | [d]+ greedy", Default::default()).unwrap();
+    /// assert_eq!(expr.to_sexpr(), r#"(alt (lit "a") (rep (class d) 1 inf greedy))"#);
+    /// ```
+    pub fn to_sexpr(&self) -> String {
+        let mut buf = String::new();
+        sexpr::write_rule(&self.0, &mut buf);
+        buf
+    }
+
+    /// Compiles the expression for every [`RegexFlavor`] and reports whether
+    /// it is supported. For each flavor that doesn't support the expression,
+    /// the first unsupported feature is returned.
+    ///
+    /// Errors that aren't caused by an unsupported feature, e.g. an unknown
+    /// variable, are returned immediately.
+    ///
+    /// ```
+    /// use pomsky::{error::Feature, options::RegexFlavor, Expr};
+    ///
+    /// let (expr, _warnings) = Expr::parse(":x('a') (<< 'b') ::x", Default::default()).unwrap();
+    /// let report = expr.compatibility_report().unwrap();
+    /// assert!(report.contains(&(RegexFlavor::DotNet, Ok(()))));
+    /// assert!(report.contains(&(RegexFlavor::Rust, Err(Feature::Lookaround))));
+    /// ```
+    pub fn compatibility_report(&self) -> Result<CompatibilityReport, CompileError> {
+        let mut report = Vec::with_capacity(RegexFlavor::ALL.len());
+        for &flavor in RegexFlavor::ALL {
+            let options = CompileOptions { flavor, ..Default::default() };
+            let result = match self.compile_to_regex(&options) {
+                Ok(_) => Ok(()),
+                Err(e) => match e.kind() {
+                    &CompileErrorKind::Unsupported(feature, _) => Err(feature),
+                    CompileErrorKind::Multiple(errors) => match errors[0].kind() {
+                        &CompileErrorKind::Unsupported(feature, _) => Err(feature),
+                        _ => return Err(e),
+                    },
+                    _ => return Err(e),
+                },
+            };
+            report.push((flavor, result));
+        }
+        Ok(report)
+    }
+
+    fn check_limits(&self, options: &CompileOptions) -> Result<(), CompileError> {
+        if options.max_nodes.is_none() && options.max_alternation_branches.is_none() {
+            return Ok(());
+        }
+
+        let mut counts = NodeCounts::default();
+        self.0.count_nodes(&mut counts);
+
+        if let Some(limit) = options.max_alternation_branches {
+            if counts.alternation_branches > limit.into() {
+                let count = counts.alternation_branches;
+                return Err(
+                    CompileErrorKind::TooManyAlternationBranches { count, limit }.at(Span::empty())
+                );
+            }
+        }
+        if let Some(limit) = options.max_nodes {
+            if counts.nodes > limit.into() {
+                let count = counts.nodes;
+                return Err(CompileErrorKind::TooManyNodes { count, limit }.at(Span::empty()));
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_to_regex<'o>(&self, options: &'o CompileOptions) -> Result<Regex<'o>, CompileError>
+    where
+        'i: 'o,
+    {
+        self.compile_to_regex_logged(options).map(|(regex, ..)| regex)
+    }
+
+    fn compile_to_regex_logged<'o>(
+        &self,
+        options: &'o CompileOptions,
+    ) -> Result<LoggedRegex<'o>, CompileError>
+    where
+        'i: 'o,
+    {
         let mut used_names = HashMap::new();
         let mut groups_count = 0;
+        if let Some(name) = &options.wrap_in_named_group {
+            used_names.insert(name.clone(), 1);
+            groups_count = 1;
+        }
+        self.check_limits(options)?;
+
+        if options.byte_mode && !matches!(options.flavor, RegexFlavor::Rust | RegexFlavor::Pcre) {
+            return Err(
+                CompileErrorKind::Unsupported(Feature::ByteMode, options.flavor).at(Span::empty())
+            );
+        }
+
+        let next_idx = groups_count + 1;
         self.0.get_capturing_groups(&mut groups_count, &mut used_names, false)?;
 
+        if (options.ascii_only_output || options.byte_mode)
+            && used_names.keys().any(|name| !name.is_ascii())
+        {
+            return Err(CompileErrorKind::Other(
+                "Group names must be ASCII when non-ASCII characters are escaped",
+            )
+            .at(Span::empty()));
+        }
+        reference::check_reference_names(&self.0, &used_names)?;
+
+        let renamed_groups = match options.name_handling {
+            NameHandling::Error => HashMap::new(),
+            NameHandling::Sanitize => group::sanitize_names(&used_names, options.flavor),
+        };
+
         let no_span = Span::empty();
 
         let start = Rule::Boundary(Boundary::new(BoundaryKind::Start, no_span));
         let end = Rule::Boundary(Boundary::new(BoundaryKind::End, no_span));
+        let line_start = Rule::Boundary(Boundary::new(BoundaryKind::LineStart, no_span));
+        let line_end = Rule::Boundary(Boundary::new(BoundaryKind::LineEnd, no_span));
+        let end_of_text = Rule::Boundary(Boundary::new(BoundaryKind::EndOfText, no_span));
+        let end_of_text_or_newline =
+            Rule::Boundary(Boundary::new(BoundaryKind::EndOfTextOrBeforeNewline, no_span));
         let grapheme = Rule::Grapheme(Grapheme);
         let codepoint = Rule::CharClass(CharClass::new(CharGroup::CodePoint, no_span));
+        let line_break = Rule::LineBreak(LineBreak);
+        let bom = Rule::Repetition(Box::new(Repetition::new(
+            Rule::Literal(Literal::new(Cow::Borrowed("\u{FEFF}"), no_span)),
+            RepetitionKind::zero_one(),
+            Quantifier::Greedy,
+            no_span,
+        )));
+
+        let emoji = emoji::emoji();
 
         let builtins = vec![
             ("Start", &start),
             ("End", &end),
+            ("LineStart", &line_start),
+            ("LineEnd", &line_end),
+            ("EndOfText", &end_of_text),
+            ("EndOfTextOrBeforeNewline", &end_of_text_or_newline),
             ("Grapheme", &grapheme),
             ("G", &grapheme),
             ("Codepoint", &codepoint),
             ("C", &codepoint),
+            ("LineBreak", &line_break),
+            ("Bom", &bom),
+            ("Emoji", &emoji),
         ];
 
+        // report all unsupported features at once, if there are several
+        let mut collector = UnsupportedCollector::new(options.flavor, builtins.clone());
+        collector.visit(&self.0);
+        let unsupported = collector.finish();
+        if unsupported.len() > 1 {
+            return Err(
+                CompileErrorKind::Multiple(unsupported.into_boxed_slice()).at(Span::empty())
+            );
+        }
+
+        let inlined;
+        let rule = if options.inline_variables {
+            inlined = inline::inline_variables(&self.0);
+            &inlined
+        } else {
+            &self.0
+        };
+
         let mut state = CompileState {
-            next_idx: 1,
+            next_idx,
             used_names,
             groups_count,
             default_quantifier: RegexQuantifier::Greedy,
             variables: builtins,
             current_vars: Default::default(),
+            subroutines: Default::default(),
+            balanced: Default::default(),
+            renamed_groups,
+            optimizations: Default::default(),
         };
-        let compiled = self.0.comp(options, &mut state)?;
-
-        let mut buf = String::new();
-        compiled.codegen(&mut buf, options.flavor);
-        Ok(buf)
+        let mut compiled = rule.comp(options, &mut state)?;
+        if !state.balanced.is_empty() {
+            let mut parts = vec![compiled];
+            parts.extend(balanced::definitions(options.flavor, &state));
+            compiled = Regex::Group(RegexGroup::new(parts, RegexCapture::None));
+        }
+        // checked after compiling, so an unsupported `define` block is reported first
+        reference::check_numeric_references(self.root())?;
+        if options.flavor == RegexFlavor::Lua {
+            compiled.check_lua_anchors().and_then(|()| compiled.check_lua_code_points()).map_err(
+                |feature| CompileErrorKind::Unsupported(feature, options.flavor).at(Span::empty()),
+            )?;
+        }
+        if let RegexFlavor::Sed { .. } = options.flavor {
+            compiled.renumber_sed_references().map_err(|kind| kind.at(Span::empty()))?;
+        }
+        let compiled = match &options.wrap_in_named_group {
+            Some(_)
+                if matches!(
+                    options.flavor,
+                    RegexFlavor::Sed { .. } | RegexFlavor::Antlr | RegexFlavor::Lua
+                ) =>
+            {
+                return Err(CompileErrorKind::Unsupported(
+                    Feature::NamedCaptureGroups,
+                    options.flavor,
+                )
+                .at(Span::empty()));
+            }
+            Some(name) => {
+                let name = group::output_group_name(name, Span::empty(), options, &state)?;
+                Regex::Group(RegexGroup::new(vec![compiled], RegexCapture::NamedCapture(name)))
+            }
+            None => compiled,
+        };
+        Ok((compiled, state.optimizations.finish(), state.renamed_groups))
     }
 
-    /// Parse a string to a `Expr` and compile it to a regex.
+    /// Parse a string to a `Expr` and compile it to a regex. Warnings that
+    /// don't [apply](Warning::applies_to) to the regex flavor are removed.
     pub fn parse_and_compile(
         input: &'i str,
         parse_options: ParseOptions,
         compile_options: CompileOptions,
     ) -> Result<(String, Vec<Warning>), CompileError> {
-        let (parsed, warnings) = Self::parse(input, parse_options)?;
-        let compiled = parsed.compile(compile_options)?;
+        let (parsed, mut warnings) = Self::parse(input, parse_options)?;
+        let compiled = parsed.compile(compile_options.clone())?;
+        warnings.retain(|warning| warning.applies_to(compile_options.flavor));
+        warnings.extend(parsed.engine_limit_warnings(&compiled, &compile_options));
         Ok((compiled, warnings))
     }
 }
 
+/// Parses and compiles the expression with the default [`ParseOptions`], and
+/// returns the compiled regex, if there was no error, together with all errors
+/// and warnings as [`Diagnostic`](error::Diagnostic)s. This is convenient for
+/// environments such as WebAssembly, where the diagnostics are displayed by
+/// the caller instead of being printed with miette.
+///
+/// The spans of the diagnostics are byte offsets into `input`.
+///
+/// ```
+/// use pomsky::{compile_to_diagnostics, error::Severity};
+///
+/// let input = "'äöü' :(unknown)";
+/// let (regex, diagnostics) = compile_to_diagnostics(input, Default::default());
+/// assert_eq!(regex, None);
+/// assert!(matches!(diagnostics[0].severity, Severity::Error));
+/// assert_eq!(&input[diagnostics[0].span.range().unwrap()], "unknown");
+///
+/// let (regex, diagnostics) = compile_to_diagnostics("'ä'+", Default::default());
+/// assert_eq!(regex.as_deref(), Some("ä+"));
+/// assert!(diagnostics.is_empty());
+/// ```
+pub fn compile_to_diagnostics(
+    input: &str,
+    options: CompileOptions,
+) -> (Option<String>, Vec<error::Diagnostic>) {
+    use error::Diagnostic;
+
+    let (parsed, warnings) = match Expr::parse(input, ParseOptions::default()) {
+        Ok(result) => result,
+        Err(err) => return (None, Diagnostic::from_parse_errors(err, input)),
+    };
+
+    let mut diagnostics: Vec<_> = warnings
+        .into_iter()
+        .filter(|warning| warning.applies_to(options.flavor))
+        .map(|warning| Diagnostic::from_warning(warning, input))
+        .collect();
+
+    match parsed.compile(options.clone()) {
+        Ok(compiled) => {
+            diagnostics.extend(
+                parsed
+                    .engine_limit_warnings(&compiled, &options)
+                    .into_iter()
+                    .map(|warning| Diagnostic::from_warning(warning, input)),
+            );
+            (Some(compiled), diagnostics)
+        }
+        Err(err) => {
+            diagnostics.extend(Diagnostic::from_compile_errors(err, input));
+            (None, diagnostics)
+        }
+    }
+}
+
+/// Generates the regex. If [`CompileOptions::ascii_only_output`] or
+/// [`CompileOptions::byte_mode`] is enabled, non-ASCII characters are escaped
+/// afterwards. If a supported [`CompileOptions::code_point_escape`] is set,
+/// the escape sequences of code points above U+FFFF are rewritten at the end.
+fn codegen(
+    compiled: &Regex<'_>,
+    options: &CompileOptions,
+    buf: &mut impl Output,
+) -> Result<(), CompileError> {
+    if options.byte_mode && options.flavor == RegexFlavor::Rust {
+        buf.push_str("(?-u)");
+    }
+
+    let escape_non_ascii = options.ascii_only_output || options.byte_mode;
+    let code_point_escape = options.code_point_escape.filter(|&style| {
+        style.is_supported(options.flavor)
+            && Some(style) != CodePointEscape::default_for(options.flavor)
+    });
+    if !escape_non_ascii && code_point_escape.is_none() {
+        compiled.codegen(buf, options.flavor);
+        return Ok(());
+    }
+
+    let mut regex = String::new();
+    compiled.codegen(&mut regex, options.flavor);
+    if escape_non_ascii {
+        if matches!(options.flavor, RegexFlavor::Sed { .. }) && !regex.is_ascii() {
+            return Err(CompileErrorKind::Other("Non-ASCII characters can't be escaped in sed")
+                .at(Span::empty()));
+        }
+        if options.flavor == RegexFlavor::Lua && !regex.is_ascii() {
+            return Err(CompileErrorKind::Other(
+                "Non-ASCII characters can't be escaped in Lua patterns",
+            )
+            .at(Span::empty()));
+        }
+        let mut escaped = String::new();
+        literal::escape_non_ascii(&regex, &mut escaped, options.flavor);
+        regex = escaped;
+    }
+    match code_point_escape {
+        Some(style) => literal::rewrite_code_point_escapes(&regex, buf, style),
+        None => buf.push_str(&regex),
+    }
+    Ok(())
+}
+
 #[cfg(feature = "dbg")]
 impl core::fmt::Debug for Expr<'_> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -0,0 +1,50 @@
+//! Contains the [`LineBreak`] type, which matches any Unicode line break.
+
+use std::borrow::Cow;
+
+use crate::{
+    alternation::RegexAlternation,
+    char_class::{CharClass, CharGroup},
+    compile::CompileResult,
+    optimization::OptimizationLog,
+    options::{CompileOptions, RegexFlavor},
+    regex::Regex,
+    span::Span,
+};
+
+/// The `LineBreak` expression, matching `\r\n` or any character that is a
+/// line break.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "dbg", derive(Debug))]
+pub(crate) struct LineBreak;
+
+impl LineBreak {
+    /// Compiles to `\R` in flavors that support it, and to the equivalent
+    /// alternation otherwise. In byte mode, only ASCII line breaks are
+    /// matched, so it is always expanded.
+    ///
+    /// Note that `\R` is atomic, so it never matches only the `\r` of `\r\n`.
+    pub(crate) fn compile(&self, options: &CompileOptions) -> CompileResult<'static> {
+        if matches!(options.flavor, RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Ruby)
+            && !options.byte_mode
+        {
+            Ok(Regex::LineBreak)
+        } else {
+            Self::expand(options)
+        }
+    }
+
+    /// Returns the alternation matched by `\R`
+    pub(crate) fn expand(options: &CompileOptions) -> CompileResult<'static> {
+        let chars = if options.byte_mode {
+            CharGroup::from_chars("\n\x0B\x0C\r")
+        } else {
+            CharGroup::from_chars("\n\x0B\x0C\r\u{85}\u{2028}\u{2029}")
+        };
+        Ok(Regex::Alternation(RegexAlternation::new(vec![
+            Regex::Literal(Cow::Borrowed("\r\n")),
+            CharClass::new(chars, Span::empty())
+                .compile(options, &mut OptimizationLog::default())?,
+        ])))
+    }
+}
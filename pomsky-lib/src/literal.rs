@@ -1,10 +1,15 @@
 use std::borrow::Cow;
 
-use crate::{compile::CompileResult, options::RegexFlavor, regex::Regex, span::Span};
+use crate::{
+    compile::{CompileResult, Output},
+    options::{CodePointEscape, CompileOptions, RegexFlavor},
+    regex::Regex,
+    span::Span,
+};
 
 #[derive(Clone, PartialEq, Eq)]
 pub(crate) struct Literal<'i> {
-    content: Cow<'i, str>,
+    pub(crate) content: Cow<'i, str>,
     pub(crate) span: Span,
 }
 
@@ -13,8 +18,12 @@ impl<'i> Literal<'i> {
         Literal { content, span }
     }
 
-    pub(crate) fn compile(&self) -> CompileResult<'i> {
-        Ok(Regex::Literal(self.content.clone()))
+    pub(crate) fn compile(&self, options: &CompileOptions) -> CompileResult<'i> {
+        let regex = Regex::Literal(self.content.clone());
+        if options.byte_mode {
+            regex.check_byte_mode().map_err(|kind| kind.at(self.span))?;
+        }
+        Ok(regex)
     }
 }
 
@@ -27,8 +36,9 @@ impl core::fmt::Debug for Literal<'_> {
 
 /// Write a char to the output buffer with proper escaping. Assumes the char is
 /// inside a character class.
-pub(crate) fn compile_char_esc_in_class(c: char, buf: &mut String, flavor: RegexFlavor) {
+pub(crate) fn compile_char_esc_in_class(c: char, buf: &mut impl Output, flavor: RegexFlavor) {
     match c {
+        '/' if matches!(flavor, RegexFlavor::Sed { .. }) => buf.push_str(r#"\/"#),
         '\\' => buf.push_str(r#"\\"#),
         '-' => buf.push_str(r#"\-"#),
         ']' => buf.push_str(r#"\]"#),
@@ -37,10 +47,33 @@ pub(crate) fn compile_char_esc_in_class(c: char, buf: &mut String, flavor: Regex
     }
 }
 
+/// Write a char to the output buffer with proper escaping, in a character
+/// class of a JavaScript regex with the `v` flag. Besides `(`, `)`, `[`, `]`,
+/// `{`, `}`, `/`, `-`, `\` and `|`, punctuation that is reserved when it
+/// appears twice in a row (e.g. `&&`) is escaped.
+pub(crate) fn compile_char_esc_in_class_set(c: char, buf: &mut impl Output, flavor: RegexFlavor) {
+    match c {
+        '(' | ')' | '[' | ']' | '{' | '}' | '/' | '-' | '\\' | '|' | '&' | '!' | '#' | '$'
+        | '%' | '*' | '+' | ',' | '.' | ':' | ';' | '<' | '=' | '>' | '?' | '@' | '^' | '`'
+        | '~' => {
+            buf.push('\\');
+            buf.push(c);
+        }
+        c => compile_char(c, buf, flavor),
+    }
+}
+
 /// Write a char to the output buffer with proper escaping. Assumes the char is
 /// not in a character class.
-pub(crate) fn codegen_char_esc(c: char, buf: &mut String, flavor: RegexFlavor) {
+pub(crate) fn codegen_char_esc(c: char, buf: &mut impl Output, flavor: RegexFlavor) {
     match c {
+        // these are only special when escaped in POSIX basic regular expressions
+        '{' | '}' | '(' | ')' | '+' | '?' | '|'
+            if flavor == (RegexFlavor::Sed { extended: false }) =>
+        {
+            buf.push(c)
+        }
+        '/' if matches!(flavor, RegexFlavor::Sed { .. }) => buf.push_str(r#"\/"#),
         '\\' => buf.push_str(r#"\\"#),
         '[' => buf.push_str(r#"\["#),
         '{' => buf.push_str(r#"\{"#),
@@ -61,15 +94,16 @@ pub(crate) fn codegen_char_esc(c: char, buf: &mut String, flavor: RegexFlavor) {
 /// Write a char to the output buffer. This escapes characters that are neither
 /// alphanumeric, nor printable ASCII characters. It does _not_ escape
 /// characters like `(` or `]` that have a special meaning.
-pub(crate) fn compile_char(c: char, buf: &mut String, flavor: RegexFlavor) {
-    use std::fmt::Write;
-
+pub(crate) fn compile_char(c: char, buf: &mut impl Output, flavor: RegexFlavor) {
     match c {
         '\n' => buf.push_str("\\n"),
         '\r' => buf.push_str("\\r"),
         '\t' => buf.push_str("\\t"),
         '\x07' => buf.push_str("\\a"),
-        '\x1B' => buf.push_str("\\e"),
+        // sed and RE2 don't support `\e`, so it is written as `\x1B` below
+        '\x1B' if !matches!(flavor, RegexFlavor::Sed { .. } | RegexFlavor::Re2) => {
+            buf.push_str("\\e")
+        }
         '\x0C' => buf.push_str("\\f"),
         ' ' => buf.push(' '),
         _ if c.is_ascii() => {
@@ -79,21 +113,102 @@ pub(crate) fn compile_char(c: char, buf: &mut String, flavor: RegexFlavor) {
                 write!(buf, "\\x{:02X}", c as u8).unwrap();
             }
         }
+        // sed has no escape sequences for non-ASCII code points, but matches them
+        // literally in UTF-8 locales
+        _ if matches!(flavor, RegexFlavor::Sed { .. }) => buf.push(c),
         _ if c.is_alphanumeric() && c.len_utf16() == 1 => {
             buf.push(c);
         }
+        _ => codegen_escaped(c, buf, flavor),
+    }
+}
+
+/// Write an escape sequence for a non-ASCII char to the output buffer, e.g.
+/// `\xE9` or `\u{1F600}`.
+fn codegen_escaped(c: char, buf: &mut impl Output, flavor: RegexFlavor) {
+    match c {
         _ if c as u32 <= 0xFF => {
             write!(buf, "\\x{:02X}", c as u32).unwrap();
         }
-        _ if c as u32 <= 0xFFFF && !matches!(flavor, RegexFlavor::Pcre) => {
+        _ if c as u32 <= 0xFFFF && !matches!(flavor, RegexFlavor::Pcre | RegexFlavor::Re2) => {
             write!(buf, "\\u{:04X}", c as u32).unwrap();
         }
         _ => {
-            match flavor {
-                RegexFlavor::Pcre => buf.push_str("\\x"),
-                _ => buf.push_str("\\u"),
+            let style = CodePointEscape::default_for(flavor).unwrap_or(CodePointEscape::Unicode);
+            codegen_code_point_escape(c as u32, style, buf);
+        }
+    }
+}
+
+fn codegen_code_point_escape(code_point: u32, style: CodePointEscape, buf: &mut impl Output) {
+    match style {
+        CodePointEscape::Hex => write!(buf, "\\x{{{code_point:X}}}").unwrap(),
+        CodePointEscape::Unicode => write!(buf, "\\u{{{code_point:X}}}").unwrap(),
+        CodePointEscape::LongUnicode => write!(buf, "\\U{code_point:08X}").unwrap(),
+    }
+}
+
+/// Rewrites the escape sequences of code points above U+FFFF in a compiled
+/// regex to the given syntax. Used when
+/// [`CompileOptions::code_point_escape`](crate::options::CompileOptions::code_point_escape)
+/// is set. Other escape sequences are copied unchanged.
+pub(crate) fn rewrite_code_point_escapes(
+    regex: &str,
+    buf: &mut impl Output,
+    style: CodePointEscape,
+) {
+    let mut rest = regex;
+    while let Some(i) = rest.find('\\') {
+        buf.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        match parse_code_point_escape(rest) {
+            Some((code_point, len)) if code_point > 0xFFFF => {
+                codegen_code_point_escape(code_point, style, buf);
+                rest = &rest[len..];
+            }
+            _ => {
+                // copy the backslash and the escaped char, so an escaped
+                // backslash isn't mistaken for the start of an escape sequence
+                let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+                buf.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+        }
+    }
+    buf.push_str(rest);
+}
+
+/// Parses an escape sequence such as `\x{1F600}`, `\u{1F600}` or `\U0001F600`
+/// at the start of the string. Returns the code point and the length of the
+/// escape sequence.
+fn parse_code_point_escape(s: &str) -> Option<(u32, usize)> {
+    let (hex, len) = if let Some(rest) = s.strip_prefix("\\x{").or_else(|| s.strip_prefix("\\u{")) {
+        let end = rest.find('}')?;
+        (&rest[..end], end + 4)
+    } else {
+        let hex = s.strip_prefix("\\U")?.get(..8)?;
+        (hex, 10)
+    };
+    if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((u32::from_str_radix(hex, 16).ok()?, len))
+}
+
+/// Escapes all non-ASCII chars in a compiled regex. Used when
+/// [`CompileOptions::ascii_only_output`](crate::options::CompileOptions::ascii_only_output)
+/// is enabled. Group names aren't escaped, so they must be ASCII.
+pub(crate) fn escape_non_ascii(regex: &str, buf: &mut impl Output, flavor: RegexFlavor) {
+    for c in regex.chars() {
+        match c {
+            _ if c.is_ascii() => buf.push(c),
+            // ANTLR doesn't support `\xFF` or `\x{...}`
+            _ if flavor == RegexFlavor::Antlr && c as u32 <= 0xFFFF => {
+                write!(buf, "\\u{:04X}", c as u32).unwrap();
             }
-            write!(buf, "{{{:X}}}", c as u32).unwrap();
+            _ if flavor == RegexFlavor::Antlr => write!(buf, "\\u{{{:X}}}", c as u32).unwrap(),
+            _ => codegen_escaped(c, buf, flavor),
         }
     }
 }
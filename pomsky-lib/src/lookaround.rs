@@ -1,19 +1,21 @@
 use std::collections::HashMap;
 
 use crate::{
-    compile::{CompileResult, CompileState},
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts, Output},
     error::{CompileError, CompileErrorKind, Feature, ParseError, ParseErrorKind},
     features::PomskyFeatures,
     options::{CompileOptions, ParseOptions, RegexFlavor},
     regex::Regex,
     rule::Rule,
     span::Span,
+    warning::Warning,
 };
 
 #[derive(Clone)]
 pub(crate) struct Lookaround<'i> {
-    kind: LookaroundKind,
-    rule: Rule<'i>,
+    pub(crate) kind: LookaroundKind,
+    pub(crate) rule: Rule<'i>,
     pub(crate) span: Span,
 }
 
@@ -50,6 +52,26 @@ impl<'i> Lookaround<'i> {
         self.rule.get_capturing_groups(count, map, within_variable)
     }
 
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        // groups in a negative lookaround never participate in a match
+        let negative =
+            matches!(self.kind, LookaroundKind::AheadNegative | LookaroundKind::BehindNegative);
+        self.rule.capture_info(info, optional || negative);
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        self.rule.lint(options, warnings, in_repetition);
+    }
+
     pub(crate) fn new(rule: Rule<'i>, kind: LookaroundKind, span: Span) -> Self {
         Lookaround { rule, kind, span }
     }
@@ -72,13 +94,11 @@ impl<'i> Lookaround<'i> {
 
     pub(crate) fn compile<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
-        if options.flavor == RegexFlavor::Rust {
-            return Err(
-                CompileErrorKind::Unsupported(Feature::Lookaround, options.flavor).at(self.span)
-            );
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
         }
 
         Ok(Regex::Lookaround(Box::new(RegexLookaround {
@@ -87,6 +107,17 @@ impl<'i> Lookaround<'i> {
         })))
     }
 
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Rust
+            | RegexFlavor::Re2
+            | RegexFlavor::Sed { .. }
+            | RegexFlavor::Antlr
+            | RegexFlavor::Lua => Some(Feature::Lookaround),
+            _ => None,
+        }
+    }
+
     pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
         let feature = match self.kind {
             LookaroundKind::Ahead => PomskyFeatures::LOOKAHEAD,
@@ -100,12 +131,12 @@ impl<'i> Lookaround<'i> {
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct RegexLookaround<'i> {
-    content: Regex<'i>,
-    kind: LookaroundKind,
+    pub(crate) content: Regex<'i>,
+    pub(crate) kind: LookaroundKind,
 }
 
 impl<'i> RegexLookaround<'i> {
-    pub(crate) fn codegen(&self, buf: &mut String, flavor: RegexFlavor) {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
         buf.push_str(match self.kind {
             LookaroundKind::Ahead => "(?=",
             LookaroundKind::Behind => "(?<=",
@@ -0,0 +1,146 @@
+//! Implements `oneOf(...)`, which matches any of a list of strings, e.g.
+//! `oneOf('cat', 'car', 'dog')`.
+
+use std::borrow::Cow;
+
+use crate::{
+    alternation::RegexAlternation,
+    char_class::{RegexCharClass, RegexClassItem},
+    compile::CompileResult,
+    group::{RegexCapture, RegexGroup},
+    regex::Regex,
+    repetition::{RegexQuantifier, RegexRepetition, RepetitionKind},
+    span::Span,
+};
+
+/// A `oneOf(...)` expression with at least one string.
+///
+/// This is equivalent to an alternation of the strings, but the strings are
+/// compiled to a trie, so they share their common prefixes:
+/// `oneOf('cat', 'car', 'dog')` compiles to `ca[tr]|dog` instead of
+/// `cat|car|dog`, which is faster to match when there are many strings.
+///
+/// Unlike an alternation, `oneOf` always matches the longest string that
+/// matches, regardless of the order of the strings: `oneOf('do', 'dog')`
+/// compiles to `dog?`.
+#[derive(Clone)]
+pub(crate) struct OneOf<'i> {
+    pub(crate) strings: Vec<Cow<'i, str>>,
+    pub(crate) span: Span,
+}
+
+impl<'i> OneOf<'i> {
+    pub(crate) fn new(strings: Vec<Cow<'i, str>>, span: Span) -> Self {
+        OneOf { strings, span }
+    }
+
+    pub(crate) fn compile(&self) -> CompileResult<'static> {
+        let mut trie = TrieNode::default();
+        for s in &self.strings {
+            trie.insert(s);
+        }
+        Ok(trie.compile())
+    }
+}
+
+/// A node of a trie. The children are in the order in which they were
+/// inserted, so the output doesn't depend on a hash function.
+#[derive(Default)]
+struct TrieNode {
+    children: Vec<(char, TrieNode)>,
+    /// Whether a string ends at this node
+    terminal: bool,
+}
+
+impl TrieNode {
+    fn insert(&mut self, s: &str) {
+        let mut node = self;
+        for c in s.chars() {
+            let idx = match node.children.iter().position(|&(child, _)| child == c) {
+                Some(idx) => idx,
+                None => {
+                    node.children.push((c, TrieNode::default()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[idx].1;
+        }
+        node.terminal = true;
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn compile(&self) -> Regex<'static> {
+        let mut node = self;
+        let mut prefix = String::new();
+        while let [(c, child)] = node.children.as_slice() {
+            if node.terminal {
+                break;
+            }
+            prefix.push(*c);
+            node = child;
+        }
+
+        let mut parts = vec![];
+        if !prefix.is_empty() {
+            parts.push(Regex::Literal(Cow::Owned(prefix)));
+        }
+
+        if !node.is_leaf() {
+            let branches = if node.children.iter().all(|(_, child)| child.is_leaf()) {
+                match node.children.as_slice() {
+                    &[(c, _)] => Regex::Char(c),
+                    children => Regex::CharClass(RegexCharClass::new(
+                        children.iter().map(|&(c, _)| RegexClassItem::Char(c)).collect(),
+                    )),
+                }
+            } else {
+                let branches = node
+                    .children
+                    .iter()
+                    .map(|(c, child)| {
+                        if child.is_leaf() {
+                            Regex::Char(*c)
+                        } else {
+                            Regex::Group(RegexGroup::new(
+                                vec![Regex::Char(*c), child.compile()],
+                                RegexCapture::None,
+                            ))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if branches.len() == 1 {
+                    branches.into_iter().next().unwrap()
+                } else {
+                    Regex::Alternation(RegexAlternation::new(branches))
+                }
+            };
+
+            if node.terminal {
+                // the longest string is preferred, even in lazy mode
+                parts.push(Regex::Repetition(Box::new(RegexRepetition::new(
+                    branches,
+                    RepetitionKind::zero_one(),
+                    RegexQuantifier::Greedy,
+                ))));
+            } else {
+                parts.push(branches);
+            }
+        }
+
+        if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Regex::Group(RegexGroup::new(parts, RegexCapture::None))
+        }
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for OneOf<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "OneOf{:?}", self.strings)
+    }
+}
@@ -0,0 +1,91 @@
+//! Contains the optimizations applied while compiling an expression, as
+//! returned by
+//! [`Expr::compile_with_optimizations`](crate::Expr::compile_with_optimizations).
+
+use std::fmt;
+
+use crate::span::Span;
+
+/// An optimization that changed the output of the compiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Optimization {
+    /// What was changed
+    pub kind: OptimizationKind,
+    /// The part of the source code that was affected
+    pub span: Span,
+}
+
+/// The kind of an [`Optimization`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationKind {
+    /// Characters and ranges in a character class that overlap or are
+    /// adjacent were merged. This is only done when
+    /// [`CompileOptions::canonical`](crate::options::CompileOptions::canonical)
+    /// or
+    /// [`CompileOptions::minimal_classes`](crate::options::CompileOptions::minimal_classes)
+    /// is enabled.
+    MergedClassItems,
+    /// Characters and ranges in a character class were replaced with a
+    /// shorthand that matches the same characters, e.g. `0-9` with `\d`.
+    /// This is only done when
+    /// [`CompileOptions::minimal_classes`](crate::options::CompileOptions::minimal_classes)
+    /// is enabled.
+    ClassShorthand,
+    /// A prefix that adjacent alternatives have in common was factored out,
+    /// e.g. `'abc' | 'abd'` was compiled to `ab(?:c|d)`. This is only done
+    /// with [`OptimizationLevel::Full`](crate::options::OptimizationLevel::Full).
+    FactoredPrefix,
+    /// An optional expression that never consumes characters, e.g. `%?` or
+    /// `(>> 'a')?`, was removed, since it always matches. This is only done
+    /// with [`OptimizationLevel::Full`](crate::options::OptimizationLevel::Full),
+    /// if the expression contains no capturing groups.
+    RemovedOptionalAssertion,
+}
+
+impl OptimizationKind {
+    fn description(self) -> &'static str {
+        match self {
+            OptimizationKind::MergedClassItems => {
+                "merged overlapping characters and ranges in a character class"
+            }
+            OptimizationKind::ClassShorthand => {
+                "replaced characters in a character class with a shorthand"
+            }
+            OptimizationKind::FactoredPrefix => "factored out a common prefix of alternatives",
+            OptimizationKind::RemovedOptionalAssertion => {
+                "removed an optional assertion, which always matches"
+            }
+        }
+    }
+}
+
+impl fmt::Display for Optimization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.kind.description())?;
+        if let Some(range) = self.span.range() {
+            write!(f, " at {}..{}", range.start, range.end)?;
+        }
+        Ok(())
+    }
+}
+
+/// Records optimizations while compiling. An optimization in a variable is
+/// only recorded once, even if the variable is used several times.
+#[derive(Default, Clone)]
+pub(crate) struct OptimizationLog {
+    optimizations: Vec<Optimization>,
+}
+
+impl OptimizationLog {
+    pub(crate) fn record(&mut self, kind: OptimizationKind, span: Span) {
+        let optimization = Optimization { kind, span };
+        if !self.optimizations.contains(&optimization) {
+            self.optimizations.push(optimization);
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<Optimization> {
+        self.optimizations
+    }
+}
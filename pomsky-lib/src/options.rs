@@ -4,7 +4,6 @@ use crate::features::PomskyFeatures;
 
 /// Options passed to the pomsky parser
 #[derive(Debug, Clone, Copy)]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ParseOptions {
     /// The maximum number of digits in a `range` expression. Defaults to 6.
     ///
@@ -15,26 +14,328 @@ pub struct ParseOptions {
 
     /// Allowed pomsky features. By default, all features are allowed.
     pub allowed_features: PomskyFeatures,
+
+    /// Whether to emit a warning for capturing groups within a repetition,
+    /// such as `(:('a' | 'b'))*`. These only capture the text of the last
+    /// repetition, which can be surprising. Defaults to `false`.
+    pub warn_capture_in_repetition: bool,
+
+    /// Whether to emit a warning for character classes that contain the same
+    /// characters as a named ASCII class, e.g. `['0'-'9']`, which can be
+    /// written as `[ascii_digit]`. `[digit]` and `[word]` aren't suggested,
+    /// because they also match non-ASCII characters. Defaults to `false`.
+    pub suggest_named_classes: bool,
+
+    /// Whether to emit a warning for `End`, which is compiled to `\z` in most
+    /// flavors. Unlike `$`, it doesn't match before a newline at the end of
+    /// the string. The warning is only shown for flavors where `$` matches
+    /// there. Defaults to `false`.
+    pub warn_end_anchor: bool,
+
+    /// A function that is called for character class names that aren't known
+    /// to pomsky, such as `[safe_filename]`. If it returns a [`CustomClass`],
+    /// the name is replaced with the class. If it returns `None`, an error is
+    /// reported as usual.
+    ///
+    /// ```
+    /// use pomsky::{options::{CustomClass, ParseOptions}, Expr};
+    ///
+    /// let options = ParseOptions {
+    ///     resolve_class: Some(|name| match name {
+    ///         "hex" => Some(CustomClass::new().range('0', '9').range('a', 'f')),
+    ///         _ => None,
+    ///     }),
+    ///     ..Default::default()
+    /// };
+    /// let (regex, _warnings) =
+    ///     Expr::parse_and_compile("[hex '_']+", options, Default::default()).unwrap();
+    /// assert_eq!(regex, "[0-9a-f_]+");
+    /// ```
+    pub resolve_class: Option<ClassResolver>,
 }
 
 impl Default for ParseOptions {
     fn default() -> Self {
-        Self { max_range_size: 6, allowed_features: Default::default() }
+        Self {
+            max_range_size: 6,
+            allowed_features: Default::default(),
+            warn_capture_in_repetition: false,
+            suggest_named_classes: false,
+            warn_end_anchor: false,
+            resolve_class: None,
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ParseOptions {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(ParseOptions {
+            max_range_size: u8::arbitrary(u)?,
+            allowed_features: PomskyFeatures::arbitrary(u)?,
+            warn_capture_in_repetition: bool::arbitrary(u)?,
+            suggest_named_classes: bool::arbitrary(u)?,
+            warn_end_anchor: bool::arbitrary(u)?,
+            resolve_class: None,
+        })
+    }
+}
+
+/// A function that resolves character class names that aren't known to
+/// pomsky. See [`ParseOptions::resolve_class`].
+pub type ClassResolver = fn(&str) -> Option<CustomClass>;
+
+/// A character class returned by a [`ClassResolver`]. It can contain
+/// characters and ranges of characters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomClass {
+    pub(crate) items: Vec<(char, char)>,
+}
+
+impl CustomClass {
+    /// Creates an empty character class
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds each character in the string to the class
+    pub fn chars(mut self, chars: &str) -> Self {
+        self.items.extend(chars.chars().map(|c| (c, c)));
+        self
+    }
+
+    /// Adds a range of characters (inclusive) to the class. If `first` is
+    /// greater than `last`, an error is reported when the class is used.
+    pub fn range(mut self, first: char, last: char) -> Self {
+        self.items.push((first, last));
+        self
     }
 }
 
 /// Options passed to the pomsky compiler
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CompileOptions {
     /// The targeted regex flavor. Pomsky makes sure that the emitted regex is
     /// compatible with this flavor.
     pub flavor: RegexFlavor,
+
+    /// If set, the entire regex is wrapped in a named capturing group with
+    /// this name. The wrapping group is group 1, so the groups in the
+    /// expression are numbered starting at 2.
+    ///
+    /// This is an error in flavors that don't support named capturing groups.
+    pub wrap_in_named_group: Option<String>,
+
+    /// Whether the regex is used with the `v` flag (`unicodeSets`) in
+    /// JavaScript. This flag requires more characters to be escaped in
+    /// character classes, but allows nested character classes, so negated
+    /// shorthands such as `[!w 'a']` are supported. It has no effect in other
+    /// flavors.
+    pub unicode_sets: bool,
+
+    /// Whether to normalize the output, so that small changes to the input
+    /// produce small changes to the output. Currently, the characters and
+    /// ranges in character classes are sorted and merged, e.g. `['z' 'a'-'f'
+    /// 'b']` becomes `[a-fz]`.
+    ///
+    /// The output is deterministic regardless of this option; compiling the
+    /// same expression with the same options always produces the same regex.
+    pub canonical: bool,
+
+    /// Whether to emit character classes in the shortest equivalent form. The
+    /// characters and ranges are merged like with
+    /// [`canonical`](CompileOptions::canonical), a class with a single
+    /// character is emitted without brackets, and in JavaScript and RE2,
+    /// where `\w`, `\d` and `\s` only match ASCII characters, the characters
+    /// they match are replaced with the shorthand, e.g. `['0'-'9' 'a'-'f']`
+    /// becomes `[\da-f]`.
+    ///
+    /// Shorthands are only used where they match exactly the same characters,
+    /// so the regex still matches the same strings.
+    pub minimal_classes: bool,
+
+    /// Whether to compile word boundaries (`%` and `!%`) to lookarounds over
+    /// word characters instead of `\b` and `\B`. The word characters are the
+    /// same as in `[w]`, so this is useful in JavaScript, where `\b` only
+    /// considers ASCII characters.
+    ///
+    /// This is an error in flavors that don't support lookbehind.
+    pub explicit_word_boundary: bool,
+
+    /// Whether to escape all non-ASCII characters, so the output only consists
+    /// of ASCII characters. This is useful when the regex is passed through
+    /// systems that don't handle other encodings correctly. Characters are
+    /// escaped with the syntax of the flavor, e.g. `\u{1F600}` in JavaScript
+    /// or `\x{1F600}` in PCRE.
+    ///
+    /// This is an error if the expression contains a group name that isn't
+    /// ASCII, or if the sed flavor is used, which has no escape sequences for
+    /// non-ASCII characters.
+    pub ascii_only_output: bool,
+
+    /// Whether to emit POSIX classes such as `[:alpha:]` or `[:digit:]` in
+    /// character classes, where they match the same characters. This is the
+    /// case for the ASCII classes, e.g. `[ascii_alpha]` compiles to
+    /// `[[:alpha:]]` instead of `[a-zA-Z]`. Other classes are emitted as
+    /// usual.
+    ///
+    /// This only has an effect in the PCRE, Rust and RE2 flavors, where POSIX
+    /// classes only match ASCII characters.
+    pub posix_classes: bool,
+
+    /// The preferred syntax for escaping code points above U+FFFF, such as
+    /// emoji. These code points are always escaped. If `None`, or if the
+    /// flavor doesn't support the preferred syntax, the flavor's default
+    /// syntax is used. See [`CodePointEscape`] for the supported flavors.
+    pub code_point_escape: Option<CodePointEscape>,
+
+    /// What to do with group names that are valid in pomsky, but not in the
+    /// targeted flavor, e.g. names that aren't ASCII in PCRE. This also
+    /// applies to the name of
+    /// [`wrap_in_named_group`](CompileOptions::wrap_in_named_group). The
+    /// default is to report an error.
+    pub name_handling: NameHandling,
+
+    /// The maximum number of alternatives in all alternations combined. If
+    /// the expression has more, compiling it fails. This protects against
+    /// machine-generated expressions that are too large.
+    ///
+    /// Like [`max_nodes`](CompileOptions::max_nodes), this is checked before
+    /// compiling, so alternations generated by `range` expressions aren't
+    /// counted.
+    pub max_alternation_branches: Option<u32>,
+
+    /// The maximum number of nodes in the syntax tree. If the expression has
+    /// more, compiling it fails. A variable's content is counted once where it
+    /// is declared, not every time it is used.
+    pub max_nodes: Option<u32>,
+
+    /// Whether to replace variables that are only used once with their
+    /// content before compiling, like [`Expr::inline_variables`]. This
+    /// doesn't change the output, since variables are expanded where they
+    /// are used either way.
+    ///
+    /// [`Expr::inline_variables`]: crate::Expr::inline_variables
+    pub inline_variables: bool,
+
+    /// Which optimizations are applied to the output. See
+    /// [`OptimizationLevel`] for the optimizations of each level.
+    pub optimization_level: OptimizationLevel,
+
+    /// Whether the regex matches bytes instead of code points, e.g. to match
+    /// binary data. In byte mode, `.` and `Codepoint` match a single byte,
+    /// code points up to U+FF match the byte with the same value, and
+    /// shorthands such as `[w]` and word boundaries only consider ASCII
+    /// characters. Non-ASCII characters are escaped, e.g. `'é'` compiles to
+    /// `\xE9`, which matches the byte `0xE9`.
+    ///
+    /// Unicode properties, `Grapheme` and code points above U+FF are an error
+    /// in byte mode. `LineBreak` only matches ASCII line breaks.
+    ///
+    /// Byte mode is supported in these flavors:
+    ///
+    /// - Rust: The regex starts with `(?-u)`, which disables Unicode mode. It
+    ///   must be used with `regex::bytes::Regex`.
+    /// - PCRE: The regex must be compiled without the `PCRE2_UTF` option, or
+    ///   the `u` modifier in PHP.
+    ///
+    /// Other flavors report an error.
+    pub byte_mode: bool,
+}
+
+/// How much the output is optimized, used with
+/// [`CompileOptions::optimization_level`]. Optimizations never change which
+/// strings are matched, or which groups capture which text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum OptimizationLevel {
+    /// Only simple optimizations, e.g. removing unnecessary groups
+    #[default]
+    Basic,
+    /// Additionally, prefixes that adjacent alternatives have in common are
+    /// factored out, e.g. `'abc' | 'abd'` compiles to `ab(?:c|d)`. The
+    /// alternatives are still tried in the same order. This isn't done in the
+    /// sed flavor, where the added groups would be capturing, or in the Lua
+    /// flavor, which doesn't support alternations. Optional expressions that
+    /// don't consume characters, e.g. `%?`, are removed, unless they contain a
+    /// capturing group.
+    Full,
+}
+
+/// A syntax for escaping code points above U+FFFF, used with
+/// [`CompileOptions::code_point_escape`]. The default syntax of each flavor is
+/// marked below; the Rust flavor supports all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum CodePointEscape {
+    /// `\x{1F600}`. This is the default in PCRE, Java and RE2.
+    Hex,
+    /// `\u{1F600}`. This is the default in JavaScript, .NET, Ruby, Rust and
+    /// ANTLR.
+    Unicode,
+    /// `\U0001F600`, with exactly 8 hexadecimal digits. This is the default
+    /// in Python.
+    LongUnicode,
+}
+
+impl CodePointEscape {
+    /// Returns the syntax used for the flavor by default, or `None` if the
+    /// flavor doesn't support escaping code points (sed).
+    pub(crate) fn default_for(flavor: RegexFlavor) -> Option<Self> {
+        match flavor {
+            RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Re2 => Some(CodePointEscape::Hex),
+            RegexFlavor::Python => Some(CodePointEscape::LongUnicode),
+            RegexFlavor::Sed { .. } | RegexFlavor::Lua => None,
+            _ => Some(CodePointEscape::Unicode),
+        }
+    }
+
+    /// Returns `true` if the flavor supports this syntax.
+    pub(crate) fn is_supported(self, flavor: RegexFlavor) -> bool {
+        flavor == RegexFlavor::Rust || CodePointEscape::default_for(flavor) == Some(self)
+    }
+}
+
+/// What to do with a group name that isn't supported by the targeted flavor,
+/// used with [`CompileOptions::name_handling`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum NameHandling {
+    /// Compiling fails with an error that explains why the name is invalid
+    #[default]
+    Error,
+    /// Invalid characters are replaced with underscores (or removed if the
+    /// flavor doesn't allow underscores), and the name is changed further if
+    /// it is still invalid or the same as another name. References to the
+    /// group are updated. The new names are returned by
+    /// [`Expr::compile_with_renamed_groups`](crate::Expr::compile_with_renamed_groups).
+    Sanitize,
+}
+
+/// Flags that are passed to the regex engine separately from the regex. They
+/// are used by [`Expr::compile_with_flags`](crate::Expr::compile_with_flags).
+///
+/// Note that these flags change the meaning of the regex: For example, with
+/// `multiline`, `Start` and `End` also match at the start and end of each
+/// line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RegexFlags {
+    /// Whether letters are matched case-insensitively (the `i` flag)
+    pub ignore_case: bool,
+
+    /// Whether `^` and `$` match at the start and end of each line (the `m`
+    /// flag)
+    pub multiline: bool,
+
+    /// Whether the dot also matches line breaks (the `s` flag)
+    pub dot_all: bool,
 }
 
 /// A regex flavor is a regex engine or a set of regex engines that are similar
 /// enough that they can be treated the same for the purpose of writing regexes.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[non_exhaustive]
 pub enum RegexFlavor {
@@ -52,6 +353,94 @@ pub enum RegexFlavor {
     Ruby,
     /// The Rust `regex` crate
     Rust,
+    /// GNU `sed`. When `extended` is `true`, the output is meant for `sed -E`
+    /// (or `sed -r`), which uses POSIX extended regular expressions.
+    /// Otherwise, basic regular expressions are emitted, where groups,
+    /// alternations and most quantifiers must be escaped.
+    ///
+    /// POSIX regular expressions have no non-capturing groups, so Pomsky
+    /// emits a capturing group wherever it would otherwise emit a
+    /// non-capturing group. This changes the numbers of the groups after it;
+    /// backreferences are adjusted, but references in the replacement of an
+    /// `s/…/…/` command must use the numbers in the emitted regex. GNU sed
+    /// only supports backreferences to the first 9 groups. Forward slashes
+    /// are escaped, so the output can be used in an `s/…/…/` command.
+    Sed {
+        /// Whether `sed` is invoked with extended regex syntax (`-E` or `-r`)
+        extended: bool,
+    },
+    /// [RE2](https://github.com/google/re2), as used in Go's `regexp` package
+    /// and in the C++ library of the same name. RE2 guarantees that matching
+    /// takes linear time, so Pomsky rejects backreferences, lookaround and
+    /// other features that would require backtracking. Repetitions are
+    /// limited to 1000.
+    ///
+    /// RE2's shorthands only match ASCII characters, so `[w]`, `[d]` and `[s]`
+    /// are emitted as Unicode categories. Since RE2 doesn't support the
+    /// `Alphabetic` property, `[w]` uses letters and letter numbers instead.
+    /// Word boundaries (`%`) still only consider ASCII characters.
+    Re2,
+    /// Lexer rules of [ANTLR](https://www.antlr.org/) grammars. String literals
+    /// are quoted (`'foo'`), elements of a sequence are separated by spaces,
+    /// and negated character sets are written as `~[...]`.
+    ///
+    /// ANTLR has no counted repetitions, so `x{2,4}` is expanded to
+    /// `x x (x x?)?`. Lexer rules can't capture text, so capturing groups are
+    /// emitted as plain groups; named groups, boundaries, lookaround and
+    /// references aren't supported.
+    Antlr,
+    /// [Lua patterns](https://www.lua.org/manual/5.4/manual.html#6.4.1), as
+    /// used by `string.find`, `string.match` and `string.gsub`. Lua patterns
+    /// aren't regular expressions: There is no alternation, only single
+    /// characters and character classes can be repeated, and `^` and `$` are
+    /// only anchors at the start and end of the pattern. Groups are always
+    /// capturing, so non-capturing groups are emitted without parentheses.
+    ///
+    /// Counted repetitions are expanded, e.g. `x{2,3}` becomes `xxx?`.
+    /// Lua patterns match bytes, so non-ASCII characters can't be used in
+    /// character classes or repeated. `Codepoint`, negated character classes
+    /// and negated shorthands are only allowed in a `*` or `+` repetition,
+    /// since they would otherwise match a single byte.
+    /// `[w]`, `[d]` and `[s]` only match ASCII characters.
+    Lua,
+}
+
+impl RegexFlavor {
+    /// All regex flavors supported by pomsky. `sed` is included twice, with
+    /// basic and extended syntax.
+    pub const ALL: &'static [RegexFlavor] = &[
+        RegexFlavor::Pcre,
+        RegexFlavor::Python,
+        RegexFlavor::Java,
+        RegexFlavor::JavaScript,
+        RegexFlavor::DotNet,
+        RegexFlavor::Ruby,
+        RegexFlavor::Rust,
+        RegexFlavor::Sed { extended: false },
+        RegexFlavor::Sed { extended: true },
+        RegexFlavor::Re2,
+        RegexFlavor::Antlr,
+        RegexFlavor::Lua,
+    ];
+
+    /// Returns the name of the flavor shown in error messages. This is the
+    /// name of the variant, except for `sed`, which is `sed` or `sed -E`.
+    pub fn name(self) -> &'static str {
+        match self {
+            RegexFlavor::Pcre => "Pcre",
+            RegexFlavor::Python => "Python",
+            RegexFlavor::Java => "Java",
+            RegexFlavor::JavaScript => "JavaScript",
+            RegexFlavor::DotNet => "DotNet",
+            RegexFlavor::Ruby => "Ruby",
+            RegexFlavor::Rust => "Rust",
+            RegexFlavor::Sed { extended: false } => "sed",
+            RegexFlavor::Sed { extended: true } => "sed -E",
+            RegexFlavor::Re2 => "Re2",
+            RegexFlavor::Antlr => "Antlr",
+            RegexFlavor::Lua => "Lua",
+        }
+    }
 }
 
 impl Default for RegexFlavor {
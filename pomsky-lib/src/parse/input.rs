@@ -4,6 +4,7 @@ use nom::{InputIter, InputLength, InputTake};
 
 use crate::{
     error::{ParseError, ParseErrorKind},
+    options::{ClassResolver, CustomClass},
     span::Span,
     warning::Warning,
 };
@@ -16,6 +17,7 @@ pub(crate) struct Input<'i, 'b> {
     tokens: &'b [(Token, Span)],
     recursion: u16,
     warnings: &'b RefCell<Vec<Warning>>,
+    resolve_class: Option<ClassResolver>,
 }
 
 impl<'i, 'b> Input<'i, 'b> {
@@ -24,11 +26,18 @@ impl<'i, 'b> Input<'i, 'b> {
         tokens: &'b [(Token, Span)],
         warnings: &'b RefCell<Vec<Warning>>,
         recursion: u16,
+        resolve_class: Option<ClassResolver>,
     ) -> Result<Self, ParseError> {
         let mut errors = vec![];
+        let mut in_class = false;
         for (i, &(t, span)) in tokens.iter().enumerate() {
             match t {
+                Token::OpenBracket => in_class = true,
+                Token::CloseBracket => in_class = false,
                 Token::Error => errors.push((span, None)),
+                // negated property value, e.g. `[gc=^Lu]`
+                Token::ErrorMsg(ParseErrorMsg::Caret)
+                    if in_class && i > 0 && tokens[i - 1].0 == Token::Equals => {}
                 Token::ErrorMsg(ParseErrorMsg::Caret)
                     if i > 0 && tokens[i - 1].0 == Token::OpenBracket =>
                 {
@@ -40,7 +49,7 @@ impl<'i, 'b> Input<'i, 'b> {
         }
 
         match errors.len() {
-            0 => Ok(Input { source, tokens, recursion, warnings }),
+            0 => Ok(Input { source, tokens, recursion, warnings, resolve_class }),
             1 => {
                 let (span, msg) = errors.pop().unwrap();
                 Err(msg
@@ -84,6 +93,12 @@ impl<'i, 'b> Input<'i, 'b> {
         self.warnings.borrow_mut().push(warning);
     }
 
+    /// Resolves a character class name that isn't known to pomsky, using the
+    /// [`ClassResolver`] from the parse options.
+    pub(super) fn resolve_class(&self, name: &str) -> Option<CustomClass> {
+        self.resolve_class.and_then(|resolve| resolve(name))
+    }
+
     pub(crate) fn span(&self) -> Span {
         self.tokens
             .first()
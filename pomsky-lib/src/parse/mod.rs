@@ -6,4 +6,8 @@ mod tokenize;
 
 pub(crate) use input::Input;
 pub(crate) use parsers::parse;
-pub(crate) use token::{ParseErrorMsg, Token};
+#[cfg(feature = "timings")]
+pub(crate) use parsers::parse_tokens;
+pub use token::{ParseErrorMsg, Token};
+#[cfg(feature = "timings")]
+pub(crate) use tokenize::tokenize;
@@ -8,39 +8,61 @@ use std::{
 use nom::{
     branch::alt,
     combinator::{cut, map, opt, value},
-    multi::{many0, many1, separated_list0},
-    sequence::{pair, preceded, separated_pair, tuple},
+    multi::{many0, many1, separated_list0, separated_list1},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
     IResult, Parser,
 };
 
 use crate::{
     alternation::Alternation,
+    atomic::Atomic,
+    balanced::Balanced,
     boundary::{Boundary, BoundaryKind},
+    capture_each::CaptureEach,
     char_class::{CharClass, CharGroup},
+    conditional::Conditional,
     error::{
         CharClassError, CharStringError, CodePointError, NumberError, ParseError, ParseErrorKind,
     },
+    flags::{Flag, FlagGroup},
     group::{Capture, Group},
     literal::Literal,
     lookaround::{Lookaround, LookaroundKind},
+    one_of::OneOf,
+    options::ClassResolver,
     range::Range,
     reference::{Reference, ReferenceTarget},
     repetition::{Quantifier, Repetition, RepetitionError, RepetitionKind},
+    reset::BranchReset,
     rule::Rule,
     span::Span,
     stmt::{BooleanSetting, Let, Stmt, StmtExpr},
     var::Variable,
-    warning::{DeprecationWarning, Warning, WarningKind},
+    warning::{DeprecationWarning, RepetitionWarning, Warning, WarningKind},
+    word::Word,
 };
 
-use super::{Input, Token};
+use super::{Input, ParseErrorMsg, Token};
 
 pub(super) type PResult<'i, 'b, T> = IResult<Input<'i, 'b>, T, ParseError>;
 
-pub(crate) fn parse(source: &str, recursion: u16) -> Result<(Rule<'_>, Vec<Warning>), ParseError> {
+pub(crate) fn parse(
+    source: &str,
+    recursion: u16,
+    resolve_class: Option<ClassResolver>,
+) -> Result<(Rule<'_>, Vec<Warning>), ParseError> {
     let tokens = super::tokenize::tokenize(source);
+    parse_tokens(source, &tokens, recursion, resolve_class)
+}
+
+pub(crate) fn parse_tokens<'i>(
+    source: &'i str,
+    tokens: &[(Token, Span)],
+    recursion: u16,
+    resolve_class: Option<ClassResolver>,
+) -> Result<(Rule<'i>, Vec<Warning>), ParseError> {
     let warnings = RefCell::new(vec![]);
-    let input = Input::from(source, &tokens, &warnings, recursion)?;
+    let input = Input::from(source, tokens, &warnings, recursion, resolve_class)?;
 
     let (rest, rules) = parse_modified(input)?;
     if rest.is_empty() {
@@ -92,21 +114,17 @@ pub(super) fn parse_modified<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Ru
                         (stmt, span_start.join(span_end))
                     },
                 ),
+                map(parse_let, |(r#let, span)| (Stmt::Let(r#let), span)),
                 map(
                     tuple((
-                        "let",
-                        cut(map_err(parse_ident, |e| match e.kind {
-                            ParseErrorKind::UnexpectedKeyword(kw) => {
-                                ParseErrorKind::KeywordAfterLet(kw).at(e.span)
-                            }
-                            _ => e,
-                        })),
-                        cut(Token::Equals),
-                        cut(recurse(parse_or)),
-                        cut(Token::Semicolon),
+                        "define",
+                        cut(Token::OpenBrace),
+                        many0(parse_let),
+                        cut(Token::CloseBrace),
                     )),
-                    |((_, span_start), (name, name_span), _, rule, (_, span_end))| {
-                        (Stmt::Let(Let::new(name, rule, name_span)), span_start.join(span_end))
+                    |((_, span_start), _, lets, (_, span_end))| {
+                        let lets = lets.into_iter().map(|(r#let, _)| r#let).collect();
+                        (Stmt::Define(lets), span_start.join(span_end))
                     },
                 ),
             ))),
@@ -116,7 +134,12 @@ pub(super) fn parse_modified<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Ru
             if stmts.len() > 1 {
                 let mut set = HashSet::new();
                 for (stmt, _) in &stmts {
-                    if let Stmt::Let(l) = stmt {
+                    let lets = match stmt {
+                        Stmt::Let(l) => std::slice::from_ref(l),
+                        Stmt::Define(lets) => lets.as_slice(),
+                        Stmt::Enable(_) | Stmt::Disable(_) => &[],
+                    };
+                    for l in lets {
                         if set.contains(l.name()) {
                             return Err(ParseErrorKind::LetBindingExists.at(l.name_span));
                         }
@@ -135,6 +158,26 @@ pub(super) fn parse_modified<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Ru
     )(input)
 }
 
+fn parse_let<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (Let<'i>, Span)> {
+    map(
+        tuple((
+            "let",
+            cut(map_err(parse_ident, |e| match e.kind {
+                ParseErrorKind::UnexpectedKeyword(kw) => {
+                    ParseErrorKind::KeywordAfterLet(kw).at(e.span)
+                }
+                _ => e,
+            })),
+            cut(Token::Equals),
+            cut(recurse(parse_or)),
+            cut(Token::Semicolon),
+        )),
+        |((_, span_start), (name, name_span), _, rule, (_, span_end))| {
+            (Let::new(name, rule, name_span), span_start.join(span_end))
+        },
+    )(input)
+}
+
 pub(super) fn parse_or<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
     map(separated_list0(Token::Pipe, parse_sequence), |mut rules| {
         if rules.len() == 1 {
@@ -186,17 +229,26 @@ pub(super) fn parse_fixes<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<
                 }
 
                 let mut prev_syntax = RepSyntax::ExplicitQuantifier;
+                let mut prev_span = Span::empty();
                 for (kind, quantifier, span, syntax) in repetitions {
-                    if matches!(
-                        (&prev_syntax, &syntax),
-                        (RepSyntax::Other | RepSyntax::QuestionMark, RepSyntax::QuestionMark)
-                    ) {
-                        return Err(ParseErrorKind::Repetition(
-                            RepetitionError::QuestionMarkAfterRepetition,
-                        )
-                        .at(span));
+                    match (&prev_syntax, &syntax) {
+                        (RepSyntax::ExplicitQuantifier, _) => {}
+                        (_, RepSyntax::QuestionMark) => {
+                            return Err(ParseErrorKind::Repetition(
+                                RepetitionError::QuestionMarkAfterRepetition,
+                            )
+                            .at(span));
+                        }
+                        (RepSyntax::Symbol, RepSyntax::Symbol) => {
+                            return Err(ParseErrorKind::Repetition(
+                                RepetitionError::DoubleQuantifier,
+                            )
+                            .at(prev_span.join(span)));
+                        }
+                        _ => {}
                     }
                     prev_syntax = syntax;
+                    prev_span = span;
 
                     let span = rule.span().join(span);
                     rule =
@@ -221,6 +273,8 @@ pub(super) fn parse_lookaround<'i, 'b>(
 pub(super) enum RepSyntax {
     ExplicitQuantifier,
     QuestionMark,
+    /// `*` or `+`
+    Symbol,
     Other,
 }
 
@@ -233,14 +287,16 @@ pub(super) fn parse_repetition<'i, 'b>(
                 map(Token::QuestionMark, |(_, span)| {
                     (RepetitionKind::zero_one(), span, RepSyntax::QuestionMark)
                 }),
-                map(Token::Star, |(_, span)| (RepetitionKind::zero_inf(), span, RepSyntax::Other)),
-                map(Token::Plus, |(_, span)| (RepetitionKind::one_inf(), span, RepSyntax::Other)),
+                map(Token::Star, |(_, span)| (RepetitionKind::zero_inf(), span, RepSyntax::Symbol)),
+                map(Token::Plus, |(_, span)| (RepetitionKind::one_inf(), span, RepSyntax::Symbol)),
                 parse_braced_repetition,
+                parse_keyword_repetition,
             )),
             map(
                 opt(alt((
                     map("greedy", |(_, span)| (Quantifier::Greedy, span)),
                     map("lazy", |(_, span)| (Quantifier::Lazy, span)),
+                    map("possessive", |(_, span)| (Quantifier::Possessive, span)),
                 ))),
                 |a| match a {
                     Some((q, span)) => (q, span, RepSyntax::ExplicitQuantifier),
@@ -256,6 +312,7 @@ pub(super) fn parse_repetition<'i, 'b>(
                 match (rs1, rs2) {
                     (_, RepSyntax::ExplicitQuantifier) => RepSyntax::ExplicitQuantifier,
                     (RepSyntax::QuestionMark, _) => RepSyntax::QuestionMark,
+                    (RepSyntax::Symbol, _) => RepSyntax::Symbol,
                     _ => RepSyntax::Other,
                 },
             )
@@ -263,25 +320,84 @@ pub(super) fn parse_repetition<'i, 'b>(
     )(input)
 }
 
+fn parse_u32<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, u32> {
+    try_map(Token::Number, |(s, _)| from_str(s), nom::Err::Failure)(input)
+}
+
+fn parse_u32_with_span<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (u32, Span)> {
+    try_map(Token::Number, |(s, span)| Ok((from_str(s)?, span)), nom::Err::Failure)(input)
+}
+
+/// Creates a repetition from its bounds. If the bounds are descending, the
+/// error spans both numbers.
+fn repetition_from_bounds(
+    (lower, lower_span): (u32, Span),
+    upper: Option<(u32, Span)>,
+) -> Result<RepetitionKind, ParseError> {
+    RepetitionKind::try_from((lower, upper.map(|(upper, _)| upper))).map_err(|e| {
+        let span = upper.map_or(lower_span, |(_, upper_span)| lower_span.join(upper_span));
+        ParseErrorKind::Repetition(e).at(span)
+    })
+}
+
 pub(super) fn parse_braced_repetition<'i, 'b>(
     input: Input<'i, 'b>,
 ) -> PResult<'i, 'b, (RepetitionKind, Span, RepSyntax)> {
-    fn parse_u32<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, u32> {
-        try_map(Token::Number, |(s, _)| from_str(s), nom::Err::Failure)(input)
+    let (mut input, ((_, start), (rep, clamped), (_, end))) = tuple((
+        Token::OpenBrace,
+        cut(alt((
+            try_map2(
+                separated_pair(opt(parse_u32_with_span), Token::Comma, opt(parse_u32_with_span)),
+                |(lower, upper)| {
+                    let lower = lower.unwrap_or((0, Span::empty()));
+                    Ok((repetition_from_bounds(lower, upper)?, false))
+                },
+                nom::Err::Failure,
+            ),
+            try_map(
+                separated_pair(parse_u32, Token::PlusMinus, parse_u32),
+                |(center, tolerance)| {
+                    let upper = center.checked_add(tolerance).ok_or(NumberError::TooLarge)?;
+                    let lower = center.saturating_sub(tolerance);
+                    Ok((RepetitionKind::try_from((lower, Some(upper)))?, tolerance > center))
+                },
+                nom::Err::Failure,
+            ),
+            map(parse_u32, |n| (RepetitionKind::fixed(n), false)),
+        ))),
+        cut(Token::CloseBrace),
+    ))(input)?;
+
+    let span = start.join(end);
+    if clamped {
+        input.add_warning(
+            WarningKind::Repetition(RepetitionWarning::ToleranceExceedsCenter).at(span),
+        );
     }
+    Ok((input, (rep, span, RepSyntax::Other)))
+}
 
+/// Parses a repetition with keywords, e.g. `repeat 3 times`, `repeat 2 to 5 times`
+/// or `repeat 3+ times`
+pub(super) fn parse_keyword_repetition<'i, 'b>(
+    input: Input<'i, 'b>,
+) -> PResult<'i, 'b, (RepetitionKind, Span, RepSyntax)> {
     map(
         tuple((
-            Token::OpenBrace,
+            "repeat",
             cut(alt((
-                try_map(
-                    separated_pair(opt(parse_u32), Token::Comma, opt(parse_u32)),
-                    |(lower, upper)| Ok(RepetitionKind::try_from((lower.unwrap_or(0), upper))?),
+                try_map2(
+                    separated_pair(parse_u32_with_span, "to", parse_u32_with_span),
+                    |(lower, upper)| repetition_from_bounds(lower, Some(upper)),
                     nom::Err::Failure,
                 ),
+                map(terminated(parse_u32, Token::Plus), |lower_bound| RepetitionKind {
+                    lower_bound,
+                    upper_bound: None,
+                }),
                 map(parse_u32, RepetitionKind::fixed),
             ))),
-            cut(Token::CloseBrace),
+            cut(map_err("times", |e| ParseErrorKind::Expected("`times`").at(e.span))),
         )),
         |((_, start), rep, (_, end))| (rep, start.join(end), RepSyntax::Other),
     )(input)
@@ -290,6 +406,18 @@ pub(super) fn parse_braced_repetition<'i, 'b>(
 pub(super) fn parse_atom<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
     alt((
         parse_group,
+        // keywords followed by parentheses, e.g. `word(...)`
+        alt((
+            parse_word,
+            parse_reset,
+            parse_atomic,
+            parse_balanced,
+            parse_one_of,
+            parse_capture_each,
+            parse_conditional,
+            parse_flags,
+        )),
+        parse_verbatim,
         parse_string,
         parse_char_class,
         parse_boundary,
@@ -307,7 +435,8 @@ pub(super) fn parse_atom<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'
 pub(super) fn parse_group<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
     fn parse_capture<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (Capture<'i>, Span)> {
         map(pair(Token::Colon, opt(Token::Identifier)), |((_, span1), name)| {
-            (Capture::new(name.map(|(s, _)| s)), span1)
+            let name_span = name.map_or(Span::empty(), |(_, span)| span);
+            (Capture::new(name.map(|(s, _)| s), name_span), span1)
         })(input)
     }
 
@@ -330,6 +459,191 @@ pub(super) fn parse_group<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<
     )(input)
 }
 
+pub(super) fn parse_word<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    map(
+        pair("word", cut(tuple((Token::OpenParen, recurse(parse_modified), Token::CloseParen)))),
+        |((_, start), (_, rule, (_, end)))| Rule::Word(Box::new(Word::new(rule, start.join(end)))),
+    )(input)
+}
+
+pub(super) fn parse_capture_each<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    map(
+        pair(
+            "captureEach",
+            cut(tuple((Token::OpenParen, recurse(parse_modified), Token::CloseParen))),
+        ),
+        |((_, start), (_, rule, (_, end)))| {
+            Rule::CaptureEach(Box::new(CaptureEach::new(rule, start.join(end))))
+        },
+    )(input)
+}
+
+pub(super) fn parse_reset<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    map(
+        pair("reset", cut(tuple((Token::OpenParen, recurse(parse_modified), Token::CloseParen)))),
+        |((_, start), (_, rule, (_, end)))| {
+            let branches = match rule {
+                Rule::Alternation(a) => a.into_rules(),
+                rule => vec![rule],
+            };
+            Rule::BranchReset(BranchReset::new(branches, start.join(end)))
+        },
+    )(input)
+}
+
+pub(super) fn parse_atomic<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    map(
+        pair("atomic", cut(tuple((Token::OpenParen, recurse(parse_modified), Token::CloseParen)))),
+        |((_, start), (_, rule, (_, end)))| {
+            Rule::Atomic(Box::new(Atomic::new(rule, start.join(end))))
+        },
+    )(input)
+}
+
+/// Parses a conditional, `if ::group { ... }`, optionally followed by
+/// `else { ... }`.
+pub(super) fn parse_conditional<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    fn parse_block<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (Rule<'i>, Span)> {
+        map(
+            tuple((Token::OpenBrace, recurse(parse_modified), cut(Token::CloseBrace))),
+            |(_, rule, (_, end))| (rule, end),
+        )(input)
+    }
+
+    map(
+        pair(
+            "if",
+            cut(tuple((
+                parse_reference_target,
+                parse_block,
+                opt(preceded("else", cut(parse_block))),
+            ))),
+        ),
+        |((_, start), (condition, (then, then_end), otherwise))| {
+            let end = otherwise.as_ref().map_or(then_end, |&(_, end)| end);
+            let otherwise = otherwise.map(|(rule, _)| rule);
+            Rule::Conditional(Box::new(Conditional::new(
+                condition,
+                then,
+                otherwise,
+                start.join(end),
+            )))
+        },
+    )(input)
+}
+
+/// Parses a block with inline flags, e.g. `enable case_insensitive, dot_all { ... }`.
+/// The block is only recognized once the opening brace is found, so that
+/// `enable lazy;` is parsed as a statement.
+pub(super) fn parse_flags<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    try_map2(
+        pair(
+            tuple((
+                alt((
+                    map("enable", |(_, span)| (true, span)),
+                    map("disable", |(_, span)| (false, span)),
+                )),
+                separated_list1(Token::Comma, Token::Identifier),
+                Token::OpenBrace,
+            )),
+            cut(pair(recurse(parse_modified), Token::CloseBrace)),
+        ),
+        |(((enable, start), names, _), (rule, (_, end)))| {
+            let flags = names
+                .into_iter()
+                .map(|(name, span)| {
+                    Flag::from_name(name)
+                        .ok_or_else(|| ParseErrorKind::UnknownFlag(name.to_string()).at(span))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(Rule::Flags(Box::new(FlagGroup::new(enable, flags, rule, start.join(end)))))
+        },
+        nom::Err::Failure,
+    )(input)
+}
+
+/// Parses `balanced(open, close)`. Each delimiter is a string containing a
+/// single code point, or a code point such as `U+28`.
+pub(super) fn parse_balanced<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    fn parse_delimiter<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (char, Span)> {
+        alt((
+            try_map(
+                Token::String,
+                |(s, span)| {
+                    let s = parse_quoted_text(s)?;
+                    let mut chars = s.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Ok((c, span)),
+                        _ => Err(ParseErrorKind::BalancedDelimiterNotChar),
+                    }
+                },
+                nom::Err::Failure,
+            ),
+            parse_code_point,
+            err(|| ParseErrorKind::ExpectedCodePointOrChar),
+        ))(input)
+    }
+
+    try_map2(
+        pair(
+            "balanced",
+            cut(tuple((
+                Token::OpenParen,
+                parse_delimiter,
+                Token::Comma,
+                parse_delimiter,
+                Token::CloseParen,
+            ))),
+        ),
+        |((_, start), (_, (open, open_span), _, (close, close_span), (_, end)))| {
+            if open == close {
+                return Err(ParseErrorKind::BalancedDelimitersEqual.at(open_span.join(close_span)));
+            }
+            Ok(Rule::Balanced(Balanced::new(open, close, start.join(end))))
+        },
+        nom::Err::Failure,
+    )(input)
+}
+
+/// Parses `verbatim("...")`. The content of the string is matched literally,
+/// without processing escape sequences, so backslashes are matched as literal
+/// backslashes. The quotes still have to be balanced, so a double-quoted string
+/// can't end with a backslash.
+pub(super) fn parse_verbatim<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    map(
+        pair("verbatim", cut(tuple((Token::OpenParen, Token::String, Token::CloseParen)))),
+        |((_, start), (_, (s, _), (_, end)))| {
+            Rule::Literal(Literal::new(Cow::Borrowed(strip_first_last(s)), start.join(end)))
+        },
+    )(input)
+}
+
+/// Parses `oneOf(...)` with a comma-separated list of strings
+pub(super) fn parse_one_of<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    fn parse_item<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Cow<'i, str>> {
+        try_map(Token::String, |(s, _)| parse_quoted_text(s), nom::Err::Failure)(input)
+    }
+
+    try_map2(
+        pair(
+            "oneOf",
+            cut(tuple((
+                Token::OpenParen,
+                separated_list0(Token::Comma, parse_item),
+                Token::CloseParen,
+            ))),
+        ),
+        |((_, start), (_, strings, (_, end)))| {
+            let span = start.join(end);
+            if strings.is_empty() {
+                return Err(ParseErrorKind::OneOfEmpty.at(span));
+            }
+            Ok(Rule::OneOf(OneOf::new(strings, span)))
+        },
+        nom::Err::Failure,
+    )(input)
+}
+
 pub(super) fn parse_string<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
     try_map(
         Token::String,
@@ -402,26 +716,39 @@ pub(super) fn parse_char_class<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b,
         }
     }
 
-    fn parse_char_group<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, CharGroup> {
+    /// Parses the content of a character class. Also returns whether it only
+    /// contains strings, code points and ranges.
+    fn parse_char_group<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (CharGroup, bool)> {
         let span1 = input.span();
 
         let (input, ranges) = many0(alt((
-            parse_chars_or_range,
-            parse_dot,
-            try_map(
-                pair(opt(Token::Not), Token::Identifier),
-                |(not, (s, _))| {
-                    // FIXME: When this fails on a negative item, the span of the exclamation mark
-                    // is used instead of the identifier's span
-                    CharGroup::try_from_group_name(s, not.is_some())
-                        .map_err(ParseErrorKind::CharClass)
-                },
-                nom::Err::Failure,
+            map(parse_chars_or_range, |group| (group, true)),
+            map(parse_dot, |group| (group, false)),
+            map(
+                try_map(
+                    tuple((
+                        opt(Token::Not),
+                        Token::Identifier,
+                        Token::Equals,
+                        cut(opt(alt((Token::Not, Token::ErrorMsg(ParseErrorMsg::Caret))))),
+                        cut(Token::Identifier),
+                    )),
+                    |(not1, (property, _), _, not2, (value, _))| {
+                        // a double negation, e.g. `[!gc=!Lu]`, cancels out
+                        let negative = not1.is_some() != not2.is_some();
+                        CharGroup::try_from_property(property, value, negative)
+                            .map_err(ParseErrorKind::CharClass)
+                    },
+                    nom::Err::Failure,
+                ),
+                |group| (group, false),
             ),
+            map(parse_named_class, |group| (group, false)),
             err(|| ParseErrorKind::CharClass(CharClassError::Invalid)),
         )))(input)?;
 
-        let mut iter = ranges.into_iter();
+        let only_chars = ranges.iter().all(|&(_, only_chars)| only_chars);
+        let mut iter = ranges.into_iter().map(|(group, _)| group);
         let mut class = iter.next().unwrap_or_else(|| CharGroup::Items(vec![]));
 
         for range in iter {
@@ -429,7 +756,27 @@ pub(super) fn parse_char_class<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b,
                 nom::Err::Failure(ParseErrorKind::CharClass(e).at(span1.join(input.span().start())))
             })?;
         }
-        Ok((input, class))
+        Ok((input, (class, only_chars)))
+    }
+
+    fn parse_named_class<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, CharGroup> {
+        // FIXME: When this fails on a negative item, the span of the exclamation mark
+        // is used instead of the identifier's span
+        let span = input.span();
+        let (input, (not, (name, _))) = pair(opt(Token::Not), Token::Identifier)(input)?;
+
+        let group = match CharGroup::try_from_group_name(name, not.is_some()) {
+            Err(err @ CharClassError::UnknownNamedClass { .. }) => {
+                match input.resolve_class(name) {
+                    Some(_) if not.is_some() => Err(CharClassError::Negative),
+                    Some(class) => CharGroup::try_from_custom_class(class),
+                    None => Err(err),
+                }
+            }
+            result => result,
+        };
+        let group = group.map_err(|e| nom::Err::Failure(ParseErrorKind::CharClass(e).at(span)))?;
+        Ok((input, group))
     }
 
     fn parse_dot<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, CharGroup> {
@@ -439,14 +786,29 @@ pub(super) fn parse_char_class<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b,
     }
 
     try_map(
-        tuple((Token::OpenBracket, cut(parse_char_group), cut(Token::CloseBracket))),
-        |((_, start), inner, (_, end))| {
-            if let CharGroup::Items(v) = &inner {
-                if v.is_empty() {
-                    return Err(ParseErrorKind::CharClass(CharClassError::Empty));
+        tuple((
+            Token::OpenBracket,
+            cut(parse_char_group),
+            many0(preceded(Token::Ampersand, cut(parse_char_group))),
+            cut(Token::CloseBracket),
+        )),
+        |((_, start), (inner, only_chars), intersections, (_, end))| {
+            for group in std::iter::once(&inner).chain(intersections.iter().map(|(g, _)| g)) {
+                match group {
+                    CharGroup::Items(v) if v.is_empty() => {
+                        return Err(ParseErrorKind::CharClass(CharClassError::Empty));
+                    }
+                    // `[.]` and `[cp]` can't be combined with anything else
+                    CharGroup::Dot | CharGroup::CodePoint if !intersections.is_empty() => {
+                        return Err(ParseErrorKind::CharClass(CharClassError::Unallowed));
+                    }
+                    _ => {}
                 }
             }
-            Ok(Rule::CharClass(CharClass::new(inner, start.join(end))))
+            let mut class = CharClass::new(inner, start.join(end));
+            class.only_chars = only_chars && intersections.is_empty();
+            class.intersections = intersections.into_iter().map(|(group, _)| group).collect();
+            Ok(Rule::CharClass(class))
         },
         nom::Err::Failure,
     )(input)
@@ -566,8 +928,9 @@ pub(super) fn parse_ident<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, (&'i
     try_map(
         Token::Identifier,
         |(name, span)| match name {
-            "let" | "lazy" | "greedy" | "range" | "base" | "atomic" | "enable" | "disable"
-            | "if" | "else" | "recursion" => {
+            "let" | "lazy" | "greedy" | "possessive" | "range" | "base" | "atomic" | "enable"
+            | "disable" | "if" | "else" | "recursion" | "repeat" | "word" | "reset" | "define"
+            | "verbatim" | "balanced" | "oneOf" | "captureEach" => {
                 Err(ParseErrorKind::UnexpectedKeyword(name.to_string()))
             }
             _ => Ok((name, span)),
@@ -625,6 +988,12 @@ pub(super) fn parse_start_end<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, B
             BoundaryKind::NotWord => {
                 unreachable!("parse_start_end parsed a negative word boundary")
             }
+            BoundaryKind::LineStart
+            | BoundaryKind::LineEnd
+            | BoundaryKind::EndOfText
+            | BoundaryKind::EndOfTextOrBeforeNewline => {
+                unreachable!("parse_start_end parsed a built-in variable")
+            }
         })
         .at(boundary.span),
     );
@@ -633,6 +1002,10 @@ pub(super) fn parse_start_end<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, B
 }
 
 pub(super) fn parse_reference<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Rule<'i>> {
+    map(parse_reference_target, Rule::Reference)(input)
+}
+
+fn parse_reference_target<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, Reference<'i>> {
     preceded(
         Token::Backref,
         alt((
@@ -640,20 +1013,20 @@ pub(super) fn parse_reference<'i, 'b>(input: Input<'i, 'b>) -> PResult<'i, 'b, R
                 Token::Number,
                 |(s, span)| {
                     let target = ReferenceTarget::Number(from_str(s)?);
-                    Ok(Rule::Reference(Reference::new(target, span)))
+                    Ok(Reference::new(target, span))
                 },
                 nom::Err::Failure,
             ),
             map(Token::Identifier, |(s, span)| {
                 let target = ReferenceTarget::Named(s);
-                Rule::Reference(Reference::new(target, span))
+                Reference::new(target, span)
             }),
             try_map(
                 pair(alt((Token::Plus, Token::Dash)), Token::Number),
                 |((sign, span1), (s, span2))| {
                     let num = if sign == "-" { from_str(&format!("-{s}")) } else { from_str(s) }?;
                     let target = ReferenceTarget::Relative(num);
-                    Ok(Rule::Reference(Reference::new(target, span1.join(span2))))
+                    Ok(Reference::new(target, span1.join(span2)))
                 },
                 nom::Err::Failure,
             ),
@@ -7,6 +7,7 @@ use crate::{
 
 use super::input::Input;
 
+/// A token in a Pomsky expression
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 #[non_exhaustive]
 pub enum Token {
@@ -40,7 +41,10 @@ pub enum Token {
     CloseBrace,
     /// `,` (comma in repetition)
     Comma,
+    /// `±` (tolerance in repetition)
+    PlusMinus,
 
+    /// `!` (negation)
     Not,
 
     /// `[` (open character class)
@@ -52,6 +56,9 @@ pub enum Token {
     /// `]` (close character class)
     CloseBracket,
 
+    /// `&` (intersection of character classes)
+    Ampersand,
+
     /// `.` (any code point except newline)
     Dot,
 
@@ -83,61 +90,88 @@ pub enum Token {
     Identifier,
 
     // match illegal tokens for which we want to show a better error message
+    /// An illegal token for which a helpful error message is shown
     ErrorMsg(ParseErrorMsg),
 
+    /// An illegal token
     Error,
 }
 
+/// An error message for a token that is invalid in Pomsky, but might be valid
+/// in other regex syntaxes
 #[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
 #[non_exhaustive]
 pub enum ParseErrorMsg {
+    /// `^`
     #[error("`^` is not a valid token")]
     Caret,
+    /// `[^`
     #[error("`^` is not a valid token")]
     CaretInGroup,
+    /// `$`
     #[error("`$` is not a valid token")]
     Dollar,
 
+    /// `(?:`
     #[error("This syntax is not supported")]
     GroupNonCapturing,
+    /// `(?=`
     #[error("This syntax is not supported")]
     GroupLookahead,
+    /// `(?!`
     #[error("This syntax is not supported")]
     GroupLookaheadNeg,
+    /// `(?<=`
     #[error("This syntax is not supported")]
     GroupLookbehind,
+    /// `(?<!`
     #[error("This syntax is not supported")]
     GroupLookbehindNeg,
+    /// `(?<name>` or `(?P<name>`
     #[error("This syntax is not supported")]
     GroupNamedCapture,
+    /// `(?P=name)`
     #[error("This syntax is not supported")]
     GroupPcreBackreference,
+    /// `(?#`
     #[error("Comments have a different syntax")]
     GroupComment,
-    #[error("Atomic groups are not supported")]
+    /// `(?>`
+    #[error("This syntax is not supported")]
     GroupAtomic,
-    #[error("Conditionals are not supported")]
+    /// `(?(`
+    #[error("This syntax is not supported")]
     GroupConditional,
+    /// `(?|`
     #[error("Branch reset groups are not supported")]
     GroupBranchReset,
+    /// A subroutine call, e.g. `(?P>name)` or `(?&name)`
     #[error("Subroutines are not supported")]
     GroupSubroutineCall,
+    /// Another group syntax starting with `(?`
     #[error("This syntax is not supported")]
     GroupOther,
 
+    /// A backslash escape, e.g. `\b`
     #[error("Backslash escapes are not supported")]
     Backslash,
+    /// `\uFFFF`
     #[error("Backslash escapes are not supported")]
     BackslashU4,
+    /// `\xFF`
     #[error("Backslash escapes are not supported")]
     BackslashX2,
+    /// `\u{FFFF}` or `\x{FFFF}`
     #[error("Backslash escapes are not supported")]
     BackslashUnicode,
+    /// `\p{Property}` or `\P{Property}`
     #[error("Backslash escapes are not supported")]
     BackslashProperty,
+    /// A backreference, e.g. `\g<name>` or `\k<name>`
     #[error("Backslash escapes are not supported")]
     BackslashGK,
 
+    /// A string without a closing quote
     #[error("This string literal doesn't have a closing quote")]
     UnclosedString,
 }
@@ -158,6 +192,7 @@ impl core::fmt::Display for Token {
             Token::OpenBrace => "`{`",
             Token::CloseBrace => "`}`",
             Token::Comma => "`,`",
+            Token::PlusMinus => "`±`",
             Token::LookAhead => "`>>`",
             Token::LookBehind => "`<<`",
             Token::Backref => "`::`",
@@ -165,6 +200,7 @@ impl core::fmt::Display for Token {
             Token::OpenBracket => "`[`",
             Token::Dash => "`-`",
             Token::CloseBracket => "`]`",
+            Token::Ampersand => "`&`",
             Token::Dot => "`.`",
             Token::Semicolon => "`;`",
             Token::Equals => "`=`",
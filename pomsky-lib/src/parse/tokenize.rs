@@ -69,10 +69,12 @@ pub(crate) fn tokenize(mut input: &str) -> Vec<(Token, Span)> {
                     if c == '{' => (1, Token::OpenBrace);
                     if c == '}' => (1, Token::CloseBrace);
                     if c == ',' => (1, Token::Comma);
+                    if c == '±' => (c.len_utf8(), Token::PlusMinus);
                     if c == '!' => (1, Token::Not);
                     if c == '[' => (1, Token::OpenBracket);
                     if c == '-' => (1, Token::Dash);
                     if c == ']' => (1, Token::CloseBracket);
+                    if c == '&' => (1, Token::Ampersand);
                     if c == '.' => (1, Token::Dot);
                     if c == ';' => (1, Token::Semicolon);
                     if c == '=' => (1, Token::Equals);
@@ -121,6 +123,9 @@ pub(crate) fn tokenize(mut input: &str) -> Vec<(Token, Span)> {
     result
 }
 
+/// Returns the index of the first `"` that isn't preceded by a backslash.
+/// Line breaks don't end the string, so a string can span several lines; the
+/// line breaks are part of its content and are matched literally.
 fn find_unescaped_quote(input: &str) -> Option<usize> {
     let mut s = input;
 
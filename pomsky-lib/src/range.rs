@@ -15,9 +15,9 @@ use crate::{
 
 #[derive(Clone, PartialEq, Eq)]
 pub(crate) struct Range {
-    start: Vec<u8>,
-    end: Vec<u8>,
-    radix: u8,
+    pub(crate) start: Vec<u8>,
+    pub(crate) end: Vec<u8>,
+    pub(crate) radix: u8,
     pub(crate) span: Span,
 }
 
@@ -1,12 +1,76 @@
+use std::{borrow::Cow, collections::HashMap};
+
 use crate::{
-    compile::{CompileResult, CompileState},
-    error::{CompileErrorKind, Feature, ParseError},
+    ast::{self, Node, NodeKind},
+    captures::{CaptureInfo, CaptureOccurrence, OccurrenceKind},
+    compile::{CompileResult, CompileState, Output},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
     features::PomskyFeatures,
+    group::output_group_name,
     options::{CompileOptions, ParseOptions, RegexFlavor},
     regex::Regex,
+    rule::Rule,
     span::Span,
 };
 
+/// Checks that every named reference refers to a group that exists. This runs
+/// before compiling, after all groups have been collected, so references to
+/// groups defined later in the expression are accepted. References within
+/// variables are only checked when the variable is compiled.
+pub(crate) fn check_reference_names(
+    rule: &Rule<'_>,
+    used_names: &HashMap<String, u32>,
+) -> Result<(), CompileError> {
+    let mut info = CaptureInfo::default();
+    rule.capture_info(&mut info, false);
+    for occurrence in info.occurrences {
+        if occurrence.kind == OccurrenceKind::Reference
+            && !used_names.contains_key(&occurrence.name)
+        {
+            return Err(unknown_name(&occurrence.name, occurrence.span.into(), used_names));
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error if the expression contains both a `define` block and a
+/// numeric reference or condition, e.g. `::1`. The variables in a `define`
+/// block are compiled to capturing groups, which are numbered like any other
+/// group, so `::1` might refer to a variable instead of the intended group.
+pub(crate) fn check_numeric_references(root: Node<'_, '_>) -> Result<(), CompileError> {
+    let mut has_define = false;
+    let mut numeric_reference = None;
+    find_numeric_references(root, &mut has_define, &mut numeric_reference);
+    match numeric_reference {
+        Some(span) if has_define => Err(CompileErrorKind::NumericReferenceWithDefine.at(span)),
+        _ => Ok(()),
+    }
+}
+
+fn find_numeric_references(node: Node<'_, '_>, has_define: &mut bool, found: &mut Option<Span>) {
+    match node.kind() {
+        NodeKind::Define { .. } => *has_define = true,
+        NodeKind::Reference(ast::ReferenceTarget::Number(_))
+        | NodeKind::Conditional(ast::ReferenceTarget::Number(_)) => {
+            found.get_or_insert(node.span());
+        }
+        _ => {}
+    }
+    for child in node.children() {
+        find_numeric_references(child, has_define, found);
+    }
+}
+
+#[cfg_attr(not(feature = "suggestions"), allow(unused_variables))]
+fn unknown_name(name: &str, span: Span, used_names: &HashMap<String, u32>) -> CompileError {
+    CompileErrorKind::UnknownReferenceName {
+        found: name.into(),
+        #[cfg(feature = "suggestions")]
+        similar: crate::util::find_suggestion(name, used_names.keys().map(|key| key.as_str())),
+    }
+    .at(span)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(crate) struct Reference<'i> {
     pub(crate) target: ReferenceTarget<'i>,
@@ -32,12 +96,79 @@ impl<'i> Reference<'i> {
         Reference { target, span }
     }
 
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo) {
+        if let ReferenceTarget::Named(name) = self.target {
+            info.occurrences.push(CaptureOccurrence {
+                name: name.to_string(),
+                kind: OccurrenceKind::Reference,
+                span: self.span.range_unchecked(),
+            });
+        }
+    }
+
     pub(crate) fn compile(
         &self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState,
     ) -> CompileResult<'i> {
-        let (direction, number) = match self.target {
+        let (direction, number) = self.resolve(state)?;
+
+        match options.flavor {
+            RegexFlavor::Rust | RegexFlavor::Re2 | RegexFlavor::Antlr => {
+                Err(CompileErrorKind::Unsupported(
+                    if direction == ReferenceDirection::Backwards {
+                        Feature::Backreference
+                    } else {
+                        Feature::ForwardReference
+                    },
+                    options.flavor,
+                )
+                .at(self.span))
+            }
+            RegexFlavor::Lua | RegexFlavor::Sed { .. }
+                if direction == ReferenceDirection::Forwards =>
+            {
+                Err(CompileErrorKind::Unsupported(Feature::ForwardReference, options.flavor)
+                    .at(self.span))
+            }
+            // Lua only supports `%1` to `%9`
+            RegexFlavor::Lua if number > 9 => Err(CompileErrorKind::Other(
+                "Lua patterns only support backreferences to the first 9 groups",
+            )
+            .at(self.span)),
+            // sed only supports `\1` to `\9`. POSIX has no non-capturing groups, so the
+            // number in the emitted regex may be greater; references are renumbered
+            // after compiling
+            RegexFlavor::Sed { .. } if number > 9 => Err(CompileErrorKind::Other(
+                "sed only supports backreferences to the first 9 groups",
+            )
+            .at(self.span)),
+            RegexFlavor::JavaScript | RegexFlavor::Python
+                if direction == ReferenceDirection::Forwards =>
+            {
+                Err(CompileErrorKind::Unsupported(Feature::ForwardReference, options.flavor)
+                    .at(self.span))
+            }
+            _ => Ok(Regex::Reference(self.output_reference(number, options, state)?)),
+        }
+    }
+
+    /// Compiles the reference as the condition of a conditional, e.g. the `1`
+    /// in `(?(1)a|b)`. Unlike backreferences, conditions may point forwards.
+    pub(crate) fn compile_condition(
+        &self,
+        options: &CompileOptions,
+        state: &CompileState,
+    ) -> Result<RegexReference<'i>, CompileError> {
+        let (_, number) = self.resolve(state)?;
+        self.output_reference(number, options, state)
+    }
+
+    /// Returns whether the reference points backwards or forwards, and the
+    /// number of the referenced group. Returns an error if the group doesn't
+    /// exist.
+    fn resolve(&self, state: &CompileState) -> Result<(ReferenceDirection, u32), CompileError> {
+        Ok(match self.target {
             ReferenceTarget::Named(name) => match state.used_names.get(name) {
                 Some(&n) => {
                     let direction = if n >= state.next_idx {
@@ -47,17 +178,7 @@ impl<'i> Reference<'i> {
                     };
                     (direction, n)
                 }
-                None => {
-                    return Err(CompileErrorKind::UnknownReferenceName {
-                        found: name.into(),
-                        #[cfg(feature = "suggestions")]
-                        similar: crate::util::find_suggestion(
-                            name,
-                            state.used_names.keys().map(|key| key.as_str()),
-                        ),
-                    }
-                    .at(self.span));
-                }
+                None => return Err(unknown_name(name, self.span, &state.used_names)),
             },
             ReferenceTarget::Number(idx) => {
                 let direction = if idx > 99 {
@@ -88,28 +209,54 @@ impl<'i> Reference<'i> {
                     1..=i32::MAX => offset + (state.next_idx as i32) - 1,
                 };
                 if num <= 0 || (num as u32) > state.groups_count {
-                    return Err(CompileErrorKind::UnknownReferenceNumber(num).at(self.span));
+                    return Err(CompileErrorKind::UnknownRelativeReference(offset).at(self.span));
                 }
 
                 (direction, num as u32)
             }
-        };
+        })
+    }
 
+    /// Returns the reference to the group with the given number as it is
+    /// emitted. Python and .NET refer to some groups by name, and .NET
+    /// numbers groups differently, so the number is adjusted.
+    fn output_reference(
+        &self,
+        number: u32,
+        options: &CompileOptions,
+        state: &CompileState,
+    ) -> Result<RegexReference<'i>, CompileError> {
         match options.flavor {
-            RegexFlavor::Rust => Err(CompileErrorKind::Unsupported(
-                if direction == ReferenceDirection::Backwards {
-                    Feature::Backreference
-                } else {
-                    Feature::ForwardReference
-                },
-                options.flavor,
-            )
-            .at(self.span)),
-            RegexFlavor::JavaScript if direction == ReferenceDirection::Forwards => {
-                Err(CompileErrorKind::Unsupported(Feature::ForwardReference, options.flavor)
-                    .at(self.span))
+            // Python has a dedicated syntax for named backreferences
+            RegexFlavor::Python => {
+                let name = match self.target {
+                    ReferenceTarget::Named(name) => {
+                        Some(output_group_name(name, self.span, options, state)?)
+                    }
+                    _ => None,
+                };
+                Ok(RegexReference { number, name })
+            }
+            // .NET numbers named groups after all unnamed groups, so a reference
+            // to a named group must be emitted by name, even if it has a number,
+            // and the named groups before an unnamed group don't count
+            RegexFlavor::DotNet => {
+                let name = match self.target {
+                    ReferenceTarget::Named(name) => {
+                        Some(output_group_name(name, self.span, options, state)?)
+                    }
+                    _ => match state.used_names.iter().find(|&(_, &n)| n == number) {
+                        Some((name, _)) => Some(Cow::Owned(
+                            output_group_name(name, self.span, options, state)?.into_owned(),
+                        )),
+                        None => None,
+                    },
+                };
+                let named_before = state.used_names.values().filter(|&&n| n < number).count();
+                let number = number - named_before as u32;
+                Ok(RegexReference { number, name })
             }
-            _ => Ok(Regex::Reference(RegexReference { number })),
+            _ => Ok(RegexReference { number, name: None }),
         }
     }
 
@@ -130,16 +277,22 @@ impl std::fmt::Debug for Reference<'_> {
 }
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
-pub(crate) struct RegexReference {
-    number: u32,
+pub(crate) struct RegexReference<'i> {
+    pub(crate) number: u32,
+    /// The name of the referenced group in the output, if the reference is
+    /// emitted by name. This is only the case in flavors with a named
+    /// backreference syntax.
+    pub(crate) name: Option<Cow<'i, str>>,
 }
 
-impl RegexReference {
-    pub(crate) fn codegen(&self, buf: &mut String, _: RegexFlavor) {
-        use std::fmt::Write;
-
+impl RegexReference<'_> {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
         debug_assert!(self.number <= 99);
 
-        write!(buf, "\\{}", self.number).unwrap();
+        match (&self.name, flavor) {
+            (Some(name), RegexFlavor::Python) => write!(buf, "(?P={name})").unwrap(),
+            (Some(name), RegexFlavor::DotNet) => write!(buf, "\\k<{name}>").unwrap(),
+            _ => write!(buf, "\\{}", self.number).unwrap(),
+        }
     }
 }
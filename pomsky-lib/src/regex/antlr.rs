@@ -0,0 +1,221 @@
+//! Emits ANTLR lexer rules. The syntax differs from regular expressions in
+//! several ways: String literals are quoted, elements of a sequence are
+//! separated by spaces, and negated character sets are written as `~[...]`.
+//! There are no counted repetitions, so they are expanded.
+//!
+//! Expressions that can't be expressed in ANTLR, such as boundaries or
+//! lookaround, are rejected when compiling, before the code is generated.
+
+use crate::{
+    char_class::{RegexCharClass, RegexClassItem},
+    compile::Output,
+    options::RegexFlavor,
+    repetition::{RegexQuantifier, RegexRepetition, RepetitionKind},
+};
+
+use super::{Regex, RegexShorthand};
+
+pub(super) fn codegen(regex: &Regex<'_>, buf: &mut impl Output) {
+    match regex {
+        Regex::Literal(l) => {
+            if !l.is_empty() {
+                codegen_literal(l.chars(), buf);
+            }
+        }
+        &Regex::Char(c) => codegen_literal([c].into_iter(), buf),
+        Regex::CharClass(class) => codegen_class(class, buf),
+        &Regex::Shorthand(s) => {
+            buf.push('[');
+            codegen_shorthand(s, buf);
+            buf.push(']');
+        }
+        &Regex::Property { value, negative } => {
+            buf.push('[');
+            value.codegen(buf, negative, RegexFlavor::Antlr);
+            buf.push(']');
+        }
+        // the dot matches anything except `\n` in Pomsky, but anything in ANTLR
+        Regex::Dot => buf.push_str("~[\\n]"),
+        Regex::Group(group) => codegen_sequence(&group.parts, buf),
+        Regex::Alternation(alt) => {
+            for (i, part) in alt.parts.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(" | ");
+                }
+                codegen(part, buf);
+            }
+        }
+        Regex::Repetition(rep) => codegen_repetition(rep, buf),
+        Regex::ClassIntersection(_)
+        | Regex::Grapheme
+        | Regex::LineBreak
+        | Regex::Boundary(_)
+        | Regex::Lookaround(_)
+        | Regex::Reference(_)
+        | Regex::Conditional(_)
+        | Regex::SubroutineCall(_)
+        | Regex::Recursion(_) => {
+            unreachable!("this expression is rejected when compiling for ANTLR")
+        }
+    }
+}
+
+/// Returns `true` if the regex doesn't emit any code
+fn is_empty(regex: &Regex<'_>) -> bool {
+    match regex {
+        Regex::Literal(l) => l.is_empty(),
+        Regex::Group(group) => group.parts.iter().all(is_empty),
+        _ => false,
+    }
+}
+
+/// Returns `true` if the regex can be repeated without parentheses
+fn is_atom(regex: &Regex<'_>) -> bool {
+    match regex {
+        Regex::Literal(_)
+        | Regex::Char(_)
+        | Regex::CharClass(_)
+        | Regex::Shorthand(_)
+        | Regex::Property { .. }
+        | Regex::Dot => true,
+        Regex::Group(group) => {
+            let mut parts = group.parts.iter().filter(|part| !is_empty(part));
+            match (parts.next(), parts.next()) {
+                (Some(part), None) => is_atom(part),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn codegen_sequence(parts: &[Regex<'_>], buf: &mut impl Output) {
+    let parts: Vec<_> = parts.iter().filter(|part| !is_empty(part)).collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            buf.push(' ');
+        }
+        if parts.len() > 1 && matches!(part, Regex::Alternation(_)) {
+            buf.push('(');
+            codegen(part, buf);
+            buf.push(')');
+        } else {
+            codegen(part, buf);
+        }
+    }
+}
+
+/// Emits a repetition. ANTLR only supports `?`, `*` and `+`, so other
+/// repetitions are expanded, e.g. `x{2,4}` becomes `x x (x x?)?`.
+fn codegen_repetition(rep: &RegexRepetition<'_>, buf: &mut impl Output) {
+    if is_empty(&rep.content) {
+        return;
+    }
+
+    let mut atom = String::new();
+    if is_atom(&rep.content) {
+        codegen(&rep.content, &mut atom);
+    } else {
+        atom.push('(');
+        codegen(&rep.content, &mut atom);
+        atom.push(')');
+    }
+    let lazy = if let RegexQuantifier::Lazy = rep.quantifier { "?" } else { "" };
+
+    let RepetitionKind { lower_bound, upper_bound } = rep.kind;
+    let mut parts = vec![atom.as_str(); lower_bound as usize];
+    let rest = match upper_bound {
+        None if lower_bound == 0 => format!("{atom}*{lazy}"),
+        None => {
+            parts.pop();
+            format!("{atom}+{lazy}")
+        }
+        Some(upper_bound) => {
+            let mut optional = String::new();
+            for _ in lower_bound..upper_bound {
+                optional = if optional.is_empty() {
+                    format!("{atom}?{lazy}")
+                } else {
+                    format!("({atom} {optional})?{lazy}")
+                };
+            }
+            optional
+        }
+    };
+    if !rest.is_empty() {
+        parts.push(&rest);
+    }
+    buf.push_str(&parts.join(" "));
+}
+
+fn codegen_class(class: &RegexCharClass, buf: &mut impl Output) {
+    if class.negative {
+        buf.push('~');
+    }
+    buf.push('[');
+    for item in &class.items {
+        match *item {
+            RegexClassItem::Char(c) => codegen_char_in_set(c, buf),
+            RegexClassItem::Range { first, last } => {
+                codegen_char_in_set(first, buf);
+                buf.push('-');
+                codegen_char_in_set(last, buf);
+            }
+            RegexClassItem::Shorthand(s) => codegen_shorthand(s, buf),
+            RegexClassItem::Property { negative, value } => {
+                value.codegen(buf, negative, RegexFlavor::Antlr);
+            }
+            RegexClassItem::Nested { .. } => {
+                unreachable!("nested classes are only emitted for JavaScript")
+            }
+        }
+    }
+    buf.push(']');
+}
+
+/// Writes a shorthand within a character set. Word characters and digits are
+/// replaced with Unicode properties when compiling for ANTLR, so only `\s` and
+/// `\S` remain.
+fn codegen_shorthand(shorthand: RegexShorthand, buf: &mut impl Output) {
+    match shorthand {
+        RegexShorthand::Space => buf.push_str("\\p{White_Space}"),
+        RegexShorthand::NotSpace => buf.push_str("\\P{White_Space}"),
+        _ => unreachable!("only `\\s` and `\\S` are emitted for ANTLR"),
+    }
+}
+
+fn codegen_literal(chars: impl Iterator<Item = char>, buf: &mut impl Output) {
+    buf.push('\'');
+    for c in chars {
+        match c {
+            '\'' => buf.push_str("\\'"),
+            c => codegen_char(c, buf),
+        }
+    }
+    buf.push('\'');
+}
+
+fn codegen_char_in_set(c: char, buf: &mut impl Output) {
+    match c {
+        ']' | '\\' | '-' => {
+            buf.push('\\');
+            buf.push(c);
+        }
+        c => codegen_char(c, buf),
+    }
+}
+
+fn codegen_char(c: char, buf: &mut impl Output) {
+    match c {
+        '\\' => buf.push_str("\\\\"),
+        '\n' => buf.push_str("\\n"),
+        '\r' => buf.push_str("\\r"),
+        '\t' => buf.push_str("\\t"),
+        '\x08' => buf.push_str("\\b"),
+        '\x0C' => buf.push_str("\\f"),
+        ' ' => buf.push(' '),
+        _ if c.is_ascii_graphic() || (!c.is_ascii() && c.is_alphanumeric()) => buf.push(c),
+        _ if c as u32 <= 0xFFFF => write!(buf, "\\u{:04X}", c as u32).unwrap(),
+        _ => write!(buf, "\\u{{{:X}}}", c as u32).unwrap(),
+    }
+}
@@ -0,0 +1,369 @@
+//! Emits Lua patterns. Lua patterns aren't regular expressions: They have no
+//! alternation, and quantifiers can only be applied to a single character or
+//! character class. `%` is used as the escape character, and `-` is the lazy
+//! version of `*`.
+//!
+//! Lua patterns match bytes, not code points. Non-ASCII characters in
+//! literals are emitted as UTF-8, so they match the same strings, but `.`,
+//! negated classes such as `[^\n]` and negated shorthands such as `%S` match
+//! a single byte, which might be part of a multi-byte character. They're only
+//! allowed in a `*`, `+` or `-` repetition, where the repeated bytes together
+//! form whole characters.
+//!
+//! Expressions that can't be expressed as a Lua pattern are rejected when
+//! compiling with [`Regex::check_lua`], before the code is generated.
+
+use crate::{
+    boundary::BoundaryKind,
+    char_class::{RegexCharClass, RegexClassItem},
+    compile::Output,
+    error::Feature,
+    group::RegexCapture,
+    repetition::{RegexQuantifier, RegexRepetition, RepetitionKind},
+};
+
+use super::{Regex, RegexShorthand};
+
+impl Regex<'_> {
+    /// Returns the first feature in the regex that can't be expressed as a
+    /// Lua pattern. This is called for every compiled rule, so the error
+    /// points to the innermost expression that isn't supported.
+    ///
+    /// The position of anchors is checked separately by
+    /// [`Regex::check_lua_anchors`], since it depends on the whole pattern.
+    pub(crate) fn check_lua(&self) -> Result<(), Feature> {
+        match self {
+            Regex::Literal(_)
+            | Regex::Char(_)
+            | Regex::Dot
+            | Regex::Boundary(_)
+            | Regex::Reference(_) => Ok(()),
+            Regex::CharClass(class) => check_class(class),
+            Regex::Shorthand(s) => check_shorthand(*s),
+            Regex::Property { .. } => Err(Feature::UnicodeProp),
+            Regex::ClassIntersection(_) | Regex::Conditional(_) => {
+                unreachable!("this expression is rejected when compiling for Lua")
+            }
+            Regex::Grapheme => Err(Feature::Grapheme),
+            Regex::Alternation(alt) if alt.parts.len() > 1 => Err(Feature::Alternation),
+            Regex::Alternation(alt) => alt.parts.iter().try_for_each(Regex::check_lua),
+            // `\R` is only emitted for PCRE, Java and Ruby, otherwise it is an alternation
+            Regex::LineBreak => Err(Feature::Alternation),
+            Regex::Group(group) => group.parts.iter().try_for_each(Regex::check_lua),
+            Regex::Repetition(rep) => {
+                rep.content.check_lua()?;
+                if !is_single_char(&rep.content) {
+                    return Err(Feature::ComplexRepetition);
+                }
+                // there's no lazy version of `?`
+                let RepetitionKind { lower_bound, upper_bound } = rep.kind;
+                if let (RegexQuantifier::Lazy, Some(upper_bound)) = (rep.quantifier, upper_bound) {
+                    if upper_bound > lower_bound {
+                        return Err(Feature::LazyQuantifier);
+                    }
+                }
+                Ok(())
+            }
+            Regex::Lookaround(_) => Err(Feature::Lookaround),
+            Regex::SubroutineCall(_) => Err(Feature::SubroutineCall),
+            Regex::Recursion(_) => Err(Feature::Recursion),
+        }
+    }
+
+    /// Returns an error if `^` isn't at the start of the pattern, or `$`
+    /// isn't at the end. Anywhere else, Lua matches them literally.
+    pub(crate) fn check_lua_anchors(&self) -> Result<(), Feature> {
+        check_anchors(self, true, true)
+    }
+
+    /// Returns an error if something that matches a single code point, which
+    /// might not be ASCII, isn't repeated with `*`, `+` or `-`. For example,
+    /// `'a' C 'b'` can't be emitted as `a.b`, since `.` matches one byte, so
+    /// it wouldn't match `aäb`.
+    pub(crate) fn check_lua_code_points(&self) -> Result<(), Feature> {
+        match self {
+            Regex::Group(group) => group.parts.iter().try_for_each(Regex::check_lua_code_points),
+            Regex::Alternation(alt) => alt.parts.iter().try_for_each(Regex::check_lua_code_points),
+            Regex::Repetition(rep) if repeats_whole_chars(rep) => Ok(()),
+            Regex::Repetition(rep) => rep.content.check_lua_code_points(),
+            _ if matches_single_byte(self) => Err(Feature::SingleByteCodePoint),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns `true` if the repetition is emitted as a single quantifier that
+/// matches any number of bytes, i.e. `*`, `+` or `-`. `x{2,}` is emitted as
+/// `xx+`, where the first `x` matches a single byte.
+fn repeats_whole_chars(rep: &RegexRepetition<'_>) -> bool {
+    let RepetitionKind { lower_bound, upper_bound } = rep.kind;
+    let greedy = matches!(rep.quantifier, RegexQuantifier::Greedy);
+    upper_bound.is_none() && (lower_bound == 0 || (lower_bound == 1 && greedy))
+}
+
+/// Returns `true` if the regex matches a non-ASCII code point, but is
+/// emitted as something that matches a single byte, e.g. `.` or `[^\n]`
+fn matches_single_byte(regex: &Regex<'_>) -> bool {
+    match regex {
+        Regex::Dot => true,
+        Regex::CharClass(class) => {
+            class.negative
+                || class.items.iter().any(|item| {
+                    matches!(
+                        item,
+                        RegexClassItem::Shorthand(
+                            RegexShorthand::NotDigit | RegexShorthand::NotSpace
+                        )
+                    )
+                })
+        }
+        Regex::Shorthand(s) => matches!(
+            s,
+            RegexShorthand::NotWord | RegexShorthand::NotDigit | RegexShorthand::NotSpace
+        ),
+        _ => false,
+    }
+}
+
+fn check_anchors(regex: &Regex<'_>, at_start: bool, at_end: bool) -> Result<(), Feature> {
+    match regex {
+        Regex::Boundary(BoundaryKind::Start) if !at_start => Err(Feature::AnchorPosition),
+        Regex::Boundary(BoundaryKind::End | BoundaryKind::EndOfText) if !at_end => {
+            Err(Feature::AnchorPosition)
+        }
+        Regex::Group(group) => {
+            // a capturing group is emitted with parentheses, so the anchor
+            // wouldn't be at the start or end of the pattern
+            let (at_start, at_end) = match group.capture {
+                RegexCapture::None | RegexCapture::NoneWithParens => (at_start, at_end),
+                _ => (false, false),
+            };
+            let parts: Vec<_> = group.parts.iter().filter(|part| !is_empty(part)).collect();
+            for (i, part) in parts.iter().enumerate() {
+                check_anchors(part, at_start && i == 0, at_end && i == parts.len() - 1)?;
+            }
+            Ok(())
+        }
+        Regex::Alternation(alt) => {
+            alt.parts.iter().try_for_each(|part| check_anchors(part, at_start, at_end))
+        }
+        Regex::Repetition(rep) => check_anchors(&rep.content, false, false),
+        _ => Ok(()),
+    }
+}
+
+fn check_class(class: &RegexCharClass) -> Result<(), Feature> {
+    for item in &class.items {
+        match *item {
+            RegexClassItem::Char(c) | RegexClassItem::Range { last: c, .. } if !c.is_ascii() => {
+                return Err(Feature::NonAsciiCharClass);
+            }
+            RegexClassItem::Char(_) | RegexClassItem::Range { .. } => {}
+            // `[^%w_]` can't be part of another set
+            RegexClassItem::Shorthand(RegexShorthand::NotWord) => {
+                return Err(Feature::NegativeShorthandInClass);
+            }
+            RegexClassItem::Shorthand(s) => check_shorthand(s)?,
+            RegexClassItem::Property { .. } => return Err(Feature::UnicodeProp),
+            RegexClassItem::Nested { .. } => return Err(Feature::NegativeShorthandW),
+        }
+    }
+    Ok(())
+}
+
+fn check_shorthand(shorthand: RegexShorthand) -> Result<(), Feature> {
+    match shorthand {
+        RegexShorthand::Word
+        | RegexShorthand::NotWord
+        | RegexShorthand::Digit
+        | RegexShorthand::NotDigit
+        | RegexShorthand::Space
+        | RegexShorthand::NotSpace => Ok(()),
+        _ => Err(Feature::UnicodeProp),
+    }
+}
+
+/// Returns `true` if the regex doesn't emit any code
+fn is_empty(regex: &Regex<'_>) -> bool {
+    match regex {
+        Regex::Literal(l) => l.is_empty(),
+        Regex::Group(group) if matches!(group.capture, RegexCapture::None) => {
+            group.parts.iter().all(is_empty)
+        }
+        _ => false,
+    }
+}
+
+/// Returns `true` if the regex matches a single byte, so it can be repeated
+fn is_single_char(regex: &Regex<'_>) -> bool {
+    match regex {
+        Regex::Literal(l) => l.len() == 1,
+        Regex::Char(c) => c.is_ascii(),
+        Regex::CharClass(_) | Regex::Shorthand(_) | Regex::Dot => true,
+        Regex::Group(group)
+            if matches!(group.capture, RegexCapture::None | RegexCapture::NoneWithParens) =>
+        {
+            let mut parts = group.parts.iter().filter(|part| !is_empty(part));
+            match (parts.next(), parts.next()) {
+                (Some(part), None) => is_single_char(part),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn codegen(regex: &Regex<'_>, buf: &mut impl Output) {
+    match regex {
+        Regex::Literal(l) => {
+            for c in l.chars() {
+                codegen_char(c, buf);
+            }
+        }
+        &Regex::Char(c) => codegen_char(c, buf),
+        Regex::CharClass(class) => codegen_class(class, buf),
+        &Regex::Shorthand(s) => buf.push_str(match s {
+            RegexShorthand::Word => "[%w_]",
+            RegexShorthand::NotWord => "[^%w_]",
+            _ => shorthand_in_class(s),
+        }),
+        Regex::Dot => buf.push('.'),
+        Regex::Group(group) => {
+            let capturing = matches!(group.capture, RegexCapture::Capture);
+            if capturing {
+                buf.push('(');
+            }
+            for part in &group.parts {
+                codegen(part, buf);
+            }
+            if capturing {
+                buf.push(')');
+            }
+        }
+        Regex::Alternation(alt) => {
+            for part in &alt.parts {
+                codegen(part, buf);
+            }
+        }
+        Regex::Repetition(rep) => codegen_repetition(rep, buf),
+        Regex::Boundary(BoundaryKind::Start) => buf.push('^'),
+        Regex::Boundary(_) => buf.push('$'),
+        Regex::Reference(r) => {
+            buf.push('%');
+            buf.push_str(&r.number.to_string());
+        }
+        Regex::Property { .. }
+        | Regex::ClassIntersection(_)
+        | Regex::Grapheme
+        | Regex::LineBreak
+        | Regex::Lookaround(_)
+        | Regex::Conditional(_)
+        | Regex::SubroutineCall(_)
+        | Regex::Recursion(_) => {
+            unreachable!("this expression is rejected when compiling for Lua")
+        }
+    }
+}
+
+/// Emits a repetition. Lua only supports `*`, `+`, `-` and `?`, so other
+/// repetitions are expanded, e.g. `x{2,4}` becomes `xxx?x?`.
+fn codegen_repetition(rep: &RegexRepetition<'_>, buf: &mut impl Output) {
+    let mut atom = String::new();
+    codegen(&rep.content, &mut atom);
+    let greedy = matches!(rep.quantifier, RegexQuantifier::Greedy);
+
+    let RepetitionKind { lower_bound, upper_bound } = rep.kind;
+    match upper_bound {
+        None => {
+            let (times, quantifier) = match (lower_bound, greedy) {
+                (0, true) => (0, '*'),
+                (_, true) => (lower_bound - 1, '+'),
+                (_, false) => (lower_bound, '-'),
+            };
+            for _ in 0..times {
+                buf.push_str(&atom);
+            }
+            buf.push_str(&atom);
+            buf.push(quantifier);
+        }
+        Some(upper_bound) => {
+            for _ in 0..lower_bound {
+                buf.push_str(&atom);
+            }
+            for _ in lower_bound..upper_bound {
+                buf.push_str(&atom);
+                buf.push('?');
+            }
+        }
+    }
+}
+
+fn codegen_class(class: &RegexCharClass, buf: &mut impl Output) {
+    buf.push('[');
+    if class.negative {
+        buf.push('^');
+    }
+    for item in &class.items {
+        match *item {
+            RegexClassItem::Char(c) => codegen_char_in_set(c, buf),
+            RegexClassItem::Range { first, last } => codegen_range(first, last, buf),
+            RegexClassItem::Shorthand(RegexShorthand::Word) => buf.push_str("%w_"),
+            RegexClassItem::Shorthand(s) => buf.push_str(shorthand_in_class(s)),
+            RegexClassItem::Property { .. } | RegexClassItem::Nested { .. } => {
+                unreachable!("this class item is rejected when compiling for Lua")
+            }
+        }
+    }
+    buf.push(']');
+}
+
+/// Emits a range in a set. The ends of a range can't be escaped, so escaped
+/// characters at the ends are emitted separately, e.g. `[%-.]` is emitted as
+/// `%-.`, since `%--.` wouldn't be valid.
+fn codegen_range(mut first: char, mut last: char, buf: &mut impl Output) {
+    while first < last && is_special_in_set(first) {
+        codegen_char_in_set(first, buf);
+        first = (first as u8 + 1) as char;
+    }
+    while first < last && is_special_in_set(last) {
+        codegen_char_in_set(last, buf);
+        last = (last as u8 - 1) as char;
+    }
+    if first == last {
+        codegen_char_in_set(first, buf);
+    } else {
+        buf.push(first);
+        buf.push('-');
+        buf.push(last);
+    }
+}
+
+/// Returns the shorthand for the `%` classes that Lua supports. `\w` is
+/// handled by the caller, since `%w` doesn't include the underscore.
+fn shorthand_in_class(shorthand: RegexShorthand) -> &'static str {
+    match shorthand {
+        RegexShorthand::Digit => "%d",
+        RegexShorthand::NotDigit => "%D",
+        RegexShorthand::Space => "%s",
+        RegexShorthand::NotSpace => "%S",
+        _ => unreachable!("this shorthand is rejected when compiling for Lua"),
+    }
+}
+
+fn is_special_in_set(c: char) -> bool {
+    matches!(c, '%' | ']' | '[' | '^' | '-')
+}
+
+fn codegen_char_in_set(c: char, buf: &mut impl Output) {
+    if is_special_in_set(c) {
+        buf.push('%');
+    }
+    buf.push(c);
+}
+
+fn codegen_char(c: char, buf: &mut impl Output) {
+    if matches!(c, '^' | '$' | '(' | ')' | '%' | '.' | '[' | ']' | '*' | '+' | '-' | '?') {
+        buf.push('%');
+    }
+    buf.push(c);
+}
@@ -5,9 +5,12 @@ use crate::{
     boundary::BoundaryKind,
     char_class::{
         unicode::{Category, CodeBlock, OtherProperties, Script},
-        RegexCharClass, RegexClassItem,
+        RegexCharClass, RegexClassIntersection, RegexClassItem,
     },
-    group::RegexGroup,
+    compile::Output,
+    conditional::RegexConditional,
+    error::CompileErrorKind,
+    group::{RegexCapture, RegexGroup},
     literal,
     lookaround::RegexLookaround,
     options::RegexFlavor,
@@ -15,6 +18,13 @@ use crate::{
     repetition::RegexRepetition,
 };
 
+mod antlr;
+mod lua;
+mod prefix;
+mod sed;
+
+pub(crate) use prefix::factor_common_prefixes;
+
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) enum Regex<'i> {
     /// A literal string
@@ -23,12 +33,16 @@ pub(crate) enum Regex<'i> {
     Char(char),
     /// A character class, delimited with square brackets
     CharClass(RegexCharClass),
+    /// An intersection of character classes, e.g. `[[\p{L}]&&[a-z]]`
+    ClassIntersection(RegexClassIntersection),
     /// A shorthand such as `\w`
     Shorthand(RegexShorthand),
     /// A (Unicode) property such as Letter, Greek or Alphabetic
     Property { value: RegexProperty, negative: bool },
     /// A Unicode grapheme
     Grapheme,
+    /// Any Unicode line break, `\R`
+    LineBreak,
     /// The dot, matching anything except `\n`
     Dot,
     /// A group, i.e. a sequence of rules, possibly wrapped in parentheses.
@@ -45,7 +59,15 @@ pub(crate) enum Regex<'i> {
     /// A (positive or negative) lookahead or lookbehind.
     Lookaround(Box<RegexLookaround<'i>>),
     /// A backreference or forward reference.
-    Reference(RegexReference),
+    Reference(RegexReference<'i>),
+    /// A conditional, e.g. `(?(1)a|b)`
+    Conditional(Box<RegexConditional<'i>>),
+    /// A call to a subpattern defined in a `(?(DEFINE)...)` group, e.g.
+    /// `(?&name)`
+    SubroutineCall(Cow<'i, str>),
+    /// A call to the capturing group with this number, which matches its
+    /// pattern recursively, e.g. `(?1)`
+    Recursion(u32),
 }
 
 #[derive(Clone, Copy)]
@@ -59,6 +81,8 @@ pub(crate) enum RegexShorthand {
     NotSpace,
     VertSpace,
     HorizSpace,
+    NotVertSpace,
+    NotHorizSpace,
 }
 
 #[derive(Clone, Copy)]
@@ -66,6 +90,7 @@ pub(crate) enum RegexShorthand {
 pub(crate) enum RegexProperty {
     Category(Category),
     Script(Script),
+    ScriptExtension(Script),
     Block(CodeBlock),
     Other(OtherProperties),
 }
@@ -81,7 +106,14 @@ impl RegexProperty {
 }
 
 impl<'i> Regex<'i> {
-    pub(crate) fn codegen(&self, buf: &mut String, flavor: RegexFlavor) {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        if flavor == RegexFlavor::Antlr {
+            return antlr::codegen(self, buf);
+        }
+        if flavor == RegexFlavor::Lua {
+            return lua::codegen(self, buf);
+        }
+
         match self {
             Regex::Literal(l) => {
                 for c in l.chars() {
@@ -92,16 +124,86 @@ impl<'i> Regex<'i> {
                 literal::codegen_char_esc(c, buf, flavor);
             }
             Regex::CharClass(c) => c.codegen(buf, flavor),
-            Regex::Shorthand(s) => s.codegen(buf),
+            Regex::ClassIntersection(i) => i.codegen(buf, flavor),
+            Regex::Shorthand(s) => s.codegen(buf, flavor),
             Regex::Property { value, negative } => value.codegen(buf, *negative, flavor),
             Regex::Grapheme => buf.push_str("\\X"),
+            Regex::LineBreak => buf.push_str("\\R"),
             Regex::Dot => buf.push('.'),
             Regex::Group(g) => g.codegen(buf, flavor),
             Regex::Alternation(a) => a.codegen(buf, flavor),
             Regex::Repetition(r) => r.codegen(buf, flavor),
-            Regex::Boundary(b) => b.codegen(buf),
+            Regex::Boundary(b) => b.codegen(buf, flavor),
             Regex::Lookaround(l) => l.codegen(buf, flavor),
             Regex::Reference(r) => r.codegen(buf, flavor),
+            Regex::Conditional(c) => c.codegen(buf, flavor),
+            Regex::SubroutineCall(name) => {
+                buf.push_str("(?&");
+                buf.push_str(name);
+                buf.push(')');
+            }
+            &Regex::Recursion(number) => {
+                if flavor == RegexFlavor::Ruby {
+                    write!(buf, "\\g<{number}>").unwrap();
+                } else {
+                    write!(buf, "(?{number})").unwrap();
+                }
+            }
+        }
+    }
+
+    /// Returns an error if the regex can't be matched in
+    /// [byte mode](crate::options::CompileOptions::byte_mode), because it
+    /// contains a Unicode property, a grapheme or a code point above U+FF.
+    pub(crate) fn check_byte_mode(&self) -> Result<(), CompileErrorKind> {
+        match self {
+            Regex::Literal(l) => l.chars().try_for_each(check_byte),
+            &Regex::Char(c) => check_byte(c),
+            Regex::CharClass(c) => c.items.iter().try_for_each(RegexClassItem::check_byte_mode),
+            Regex::ClassIntersection(i) => i
+                .operands
+                .iter()
+                .flat_map(|class| &class.items)
+                .try_for_each(RegexClassItem::check_byte_mode),
+            Regex::Property { .. } => {
+                Err(CompileErrorKind::UnicodeInByteMode("Unicode properties"))
+            }
+            Regex::Grapheme => Err(CompileErrorKind::UnicodeInByteMode("`Grapheme`")),
+            Regex::Group(g) => g.parts.iter().try_for_each(Regex::check_byte_mode),
+            Regex::Alternation(a) => a.parts.iter().try_for_each(Regex::check_byte_mode),
+            Regex::Repetition(r) => r.content.check_byte_mode(),
+            Regex::Lookaround(l) => l.content.check_byte_mode(),
+            Regex::Conditional(c) => {
+                c.then.check_byte_mode()?;
+                c.otherwise.iter().try_for_each(Regex::check_byte_mode)
+            }
+            Regex::Shorthand(_)
+            | Regex::LineBreak
+            | Regex::Dot
+            | Regex::Boundary(_)
+            | Regex::Reference(_)
+            | Regex::SubroutineCall(_)
+            | Regex::Recursion(_) => Ok(()),
+        }
+    }
+
+    /// Returns `true` if this is a boundary or lookaround, possibly wrapped in
+    /// a non-capturing group. These never consume characters, so repeating
+    /// them is pointless.
+    pub(crate) fn is_assertion(&self) -> bool {
+        match self {
+            Regex::Boundary(_) | Regex::Lookaround(_) => true,
+            Regex::Group(g) => match (&g.capture, g.parts.as_slice()) {
+                (
+                    RegexCapture::None
+                    | RegexCapture::NoneWithParens
+                    | RegexCapture::Atomic
+                    | RegexCapture::Flags { .. },
+                    [part],
+                ) => part.is_assertion(),
+                _ => false,
+            },
+            _ => false,
         }
     }
 
@@ -112,11 +214,16 @@ impl<'i> Regex<'i> {
             | Regex::Char(_)
             | Regex::Group(_)
             | Regex::CharClass(_)
+            | Regex::ClassIntersection(_)
             | Regex::Grapheme
+            | Regex::LineBreak
             | Regex::Repetition(_)
             | Regex::Boundary(_)
             | Regex::Lookaround(_)
             | Regex::Reference(_)
+            | Regex::Conditional(_)
+            | Regex::SubroutineCall(_)
+            | Regex::Recursion(_)
             | Regex::Shorthand(_)
             | Regex::Property { .. }
             | Regex::Dot => false,
@@ -129,11 +236,16 @@ impl<'i> Regex<'i> {
             Regex::Group(g) => g.needs_parens_before_repetition(),
             Regex::Repetition(_) | Regex::Alternation(_) => true,
             Regex::CharClass(_)
+            | Regex::ClassIntersection(_)
             | Regex::Char(_)
             | Regex::Grapheme
+            | Regex::LineBreak
             | Regex::Boundary(_)
             | Regex::Lookaround(_)
             | Regex::Reference(_)
+            | Regex::Conditional(_)
+            | Regex::SubroutineCall(_)
+            | Regex::Recursion(_)
             | Regex::Shorthand(_)
             | Regex::Property { .. }
             | Regex::Dot => false,
@@ -141,8 +253,45 @@ impl<'i> Regex<'i> {
     }
 }
 
+fn check_byte(c: char) -> Result<(), CompileErrorKind> {
+    if c as u32 > 0xFF {
+        Err(CompileErrorKind::CodePointInByteMode(c))
+    } else {
+        Ok(())
+    }
+}
+
+impl RegexClassItem {
+    fn check_byte_mode(&self) -> Result<(), CompileErrorKind> {
+        match *self {
+            RegexClassItem::Char(c) => check_byte(c),
+            RegexClassItem::Range { last, .. } => check_byte(last),
+            RegexClassItem::Shorthand(_) => Ok(()),
+            RegexClassItem::Property { .. } => {
+                Err(CompileErrorKind::UnicodeInByteMode("Unicode properties"))
+            }
+            RegexClassItem::Nested { items, .. } => {
+                items.iter().try_for_each(RegexClassItem::check_byte_mode)
+            }
+        }
+    }
+}
+
 impl RegexShorthand {
-    pub(crate) fn codegen(&self, buf: &mut String) {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
+        if let RegexFlavor::Sed { .. } = flavor {
+            // GNU sed supports `\w`, `\W`, `\s` and `\S`, but no other shorthands
+            match self {
+                RegexShorthand::Digit | RegexShorthand::VertSpace | RegexShorthand::HorizSpace => {
+                    buf.push('[');
+                    self.codegen_posix_in_class(buf);
+                    return buf.push(']');
+                }
+                RegexShorthand::NotDigit => return buf.push_str("[^[:digit:]]"),
+                _ => {}
+            }
+        }
+
         match self {
             RegexShorthand::Word => buf.push_str("\\w"),
             RegexShorthand::Digit => buf.push_str("\\d"),
@@ -152,12 +301,34 @@ impl RegexShorthand {
             RegexShorthand::NotSpace => buf.push_str("\\S"),
             RegexShorthand::VertSpace => buf.push_str("\\v"),
             RegexShorthand::HorizSpace => buf.push_str("\\h"),
+            RegexShorthand::NotVertSpace => buf.push_str("\\V"),
+            RegexShorthand::NotHorizSpace => buf.push_str("\\H"),
+        }
+    }
+
+    /// Writes the shorthand within a POSIX bracket expression. Negative
+    /// shorthands are rejected when compiling for sed, because they can't be
+    /// expressed in bracket expressions.
+    pub(crate) fn codegen_posix_in_class(&self, buf: &mut impl Output) {
+        match self {
+            RegexShorthand::Word => buf.push_str("[:alnum:]_"),
+            RegexShorthand::Digit => buf.push_str("[:digit:]"),
+            RegexShorthand::Space => buf.push_str("[:space:]"),
+            RegexShorthand::HorizSpace => buf.push_str("[:blank:]"),
+            RegexShorthand::VertSpace => buf.push_str("\\n\\v\\f\\r"),
+            RegexShorthand::NotWord
+            | RegexShorthand::NotDigit
+            | RegexShorthand::NotSpace
+            | RegexShorthand::NotVertSpace
+            | RegexShorthand::NotHorizSpace => {
+                unreachable!("negative shorthands are rejected in POSIX bracket expressions")
+            }
         }
     }
 }
 
 impl RegexProperty {
-    pub(crate) fn codegen(&self, buf: &mut String, negative: bool, flavor: RegexFlavor) {
+    pub(crate) fn codegen(&self, buf: &mut impl Output, negative: bool, flavor: RegexFlavor) {
         if negative {
             buf.push_str("\\P{");
         } else {
@@ -165,7 +336,17 @@ impl RegexProperty {
         }
         match self {
             RegexProperty::Category(c) => buf.push_str(c.as_str()),
-            RegexProperty::Script(s) => buf.push_str(s.as_str()),
+            RegexProperty::Script(s) => {
+                // JavaScript and ANTLR require the property name for scripts
+                if let RegexFlavor::JavaScript | RegexFlavor::Antlr = flavor {
+                    buf.push_str("Script=");
+                }
+                buf.push_str(s.as_str());
+            }
+            RegexProperty::ScriptExtension(s) => {
+                buf.push_str("scx=");
+                buf.push_str(s.as_str());
+            }
             RegexProperty::Block(b) => match flavor {
                 RegexFlavor::DotNet => {
                     buf.push_str("Is");
@@ -0,0 +1,327 @@
+//! Factors out literal text that all alternatives of an alternation start
+//! with, e.g. `(?P<a>foo)|(?P<b>fob)` becomes `fo(?:(?P<a>o)|(?P<b>b))`. This
+//! is used by [`Expr::compile_shared_prefix`](crate::Expr::compile_shared_prefix).
+//!
+//! This module also contains [`factor_common_prefixes`], which factors out
+//! prefixes of adjacent alternatives without changing the captured text. It
+//! is used with [`OptimizationLevel::Full`](crate::options::OptimizationLevel::Full).
+
+use std::borrow::Cow;
+
+use crate::{
+    alternation::RegexAlternation,
+    group::{RegexCapture, RegexGroup},
+    options::RegexFlavor,
+    repetition::{RegexQuantifier, RegexRepetition, RepetitionKind},
+};
+
+use super::Regex;
+
+impl<'i> Regex<'i> {
+    /// Moves the literal prefix shared by all alternatives in front of the
+    /// alternation. The alternatives must be groups; the prefix is removed
+    /// from the contents of each group.
+    pub(crate) fn factor_shared_prefix(self) -> Self {
+        let mut branches = match self {
+            Regex::Alternation(alt) => alt.parts,
+            regex => vec![regex],
+        };
+
+        let mut prefix: Option<String> = None;
+        for branch in &branches {
+            let mut buf = String::new();
+            if let Regex::Group(g) = branch {
+                literal_prefix(&g.parts, &mut buf);
+            }
+            prefix = Some(match prefix {
+                Some(prefix) => {
+                    let len = prefix
+                        .char_indices()
+                        .zip(buf.chars())
+                        .find(|&((_, a), b)| a != b)
+                        .map_or(prefix.len().min(buf.len()), |((i, _), _)| i);
+                    prefix[..len].to_string()
+                }
+                None => buf,
+            });
+        }
+        let prefix = prefix.unwrap_or_default();
+
+        if !prefix.is_empty() {
+            for branch in &mut branches {
+                if let Regex::Group(g) = branch {
+                    strip_prefix(&mut g.parts, prefix.len());
+                }
+            }
+        }
+
+        let rest = if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Regex::Alternation(RegexAlternation::new(branches))
+        };
+        if prefix.is_empty() {
+            rest
+        } else {
+            Regex::Group(RegexGroup::new(
+                vec![Regex::Literal(Cow::Owned(prefix)), rest],
+                RegexCapture::None,
+            ))
+        }
+    }
+}
+
+/// Appends the literal text at the start of a sequence to `buf`. Returns
+/// `true` if the whole sequence is literal text.
+fn literal_prefix(parts: &[Regex<'_>], buf: &mut String) -> bool {
+    parts.iter().all(|part| match part {
+        Regex::Literal(l) => {
+            buf.push_str(l);
+            true
+        }
+        &Regex::Char(c) => {
+            buf.push(c);
+            true
+        }
+        Regex::Group(g) if matches!(g.capture, RegexCapture::None) => literal_prefix(&g.parts, buf),
+        _ => false,
+    })
+}
+
+/// Removes `len` bytes of literal text from the start of a sequence. Returns
+/// the number of bytes that couldn't be removed.
+fn strip_prefix(parts: &mut [Regex<'_>], mut len: usize) -> usize {
+    for part in parts {
+        if len == 0 {
+            break;
+        }
+        match part {
+            Regex::Literal(l) => {
+                let n = len.min(l.len());
+                match l {
+                    Cow::Borrowed(s) => *s = &s[n..],
+                    Cow::Owned(s) => {
+                        s.drain(..n);
+                    }
+                }
+                len -= n;
+            }
+            &mut Regex::Char(c) => {
+                *part = Regex::Literal(Cow::Borrowed(""));
+                len -= c.len_utf8();
+            }
+            Regex::Group(g) if matches!(g.capture, RegexCapture::None) => {
+                len = strip_prefix(&mut g.parts, len);
+            }
+            _ => break,
+        }
+    }
+    len
+}
+
+/// Factors out prefixes that adjacent alternatives have in common, e.g.
+/// `abc|abd|x` becomes `ab(?:c|d)|x`. The prefix can consist of characters
+/// and of items that match a single character, such as character classes.
+/// This is done recursively, so the alternatives are turned into a trie.
+///
+/// Only adjacent alternatives are merged, so the order in which the
+/// alternatives are tried doesn't change. Capturing groups are never part of
+/// a prefix, so they keep their numbers.
+///
+/// A prefix is only factored out if at most the last alternative is left
+/// empty; in that case, the remaining alternatives are made optional, e.g.
+/// `ab|a` becomes `ab?`.
+pub(crate) fn factor_common_prefixes(
+    branches: Vec<Regex<'_>>,
+    flavor: RegexFlavor,
+) -> Vec<Regex<'_>> {
+    let prefixes: Vec<_> = branches.iter().map(BranchPrefix::new).collect();
+
+    // the ranges of adjacent alternatives that share a prefix, and the prefix length
+    let mut runs = vec![];
+    let mut start = 0;
+    while start < branches.len() {
+        let first = &prefixes[start];
+        let mut end = start + 1;
+        while end < branches.len()
+            && matches!((first.atoms.first(), prefixes[end].atoms.first()),
+                (Some(a), Some(b)) if a.same_as(b, flavor))
+        {
+            end += 1;
+        }
+
+        if end - start > 1 {
+            let mut len = prefixes[start + 1..end]
+                .iter()
+                .map(|other| first.common_len(other, flavor))
+                .min()
+                .unwrap_or(0);
+            // only the last alternative may become empty
+            for p in &prefixes[start..end - 1] {
+                len = len.min(p.total_len - 1);
+            }
+            len = len.min(prefixes[end - 1].total_len);
+            if len > 0 {
+                runs.push((start, end, len));
+            }
+        }
+        start = end;
+    }
+
+    if runs.is_empty() {
+        return branches;
+    }
+
+    let mut result = Vec::with_capacity(branches.len());
+    let mut branches = branches.into_iter().enumerate().peekable();
+    for (start, end, len) in runs {
+        while let Some((_, branch)) = branches.next_if(|&(i, _)| i < start) {
+            result.push(branch);
+        }
+
+        let mut prefix = vec![];
+        let mut suffixes = vec![];
+        for (_, branch) in branches.by_ref().take(end - start) {
+            let mut parts = vec![];
+            flatten(branch, &mut parts);
+            let suffix = parts.split_off(len);
+            if prefix.is_empty() {
+                prefix = parts;
+            }
+            suffixes.push(suffix);
+        }
+
+        // only the last suffix can be empty
+        let optional = suffixes.last().is_some_and(Vec::is_empty);
+        if optional {
+            suffixes.pop();
+        }
+        let suffixes = suffixes.into_iter().map(sequence).collect();
+        let mut rest = alternation(factor_common_prefixes(suffixes, flavor));
+        if optional {
+            rest = Regex::Repetition(Box::new(RegexRepetition::new(
+                rest,
+                RepetitionKind::zero_one(),
+                RegexQuantifier::Greedy,
+            )));
+        }
+
+        prefix.push(rest);
+        result.push(sequence(prefix));
+    }
+    result.extend(branches.map(|(_, branch)| branch));
+    result
+}
+
+/// The start of an alternative that could be part of a common prefix
+struct BranchPrefix<'a, 'i> {
+    atoms: Vec<Atom<'a, 'i>>,
+    /// The number of parts when the alternative is flattened with [`flatten`]
+    total_len: usize,
+}
+
+enum Atom<'a, 'i> {
+    Char(char),
+    Item(&'a Regex<'i>),
+}
+
+impl<'a, 'i> BranchPrefix<'a, 'i> {
+    fn new(branch: &'a Regex<'i>) -> Self {
+        let mut prefix = BranchPrefix { atoms: vec![], total_len: 0 };
+        prefix.push(branch, true);
+        prefix
+    }
+
+    /// Adds the atoms of a regex as long as `comparable` is true. Returns
+    /// whether the following atoms are still comparable.
+    fn push(&mut self, regex: &'a Regex<'i>, mut comparable: bool) -> bool {
+        match regex {
+            Regex::Literal(l) => {
+                for c in l.chars() {
+                    if comparable {
+                        self.atoms.push(Atom::Char(c));
+                    }
+                    self.total_len += 1;
+                }
+            }
+            Regex::Group(g) if matches!(g.capture, RegexCapture::None) => {
+                for part in &g.parts {
+                    comparable = self.push(part, comparable);
+                }
+            }
+            _ => {
+                if comparable {
+                    match regex {
+                        &Regex::Char(c) => self.atoms.push(Atom::Char(c)),
+                        Regex::CharClass(_)
+                        | Regex::Shorthand(_)
+                        | Regex::Property { .. }
+                        | Regex::Dot
+                        | Regex::Boundary(_) => self.atoms.push(Atom::Item(regex)),
+                        _ => comparable = false,
+                    }
+                }
+                self.total_len += 1;
+            }
+        }
+        comparable
+    }
+
+    fn common_len(&self, other: &Self, flavor: RegexFlavor) -> usize {
+        self.atoms.iter().zip(&other.atoms).take_while(|(a, b)| a.same_as(b, flavor)).count()
+    }
+}
+
+impl Atom<'_, '_> {
+    fn same_as(&self, other: &Self, flavor: RegexFlavor) -> bool {
+        match (self, other) {
+            (Atom::Char(a), Atom::Char(b)) => a == b,
+            (Atom::Item(a), Atom::Item(b)) => {
+                let (mut a_buf, mut b_buf) = (String::new(), String::new());
+                a.codegen(&mut a_buf, flavor);
+                b.codegen(&mut b_buf, flavor);
+                a_buf == b_buf
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Flattens non-capturing groups and splits literals into characters, so
+/// that the result corresponds to the atoms of a [`BranchPrefix`]
+fn flatten<'i>(regex: Regex<'i>, parts: &mut Vec<Regex<'i>>) {
+    match regex {
+        Regex::Literal(l) => parts.extend(l.chars().map(Regex::Char)),
+        Regex::Group(g) if matches!(g.capture, RegexCapture::None) => {
+            for part in g.parts {
+                flatten(part, parts);
+            }
+        }
+        regex => parts.push(regex),
+    }
+}
+
+/// Creates a sequence, merging adjacent characters into literals
+fn sequence(parts: Vec<Regex<'_>>) -> Regex<'_> {
+    let mut merged = Vec::with_capacity(parts.len());
+    for part in parts {
+        match (merged.last_mut(), part) {
+            (Some(Regex::Literal(l)), Regex::Char(c)) => l.to_mut().push(c),
+            (_, Regex::Char(c)) => merged.push(Regex::Literal(Cow::Owned(c.to_string()))),
+            (_, part) => merged.push(part),
+        }
+    }
+    match merged.len() {
+        0 => Regex::Literal(Cow::Borrowed("")),
+        1 => merged.pop().unwrap(),
+        _ => Regex::Group(RegexGroup::new(merged, RegexCapture::None)),
+    }
+}
+
+fn alternation(mut branches: Vec<Regex<'_>>) -> Regex<'_> {
+    if branches.len() == 1 {
+        branches.pop().unwrap()
+    } else {
+        Regex::Alternation(RegexAlternation::new(branches))
+    }
+}
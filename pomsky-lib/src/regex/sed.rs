@@ -0,0 +1,90 @@
+//! Numbers the groups of regexes emitted for GNU `sed`. POSIX regular
+//! expressions have no non-capturing groups, so every group is emitted as a
+//! capturing group, e.g. `('a'|'b') :('c')` becomes `\(a\|b\)\(c\)`. This
+//! shifts the numbers of the capturing groups after it, so backreferences
+//! must be adjusted before the code is generated.
+
+use std::borrow::Cow;
+
+use crate::{error::CompileErrorKind, group::RegexCapture};
+
+use super::Regex;
+
+impl Regex<'_> {
+    /// Changes the numbers of backreferences to the numbers of the referenced
+    /// groups in the emitted regex. Returns an error if a backreference points
+    /// to a group after the 9th, since sed only supports `\1` to `\9`.
+    ///
+    /// This must match where [`Regex::codegen`] emits parentheses.
+    pub(crate) fn renumber_sed_references(&mut self) -> Result<(), CompileErrorKind> {
+        SedGroups::default().visit(self)
+    }
+}
+
+#[derive(Default)]
+struct SedGroups {
+    /// The number of groups opened so far
+    count: u32,
+    /// The number of each capturing group in the emitted regex, in the order
+    /// in which they are opened
+    captures: Vec<u32>,
+}
+
+impl SedGroups {
+    fn visit(&mut self, regex: &mut Regex<'_>) -> Result<(), CompileErrorKind> {
+        match regex {
+            Regex::Group(group) => match group.capture {
+                RegexCapture::Capture | RegexCapture::NamedCapture(_) => {
+                    self.count += 1;
+                    self.captures.push(self.count);
+                    group.parts.iter_mut().try_for_each(|part| self.visit(part))
+                }
+                RegexCapture::None => group.parts.iter_mut().try_for_each(|part| {
+                    self.count += u32::from(part.needs_parens_in_group());
+                    self.visit(part)
+                }),
+                RegexCapture::NoneWithParens => group.parts.iter_mut().try_for_each(|part| {
+                    self.count += 1;
+                    self.visit(part)
+                }),
+                _ => group.parts.iter_mut().try_for_each(|part| self.visit(part)),
+            },
+            Regex::Alternation(alt) => alt.parts.iter_mut().try_for_each(|part| self.visit(part)),
+            Regex::Repetition(rep) => {
+                if let Regex::Literal(Cow::Borrowed("")) = rep.content {
+                    return Ok(());
+                }
+                self.count += u32::from(rep.content.needs_parens_before_repetition());
+                self.visit(&mut rep.content)
+            }
+            Regex::Lookaround(look) => self.visit(&mut look.content),
+            Regex::Reference(reference) => {
+                // forward references are rejected, so the group was already opened
+                let number = self.captures[reference.number as usize - 1];
+                if number > 9 {
+                    return Err(CompileErrorKind::Other(
+                        "sed only supports backreferences to the first 9 groups, \
+                        including groups emitted for non-capturing groups",
+                    ));
+                }
+                reference.number = number;
+                Ok(())
+            }
+            Regex::Conditional(_) => {
+                unreachable!("conditionals are rejected when compiling for sed")
+            }
+            Regex::Literal(_)
+            | Regex::Char(_)
+            | Regex::CharClass(_)
+            | Regex::ClassIntersection(_)
+            | Regex::Shorthand(_)
+            | Regex::Property { .. }
+            | Regex::Grapheme
+            | Regex::LineBreak
+            | Regex::Dot
+            | Regex::Boundary(_)
+            | Regex::SubroutineCall(_)
+            | Regex::Recursion(_) => Ok(()),
+        }
+    }
+}
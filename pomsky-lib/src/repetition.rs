@@ -1,20 +1,23 @@
 use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
-    compile::{CompileResult, CompileState},
-    error::{CompileError, ParseError},
-    group::{RegexCapture, RegexGroup},
-    options::{CompileOptions, ParseOptions, RegexFlavor},
+    captures::CaptureInfo,
+    compile::{CompileResult, CompileState, NodeCounts, Output},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
+    group::{codegen_close_paren, codegen_open_paren, RegexCapture, RegexGroup},
+    optimization::OptimizationKind,
+    options::{CompileOptions, OptimizationLevel, ParseOptions, RegexFlavor},
     regex::Regex,
     rule::Rule,
     span::Span,
+    warning::{RepetitionWarning, Warning, WarningKind},
 };
 
 #[derive(Clone)]
 pub(crate) struct Repetition<'i> {
     pub(crate) rule: Rule<'i>,
-    kind: RepetitionKind,
-    quantifier: Quantifier,
+    pub(crate) kind: RepetitionKind,
+    pub(crate) quantifier: Quantifier,
     pub(crate) span: Span,
 }
 
@@ -37,13 +40,74 @@ impl<'i> Repetition<'i> {
         self.rule.get_capturing_groups(count, map, within_variable)
     }
 
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        self.rule.capture_info(info, optional || self.kind.lower_bound == 0);
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        if let Some(inner) = self.nested_unbounded_repetition() {
+            warnings.push(WarningKind::Repetition(RepetitionWarning::NestedRepetition).at(inner));
+        }
+
+        let repeats = !matches!(self.kind.upper_bound, Some(0 | 1));
+        self.rule.lint(options, warnings, in_repetition || repeats);
+    }
+
+    /// If this repetition is unbounded and directly contains another unbounded
+    /// repetition, e.g. `('a'+)*`, returns the span of the inner repetition.
+    /// A single non-capturing group is looked through, but capturing groups
+    /// aren't, since removing them changes what is captured. Possessive inner
+    /// repetitions are ignored, because they can't backtrack.
+    fn nested_unbounded_repetition(&self) -> Option<Span> {
+        if self.kind.upper_bound.is_some() {
+            return None;
+        }
+        let inner = match &self.rule {
+            Rule::Group(g) if !g.is_capturing() && g.parts.len() == 1 => &g.parts[0],
+            rule => rule,
+        };
+        match inner {
+            Rule::Repetition(r)
+                if r.kind.upper_bound.is_none() && r.quantifier != Quantifier::Possessive =>
+            {
+                Some(r.span)
+            }
+            _ => None,
+        }
+    }
+
     pub(crate) fn compile<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
+        let next_idx = state.next_idx;
         let mut content = self.rule.comp(options, state)?;
 
+        if content.is_assertion() && !matches!(self.kind.upper_bound, Some(0 | 1)) {
+            return Err(CompileErrorKind::RepeatedAssertion.at(self.span));
+        }
+
+        // an optional zero-width expression always matches the empty string;
+        // it can only be removed if it doesn't capture anything
+        if options.optimization_level == OptimizationLevel::Full
+            && self.kind.lower_bound == 0
+            && state.next_idx == next_idx
+            && self.rule.is_zero_width()
+        {
+            state.optimizations.record(OptimizationKind::RemovedOptionalAssertion, self.span);
+            return Ok(Regex::Literal(Cow::Borrowed("")));
+        }
+
         if let RepetitionKind { lower_bound: 0, upper_bound: Some(1) } = self.kind {
             if let Rule::Repetition(_) = &self.rule {
                 content =
@@ -54,12 +118,43 @@ impl<'i> Repetition<'i> {
         let quantifier = match self.quantifier {
             Quantifier::Greedy => RegexQuantifier::Greedy,
             Quantifier::Lazy => RegexQuantifier::Lazy,
+            Quantifier::Possessive => RegexQuantifier::Possessive,
             Quantifier::Default => state.default_quantifier,
         };
 
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
+        if let (RegexQuantifier::Lazy, RegexFlavor::Sed { .. }) = (quantifier, options.flavor) {
+            if Some(self.kind.lower_bound) != self.kind.upper_bound {
+                return Err(CompileErrorKind::Unsupported(Feature::LazyQuantifier, options.flavor)
+                    .at(self.span));
+            }
+        }
+
         Ok(Regex::Repetition(Box::new(RegexRepetition { content, kind: self.kind, quantifier })))
     }
 
+    /// Returns a feature of the repetition that the flavor doesn't support.
+    /// Lazy quantifiers are checked when compiling, since they can be enabled
+    /// with `enable lazy;`.
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        if self.quantifier == Quantifier::Possessive
+            && !matches!(flavor, RegexFlavor::Pcre | RegexFlavor::Java | RegexFlavor::Ruby)
+        {
+            return Some(Feature::PossessiveQuantifier);
+        }
+
+        // RE2 rejects repetitions above 1000 to keep the compiled program small
+        if flavor == RegexFlavor::Re2
+            && self.kind.upper_bound.unwrap_or(0).max(self.kind.lower_bound) > 1000
+        {
+            return Some(Feature::LargeRepetition);
+        }
+        None
+    }
+
     pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
         self.rule.validate(options)
     }
@@ -80,6 +175,7 @@ impl core::fmt::Debug for Repetition<'_> {
         match self.quantifier {
             Quantifier::Greedy => write!(f, " greedy")?,
             Quantifier::Lazy => write!(f, " lazy")?,
+            Quantifier::Possessive => write!(f, " possessive")?,
             Quantifier::Default => {}
         }
         Ok(())
@@ -92,6 +188,8 @@ impl core::fmt::Debug for Repetition<'_> {
 pub(crate) enum Quantifier {
     Greedy,
     Lazy,
+    /// Like greedy, but never gives up characters it matched, e.g. `x++`
+    Possessive,
     Default,
 }
 
@@ -106,10 +204,10 @@ pub(crate) enum Quantifier {
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct RepetitionKind {
     /// The lower bound, e.g. `{4,}`
-    lower_bound: u32,
+    pub(crate) lower_bound: u32,
 
     /// The upper bound, e.g. `{0,7}`. `None` means infinity.
-    upper_bound: Option<u32>,
+    pub(crate) upper_bound: Option<u32>,
 }
 
 impl RepetitionKind {
@@ -130,20 +228,35 @@ impl RepetitionKind {
     }
 }
 
+/// An error that relates to a repetition
 #[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
 pub enum RepetitionError {
-    #[error("Lower bound can't be greater than the upper bound")]
-    NotAscending,
+    /// The lower bound is greater than the upper bound, e.g. `{5,3}`
+    #[error("Lower bound {} can't be greater than the upper bound {}", .lower, .upper)]
+    NotAscending {
+        /// The lower bound
+        lower: u32,
+        /// The upper bound
+        upper: u32,
+    },
+    /// A question mark after a repetition, e.g. `'a'+?`
     #[error("Unexpected `?` following a repetition")]
     QuestionMarkAfterRepetition,
+    /// A `*` or `+` directly after a `*` or `+` repetition, e.g. `'a'**`
+    #[error("Unexpected `*` or `+` following a repetition")]
+    DoubleQuantifier,
 }
 
 impl TryFrom<(u32, Option<u32>)> for RepetitionKind {
     type Error = RepetitionError;
 
     fn try_from((lower_bound, upper_bound): (u32, Option<u32>)) -> Result<Self, Self::Error> {
-        if lower_bound > upper_bound.unwrap_or(u32::MAX) {
-            return Err(RepetitionError::NotAscending);
+        match upper_bound {
+            Some(upper) if lower_bound > upper => {
+                return Err(RepetitionError::NotAscending { lower: lower_bound, upper });
+            }
+            _ => {}
         }
 
         Ok(RepetitionKind { lower_bound, upper_bound })
@@ -152,15 +265,16 @@ impl TryFrom<(u32, Option<u32>)> for RepetitionKind {
 
 #[cfg_attr(feature = "dbg", derive(Debug))]
 pub(crate) struct RegexRepetition<'i> {
-    content: Regex<'i>,
-    kind: RepetitionKind,
-    quantifier: RegexQuantifier,
+    pub(crate) content: Regex<'i>,
+    pub(crate) kind: RepetitionKind,
+    pub(crate) quantifier: RegexQuantifier,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum RegexQuantifier {
     Greedy,
     Lazy,
+    Possessive,
 }
 
 impl<'i> RegexRepetition<'i> {
@@ -172,25 +286,28 @@ impl<'i> RegexRepetition<'i> {
         Self { content, kind, quantifier }
     }
 
-    pub(crate) fn codegen(&self, buf: &mut String, flavor: RegexFlavor) {
-        use std::fmt::Write;
-
+    pub(crate) fn codegen(&self, buf: &mut impl Output, flavor: RegexFlavor) {
         if let Regex::Literal(Cow::Borrowed("")) = self.content {
             return;
         }
 
         if self.content.needs_parens_before_repetition() {
-            buf.push_str("(?:");
+            codegen_open_paren(buf, false, flavor);
             self.content.codegen(buf, flavor);
-            buf.push(')');
+            codegen_close_paren(buf, flavor);
         } else {
             self.content.codegen(buf, flavor);
         }
 
-        let omit_lazy = match self.kind {
+        // In POSIX basic regular expressions, all quantifiers except `*` must be escaped
+        let esc = if let RegexFlavor::Sed { extended: false } = flavor { "\\" } else { "" };
+
+        // a quantifier with a fixed number of repetitions can't backtrack, so
+        // it doesn't need a lazy or possessive modifier
+        let omit_modifier = match self.kind {
             RepetitionKind { lower_bound: 1, upper_bound: Some(1) } => return,
             RepetitionKind { lower_bound: 0, upper_bound: Some(1) } => {
-                buf.push('?');
+                write!(buf, "{esc}?").unwrap();
                 false
             }
             RepetitionKind { lower_bound: 0, upper_bound: None } => {
@@ -198,32 +315,34 @@ impl<'i> RegexRepetition<'i> {
                 false
             }
             RepetitionKind { lower_bound: 1, upper_bound: None } => {
-                buf.push('+');
+                write!(buf, "{esc}+").unwrap();
                 false
             }
             RepetitionKind { lower_bound, upper_bound: None } => {
-                write!(buf, "{{{lower_bound},}}").unwrap();
+                write!(buf, "{esc}{{{lower_bound},{esc}}}").unwrap();
                 false
             }
             RepetitionKind { lower_bound, upper_bound: Some(upper_bound) }
                 if lower_bound == upper_bound =>
             {
-                write!(buf, "{{{lower_bound}}}").unwrap();
+                write!(buf, "{esc}{{{lower_bound}{esc}}}").unwrap();
                 true
             }
             RepetitionKind { lower_bound: 0, upper_bound: Some(upper_bound) } => {
-                write!(buf, "{{0,{upper_bound}}}").unwrap();
+                write!(buf, "{esc}{{0,{upper_bound}{esc}}}").unwrap();
                 false
             }
             RepetitionKind { lower_bound, upper_bound: Some(upper_bound) } => {
-                write!(buf, "{{{lower_bound},{upper_bound}}}").unwrap();
+                write!(buf, "{esc}{{{lower_bound},{upper_bound}{esc}}}").unwrap();
                 false
             }
         };
 
-        if let RegexQuantifier::Lazy = self.quantifier {
-            if !omit_lazy {
-                buf.push('?');
+        if !omit_modifier {
+            match self.quantifier {
+                RegexQuantifier::Greedy => {}
+                RegexQuantifier::Lazy => buf.push('?'),
+                RegexQuantifier::Possessive => buf.push('+'),
             }
         }
     }
@@ -0,0 +1,168 @@
+//! Implements branch reset groups, `reset('a' | 'b')`, where the capturing
+//! groups in each alternative are numbered starting at the same number.
+
+use std::collections::HashMap;
+
+use crate::{
+    alternation::RegexAlternation,
+    captures::{CaptureGroup, CaptureInfo},
+    compile::{CompileResult, CompileState, NodeCounts},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
+    group::{RegexCapture, RegexGroup},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
+    regex::Regex,
+    rule::Rule,
+    span::Span,
+    warning::Warning,
+};
+
+/// A branch reset group, `reset('a' | 'b')`. It is compiled to `(?|a|b)`,
+/// which is only supported by PCRE.
+///
+/// The capturing groups in each alternative are numbered as if the other
+/// alternatives didn't exist, so e.g. in `reset(:('a') | :('b') :('c'))`, the
+/// groups matching `a` and `b` both have number 1. Groups after the branch
+/// reset group are numbered starting after the highest number used in any
+/// alternative.
+#[derive(Clone)]
+pub(crate) struct BranchReset<'i> {
+    pub(crate) branches: Vec<Rule<'i>>,
+    pub(crate) span: Span,
+}
+
+impl<'i> BranchReset<'i> {
+    pub(crate) fn new(branches: Vec<Rule<'i>>, span: Span) -> Self {
+        BranchReset { branches, span }
+    }
+
+    pub(crate) fn get_capturing_groups(
+        &self,
+        count: &mut u32,
+        map: &mut HashMap<String, u32>,
+        within_variable: bool,
+    ) -> Result<(), CompileError> {
+        let start = *count;
+        let mut end = start;
+        let mut names = HashMap::new();
+
+        for branch in &self.branches {
+            *count = start;
+            let mut branch_map = map.clone();
+            branch.get_capturing_groups(count, &mut branch_map, within_variable)?;
+            end = end.max(*count);
+
+            // a name can be used in several alternatives if it has the same number in each
+            for (name, index) in branch_map {
+                if map.contains_key(&name) {
+                    continue;
+                }
+                match names.get(&name) {
+                    Some(&other) if other != index => {
+                        return Err(CompileErrorKind::NameUsedMultipleTimes(name).at(self.span));
+                    }
+                    Some(_) => {}
+                    None if names.values().any(|&other| other == index) => {
+                        return Err(CompileErrorKind::Other(
+                            "Groups with the same number in `reset(...)` must have the same name",
+                        )
+                        .at(self.span));
+                    }
+                    None => {
+                        names.insert(name, index);
+                    }
+                }
+            }
+        }
+
+        *count = end;
+        map.extend(names);
+        Ok(())
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        counts.alternation_branches += self.branches.len() as u64;
+        for branch in &self.branches {
+            branch.count_nodes(counts);
+        }
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        let optional = optional || self.branches.len() > 1;
+        let start = info.groups.len();
+        let mut merged: Vec<CaptureGroup> = vec![];
+
+        for branch in &self.branches {
+            branch.capture_info(info, optional);
+            for (i, group) in info.groups.split_off(start).into_iter().enumerate() {
+                match merged.get_mut(i) {
+                    Some(existing) => {
+                        existing.name = existing.name.take().or(group.name);
+                        existing.optional |= group.optional;
+                    }
+                    None => merged.push(group),
+                }
+            }
+        }
+        info.groups.extend(merged);
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        for branch in &self.branches {
+            branch.lint(options, warnings, in_repetition);
+        }
+    }
+
+    pub(crate) fn compile<'c>(
+        &'c self,
+        options: &CompileOptions,
+        state: &mut CompileState<'c, 'i>,
+    ) -> CompileResult<'i> {
+        if let Some(feature) = self.unsupported_feature(options.flavor) {
+            return Err(CompileErrorKind::Unsupported(feature, options.flavor).at(self.span));
+        }
+
+        let start = state.next_idx;
+        let mut end = start;
+        let mut parts = Vec::with_capacity(self.branches.len());
+        for branch in &self.branches {
+            state.next_idx = start;
+            parts.push(branch.comp(options, state)?);
+            end = end.max(state.next_idx);
+        }
+        state.next_idx = end;
+
+        let alternation = Regex::Alternation(RegexAlternation::new(parts));
+        Ok(Regex::Group(RegexGroup::new(vec![alternation], RegexCapture::BranchReset)))
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match flavor {
+            RegexFlavor::Pcre => None,
+            _ => Some(Feature::BranchReset),
+        }
+    }
+
+    pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
+        for branch in &self.branches {
+            branch.validate(options)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for BranchReset<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut d = f.debug_tuple("BranchReset");
+        let mut d = &mut d;
+        for branch in &self.branches {
+            d = d.field(branch);
+        }
+        d.finish()
+    }
+}
@@ -1,22 +1,33 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
     alternation::Alternation,
+    atomic::Atomic,
+    balanced::Balanced,
     boundary::Boundary,
+    capture_each::CaptureEach,
+    captures::CaptureInfo,
     char_class::CharClass,
-    compile::{CompileResult, CompileState},
+    compile::{CompileResult, CompileState, NodeCounts},
+    conditional::Conditional,
     error::{CompileError, CompileErrorKind, ParseError, ParseErrorKind},
+    flags::FlagGroup,
     grapheme::Grapheme,
     group::Group,
+    line_break::LineBreak,
     literal::Literal,
     lookaround::Lookaround,
-    options::{CompileOptions, ParseOptions},
+    one_of::OneOf,
+    options::{CompileOptions, ParseOptions, RegexFlavor},
     range::Range,
     reference::Reference,
     repetition::Repetition,
+    reset::BranchReset,
     span::Span,
-    stmt::StmtExpr,
+    stmt::{Stmt, StmtExpr},
     var::Variable,
+    warning::Warning,
+    word::Word,
 };
 
 /// A parsed pomsky expression, which might contain more sub-expressions.
@@ -29,6 +40,8 @@ pub(crate) enum Rule<'i> {
     CharClass(CharClass),
     /// A Unicode grapheme
     Grapheme(Grapheme),
+    /// Any Unicode line break
+    LineBreak(LineBreak),
     /// A group, i.e. a sequence of rules, possibly wrapped in parentheses.
     Group(Group<'i>),
     /// An alternation, i.e. a list of alternatives; at least one of them has to
@@ -50,6 +63,22 @@ pub(crate) enum Rule<'i> {
     Range(Range),
     /// An expression preceded by a modifier such as `enable lazy;`
     StmtExpr(Box<StmtExpr<'i>>),
+    /// An expression that is matched as a whole word, `word(...)`
+    Word(Box<Word<'i>>),
+    /// A branch reset group, `reset(...)`
+    BranchReset(BranchReset<'i>),
+    /// Text enclosed in balanced delimiters, `balanced(...)`
+    Balanced(Balanced),
+    /// Any of a list of strings, `oneOf(...)`
+    OneOf(OneOf<'i>),
+    /// Capturing groups that are intended to be repeated, `captureEach(...)`
+    CaptureEach(Box<CaptureEach<'i>>),
+    /// An atomic group, `atomic(...)`
+    Atomic(Box<Atomic<'i>>),
+    /// A conditional, `if ::group { ... } else { ... }`
+    Conditional(Box<Conditional<'i>>),
+    /// A block with inline flags, `enable case_insensitive { ... }`
+    Flags(Box<FlagGroup<'i>>),
 }
 
 impl<'i> Rule<'i> {
@@ -57,7 +86,7 @@ impl<'i> Rule<'i> {
         match self {
             Rule::Literal(l) => l.span,
             Rule::CharClass(c) => c.span,
-            Rule::Grapheme(_) => Span::empty(),
+            Rule::Grapheme(_) | Rule::LineBreak(_) => Span::empty(),
             Rule::Group(g) => g.span,
             Rule::Alternation(a) => a.span,
             Rule::Repetition(r) => r.span,
@@ -67,6 +96,42 @@ impl<'i> Rule<'i> {
             Rule::Reference(r) => r.span,
             Rule::Range(r) => r.span,
             Rule::StmtExpr(m) => m.span,
+            Rule::Word(w) => w.span,
+            Rule::BranchReset(b) => b.span,
+            Rule::Balanced(b) => b.span,
+            Rule::OneOf(o) => o.span,
+            Rule::CaptureEach(c) => c.span,
+            Rule::Atomic(a) => a.span,
+            Rule::Conditional(c) => c.span,
+            Rule::Flags(f) => f.span,
+        }
+    }
+
+    /// Returns `true` if this rule never consumes any characters, e.g. a
+    /// boundary, a lookaround or an empty string. This is conservative: It
+    /// returns `false` when unsure, e.g. for variables and references.
+    pub(crate) fn is_zero_width(&self) -> bool {
+        match self {
+            Rule::Literal(l) => l.content.is_empty(),
+            Rule::Boundary(_) | Rule::Lookaround(_) => true,
+            Rule::Group(g) => g.parts.iter().all(Rule::is_zero_width),
+            Rule::Alternation(a) => a.rules.iter().all(Rule::is_zero_width),
+            Rule::Repetition(r) => r.kind.upper_bound == Some(0) || r.rule.is_zero_width(),
+            Rule::StmtExpr(s) => s.rule.is_zero_width(),
+            Rule::Word(w) => w.rule.is_zero_width(),
+            Rule::Atomic(a) => a.rule.is_zero_width(),
+            Rule::Flags(f) => f.rule.is_zero_width(),
+            Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Variable(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::BranchReset(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_)
+            | Rule::CaptureEach(_)
+            | Rule::Conditional(_) => false,
         }
     }
 
@@ -74,12 +139,21 @@ impl<'i> Rule<'i> {
         match self {
             Rule::Literal(_)
             | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
             | Rule::Group(_)
             | Rule::Alternation(_)
             | Rule::Variable(_)
             | Rule::Reference(_)
             | Rule::Range(_)
-            | Rule::StmtExpr(_) => Err(ParseErrorKind::UnallowedNot),
+            | Rule::StmtExpr(_)
+            | Rule::Word(_)
+            | Rule::BranchReset(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_)
+            | Rule::CaptureEach(_)
+            | Rule::Atomic(_)
+            | Rule::Conditional(_)
+            | Rule::Flags(_) => Err(ParseErrorKind::UnallowedNot),
 
             Rule::CharClass(c) => c.negate(),
             Rule::Repetition(r) => r.rule.negate(),
@@ -97,7 +171,7 @@ impl<'i> Rule<'i> {
         match self {
             Rule::Literal(_) => {}
             Rule::CharClass(_) => {}
-            Rule::Grapheme(_) => {}
+            Rule::Grapheme(_) | Rule::LineBreak(_) => {}
             Rule::Group(g) => g.get_capturing_groups(count, map, within_variable)?,
             Rule::Alternation(a) => a.get_capturing_groups(count, map, within_variable)?,
             Rule::Repetition(r) => r.get_capturing_groups(count, map, within_variable)?,
@@ -111,23 +185,85 @@ impl<'i> Rule<'i> {
             }
             Rule::Range(_) => {}
             Rule::StmtExpr(m) => m.get_capturing_groups(count, map, within_variable)?,
+            Rule::Word(w) => w.get_capturing_groups(count, map, within_variable)?,
+            Rule::BranchReset(b) => b.get_capturing_groups(count, map, within_variable)?,
+            Rule::Balanced(_) => {}
+            Rule::OneOf(_) => {}
+            Rule::CaptureEach(c) => c.get_capturing_groups(count, map, within_variable)?,
+            Rule::Atomic(a) => a.get_capturing_groups(count, map, within_variable)?,
+            Rule::Conditional(c) => c.get_capturing_groups(count, map, within_variable)?,
+            Rule::Flags(f) => f.get_capturing_groups(count, map, within_variable)?,
         }
         Ok(())
     }
 
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        match self {
+            Rule::Group(g) => g.capture_info(info, optional),
+            Rule::Alternation(a) => a.capture_info(info, optional),
+            Rule::Repetition(r) => r.capture_info(info, optional),
+            Rule::Lookaround(l) => l.capture_info(info, optional),
+            Rule::StmtExpr(s) => s.capture_info(info, optional),
+            Rule::Word(w) => w.capture_info(info, optional),
+            Rule::BranchReset(b) => b.capture_info(info, optional),
+            Rule::CaptureEach(c) => c.capture_info(info, optional),
+            Rule::Atomic(a) => a.capture_info(info, optional),
+            Rule::Conditional(c) => c.capture_info(info, optional),
+            Rule::Flags(f) => f.capture_info(info, optional),
+            Rule::Reference(r) => r.capture_info(info),
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Boundary(_)
+            | Rule::Variable(_)
+            | Rule::Range(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_) => {}
+        }
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        counts.nodes += 1;
+        match self {
+            Rule::Group(g) => g.count_nodes(counts),
+            Rule::Alternation(a) => a.count_nodes(counts),
+            Rule::Repetition(r) => r.count_nodes(counts),
+            Rule::Lookaround(l) => l.count_nodes(counts),
+            Rule::StmtExpr(s) => s.count_nodes(counts),
+            Rule::Word(w) => w.count_nodes(counts),
+            Rule::BranchReset(b) => b.count_nodes(counts),
+            Rule::CaptureEach(c) => c.count_nodes(counts),
+            Rule::Atomic(a) => a.count_nodes(counts),
+            Rule::Conditional(c) => c.count_nodes(counts),
+            Rule::Flags(f) => f.count_nodes(counts),
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Boundary(_)
+            | Rule::Variable(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_) => {}
+        }
+    }
+
     pub(crate) fn comp<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
-        match self {
-            Rule::Literal(l) => l.compile(),
-            Rule::CharClass(c) => c.compile(options),
+        let regex = match self {
+            Rule::Literal(l) => l.compile(options),
+            Rule::CharClass(c) => c.compile(options, &mut state.optimizations),
             Rule::Group(g) => g.compile(options, state),
             Rule::Grapheme(g) => g.compile(options),
+            Rule::LineBreak(l) => l.compile(options),
             Rule::Alternation(a) => a.compile(options, state),
             Rule::Repetition(r) => r.compile(options, state),
-            Rule::Boundary(b) => b.compile(),
+            Rule::Boundary(b) => b.compile(options),
             Rule::Lookaround(l) => l.compile(options, state),
             Rule::Variable(v) => v.compile(options, state).map_err(|mut e| {
                 e.set_missing_span(v.span);
@@ -136,7 +272,21 @@ impl<'i> Rule<'i> {
             Rule::Reference(r) => r.compile(options, state),
             Rule::Range(r) => r.compile(),
             Rule::StmtExpr(m) => m.compile(options, state),
+            Rule::Word(w) => w.compile(options, state),
+            Rule::BranchReset(b) => b.compile(options, state),
+            Rule::Balanced(b) => b.compile(options, state),
+            Rule::OneOf(o) => o.compile(),
+            Rule::CaptureEach(c) => c.compile(options, state),
+            Rule::Atomic(a) => a.compile(options, state),
+            Rule::Conditional(c) => c.compile(options, state),
+            Rule::Flags(f) => f.compile(options, state),
+        }?;
+        if options.flavor == RegexFlavor::Lua {
+            regex.check_lua().map_err(|feature| {
+                CompileErrorKind::Unsupported(feature, options.flavor).at(self.span())
+            })?;
         }
+        Ok(regex)
     }
 
     pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
@@ -144,6 +294,7 @@ impl<'i> Rule<'i> {
             Rule::Literal(_) => {}
             Rule::CharClass(_) => {}
             Rule::Grapheme(g) => g.validate(options)?,
+            Rule::LineBreak(_) => {}
             Rule::Group(g) => g.validate(options)?,
             Rule::Alternation(a) => a.validate(options)?,
             Rule::Repetition(r) => r.validate(options)?,
@@ -153,10 +304,108 @@ impl<'i> Rule<'i> {
             Rule::Reference(r) => r.validate(options)?,
             Rule::Range(r) => r.validate(options)?,
             Rule::StmtExpr(s) => s.validate(options)?,
+            Rule::Word(w) => w.validate(options)?,
+            Rule::BranchReset(b) => b.validate(options)?,
+            Rule::Balanced(_) => {}
+            Rule::OneOf(_) => {}
+            Rule::CaptureEach(c) => c.validate(options)?,
+            Rule::Atomic(a) => a.validate(options)?,
+            Rule::Conditional(c) => c.validate(options)?,
+            Rule::Flags(f) => f.validate(options)?,
         }
 
         Ok(())
     }
+
+    /// Calls `f` for every node in post-order, and replaces the node with the
+    /// result. This is used by [`Expr::transform`](crate::Expr::transform).
+    pub(crate) fn transform(mut self, f: &mut impl FnMut(Rule<'i>) -> Rule<'i>) -> Rule<'i> {
+        match &mut self {
+            Rule::Group(g) => transform_all(&mut g.parts, f),
+            Rule::Alternation(a) => transform_all(&mut a.rules, f),
+            Rule::BranchReset(b) => transform_all(&mut b.branches, f),
+            Rule::Repetition(r) => transform_in_place(&mut r.rule, f),
+            Rule::Lookaround(l) => transform_in_place(&mut l.rule, f),
+            Rule::Word(w) => transform_in_place(&mut w.rule, f),
+            Rule::CaptureEach(c) => transform_in_place(&mut c.rule, f),
+            Rule::Atomic(a) => transform_in_place(&mut a.rule, f),
+            Rule::Flags(g) => transform_in_place(&mut g.rule, f),
+            Rule::Conditional(c) => {
+                transform_in_place(&mut c.then, f);
+                if let Some(otherwise) = &mut c.otherwise {
+                    transform_in_place(otherwise, f);
+                }
+            }
+            Rule::StmtExpr(s) => {
+                match &mut s.stmt {
+                    Stmt::Let(l) => transform_in_place(&mut l.rule, f),
+                    Stmt::Define(lets) => {
+                        for l in lets {
+                            transform_in_place(&mut l.rule, f);
+                        }
+                    }
+                    Stmt::Enable(_) | Stmt::Disable(_) => {}
+                }
+                transform_in_place(&mut s.rule, f);
+            }
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::Grapheme(_)
+            | Rule::LineBreak(_)
+            | Rule::Boundary(_)
+            | Rule::Variable(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::Balanced(_)
+            | Rule::OneOf(_) => {}
+        }
+        f(self)
+    }
+
+    /// Adds warnings for constructs that are valid, but probably don't do what
+    /// the user expects. `in_repetition` is `true` if the rule can be repeated
+    /// more than once.
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        match self {
+            Rule::Literal(_) => {}
+            Rule::CharClass(c) => c.lint(options, warnings),
+            Rule::Grapheme(_) | Rule::LineBreak(_) => {}
+            Rule::Group(g) => g.lint(options, warnings, in_repetition),
+            Rule::Alternation(a) => a.lint(options, warnings, in_repetition),
+            Rule::Repetition(r) => r.lint(options, warnings, in_repetition),
+            Rule::Boundary(_) => {}
+            Rule::Lookaround(l) => l.lint(options, warnings, in_repetition),
+            Rule::Variable(v) => v.lint(options, warnings),
+            Rule::Reference(_) => {}
+            Rule::Range(_) => {}
+            Rule::StmtExpr(s) => s.lint(options, warnings, in_repetition),
+            Rule::Word(w) => w.lint(options, warnings, in_repetition),
+            Rule::BranchReset(b) => b.lint(options, warnings, in_repetition),
+            Rule::Balanced(_) => {}
+            Rule::OneOf(_) => {}
+            Rule::CaptureEach(c) => c.lint(options, warnings),
+            Rule::Atomic(a) => a.lint(options, warnings, in_repetition),
+            Rule::Conditional(c) => c.lint(options, warnings, in_repetition),
+            Rule::Flags(f) => f.lint(options, warnings, in_repetition),
+        }
+    }
+}
+
+fn transform_in_place<'i>(rule: &mut Rule<'i>, f: &mut impl FnMut(Rule<'i>) -> Rule<'i>) {
+    let placeholder = Rule::Literal(Literal::new(Cow::Borrowed(""), Span::empty()));
+    let old = std::mem::replace(rule, placeholder);
+    *rule = old.transform(f);
+}
+
+fn transform_all<'i>(rules: &mut [Rule<'i>], f: &mut impl FnMut(Rule<'i>) -> Rule<'i>) {
+    for rule in rules {
+        transform_in_place(rule, f);
+    }
 }
 
 #[cfg(feature = "dbg")]
@@ -166,6 +415,7 @@ impl core::fmt::Debug for Rule<'_> {
             Rule::Literal(arg0) => arg0.fmt(f),
             Rule::CharClass(arg0) => arg0.fmt(f),
             Rule::Grapheme(arg0) => arg0.fmt(f),
+            Rule::LineBreak(arg0) => arg0.fmt(f),
             Rule::Group(arg0) => arg0.fmt(f),
             Rule::Alternation(arg0) => arg0.fmt(f),
             Rule::Repetition(arg0) => arg0.fmt(f),
@@ -175,6 +425,14 @@ impl core::fmt::Debug for Rule<'_> {
             Rule::Reference(arg0) => arg0.fmt(f),
             Rule::Range(arg0) => arg0.fmt(f),
             Rule::StmtExpr(arg0) => arg0.fmt(f),
+            Rule::Word(arg0) => arg0.fmt(f),
+            Rule::BranchReset(arg0) => arg0.fmt(f),
+            Rule::Balanced(arg0) => arg0.fmt(f),
+            Rule::OneOf(arg0) => arg0.fmt(f),
+            Rule::CaptureEach(arg0) => arg0.fmt(f),
+            Rule::Atomic(arg0) => arg0.fmt(f),
+            Rule::Conditional(arg0) => arg0.fmt(f),
+            Rule::Flags(arg0) => arg0.fmt(f),
         }
     }
 }
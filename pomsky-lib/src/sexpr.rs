@@ -0,0 +1,283 @@
+//! Formats a parsed expression as an S-expression, as returned by
+//! [`Expr::to_sexpr`](crate::Expr::to_sexpr). Unlike the `Debug`
+//! implementations, the format is compact and meant to stay the same across
+//! versions, so it can be used in snapshot tests.
+//!
+//! For example, `'a' | [d]+` is formatted as
+//! `(alt (lit "a") (rep (class d) 1 inf default))`.
+
+use std::fmt::Write;
+
+use crate::{
+    boundary::BoundaryKind,
+    char_class::{char_group::GroupName, CharClass, CharGroup, GroupItem},
+    group::Capture,
+    lookaround::LookaroundKind,
+    reference::{Reference, ReferenceTarget},
+    repetition::{Quantifier, RepetitionKind},
+    rule::Rule,
+    stmt::{BooleanSetting, Let, Stmt},
+};
+
+pub(crate) fn write_rule(rule: &Rule<'_>, buf: &mut String) {
+    match rule {
+        Rule::Literal(l) => {
+            buf.push_str("(lit ");
+            write_string(&l.content, buf);
+            buf.push(')');
+        }
+        Rule::CharClass(c) => write_class(c, buf),
+        Rule::Grapheme(_) => buf.push_str("grapheme"),
+        Rule::LineBreak(_) => buf.push_str("line-break"),
+        Rule::Group(g) => {
+            match g.capture {
+                Some(Capture { name: Some(name), .. }) => {
+                    write!(buf, "(named-capture {name}").unwrap();
+                }
+                Some(Capture { name: None, .. }) => buf.push_str("(capture"),
+                None => buf.push_str("(seq"),
+            }
+            write_children(&g.parts, buf);
+        }
+        Rule::Alternation(a) => {
+            buf.push_str("(alt");
+            write_children(&a.rules, buf);
+        }
+        Rule::Repetition(r) => {
+            buf.push_str("(rep ");
+            write_rule(&r.rule, buf);
+            let RepetitionKind { lower_bound, upper_bound } = r.kind;
+            match upper_bound {
+                Some(upper_bound) => write!(buf, " {lower_bound} {upper_bound}").unwrap(),
+                None => write!(buf, " {lower_bound} inf").unwrap(),
+            }
+            buf.push_str(match r.quantifier {
+                Quantifier::Greedy => " greedy)",
+                Quantifier::Lazy => " lazy)",
+                Quantifier::Possessive => " possessive)",
+                Quantifier::Default => " default)",
+            });
+        }
+        Rule::Boundary(b) => buf.push_str(match b.kind() {
+            BoundaryKind::Start => "(boundary start)",
+            BoundaryKind::End => "(boundary end)",
+            BoundaryKind::LineStart => "(boundary line-start)",
+            BoundaryKind::LineEnd => "(boundary line-end)",
+            BoundaryKind::Word => "(boundary word)",
+            BoundaryKind::NotWord => "(boundary not-word)",
+            BoundaryKind::EndOfText => "(boundary end-of-text)",
+            BoundaryKind::EndOfTextOrBeforeNewline => "(boundary end-of-text-or-before-newline)",
+        }),
+        Rule::Lookaround(l) => {
+            buf.push_str(match l.kind {
+                LookaroundKind::Ahead => "(lookahead ",
+                LookaroundKind::Behind => "(lookbehind ",
+                LookaroundKind::AheadNegative => "(not-lookahead ",
+                LookaroundKind::BehindNegative => "(not-lookbehind ",
+            });
+            write_rule(&l.rule, buf);
+            buf.push(')');
+        }
+        Rule::Variable(v) => write!(buf, "(var {})", v.name).unwrap(),
+        Rule::Reference(r) => write_reference(r, buf),
+        Rule::Range(r) => {
+            let digits = |digits: &[u8]| -> String {
+                digits
+                    .iter()
+                    .map(|&d| char::from_digit(d as u32, r.radix as u32).unwrap())
+                    .collect()
+            };
+            write!(buf, "(int-range {} {} {})", digits(&r.start), digits(&r.end), r.radix).unwrap();
+        }
+        Rule::StmtExpr(s) => {
+            match &s.stmt {
+                Stmt::Enable(setting) => write!(buf, "(enable {}", setting_name(setting)).unwrap(),
+                Stmt::Disable(setting) => {
+                    write!(buf, "(disable {}", setting_name(setting)).unwrap()
+                }
+                Stmt::Let(l) => {
+                    buf.push_str("(let ");
+                    write_let(l, buf);
+                }
+                Stmt::Define(lets) => {
+                    buf.push_str("(define (");
+                    for (i, l) in lets.iter().enumerate() {
+                        if i > 0 {
+                            buf.push(' ');
+                        }
+                        buf.push('(');
+                        write_let(l, buf);
+                        buf.push(')');
+                    }
+                    buf.push(')');
+                }
+            }
+            buf.push(' ');
+            write_rule(&s.rule, buf);
+            buf.push(')');
+        }
+        Rule::Word(w) => {
+            buf.push_str("(word ");
+            write_rule(&w.rule, buf);
+            buf.push(')');
+        }
+        Rule::Balanced(b) => {
+            buf.push_str("(balanced ");
+            write_string(b.open.encode_utf8(&mut [0; 4]), buf);
+            buf.push(' ');
+            write_string(b.close.encode_utf8(&mut [0; 4]), buf);
+            buf.push(')');
+        }
+        Rule::OneOf(o) => {
+            buf.push_str("(one-of");
+            for s in &o.strings {
+                buf.push(' ');
+                write_string(s, buf);
+            }
+            buf.push(')');
+        }
+        Rule::CaptureEach(c) => {
+            buf.push_str("(capture-each ");
+            write_rule(&c.rule, buf);
+            buf.push(')');
+        }
+        Rule::BranchReset(r) => {
+            buf.push_str("(reset");
+            write_children(&r.branches, buf);
+        }
+        Rule::Conditional(c) => {
+            buf.push_str("(if ");
+            write_reference(&c.condition, buf);
+            buf.push(' ');
+            write_rule(&c.then, buf);
+            if let Some(otherwise) = &c.otherwise {
+                buf.push(' ');
+                write_rule(otherwise, buf);
+            }
+            buf.push(')');
+        }
+        Rule::Atomic(a) => {
+            buf.push_str("(atomic ");
+            write_rule(&a.rule, buf);
+            buf.push(')');
+        }
+        Rule::Flags(f) => {
+            buf.push_str(if f.enable { "(enable-flags (" } else { "(disable-flags (" });
+            for (i, flag) in f.flags.iter().enumerate() {
+                if i > 0 {
+                    buf.push(' ');
+                }
+                buf.push_str(flag.name());
+            }
+            buf.push_str(") ");
+            write_rule(&f.rule, buf);
+            buf.push(')');
+        }
+    }
+}
+
+/// Writes the rules separated by spaces, followed by a closing parenthesis
+fn write_children(rules: &[Rule<'_>], buf: &mut String) {
+    for rule in rules {
+        buf.push(' ');
+        write_rule(rule, buf);
+    }
+    buf.push(')');
+}
+
+fn write_reference(r: &Reference<'_>, buf: &mut String) {
+    match r.target {
+        ReferenceTarget::Named(name) => write!(buf, "(ref {name})").unwrap(),
+        ReferenceTarget::Number(n) => write!(buf, "(ref {n})").unwrap(),
+        ReferenceTarget::Relative(n) => write!(buf, "(ref-relative {n:+})").unwrap(),
+    }
+}
+
+fn write_let(l: &Let<'_>, buf: &mut String) {
+    buf.push_str(l.name());
+    buf.push(' ');
+    write_rule(&l.rule, buf);
+}
+
+fn setting_name(setting: &BooleanSetting) -> &'static str {
+    match setting {
+        BooleanSetting::Lazy => "lazy",
+    }
+}
+
+fn write_class(class: &CharClass, buf: &mut String) {
+    buf.push_str(if class.negative { "(not-class" } else { "(class" });
+    write_group(&class.inner, buf);
+    for group in &class.intersections {
+        buf.push_str(" &");
+        write_group(group, buf);
+    }
+    buf.push(')');
+}
+
+fn write_group(group: &CharGroup, buf: &mut String) {
+    match group {
+        CharGroup::Dot => buf.push_str(" dot"),
+        CharGroup::CodePoint => buf.push_str(" codepoint"),
+        CharGroup::Items(items) => {
+            for &item in items {
+                buf.push(' ');
+                match item {
+                    GroupItem::Char(c) => write_string(c.encode_utf8(&mut [0; 4]), buf),
+                    GroupItem::Range { first, last } => {
+                        buf.push_str("(range ");
+                        write_string(first.encode_utf8(&mut [0; 4]), buf);
+                        buf.push(' ');
+                        write_string(last.encode_utf8(&mut [0; 4]), buf);
+                        buf.push(')');
+                    }
+                    GroupItem::Named { name, negative: true } => {
+                        buf.push_str("(not ");
+                        write_group_name(name, buf);
+                        buf.push(')');
+                    }
+                    GroupItem::Named { name, negative: false } => write_group_name(name, buf),
+                }
+            }
+        }
+    }
+}
+
+fn write_group_name(name: GroupName, buf: &mut String) {
+    match name {
+        GroupName::Word => buf.push('w'),
+        GroupName::Digit => buf.push('d'),
+        GroupName::Space => buf.push('s'),
+        GroupName::HorizSpace => buf.push('h'),
+        GroupName::VertSpace => buf.push('v'),
+        GroupName::Category(c) => buf.push_str(c.as_str()),
+        GroupName::Script(s) => buf.push_str(s.as_str()),
+        GroupName::ScriptExtension(s) => {
+            buf.push_str("scx=");
+            buf.push_str(s.as_str());
+        }
+        GroupName::CodeBlock(b) => {
+            buf.push_str("In");
+            buf.push_str(b.as_str());
+        }
+        GroupName::OtherProperties(o) => buf.push_str(o.as_str()),
+    }
+}
+
+/// Writes a string in double quotes. Quotes, backslashes and control
+/// characters are escaped.
+fn write_string(s: &str, buf: &mut String) {
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() => write!(buf, "\\u{{{:X}}}", c as u32).unwrap(),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
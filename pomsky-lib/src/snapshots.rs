@@ -0,0 +1,210 @@
+//! Checks a file of pomsky expressions against their expected output. This can
+//! be used to make sure that a library of expressions still compiles to the
+//! same regexes after upgrading pomsky.
+//!
+//! A snapshot file contains entries separated by a line consisting of `=====`.
+//! Each entry has an optional header line with the flavor (`#! flavor=js`),
+//! the expression, a line consisting of `-----`, and the expected regex:
+//!
+//! ```text
+//! #! flavor=js
+//! 'a'+ | 'b'
+//! -----
+//! a+|b
+//! =====
+//! [w]+
+//! -----
+//! \w+
+//! ```
+//!
+//! The default flavor is PCRE. Flavors are spelled as in the CLI, e.g. `pcre`,
+//! `python`, `java`, `js`, `dotnet`, `ruby`, `rust`, `sed`, `sed-extended`,
+//! `re2` or `antlr`.
+
+use std::{fmt, io, path::Path};
+
+use crate::{options::RegexFlavor, Expr};
+
+/// An error that prevents a snapshot file from being checked
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    /// The file couldn't be read
+    #[error("Could not read the snapshot file: {}", .0)]
+    Io(#[from] io::Error),
+
+    /// The file has an invalid format
+    #[error("Invalid snapshot in line {}: {}", .line, .message)]
+    Format {
+        /// The line of the invalid entry, starting at 1
+        line: usize,
+        /// Describes what is wrong
+        message: String,
+    },
+}
+
+/// The result of checking all entries of a snapshot file
+#[derive(Debug, Clone)]
+pub struct SnapshotReport {
+    /// The results of the entries, in the order of the file
+    pub results: Vec<SnapshotResult>,
+}
+
+/// The result of checking one entry of a snapshot file
+#[derive(Debug, Clone)]
+pub struct SnapshotResult {
+    /// The line where the entry starts, starting at 1
+    pub line: usize,
+    /// The pomsky expression
+    pub input: String,
+    /// The flavor the expression was compiled for
+    pub flavor: RegexFlavor,
+    /// The expected regex
+    pub expected: String,
+    /// Whether the compiled regex matches the expected regex
+    pub outcome: SnapshotOutcome,
+}
+
+/// Whether a snapshot entry passed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    /// The expression compiled to the expected regex
+    Passed,
+    /// The expression compiled to a different regex
+    Mismatch {
+        /// The regex that was produced
+        got: String,
+    },
+    /// The expression couldn't be compiled
+    Error(String),
+}
+
+impl SnapshotReport {
+    /// Returns the number of entries that passed
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome == SnapshotOutcome::Passed).count()
+    }
+
+    /// Returns the number of entries that failed
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    /// Returns `true` if all entries passed
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+/// Reads a snapshot file and checks every entry. See the
+/// [module documentation](self) for the file format.
+pub fn run_snapshots(path: impl AsRef<Path>) -> Result<SnapshotReport, SnapshotError> {
+    let content = std::fs::read_to_string(path)?;
+    check_snapshots(&content)
+}
+
+/// Checks every entry in the content of a snapshot file. See the
+/// [module documentation](self) for the file format.
+///
+/// ```
+/// use pomsky::snapshots::{check_snapshots, SnapshotOutcome};
+///
+/// let report = check_snapshots("'a'+\n-----\na+\n=====\n#! flavor=js\n[w]\n-----\n\\w").unwrap();
+/// assert_eq!(report.passed(), 1);
+/// assert_eq!(
+///     report.results[1].outcome,
+///     SnapshotOutcome::Mismatch { got: "[\\p{Alphabetic}\\p{M}\\p{Nd}\\p{Pc}]".into() },
+/// );
+/// ```
+pub fn check_snapshots(content: &str) -> Result<SnapshotReport, SnapshotError> {
+    let mut results = vec![];
+    let mut line = 1;
+
+    for entry in content.split("\n=====\n") {
+        let entry_line = line;
+        line += entry.lines().count() + 1;
+
+        if entry.trim().is_empty() {
+            continue;
+        }
+
+        let (header, rest) = match entry.strip_prefix("#!") {
+            Some(rest) => rest.split_once('\n').unwrap_or((rest, "")),
+            None => ("", entry),
+        };
+        let flavor = parse_header(header)
+            .map_err(|message| SnapshotError::Format { line: entry_line, message })?;
+
+        let (input, expected) = rest.split_once("\n-----\n").ok_or_else(|| {
+            let message = "Expected a line with `-----` before the expected regex".into();
+            SnapshotError::Format { line: entry_line, message }
+        })?;
+        let expected = expected.strip_suffix('\n').unwrap_or(expected);
+
+        let options = crate::options::CompileOptions { flavor, ..Default::default() };
+        let outcome = match Expr::parse_and_compile(input, Default::default(), options) {
+            Ok((got, _)) if got == expected => SnapshotOutcome::Passed,
+            Ok((got, _)) => SnapshotOutcome::Mismatch { got },
+            Err(e) => SnapshotOutcome::Error(e.to_string()),
+        };
+
+        results.push(SnapshotResult {
+            line: entry_line,
+            input: input.to_string(),
+            flavor,
+            expected: expected.to_string(),
+            outcome,
+        });
+    }
+
+    Ok(SnapshotReport { results })
+}
+
+fn parse_header(header: &str) -> Result<RegexFlavor, String> {
+    let mut flavor = RegexFlavor::Pcre;
+    for part in header.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+        match part.split_once('=') {
+            Some(("flavor", value)) => {
+                flavor = match value.trim().to_ascii_lowercase().as_str() {
+                    "pcre" => RegexFlavor::Pcre,
+                    "python" => RegexFlavor::Python,
+                    "java" => RegexFlavor::Java,
+                    "js" | "javascript" => RegexFlavor::JavaScript,
+                    ".net" | "dotnet" => RegexFlavor::DotNet,
+                    "ruby" => RegexFlavor::Ruby,
+                    "rust" => RegexFlavor::Rust,
+                    "sed" => RegexFlavor::Sed { extended: false },
+                    "sed-extended" => RegexFlavor::Sed { extended: true },
+                    "re2" => RegexFlavor::Re2,
+                    "antlr" => RegexFlavor::Antlr,
+                    "lua" => RegexFlavor::Lua,
+                    _ => return Err(format!("Unknown flavor `{value}`")),
+                };
+            }
+            _ => return Err(format!("Unknown option `{part}`")),
+        }
+    }
+    Ok(flavor)
+}
+
+impl fmt::Display for SnapshotReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            let (label, got) = match &result.outcome {
+                SnapshotOutcome::Passed => continue,
+                SnapshotOutcome::Mismatch { got } => ("mismatch", got),
+                SnapshotOutcome::Error(e) => ("error", e),
+            };
+            writeln!(f, "line {} ({:?}): {label}", result.line, result.flavor)?;
+            writeln!(f, "  input:    {}", result.input.replace('\n', "\n            "))?;
+            writeln!(f, "  expected: {}", result.expected)?;
+            writeln!(f, "  got:      {got}")?;
+
+            // point at the first character that differs
+            if let SnapshotOutcome::Mismatch { got } = &result.outcome {
+                let same = result.expected.chars().zip(got.chars()).take_while(|(a, b)| a == b);
+                writeln!(f, "            {}^", " ".repeat(same.count()))?;
+            }
+        }
+        write!(f, "{} passed, {} failed", self.passed(), self.failed())
+    }
+}
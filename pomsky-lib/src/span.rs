@@ -30,6 +30,36 @@ impl Span {
         }
     }
 
+    /// Returns a span that can be used to slice `source`. Offsets past the end
+    /// of `source` are moved to the end. An offset within a multi-byte
+    /// character is moved to the start of the character if it is the start of
+    /// the span, and to the end of the character otherwise, so the character is
+    /// included. An empty span stays empty.
+    ///
+    /// ```
+    /// use pomsky::Expr;
+    ///
+    /// let err = Expr::parse("'a' \\", Default::default()).map(|_| ()).unwrap_err();
+    /// assert_eq!(err.span().range(), Some(4..5));
+    /// assert_eq!(err.span().clamp_to("äöü").range(), Some(4..6));
+    /// assert_eq!(err.span().clamp_to("aäöü").range(), Some(3..5));
+    /// assert_eq!(err.span().clamp_to("abc").range(), Some(3..3));
+    /// ```
+    pub fn clamp_to(self, source: &str) -> Span {
+        if self.is_empty() {
+            return self;
+        }
+        let mut start = self.start.min(source.len());
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = self.end.clamp(start, source.len());
+        while !source.is_char_boundary(end) {
+            end += 1;
+        }
+        Span { start, end }
+    }
+
     pub(crate) fn range_unchecked(self) -> Range<usize> {
         self.start..self.end
     }
@@ -74,3 +104,16 @@ impl Debug for Span {
         write!(f, "Span({}..{})", self.start, self.end)
     }
 }
+
+/// Serializes the span as an object with the byte offsets `start` and `end`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Span {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut s = serializer.serialize_struct("Span", 2)?;
+        s.serialize_field("start", &self.start)?;
+        s.serialize_field("end", &self.end)?;
+        s.end()
+    }
+}
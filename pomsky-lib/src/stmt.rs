@@ -1,19 +1,23 @@
 use std::collections::HashMap;
 
 use crate::{
-    compile::{CompileResult, CompileState},
-    error::{CompileError, ParseError},
+    captures::{CaptureGroup, CaptureInfo},
+    compile::{CompileResult, CompileState, NodeCounts},
+    error::{CompileError, CompileErrorKind, Feature, ParseError},
     features::PomskyFeatures,
-    options::{CompileOptions, ParseOptions},
+    group::{output_group_name, RegexCapture, RegexGroup},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
+    regex::Regex,
     repetition::RegexQuantifier,
     rule::Rule,
     span::Span,
+    warning::{BoundaryWarning, CompatWarning, Warning, WarningKind},
 };
 
 #[derive(Clone)]
 pub(crate) struct StmtExpr<'i> {
-    stmt: Stmt<'i>,
-    rule: Rule<'i>,
+    pub(crate) stmt: Stmt<'i>,
+    pub(crate) rule: Rule<'i>,
     pub(crate) span: Span,
 }
 
@@ -22,6 +26,10 @@ pub(crate) enum Stmt<'i> {
     Enable(BooleanSetting),
     Disable(BooleanSetting),
     Let(Let<'i>),
+    /// A `define { ... }` block. Its variables are compiled to named groups
+    /// in a `(?(DEFINE)...)` group, and using them compiles to subroutine
+    /// calls, so they can be recursive.
+    Define(Vec<Let<'i>>),
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -32,7 +40,7 @@ pub(crate) enum BooleanSetting {
 #[derive(Clone)]
 pub(crate) struct Let<'i> {
     name: &'i str,
-    rule: Rule<'i>,
+    pub(crate) rule: Rule<'i>,
     pub(crate) name_span: Span,
 }
 
@@ -57,15 +65,57 @@ impl<'i> StmtExpr<'i> {
         map: &'i mut HashMap<String, u32>,
         within_variable: bool,
     ) -> Result<(), CompileError> {
-        if let Stmt::Let(l) = &self.stmt {
-            l.rule.get_capturing_groups(count, map, true)?;
+        match &self.stmt {
+            Stmt::Let(l) => l.rule.get_capturing_groups(count, map, true)?,
+            Stmt::Define(lets) => {
+                for l in lets {
+                    if map.contains_key(l.name) {
+                        return Err(CompileErrorKind::NameUsedMultipleTimes(l.name.to_string())
+                            .at(l.name_span));
+                    }
+                    *count += 1;
+                    map.insert(l.name.to_string(), *count);
+                }
+                for l in lets {
+                    l.rule.get_capturing_groups(count, map, true)?;
+                }
+            }
+            Stmt::Enable(_) | Stmt::Disable(_) => {}
         }
         self.rule.get_capturing_groups(count, map, within_variable)
     }
 
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        match &self.stmt {
+            Stmt::Let(l) => l.rule.count_nodes(counts),
+            Stmt::Define(lets) => {
+                for l in lets {
+                    l.rule.count_nodes(counts);
+                }
+            }
+            Stmt::Enable(_) | Stmt::Disable(_) => {}
+        }
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        // capturing groups aren't allowed in `let` statements, but the variables in a `define`
+        // block are compiled to named groups, which never participate in a match
+        if let Stmt::Define(lets) = &self.stmt {
+            for l in lets {
+                info.groups.push(CaptureGroup {
+                    index: info.groups.len() as u32 + 1,
+                    name: Some(l.name.to_string()),
+                    optional: true,
+                });
+            }
+        }
+        self.rule.capture_info(info, optional);
+    }
+
     pub(crate) fn compile<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
         match &self.stmt {
@@ -89,6 +139,96 @@ impl<'i> StmtExpr<'i> {
                 state.variables.pop();
                 Ok(res)
             }
+            Stmt::Define(lets) => {
+                if let Some(feature) = self.unsupported_feature(options.flavor) {
+                    return Err(
+                        CompileErrorKind::Unsupported(feature, options.flavor).at(self.span)
+                    );
+                }
+
+                // all variables are declared first, so they can call each other
+                let first_var = state.variables.len();
+                for l in lets {
+                    state.subroutines.insert(state.variables.len());
+                    state.variables.push((l.name, &l.rule));
+                }
+
+                let mut groups = Vec::with_capacity(lets.len());
+                for l in lets {
+                    state.next_idx += 1;
+                    let content = l.rule.comp(options, state)?;
+                    groups.push(Regex::Group(RegexGroup::new(
+                        vec![content],
+                        RegexCapture::NamedCapture(output_group_name(
+                            l.name,
+                            l.name_span,
+                            options,
+                            state,
+                        )?),
+                    )));
+                }
+                let define = Regex::Group(RegexGroup::new(groups, RegexCapture::Define));
+
+                let res = self.rule.comp(options, state)?;
+                for i in first_var..state.variables.len() {
+                    state.subroutines.remove(&i);
+                }
+                state.variables.truncate(first_var);
+
+                let parts = match res {
+                    Regex::Group(g) if matches!(g.capture, RegexCapture::None) => {
+                        std::iter::once(define).chain(g.parts).collect()
+                    }
+                    res => vec![define, res],
+                };
+                Ok(Regex::Group(RegexGroup::new(parts, RegexCapture::None)))
+            }
+        }
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        match &self.stmt {
+            Stmt::Let(l) => l.rule.lint(options, warnings, false),
+            Stmt::Define(lets) => {
+                for l in lets {
+                    l.rule.lint(options, warnings, false);
+                }
+            }
+            Stmt::Enable(_) | Stmt::Disable(_) => {}
+        }
+        let len = warnings.len();
+        self.rule.lint(options, warnings, in_repetition);
+
+        // a variable named `Start` or `End` shadows the built-in variable
+        let names = match &self.stmt {
+            Stmt::Let(l) => std::slice::from_ref(l),
+            Stmt::Define(lets) => lets.as_slice(),
+            Stmt::Enable(_) | Stmt::Disable(_) => &[],
+        };
+        let shadows = |name| names.iter().any(|l| l.name == name);
+        let (shadows_start, shadows_end) = (shadows("Start"), shadows("End"));
+        if shadows_start || shadows_end {
+            let mut inner = warnings.split_off(len);
+            inner.retain(|warning| match warning.kind {
+                WarningKind::Compat(CompatWarning::StartAnchor) => !shadows_start,
+                WarningKind::Compat(CompatWarning::EndAnchor)
+                | WarningKind::Boundary(BoundaryWarning::EndBeforeNewline) => !shadows_end,
+                _ => true,
+            });
+            warnings.append(&mut inner);
+        }
+    }
+
+    pub(crate) fn unsupported_feature(&self, flavor: RegexFlavor) -> Option<Feature> {
+        match (&self.stmt, flavor) {
+            (Stmt::Define(_), RegexFlavor::Pcre) => None,
+            (Stmt::Define(_), _) => Some(Feature::DefineGroup),
+            _ => None,
         }
     }
 
@@ -102,6 +242,12 @@ impl<'i> StmtExpr<'i> {
                 options.allowed_features.require(PomskyFeatures::VARIABLES, l.name_span)?;
                 l.rule.validate(options)?;
             }
+            Stmt::Define(lets) => {
+                for l in lets {
+                    options.allowed_features.require(PomskyFeatures::VARIABLES, l.name_span)?;
+                    l.rule.validate(options)?;
+                }
+            }
         }
 
         self.rule.validate(options)
@@ -124,6 +270,12 @@ impl std::fmt::Debug for StmtExpr<'_> {
             Stmt::Enable(BooleanSetting::Lazy) => x = x.field(&DisplayDebug("enable lazy")),
             Stmt::Disable(BooleanSetting::Lazy) => x = x.field(&DisplayDebug("disable lazy")),
             Stmt::Let(r#let) => x = x.field(r#let),
+            Stmt::Define(lets) => {
+                x = x.field(&DisplayDebug("define"));
+                for r#let in lets {
+                    x = x.field(r#let);
+                }
+            }
         }
         x.field(&self.rule).finish()
     }
@@ -0,0 +1,52 @@
+//! Contains the time spent in each phase of the compiler. This module is only
+//! available with the `timings` feature.
+
+use std::{fmt, time::Duration};
+
+/// The time spent in each phase of the compiler. It is filled in by
+/// [`Expr::parse_with_timings`](crate::Expr::parse_with_timings) and
+/// [`Expr::compile_with_timings`](crate::Expr::compile_with_timings).
+///
+/// Phases that weren't executed, e.g. because an earlier phase failed, have a
+/// duration of zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// Splitting the input into tokens
+    pub tokenize: Duration,
+    /// Parsing the tokens into an abstract syntax tree
+    pub parse: Duration,
+    /// Validating the syntax tree and collecting warnings
+    pub validate: Duration,
+    /// Compiling the syntax tree to a regex syntax tree, which includes
+    /// resolving variables and expanding `range` expressions
+    pub compile: Duration,
+    /// Generating the regex string
+    pub codegen: Duration,
+}
+
+impl Timings {
+    /// Returns the total time spent in all phases
+    pub fn total(&self) -> Duration {
+        self.tokenize + self.parse + self.validate + self.compile + self.codegen
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let phases = [
+            ("tokenize", self.tokenize),
+            ("parse", self.parse),
+            ("validate", self.validate),
+            ("compile", self.compile),
+            ("codegen", self.codegen),
+            ("total", self.total()),
+        ];
+        for (i, (name, duration)) in phases.into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{name:>8}: {duration:.2?}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+//! Collects the features of an expression that aren't supported by the regex
+//! flavor, so they can be reported together instead of one at a time.
+//!
+//! This is a separate pass that runs before the expression is compiled. It
+//! only finds features that can be detected without compiling, such as
+//! lookaround or atomic groups; e.g. unsupported Unicode properties are only
+//! detected when compiling. Each node type has an `unsupported_feature` method
+//! that is also called when compiling it, so this pass never reports a
+//! feature that compiling wouldn't reject.
+
+use crate::{
+    error::{CompileError, CompileErrorKind, Feature},
+    options::RegexFlavor,
+    rule::Rule,
+    span::Span,
+    stmt::Stmt,
+};
+
+pub(crate) struct UnsupportedCollector<'c, 'i> {
+    flavor: RegexFlavor,
+    /// The variables in scope, like [`CompileState::variables`](crate::compile::CompileState)
+    variables: Vec<(&'i str, &'c Rule<'i>)>,
+    current_vars: Vec<usize>,
+    found: Vec<(Feature, Span)>,
+}
+
+impl<'c, 'i> UnsupportedCollector<'c, 'i> {
+    pub(crate) fn new(flavor: RegexFlavor, variables: Vec<(&'i str, &'c Rule<'i>)>) -> Self {
+        UnsupportedCollector { flavor, variables, current_vars: vec![], found: vec![] }
+    }
+
+    /// Returns the errors in the order in which they were found
+    pub(crate) fn finish(self) -> Vec<CompileError> {
+        let flavor = self.flavor;
+        self.found
+            .into_iter()
+            .map(|(feature, span)| CompileErrorKind::Unsupported(feature, flavor).at(span))
+            .collect()
+    }
+
+    fn push(&mut self, feature: Option<Feature>, span: Span) {
+        if let Some(feature) = feature {
+            // a variable can be used several times, but each feature is only reported once
+            if !self.found.contains(&(feature, span)) {
+                self.found.push((feature, span));
+            }
+        }
+    }
+
+    pub(crate) fn visit(&mut self, rule: &'c Rule<'i>) {
+        let flavor = self.flavor;
+        match rule {
+            Rule::Grapheme(g) => self.push(g.unsupported_feature(flavor), Span::empty()),
+            Rule::Group(g) => {
+                self.push(g.unsupported_feature(flavor), g.span);
+                for part in &g.parts {
+                    self.visit(part);
+                }
+            }
+            Rule::Alternation(a) => {
+                for rule in &a.rules {
+                    self.visit(rule);
+                }
+            }
+            Rule::Repetition(r) => {
+                self.push(r.unsupported_feature(flavor), r.span);
+                self.visit(&r.rule);
+            }
+            Rule::Boundary(b) => self.push(b.unsupported_feature(flavor), b.span),
+            Rule::Lookaround(l) => {
+                self.push(l.unsupported_feature(flavor), l.span);
+                self.visit(&l.rule);
+            }
+            Rule::Variable(v) => {
+                // resolved like in `Variable::compile`; unknown and recursive variables are
+                // reported when compiling
+                let found = self
+                    .variables
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find(|&(i, &(name, _))| name == v.name && !self.current_vars.contains(&i));
+                if let Some((i, &(_, rule))) = found {
+                    let len = self.found.len();
+                    self.current_vars.push(i);
+                    self.visit(rule);
+                    self.current_vars.pop();
+
+                    // built-in variables have no span, so the usage is reported instead
+                    for (_, span) in &mut self.found[len..] {
+                        if span.is_empty() {
+                            *span = v.span;
+                        }
+                    }
+                }
+            }
+            Rule::StmtExpr(s) => {
+                self.push(s.unsupported_feature(flavor), s.span);
+                let len = self.variables.len();
+                match &s.stmt {
+                    Stmt::Let(l) => self.variables.push((l.name(), &l.rule)),
+                    Stmt::Define(lets) => {
+                        self.variables.extend(lets.iter().map(|l| (l.name(), &l.rule)));
+                    }
+                    Stmt::Enable(_) | Stmt::Disable(_) => {}
+                }
+                self.visit(&s.rule);
+                self.variables.truncate(len);
+            }
+            Rule::Word(w) => self.visit(&w.rule),
+            Rule::BranchReset(b) => {
+                self.push(b.unsupported_feature(flavor), b.span);
+                for branch in &b.branches {
+                    self.visit(branch);
+                }
+            }
+            Rule::Balanced(b) => self.push(b.unsupported_feature(flavor), b.span),
+            Rule::CaptureEach(c) => self.visit(&c.rule),
+            Rule::Atomic(a) => {
+                self.push(a.unsupported_feature(flavor), a.span);
+                self.visit(&a.rule);
+            }
+            Rule::Flags(f) => {
+                self.push(f.unsupported_feature(flavor), f.span);
+                self.visit(&f.rule);
+            }
+            Rule::Conditional(c) => {
+                self.push(c.unsupported_feature(flavor), c.span);
+                self.visit(&c.then);
+                if let Some(otherwise) = &c.otherwise {
+                    self.visit(otherwise);
+                }
+            }
+            Rule::Literal(_)
+            | Rule::CharClass(_)
+            | Rule::LineBreak(_)
+            | Rule::Reference(_)
+            | Rule::Range(_)
+            | Rule::OneOf(_) => {}
+        }
+    }
+}
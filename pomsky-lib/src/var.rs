@@ -1,13 +1,18 @@
+use std::borrow::Cow;
+
 use crate::{
     compile::{CompileResult, CompileState},
-    error::CompileErrorKind,
-    options::CompileOptions,
+    error::{CompileErrorKind, Feature},
+    options::ParseOptions,
+    options::{CompileOptions, RegexFlavor},
+    regex::Regex,
     span::Span,
+    warning::{BoundaryWarning, CompatWarning, Warning, WarningKind},
 };
 
 #[derive(Clone, PartialEq, Eq)]
 pub(crate) struct Variable<'i> {
-    name: &'i str,
+    pub(crate) name: &'i str,
     pub(crate) span: Span,
 }
 
@@ -16,9 +21,27 @@ impl<'i> Variable<'i> {
         Variable { name, span }
     }
 
+    /// Warns about `Start` and `End`, which can't be compiled to `\A` and `\z`
+    /// in JavaScript, and optionally about `End` not matching before a final
+    /// newline. Warnings for variables that shadow them are removed by
+    /// [`StmtExpr::lint`](crate::stmt::StmtExpr::lint).
+    pub(crate) fn lint(&self, options: &ParseOptions, warnings: &mut Vec<Warning>) {
+        let warning = match self.name {
+            "Start" => CompatWarning::StartAnchor,
+            "End" => CompatWarning::EndAnchor,
+            _ => return,
+        };
+        warnings.push(WarningKind::Compat(warning).at(self.span));
+
+        if self.name == "End" && options.warn_end_anchor {
+            let warning = BoundaryWarning::EndBeforeNewline;
+            warnings.push(WarningKind::Boundary(warning).at(self.span));
+        }
+    }
+
     pub(crate) fn compile<'c>(
         &'c self,
-        options: CompileOptions,
+        options: &CompileOptions,
         state: &mut CompileState<'c, 'i>,
     ) -> CompileResult<'i> {
         let rule = state
@@ -28,15 +51,33 @@ impl<'i> Variable<'i> {
             .rev()
             .find(|&(i, &(name, _))| name == self.name && !state.current_vars.contains(&i));
 
-        if let Some((i, &(_, rule))) = rule {
-            state.current_vars.insert(i);
+        if let Some((_, &(name, _))) = rule.filter(|&(i, _)| state.subroutines.contains(&i)) {
+            if options.flavor != RegexFlavor::Pcre {
+                return Err(CompileErrorKind::Unsupported(Feature::SubroutineCall, options.flavor)
+                    .at(self.span));
+            }
+            let name = match state.renamed_groups.get(name) {
+                Some(new_name) => Cow::Owned(new_name.clone()),
+                None => Cow::Borrowed(name),
+            };
+            Ok(Regex::SubroutineCall(name))
+        } else if let Some((i, &(_, rule))) = rule {
+            state.current_vars.push(i);
             let res = rule.comp(options, state)?;
-            state.current_vars.remove(&i);
+            state.current_vars.pop();
             Ok(res)
         } else {
-            let recursive_rule = state.variables.iter().rev().find(|&&(name, _)| name == self.name);
-            if recursive_rule.is_some() {
-                Err(CompileErrorKind::RecursiveVariable.at(self.span))
+            let recursive_rule =
+                state.current_vars.iter().rposition(|&i| state.variables[i].0 == self.name);
+            if let Some(start) = recursive_rule {
+                let cycle = state.current_vars[start..]
+                    .iter()
+                    .map(|&i| state.variables[i].0)
+                    .chain([self.name])
+                    .map(Box::from)
+                    .collect();
+                Err(CompileErrorKind::RecursiveVariable { name: self.name.into(), cycle }
+                    .at(self.span))
             } else {
                 Err(CompileErrorKind::UnknownVariable {
                     found: self.name.into(),
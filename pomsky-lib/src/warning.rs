@@ -2,7 +2,7 @@
 
 use std::fmt;
 
-use crate::span::Span;
+use crate::{options::RegexFlavor, span::Span};
 
 /// A warning.
 #[derive(Debug, Clone, Copy)]
@@ -18,32 +18,112 @@ pub struct Warning {
 pub enum WarningKind {
     /// A deprecation warning
     Deprecation(DeprecationWarning),
+    /// A warning about a repetition
+    Repetition(RepetitionWarning),
+    /// A warning about a character class
+    CharClass(CharClassWarning),
+    /// A warning that the compiled regex exceeds a limit of the regex engine
+    EngineLimit(EngineLimitWarning),
+    /// A warning that an expression may behave differently in some flavors
+    Compat(CompatWarning),
+    /// A warning about a boundary or anchor
+    Boundary(BoundaryWarning),
 }
 
 impl WarningKind {
     pub(crate) fn at(self, span: Span) -> Warning {
         Warning { kind: self, span }
     }
+
+    /// Returns a stable code that identifies this kind of warning, e.g.
+    /// `W0101` for the deprecated `<%` literal. It is used as the
+    /// [`Diagnostic::code`](crate::error::Diagnostic::code) of the warning.
+    pub fn warning_code(&self) -> &'static str {
+        match self {
+            WarningKind::Deprecation(d) => match d {
+                DeprecationWarning::StartLiteral => "W0101",
+                DeprecationWarning::EndLiteral => "W0102",
+                DeprecationWarning::Dot => "W0103",
+            },
+            WarningKind::Repetition(r) => match r {
+                RepetitionWarning::ToleranceExceedsCenter => "W0201",
+                RepetitionWarning::CaptureInRepetition => "W0202",
+                RepetitionWarning::CaptureEachLastOnly => "W0203",
+                RepetitionWarning::NestedRepetition => "W0204",
+            },
+            WarningKind::CharClass(c) => match c {
+                CharClassWarning::EquivalentNamedClass(_) => "W0301",
+            },
+            WarningKind::EngineLimit(l) => match l {
+                EngineLimitWarning::PatternLength { .. } => "W0401",
+                EngineLimitWarning::CaptureGroups { .. } => "W0402",
+                EngineLimitWarning::Repetition { .. } => "W0403",
+            },
+            WarningKind::Compat(c) => match c {
+                CompatWarning::StartAnchor => "W0501",
+                CompatWarning::EndAnchor => "W0502",
+            },
+            WarningKind::Boundary(b) => match b {
+                BoundaryWarning::EndBeforeNewline => "W0601",
+            },
+        }
+    }
 }
 
-impl fmt::Display for Warning {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Warning {
+    /// Returns `false` if the warning is irrelevant when compiling for the
+    /// given regex flavor. Warnings emitted while parsing don't depend on the
+    /// flavor, so they should be filtered with this method once it is known.
+    /// [`Expr::parse_and_compile`](crate::Expr::parse_and_compile) does this
+    /// automatically.
+    ///
+    /// ```
+    /// use pomsky::{options::RegexFlavor, Expr};
+    ///
+    /// let (_, warnings) = Expr::parse("captureEach(:item([w]+) ' '?)+", Default::default()).unwrap();
+    /// assert!(warnings[0].applies_to(RegexFlavor::Pcre));
+    /// assert!(!warnings[0].applies_to(RegexFlavor::DotNet));
+    /// ```
+    pub fn applies_to(&self, flavor: RegexFlavor) -> bool {
         match self.kind {
-            WarningKind::Deprecation(d) => {
-                if let Some(std::ops::Range { start, end }) = self.span.range() {
-                    write!(f, "{d}\n  at {}..{}", start, end)
-                } else {
-                    write!(f, "{d}")
-                }
+            WarningKind::Repetition(RepetitionWarning::CaptureEachLastOnly) => {
+                flavor != RegexFlavor::DotNet
             }
+            WarningKind::Compat(_) => flavor == RegexFlavor::JavaScript,
+            // in other flavors, `$` doesn't match before a final newline either
+            WarningKind::Boundary(BoundaryWarning::EndBeforeNewline) => matches!(
+                flavor,
+                RegexFlavor::Pcre
+                    | RegexFlavor::Python
+                    | RegexFlavor::Java
+                    | RegexFlavor::DotNet
+                    | RegexFlavor::Ruby
+            ),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(std::ops::Range { start, end }) = self.span.range() {
+            write!(f, "{}\n  at {}..{}", self.kind, start, end)
+        } else {
+            write!(f, "{}", self.kind)
         }
     }
 }
 
 impl fmt::Display for WarningKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let WarningKind::Deprecation(c) = self;
-        c.fmt(f)
+        match self {
+            WarningKind::Deprecation(d) => d.fmt(f),
+            WarningKind::Repetition(r) => r.fmt(f),
+            WarningKind::CharClass(c) => c.fmt(f),
+            WarningKind::EngineLimit(l) => l.fmt(f),
+            WarningKind::Compat(c) => c.fmt(f),
+            WarningKind::Boundary(b) => b.fmt(f),
+        }
     }
 }
 
@@ -74,3 +154,173 @@ impl fmt::Display for DeprecationWarning {
         }
     }
 }
+
+/// A warning about a repetition that might not do what the user expects
+#[derive(Debug, Clone, Copy)]
+pub enum RepetitionWarning {
+    /// A repetition such as `{2 ± 5}`, where the tolerance is greater than the
+    /// center. The lower bound is clamped to 0.
+    ToleranceExceedsCenter,
+    /// A capturing group within a repetition, e.g. `(:'a')*`. Only the last
+    /// repetition is captured. This warning is only emitted when enabled with
+    /// [`ParseOptions::warn_capture_in_repetition`](crate::options::ParseOptions).
+    CaptureInRepetition,
+    /// A `captureEach(...)` expression. Only .NET retains all repetitions of
+    /// the capturing groups within, so this warning doesn't
+    /// [apply](Warning::applies_to) to .NET.
+    CaptureEachLastOnly,
+    /// An unbounded repetition directly within another unbounded repetition,
+    /// e.g. `('a'+)+`. This is equivalent to `'a'+`, but can cause
+    /// catastrophic backtracking when the expression doesn't match.
+    NestedRepetition,
+}
+
+impl fmt::Display for RepetitionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepetitionWarning::ToleranceExceedsCenter => f.write_str(
+                "The tolerance is greater than the center, so the lower bound is clamped to 0.",
+            ),
+            RepetitionWarning::CaptureInRepetition => f.write_str(
+                "This capturing group is repeated, so it only captures the last repetition.\n\
+                To capture all repetitions, wrap the repetition in a capturing group instead.",
+            ),
+            RepetitionWarning::CaptureEachLastOnly => f.write_str(
+                "Only the last repetition of each capturing group is captured in this flavor.\n\
+                All repetitions are only retained in .NET, where they are available in `Group.Captures`.",
+            ),
+            RepetitionWarning::NestedRepetition => f.write_str(
+                "This repetition is nested in another unbounded repetition, \
+                which can cause catastrophic backtracking.",
+            ),
+        }
+    }
+}
+
+/// A warning about a character class that could be written differently
+#[derive(Debug, Clone, Copy)]
+pub enum CharClassWarning {
+    /// A character class containing the same characters as a named class,
+    /// e.g. `['0'-'9']`, which is equivalent to `[ascii_digit]`. Only ASCII
+    /// classes are suggested, since `[digit]` and `[word]` also match
+    /// non-ASCII characters. This warning is only emitted when enabled with
+    /// [`ParseOptions::suggest_named_classes`](crate::options::ParseOptions).
+    EquivalentNamedClass(&'static str),
+}
+
+impl fmt::Display for CharClassWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharClassWarning::EquivalentNamedClass(name) => {
+                write!(f, "This character class can be written as `[{name}]`.")
+            }
+        }
+    }
+}
+
+/// A warning that the compiled regex exceeds a limit of the targeted regex
+/// engine, so the engine might reject it. These warnings are returned by
+/// [`Expr::engine_limit_warnings`](crate::Expr::engine_limit_warnings).
+#[derive(Debug, Clone, Copy)]
+pub enum EngineLimitWarning {
+    /// The regex is longer than the regex engine supports
+    PatternLength {
+        /// The length of the regex in bytes
+        length: usize,
+        /// The maximum length in bytes
+        limit: usize,
+        /// The targeted regex flavor
+        flavor: RegexFlavor,
+    },
+    /// The regex has more capturing groups than the regex engine supports
+    CaptureGroups {
+        /// The number of capturing groups
+        count: u32,
+        /// The maximum number of capturing groups
+        limit: u32,
+        /// The targeted regex flavor
+        flavor: RegexFlavor,
+    },
+    /// A repetition count is greater than the regex engine supports
+    Repetition {
+        /// The largest count of the repetition
+        count: u32,
+        /// The maximum repetition count
+        limit: u32,
+        /// The targeted regex flavor
+        flavor: RegexFlavor,
+    },
+}
+
+impl fmt::Display for EngineLimitWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            EngineLimitWarning::PatternLength { length, limit, flavor } => write!(
+                f,
+                "The regex is {length} bytes long, but the `{flavor}` regex flavor only \
+                supports regexes up to {limit} bytes.",
+                flavor = flavor.name()
+            ),
+            EngineLimitWarning::CaptureGroups { count, limit, flavor } => write!(
+                f,
+                "The regex has {count} capturing groups, but the `{flavor}` regex flavor only \
+                supports up to {limit}.",
+                flavor = flavor.name()
+            ),
+            EngineLimitWarning::Repetition { count, limit, flavor } => write!(
+                f,
+                "The repetition count {count} is greater than {limit}, the maximum supported \
+                by the `{flavor}` regex flavor.",
+                flavor = flavor.name()
+            ),
+        }
+    }
+}
+
+/// A warning about an expression that can't be compiled exactly in the
+/// targeted regex flavor. These warnings only [apply](Warning::applies_to) to
+/// JavaScript.
+#[derive(Debug, Clone, Copy)]
+pub enum CompatWarning {
+    /// `Start` in JavaScript, which has no `\A`, so it is compiled to `^`
+    StartAnchor,
+    /// `End` in JavaScript, which has no `\z`, so it is compiled to `$`
+    EndAnchor,
+}
+
+impl fmt::Display for CompatWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, anchor, position) = match self {
+            CompatWarning::StartAnchor => ("Start", '^', "start"),
+            CompatWarning::EndAnchor => ("End", '$', "end"),
+        };
+        write!(
+            f,
+            "`{name}` is compiled to `{anchor}` in JavaScript, which also matches at the \
+            {position} of each line when the `m` flag is set."
+        )
+    }
+}
+
+/// A warning about a boundary or anchor that might not do what the user
+/// expects
+#[derive(Debug, Clone, Copy)]
+pub enum BoundaryWarning {
+    /// `End`, which is compiled to `\z`. Unlike `$`, it doesn't match before a
+    /// newline at the end of the string. This warning is only emitted when
+    /// enabled with
+    /// [`ParseOptions::warn_end_anchor`](crate::options::ParseOptions), and
+    /// only [applies](Warning::applies_to) to flavors where `$` matches before
+    /// a final newline.
+    EndBeforeNewline,
+}
+
+impl fmt::Display for BoundaryWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundaryWarning::EndBeforeNewline => f.write_str(
+                "`End` only matches at the very end of the string, not before a final newline.",
+            ),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Implements `word(...)`, which matches an expression as a whole word.
+
+use std::collections::HashMap;
+
+use crate::{
+    boundary::{word_boundary, BoundaryKind},
+    captures::CaptureInfo,
+    char_class::RegexClassItem,
+    compile::{CompileResult, CompileState, NodeCounts},
+    error::{CompileError, CompileErrorKind, ParseError},
+    features::PomskyFeatures,
+    group::{RegexCapture, RegexGroup},
+    options::{CompileOptions, ParseOptions, RegexFlavor},
+    regex::{Regex, RegexShorthand},
+    rule::Rule,
+    span::Span,
+    warning::Warning,
+};
+
+/// An expression wrapped in `word(...)`. It is compiled to the expression
+/// surrounded by boundaries: If the expression starts or ends with a word
+/// character, a word boundary (`\b`) is used at that edge, so the word can't
+/// be part of a longer word. Otherwise, `\B` is used, so e.g. `word('-foo')`
+/// doesn't match the `-foo` in `a-foo`.
+#[derive(Clone)]
+pub(crate) struct Word<'i> {
+    pub(crate) rule: Rule<'i>,
+    pub(crate) span: Span,
+}
+
+impl<'i> Word<'i> {
+    pub(crate) fn new(rule: Rule<'i>, span: Span) -> Self {
+        Word { rule, span }
+    }
+
+    pub(crate) fn get_capturing_groups(
+        &self,
+        count: &mut u32,
+        map: &mut HashMap<String, u32>,
+        within_variable: bool,
+    ) -> Result<(), CompileError> {
+        self.rule.get_capturing_groups(count, map, within_variable)
+    }
+
+    pub(crate) fn count_nodes(&self, counts: &mut NodeCounts) {
+        self.rule.count_nodes(counts);
+    }
+
+    pub(crate) fn capture_info(&self, info: &mut CaptureInfo, optional: bool) {
+        self.rule.capture_info(info, optional);
+    }
+
+    pub(crate) fn lint(
+        &self,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        in_repetition: bool,
+    ) {
+        self.rule.lint(options, warnings, in_repetition);
+    }
+
+    pub(crate) fn compile<'c>(
+        &'c self,
+        options: &CompileOptions,
+        state: &mut CompileState<'c, 'i>,
+    ) -> CompileResult<'i> {
+        let content = self.rule.comp(options, state)?;
+        let ascii_only = options.flavor == RegexFlavor::JavaScript;
+
+        let boundary = |edge: Option<bool>, msg| match edge {
+            Some(true) => word_boundary(BoundaryKind::Word, options, self.span),
+            Some(false) => word_boundary(BoundaryKind::NotWord, options, self.span),
+            None => Err(CompileErrorKind::Other(msg).at(self.span)),
+        };
+        let start = boundary(
+            edge_is_word_char(&content, Edge::Start, ascii_only),
+            "Can't determine whether the expression in `word(...)` starts with a word character",
+        )?;
+        let end = boundary(
+            edge_is_word_char(&content, Edge::End, ascii_only),
+            "Can't determine whether the expression in `word(...)` ends with a word character",
+        )?;
+
+        Ok(Regex::Group(RegexGroup::new(vec![start, content, end], RegexCapture::None)))
+    }
+
+    pub(crate) fn validate(&self, options: &ParseOptions) -> Result<(), ParseError> {
+        options.allowed_features.require(PomskyFeatures::BOUNDARIES, self.span)?;
+        self.rule.validate(options)
+    }
+}
+
+#[cfg(feature = "dbg")]
+impl core::fmt::Debug for Word<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Word").field(&self.rule).finish()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Edge {
+    Start,
+    End,
+}
+
+/// Returns `Some(true)` if every string matched by the regex starts (or ends)
+/// with a word character, `Some(false)` if every string starts (or ends) with a
+/// character that isn't a word character, and `None` if this can't be
+/// determined.
+///
+/// In JavaScript, `\b` only considers ASCII characters as word characters.
+fn edge_is_word_char(regex: &Regex<'_>, edge: Edge, ascii_only: bool) -> Option<bool> {
+    let is_word = |c: char| {
+        if ascii_only {
+            c.is_ascii_alphanumeric() || c == '_'
+        } else {
+            c.is_alphanumeric() || c == '_'
+        }
+    };
+
+    match regex {
+        Regex::Literal(s) => {
+            let c = if edge == Edge::Start { s.chars().next() } else { s.chars().next_back() };
+            c.map(is_word)
+        }
+        &Regex::Char(c) => Some(is_word(c)),
+        Regex::CharClass(class) if !class.negative => {
+            let kinds = class.items.iter().map(|item| match *item {
+                RegexClassItem::Char(c) => Some(is_word(c)),
+                RegexClassItem::Range { first, last } => {
+                    // only ranges within a-z, A-Z or 0-9 are known to contain only word characters
+                    let ranges = [('a', 'z'), ('A', 'Z'), ('0', '9')];
+                    if first == last {
+                        Some(is_word(first))
+                    } else if ranges.iter().any(|&(a, b)| a <= first && last <= b) {
+                        Some(true)
+                    } else {
+                        None
+                    }
+                }
+                RegexClassItem::Shorthand(s) => shorthand_is_word(s),
+                RegexClassItem::Property { .. } | RegexClassItem::Nested { .. } => None,
+            });
+            all_equal(kinds)
+        }
+        &Regex::Shorthand(s) => shorthand_is_word(s),
+        Regex::LineBreak => Some(false),
+        Regex::Group(group) => {
+            // boundaries, lookarounds and DEFINE groups don't match any characters
+            let mut parts = group.parts.iter().filter(|part| {
+                !matches!(part, Regex::Boundary(_) | Regex::Lookaround(_))
+                    && !matches!(part, Regex::Group(g) if matches!(g.capture, RegexCapture::Define))
+                    && !matches!(part, Regex::Literal(s) if s.is_empty())
+            });
+            let part = if edge == Edge::Start { parts.next() } else { parts.next_back() };
+            edge_is_word_char(part?, edge, ascii_only)
+        }
+        Regex::Alternation(alt) => {
+            let kinds = alt.parts.iter().map(|part| edge_is_word_char(part, edge, ascii_only));
+            all_equal(kinds)
+        }
+        Regex::Repetition(rep) if rep.kind.lower_bound > 0 => {
+            edge_is_word_char(&rep.content, edge, ascii_only)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the value if all values are `Some` and equal
+fn all_equal(mut values: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    let first = values.next()??;
+    if values.all(|value| value == Some(first)) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+fn shorthand_is_word(shorthand: RegexShorthand) -> Option<bool> {
+    match shorthand {
+        RegexShorthand::Word | RegexShorthand::Digit => Some(true),
+        RegexShorthand::NotWord
+        | RegexShorthand::Space
+        | RegexShorthand::VertSpace
+        | RegexShorthand::HorizSpace => Some(false),
+        RegexShorthand::NotDigit
+        | RegexShorthand::NotSpace
+        | RegexShorthand::NotVertSpace
+        | RegexShorthand::NotHorizSpace => None,
+    }
+}
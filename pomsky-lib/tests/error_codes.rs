@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use pomsky::{
+    error::{
+        CharClassError, CharStringError, CodePointError, CompileErrorKind, Feature, NumberError,
+        ParseErrorKind, ParseErrorMsg, RepetitionError, Token, UnsupportedError,
+    },
+    options::RegexFlavor,
+    warning::{
+        BoundaryWarning, CharClassWarning, CompatWarning, DeprecationWarning, EngineLimitWarning,
+        RepetitionWarning, WarningKind,
+    },
+};
+
+fn parse_error_kinds() -> Vec<ParseErrorKind> {
+    use ParseErrorMsg as M;
+
+    let mut kinds = vec![
+        ParseErrorKind::Multiple(Box::new([])),
+        ParseErrorKind::UnknownToken,
+        ParseErrorKind::Dot,
+        ParseErrorKind::KeywordAfterLet("let".into()),
+        ParseErrorKind::UnexpectedKeyword("let".into()),
+        ParseErrorKind::Expected("expression"),
+        ParseErrorKind::LeftoverTokens,
+        ParseErrorKind::ExpectedToken(Token::BStart),
+        ParseErrorKind::ExpectedCodePointOrChar,
+        ParseErrorKind::RangeIsNotIncreasing,
+        ParseErrorKind::UnallowedNot,
+        ParseErrorKind::UnallowedDoubleNot,
+        ParseErrorKind::RangeIsTooBig(6),
+        ParseErrorKind::BalancedDelimiterNotChar,
+        ParseErrorKind::BalancedDelimitersEqual,
+        ParseErrorKind::OneOfEmpty,
+        ParseErrorKind::CaptureEachWithoutGroup,
+        ParseErrorKind::LetBindingExists,
+        ParseErrorKind::InvalidEscapeInStringAt(1),
+        ParseErrorKind::RecursionLimit,
+        ParseErrorKind::Nom(nom::error::ErrorKind::Char),
+        ParseErrorKind::Incomplete,
+        ParseErrorKind::UnknownFlag("foo".into()),
+    ];
+
+    let messages = [
+        M::Caret,
+        M::CaretInGroup,
+        M::Dollar,
+        M::GroupNonCapturing,
+        M::GroupLookahead,
+        M::GroupLookaheadNeg,
+        M::GroupLookbehind,
+        M::GroupLookbehindNeg,
+        M::GroupNamedCapture,
+        M::GroupPcreBackreference,
+        M::GroupComment,
+        M::GroupAtomic,
+        M::GroupConditional,
+        M::GroupBranchReset,
+        M::GroupSubroutineCall,
+        M::GroupOther,
+        M::Backslash,
+        M::BackslashU4,
+        M::BackslashX2,
+        M::BackslashUnicode,
+        M::BackslashProperty,
+        M::BackslashGK,
+        M::UnclosedString,
+    ];
+    kinds.extend(messages.map(ParseErrorKind::LexErrorWithMessage));
+
+    kinds.extend(
+        [CharStringError::Empty, CharStringError::TooManyCodePoints]
+            .map(ParseErrorKind::CharString),
+    );
+
+    let classes = [
+        CharClassError::Empty,
+        CharClassError::DescendingRange('b', 'a'),
+        CharClassError::Invalid,
+        CharClassError::Unallowed,
+        CharClassError::UnknownNamedClass {
+            found: "foo".into(),
+            #[cfg(feature = "suggestions")]
+            similar: None,
+        },
+        CharClassError::UnknownProperty {
+            found: "foo".into(),
+            #[cfg(feature = "suggestions")]
+            similar: None,
+        },
+        CharClassError::InvalidPropertyValue { property: "Script".into(), value: "Lu".into() },
+        CharClassError::Negative,
+        CharClassError::Keyword("let".into()),
+        CharClassError::UnsupportedIntersection(RegexFlavor::Rust),
+    ];
+    kinds.extend(classes.map(ParseErrorKind::CharClass));
+
+    kinds.push(ParseErrorKind::CodePoint(CodePointError::Invalid));
+
+    let numbers = [
+        NumberError::Empty,
+        NumberError::InvalidDigit,
+        NumberError::TooLarge,
+        NumberError::TooSmall,
+        NumberError::Zero,
+    ];
+    kinds.extend(numbers.map(ParseErrorKind::Number));
+
+    let repetitions = [
+        RepetitionError::NotAscending { lower: 2, upper: 1 },
+        RepetitionError::QuestionMarkAfterRepetition,
+        RepetitionError::DoubleQuantifier,
+    ];
+    kinds.extend(repetitions.map(ParseErrorKind::Repetition));
+
+    let unsupported = [
+        UnsupportedError::Grapheme,
+        UnsupportedError::NumberedGroups,
+        UnsupportedError::NamedGroups,
+        UnsupportedError::References,
+        UnsupportedError::LazyMode,
+        UnsupportedError::Ranges,
+        UnsupportedError::Variables,
+        UnsupportedError::Lookahead,
+        UnsupportedError::Lookbehind,
+        UnsupportedError::Boundaries,
+    ];
+    kinds.extend(unsupported.map(ParseErrorKind::Unsupported));
+
+    kinds
+}
+
+fn compile_error_kinds() -> Vec<CompileErrorKind> {
+    let mut kinds = vec![
+        CompileErrorKind::Multiple(Box::new([])),
+        CompileErrorKind::HugeReference,
+        CompileErrorKind::UnknownReferenceNumber(3),
+        CompileErrorKind::UnknownRelativeReference(-2),
+        CompileErrorKind::NumericReferenceWithDefine,
+        CompileErrorKind::UnknownReferenceName {
+            found: "foo".into(),
+            #[cfg(feature = "suggestions")]
+            similar: None,
+        },
+        CompileErrorKind::NameUsedMultipleTimes("foo".into()),
+        CompileErrorKind::UnsupportedGroupName {
+            name: "foo".into(),
+            flavor: RegexFlavor::Rust,
+            reason: "reason",
+        },
+        CompileErrorKind::EmptyClass,
+        CompileErrorKind::EmptyClassNegated,
+        CompileErrorKind::CaptureInLet,
+        CompileErrorKind::ReferenceInLet,
+        CompileErrorKind::UnknownVariable {
+            found: "foo".into(),
+            #[cfg(feature = "suggestions")]
+            similar: None,
+        },
+        CompileErrorKind::RecursiveVariable { name: "foo".into(), cycle: vec![] },
+        CompileErrorKind::UnsupportedInDfa("Lookaround"),
+        CompileErrorKind::TooManyAlternationBranches { count: 2, limit: 1 },
+        CompileErrorKind::TooManyNodes { count: 2, limit: 1 },
+        CompileErrorKind::Other("other"),
+        CompileErrorKind::UnicodeInByteMode("`Grapheme`"),
+        CompileErrorKind::CodePointInByteMode('ä'),
+        CompileErrorKind::RepeatedAssertion,
+    ];
+
+    let features = [
+        Feature::NamedCaptureGroups,
+        Feature::Lookaround,
+        Feature::Grapheme,
+        Feature::UnicodeBlock,
+        Feature::UnicodeProp,
+        Feature::Backreference,
+        Feature::ForwardReference,
+        Feature::RelativeReference,
+        Feature::NonNegativeRelativeReference,
+        Feature::NegativeShorthandW,
+        Feature::NegativeShorthandInClass,
+        Feature::LazyQuantifier,
+        Feature::PossessiveQuantifier,
+        Feature::EndOfText,
+        Feature::EndOfTextOrBeforeNewline,
+        Feature::BranchReset,
+        Feature::AtomicGroups,
+        Feature::Conditional,
+        Feature::DefineGroup,
+        Feature::SubroutineCall,
+        Feature::Recursion,
+        Feature::Boundary,
+        Feature::LargeRepetition,
+        Feature::Alternation,
+        Feature::ComplexRepetition,
+        Feature::WordBoundary,
+        Feature::NonAsciiCharClass,
+        Feature::AnchorPosition,
+        Feature::ByteMode,
+        Feature::InlineFlags,
+        Feature::MultilineFlag,
+        Feature::LineAnchors,
+        Feature::ScriptExtensions,
+        Feature::SingleByteCodePoint,
+    ];
+    kinds.extend(features.map(|feature| CompileErrorKind::Unsupported(feature, RegexFlavor::Rust)));
+
+    kinds
+}
+
+fn warning_kinds() -> Vec<WarningKind> {
+    vec![
+        WarningKind::Deprecation(DeprecationWarning::StartLiteral),
+        WarningKind::Deprecation(DeprecationWarning::EndLiteral),
+        WarningKind::Deprecation(DeprecationWarning::Dot),
+        WarningKind::Repetition(RepetitionWarning::ToleranceExceedsCenter),
+        WarningKind::Repetition(RepetitionWarning::CaptureInRepetition),
+        WarningKind::Repetition(RepetitionWarning::CaptureEachLastOnly),
+        WarningKind::Repetition(RepetitionWarning::NestedRepetition),
+        WarningKind::CharClass(CharClassWarning::EquivalentNamedClass("ascii_digit")),
+        WarningKind::EngineLimit(EngineLimitWarning::PatternLength {
+            length: 2,
+            limit: 1,
+            flavor: RegexFlavor::Rust,
+        }),
+        WarningKind::EngineLimit(EngineLimitWarning::CaptureGroups {
+            count: 2,
+            limit: 1,
+            flavor: RegexFlavor::Rust,
+        }),
+        WarningKind::EngineLimit(EngineLimitWarning::Repetition {
+            count: 2,
+            limit: 1,
+            flavor: RegexFlavor::Rust,
+        }),
+        WarningKind::Compat(CompatWarning::StartAnchor),
+        WarningKind::Compat(CompatWarning::EndAnchor),
+        WarningKind::Boundary(BoundaryWarning::EndBeforeNewline),
+    ]
+}
+
+fn assert_valid_code(code: &str, prefix: char) {
+    assert_eq!(code.len(), 5, "invalid code {code}");
+    assert!(code.starts_with(prefix), "invalid code {code}");
+    assert!(code[1..].bytes().all(|b| b.is_ascii_digit()), "invalid code {code}");
+}
+
+#[test]
+fn error_codes_are_unique() {
+    let mut codes = HashMap::new();
+    let mut insert = |code: &'static str, kind: String| {
+        if let Some(other) = codes.insert(code, kind.clone()) {
+            panic!("{kind} and {other} have the same code {code}");
+        }
+    };
+
+    for kind in parse_error_kinds() {
+        assert_valid_code(kind.error_code(), 'P');
+        insert(kind.error_code(), format!("{kind:?}"));
+    }
+    for kind in compile_error_kinds() {
+        assert_valid_code(kind.error_code(), 'C');
+        insert(kind.error_code(), format!("{kind:?}"));
+    }
+    for kind in warning_kinds() {
+        assert_valid_code(kind.warning_code(), 'W');
+        insert(kind.warning_code(), format!("{kind:?}"));
+    }
+}
+
+#[test]
+fn parse_error_in_compile_error_keeps_code() {
+    let kind = ParseErrorKind::LexErrorWithMessage(ParseErrorMsg::Dollar);
+    assert_eq!(CompileErrorKind::ParseError(kind.clone()).error_code(), kind.error_code());
+}
@@ -6,7 +6,12 @@ use std::{
 
 use pomsky::{
     error::CompileError,
-    options::{CompileOptions, ParseOptions, RegexFlavor},
+    explain::Locale,
+    options::{
+        CodePointEscape, CompileOptions, CustomClass, NameHandling, OptimizationLevel,
+        ParseOptions, RegexFlags, RegexFlavor,
+    },
+    warning::Warning,
 };
 
 use crate::{color::Color::*, Args};
@@ -20,16 +25,64 @@ pub(crate) enum TestResult {
     Panic { message: Option<String> },
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct Options {
     flavor: RegexFlavor,
+    wrap_in_named_group: Option<String>,
+    warn_capture_in_repetition: bool,
+    suggest_named_classes: bool,
+    warn_end_anchor: bool,
+    complement: bool,
+    sexpr: bool,
+    custom_classes: bool,
+    unicode_sets: bool,
+    canonical: bool,
+    minimal_classes: bool,
+    explicit_word_boundary: bool,
+    ascii_only_output: bool,
+    posix_classes: bool,
+    code_point_escape: Option<CodePointEscape>,
+    sanitize_names: bool,
+    flags: Option<RegexFlags>,
+    explain: Option<Locale>,
+    max_alternation_branches: Option<u32>,
+    max_nodes: Option<u32>,
+    inline_variables: bool,
+    optimization_level: OptimizationLevel,
+    byte_mode: bool,
     ignore: bool,
     expected_outcome: Outcome,
 }
 
 impl Default for Options {
     fn default() -> Self {
-        Self { flavor: RegexFlavor::Pcre, ignore: false, expected_outcome: Outcome::Success }
+        Self {
+            flavor: RegexFlavor::Pcre,
+            wrap_in_named_group: None,
+            warn_capture_in_repetition: false,
+            suggest_named_classes: false,
+            warn_end_anchor: false,
+            complement: false,
+            sexpr: false,
+            custom_classes: false,
+            unicode_sets: false,
+            canonical: false,
+            minimal_classes: false,
+            explicit_word_boundary: false,
+            ascii_only_output: false,
+            posix_classes: false,
+            code_point_escape: None,
+            sanitize_names: false,
+            flags: None,
+            explain: None,
+            max_alternation_branches: None,
+            max_nodes: None,
+            inline_variables: false,
+            optimization_level: OptimizationLevel::Basic,
+            byte_mode: false,
+            ignore: false,
+            expected_outcome: Outcome::Success,
+        }
     }
 }
 
@@ -50,6 +103,11 @@ impl Options {
                         "python" => RegexFlavor::Python,
                         "rust" => RegexFlavor::Rust,
                         "ruby" => RegexFlavor::Ruby,
+                        "sed" => RegexFlavor::Sed { extended: false },
+                        "sed-extended" => RegexFlavor::Sed { extended: true },
+                        "re2" => RegexFlavor::Re2,
+                        "antlr" => RegexFlavor::Antlr,
+                        "lua" => RegexFlavor::Lua,
                         _ => {
                             eprintln!("{}: Unknown flavor {value:?}", Yellow("Warning"));
                             eprintln!("  in {path:?}");
@@ -57,6 +115,89 @@ impl Options {
                         }
                     };
                 }
+                "wrap" => result.wrap_in_named_group = Some(value.to_string()),
+                "warn_capture_in_repetition" => result.warn_capture_in_repetition = true,
+                "suggest_named_classes" => result.suggest_named_classes = true,
+                "warn_end_anchor" => result.warn_end_anchor = true,
+                "complement" => result.complement = true,
+                "sexpr" => result.sexpr = true,
+                "custom_classes" => result.custom_classes = true,
+                "unicode_sets" => result.unicode_sets = true,
+                "canonical" => result.canonical = true,
+                "minimal_classes" => result.minimal_classes = true,
+                "explicit_word_boundary" => result.explicit_word_boundary = true,
+                "ascii_only_output" => result.ascii_only_output = true,
+                "posix_classes" => result.posix_classes = true,
+                "inline_variables" => result.inline_variables = true,
+                "byte_mode" => result.byte_mode = true,
+                "optimization_level" => {
+                    result.optimization_level = match value {
+                        "basic" => OptimizationLevel::Basic,
+                        "full" => OptimizationLevel::Full,
+                        _ => {
+                            eprintln!(
+                                "{}: Unknown optimization level {value:?}",
+                                Yellow("Warning")
+                            );
+                            eprintln!("  in {path:?}");
+                            continue;
+                        }
+                    };
+                }
+                "code_point_escape" => {
+                    result.code_point_escape = match value {
+                        "hex" => Some(CodePointEscape::Hex),
+                        "unicode" => Some(CodePointEscape::Unicode),
+                        "long_unicode" => Some(CodePointEscape::LongUnicode),
+                        _ => {
+                            eprintln!("{}: Unknown escape style {value:?}", Yellow("Warning"));
+                            eprintln!("  in {path:?}");
+                            continue;
+                        }
+                    }
+                }
+                "sanitize_names" => result.sanitize_names = true,
+                "flags" => {
+                    let mut flags = RegexFlags::default();
+                    for c in value.chars() {
+                        match c {
+                            'i' => flags.ignore_case = true,
+                            'm' => flags.multiline = true,
+                            's' => flags.dot_all = true,
+                            _ => {
+                                eprintln!("{}: Unknown flag {c:?}", Yellow("Warning"));
+                                eprintln!("  in {path:?}");
+                            }
+                        }
+                    }
+                    result.flags = Some(flags);
+                }
+                "explain" => {
+                    result.explain = match value {
+                        "en" | "" => Some(Locale::English),
+                        "it" => Some(Locale::Italian),
+                        _ => {
+                            eprintln!("{}: Unknown locale {value:?}", Yellow("Warning"));
+                            eprintln!("  in {path:?}");
+                            continue;
+                        }
+                    }
+                }
+                "max_alternation_branches" | "max_nodes" => {
+                    let limit = match value.parse() {
+                        Ok(limit) => limit,
+                        Err(_) => {
+                            eprintln!("{}: Invalid limit {value:?}", Yellow("Warning"));
+                            eprintln!("  in {path:?}");
+                            continue;
+                        }
+                    };
+                    if key == "max_nodes" {
+                        result.max_nodes = Some(limit);
+                    } else {
+                        result.max_alternation_branches = Some(limit);
+                    }
+                }
                 "expect" => {
                     result.expected_outcome = match value {
                         "success" => Outcome::Success,
@@ -112,14 +253,72 @@ pub(crate) fn test_file(content: &str, path: &Path, args: &Args, bless: bool) ->
     }
 
     catch_panics(|| {
-        let parsed = pomsky::Expr::parse_and_compile(
-            input,
-            ParseOptions::default(),
-            CompileOptions { flavor: options.flavor },
-        );
+        let parse_options = ParseOptions {
+            warn_capture_in_repetition: options.warn_capture_in_repetition,
+            suggest_named_classes: options.suggest_named_classes,
+            warn_end_anchor: options.warn_end_anchor,
+            resolve_class: if options.custom_classes { Some(resolve_class) } else { None },
+            ..ParseOptions::default()
+        };
+        let compile_options = CompileOptions {
+            flavor: options.flavor,
+            wrap_in_named_group: options.wrap_in_named_group.clone(),
+            unicode_sets: options.unicode_sets,
+            canonical: options.canonical,
+            minimal_classes: options.minimal_classes,
+            explicit_word_boundary: options.explicit_word_boundary,
+            ascii_only_output: options.ascii_only_output,
+            posix_classes: options.posix_classes,
+            code_point_escape: options.code_point_escape,
+            name_handling: if options.sanitize_names {
+                NameHandling::Sanitize
+            } else {
+                NameHandling::Error
+            },
+            max_alternation_branches: options.max_alternation_branches,
+            max_nodes: options.max_nodes,
+            inline_variables: options.inline_variables,
+            optimization_level: options.optimization_level,
+            byte_mode: options.byte_mode,
+        };
+        let compile = || {
+            if let Some(locale) = options.explain {
+                compile_explanation(input, parse_options, compile_options.clone(), locale)
+            } else if options.complement {
+                compile_complement(input, parse_options, compile_options.clone())
+            } else if let Some(flags) = options.flags {
+                compile_with_flags(input, parse_options, compile_options.clone(), flags)
+            } else if options.sexpr {
+                pomsky::Expr::parse(input, parse_options)
+                    .map(|(mut expr, warnings)| {
+                        if options.inline_variables {
+                            expr.inline_variables();
+                        }
+                        (expr.to_sexpr(), warnings)
+                    })
+                    .map_err(Into::into)
+            } else {
+                pomsky::Expr::parse_and_compile(input, parse_options, compile_options.clone())
+            }
+        };
+        let parsed = compile();
 
         match parsed {
             Ok((mut got, warnings)) => {
+                // the output must be the same when compiling the expression again
+                let again = compile().map(|(regex, _)| regex);
+                if !matches!(&again, Ok(again) if *again == got) {
+                    return TestResult::IncorrectResult {
+                        input: input.to_string(),
+                        expected: Ok(got),
+                        got: again.map_err(|err| error_to_string(err, input)),
+                    };
+                }
+
+                // warnings that don't apply to the flavor are removed, like in
+                // `parse_and_compile`
+                let warnings =
+                    warnings.into_iter().filter(|w| w.applies_to(compile_options.flavor));
                 for warning in warnings {
                     got.push_str("\nWARNING: ");
                     got.write_fmt(format_args!("{}", warning)).unwrap();
@@ -131,7 +330,7 @@ pub(crate) fn test_file(content: &str, path: &Path, args: &Args, bless: bool) ->
                         let contents = create_content(
                             input,
                             &got,
-                            Options { expected_outcome: Outcome::Success, ..options },
+                            Options { expected_outcome: Outcome::Success, ..options.clone() },
                         );
                         std::fs::write(path, contents)
                             .expect("Failed to bless test because of IO error");
@@ -154,7 +353,7 @@ pub(crate) fn test_file(content: &str, path: &Path, args: &Args, bless: bool) ->
                         let contents = create_content(
                             input,
                             &err,
-                            Options { expected_outcome: Outcome::Error, ..options },
+                            Options { expected_outcome: Outcome::Error, ..options.clone() },
                         );
                         std::fs::write(path, contents)
                             .expect("Failed to bless test because of IO error");
@@ -173,6 +372,50 @@ pub(crate) fn test_file(content: &str, path: &Path, args: &Args, bless: bool) ->
     .unwrap_or_else(|message| TestResult::Panic { message })
 }
 
+/// Resolver for custom character classes, used by testcases with the
+/// `custom_classes` option
+fn resolve_class(name: &str) -> Option<CustomClass> {
+    match name {
+        "hex" => Some(CustomClass::new().range('0', '9').range('a', 'f').range('A', 'F')),
+        "vowel" => Some(CustomClass::new().chars("aeiou")),
+        "descending" => Some(CustomClass::new().range('z', 'a')),
+        "empty" => Some(CustomClass::new()),
+        _ => None,
+    }
+}
+
+fn compile_complement(
+    input: &str,
+    parse_options: ParseOptions,
+    compile_options: CompileOptions,
+) -> Result<(String, Vec<Warning>), CompileError> {
+    let (parsed, warnings) = pomsky::Expr::parse(input, parse_options)?;
+    let compiled = parsed.complement()?.compile(compile_options)?;
+    Ok((compiled, warnings))
+}
+
+fn compile_with_flags(
+    input: &str,
+    parse_options: ParseOptions,
+    compile_options: CompileOptions,
+    flags: RegexFlags,
+) -> Result<(String, Vec<Warning>), CompileError> {
+    let (parsed, warnings) = pomsky::Expr::parse(input, parse_options)?;
+    let (regex, flags) = parsed.compile_with_flags(compile_options, flags)?;
+    Ok((format!("{regex}\nFLAGS: {flags}"), warnings))
+}
+
+fn compile_explanation(
+    input: &str,
+    parse_options: ParseOptions,
+    compile_options: CompileOptions,
+    locale: Locale,
+) -> Result<(String, Vec<Warning>), CompileError> {
+    let (parsed, warnings) = pomsky::Expr::parse(input, parse_options)?;
+    let explanation = parsed.explain(compile_options, locale)?;
+    Ok((explanation, warnings))
+}
+
 fn error_to_string(err: CompileError, input: &str) -> String {
     let diagnostics = err.diagnostics(input);
     diagnostics
@@ -211,8 +454,93 @@ fn create_content(input: &str, outcome: &str, options: Options) -> String {
     if options.ignore {
         option_strings.push(String::from("ignore"));
     }
-    if options.flavor != RegexFlavor::Pcre {
-        option_strings.push(format!("flavor={:?}", options.flavor));
+    match options.flavor {
+        RegexFlavor::Pcre => {}
+        RegexFlavor::Sed { extended: false } => option_strings.push("flavor=sed".into()),
+        RegexFlavor::Sed { extended: true } => option_strings.push("flavor=sed-extended".into()),
+        flavor => option_strings.push(format!("flavor={flavor:?}")),
+    }
+    if let Some(name) = options.wrap_in_named_group {
+        option_strings.push(format!("wrap={name}"));
+    }
+    if options.warn_capture_in_repetition {
+        option_strings.push(String::from("warn_capture_in_repetition"));
+    }
+    if options.suggest_named_classes {
+        option_strings.push(String::from("suggest_named_classes"));
+    }
+    if options.warn_end_anchor {
+        option_strings.push(String::from("warn_end_anchor"));
+    }
+    if options.complement {
+        option_strings.push(String::from("complement"));
+    }
+    if options.sexpr {
+        option_strings.push(String::from("sexpr"));
+    }
+    if options.custom_classes {
+        option_strings.push(String::from("custom_classes"));
+    }
+    if options.unicode_sets {
+        option_strings.push(String::from("unicode_sets"));
+    }
+    if options.canonical {
+        option_strings.push(String::from("canonical"));
+    }
+    if options.minimal_classes {
+        option_strings.push(String::from("minimal_classes"));
+    }
+    if options.explicit_word_boundary {
+        option_strings.push(String::from("explicit_word_boundary"));
+    }
+    if options.ascii_only_output {
+        option_strings.push(String::from("ascii_only_output"));
+    }
+    if options.posix_classes {
+        option_strings.push(String::from("posix_classes"));
+    }
+    match options.code_point_escape {
+        Some(CodePointEscape::Hex) => option_strings.push(String::from("code_point_escape=hex")),
+        Some(CodePointEscape::Unicode) => {
+            option_strings.push(String::from("code_point_escape=unicode"))
+        }
+        Some(CodePointEscape::LongUnicode) => {
+            option_strings.push(String::from("code_point_escape=long_unicode"))
+        }
+        None => {}
+    }
+    if options.sanitize_names {
+        option_strings.push(String::from("sanitize_names"));
+    }
+    if let Some(flags) = options.flags {
+        let mut letters = String::new();
+        for (enabled, c) in [(flags.ignore_case, 'i'), (flags.multiline, 'm'), (flags.dot_all, 's')]
+        {
+            if enabled {
+                letters.push(c);
+            }
+        }
+        option_strings.push(format!("flags={letters}"));
+    }
+    if let Some(limit) = options.max_alternation_branches {
+        option_strings.push(format!("max_alternation_branches={limit}"));
+    }
+    if let Some(limit) = options.max_nodes {
+        option_strings.push(format!("max_nodes={limit}"));
+    }
+    if options.inline_variables {
+        option_strings.push(String::from("inline_variables"));
+    }
+    if options.optimization_level == OptimizationLevel::Full {
+        option_strings.push(String::from("optimization_level=full"));
+    }
+    if options.byte_mode {
+        option_strings.push(String::from("byte_mode"));
+    }
+    match options.explain {
+        Some(Locale::English) => option_strings.push(String::from("explain=en")),
+        Some(Locale::Italian) => option_strings.push(String::from("explain=it")),
+        Some(_) | None => {}
     }
 
     let option_strings = if option_strings.is_empty() {
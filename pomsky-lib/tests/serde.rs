@@ -0,0 +1,49 @@
+#![cfg(feature = "serde")]
+
+use pomsky::{
+    error::{Diagnostic, Severity},
+    Expr,
+};
+
+#[test]
+fn serialize_parse_error() {
+    let input = "'a' | [foo]";
+    let err = Expr::parse(input, Default::default()).map(|_| ()).unwrap_err();
+    let diagnostics = Diagnostic::from_parse_errors(err, input);
+
+    assert_eq!(
+        serde_json::to_string(&diagnostics).unwrap(),
+        r#"[{"severity":"error","code":"P0305","msg":"Unknown character class `foo`","span":{"start":7,"end":10}}]"#
+    );
+}
+
+#[test]
+fn serialize_help_and_code() {
+    let diagnostic = Diagnostic::ad_hoc(
+        Severity::Warning,
+        Some("W0001".into()),
+        "Some warning".into(),
+        Some("Some help".into()),
+    );
+
+    assert_eq!(
+        serde_json::to_string(&diagnostic).unwrap(),
+        r#"{"severity":"warning","code":"W0001","msg":"Some warning","help":"Some help"}"#
+    );
+}
+
+#[test]
+fn serialize_ast() {
+    let (expr, _) = Expr::parse(r#":x("a\\b"+) | ::x"#, Default::default()).unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&expr.root()).unwrap(),
+        concat!(
+            r#"{"kind":"alternation","span":{"start":0,"end":17},"children":["#,
+            r#"{"kind":"capturing_group","name":"x","index":1,"span":{"start":0,"end":11},"children":["#,
+            r#"{"kind":"repetition","min":1,"max":null,"span":{"start":3,"end":10},"children":["#,
+            r#"{"kind":"literal","value":"a\\b","span":{"start":3,"end":9},"children":[]}]}]},"#,
+            r#"{"kind":"reference","name":"x","span":{"start":16,"end":17},"children":[]}]}"#,
+        )
+    );
+}
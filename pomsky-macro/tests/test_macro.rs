@@ -28,5 +28,5 @@ fn composite_tokens() {
         Start "Test" End
     );
 
-    assert_eq!(REGEX, "^Test$");
+    assert_eq!(REGEX, r"\ATest\z");
 }